@@ -0,0 +1,31 @@
+//! Общий замер для `benches/`. Criterion в офлайн-сборке не достать: его нет в
+//! локальном реестре, а завендорить его с зависимостями не выходит. Поэтому замер
+//! свой: прогрев, несколько серий и медиана по сериям, чтобы один выброс не сдвигал
+//! цифру. Минимум и максимум печатаются рядом, по ним видно, насколько шумит машина.
+
+use std::time::{Duration, Instant};
+
+/// Серий на один замер
+const SAMPLES: usize = 15;
+
+/// Прогрев перед замером, в сериях
+const WARMUP: usize = 3;
+
+/// Медиана времени одного повтора `f` по `SAMPLES` сериям из `rounds` повторов
+pub fn measure(name: &str, rounds: u32, mut f: impl FnMut()) -> Duration {
+    let mut series = || {
+        let started = Instant::now();
+        for _ in 0..rounds {
+            f();
+        }
+        started.elapsed() / rounds
+    };
+    for _ in 0..WARMUP {
+        series();
+    }
+    let mut samples: Vec<Duration> = (0..SAMPLES).map(|_| series()).collect();
+    samples.sort();
+    let (median, min, max) = (samples[SAMPLES / 2], samples[0], samples[SAMPLES - 1]);
+    println!("{name:<40} {median:>12.2?}   [{min:.2?} … {max:.2?}]");
+    median
+}
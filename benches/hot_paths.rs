@@ -0,0 +1,82 @@
+//! Горячие пути игры: шаг с короткой и очень длинной змейкой, еда на почти полном
+//! поле и отрисовка поля в `TestBackend` размером 80×24 и 240×60. Терминал не нужен.
+//! Criterion офлайн не завендорить, поэтому замер свой, из `common`: медиана по
+//! сериям после прогрева. Запуск: `cargo bench --bench hot_paths`.
+
+mod common;
+
+use std::hint::black_box;
+
+use ratatui::{
+    backend::TestBackend,
+    widgets::{Block, Borders},
+    Terminal,
+};
+use zmiy::{
+    game::{DirectionSnake, Game, Point},
+    theme::{ColorSupport, Theme},
+    widget::GameWidget,
+};
+
+use common::measure;
+
+/// Змейка змейкой по рядам сверху: `length` клеток, голова в конце и смотрит
+/// вправо или влево вдоль своего ряда. Еда в нижнем правом углу, подальше от змейки.
+fn serpentine(width: u16, height: u16, length: usize) -> Game {
+    let mut cells: Vec<Point> = (0..length)
+        .map(|i| {
+            let (row, col) = ((i / width as usize) as u16, (i % width as usize) as u16);
+            Point { x: if row.is_multiple_of(2) { col } else { width - 1 - col }, y: row }
+        })
+        .collect();
+    let head_row = cells.last().map_or(0, |p| p.y);
+    cells.reverse();
+    let mut game = Game::with_seed(width, height, 1);
+    game.set_snake(cells);
+    game.dir = if head_row.is_multiple_of(2) { DirectionSnake::Right } else { DirectionSnake::Left };
+    game.food = Point { x: width - 1, y: height - 1 };
+    game
+}
+
+/// Полный кадр поля в буфере `TestBackend` размером с терминал
+fn render(width: u16, height: u16, rounds: u32) {
+    let theme = Theme::new(ColorSupport::TrueColor);
+    // Поле внутри рамки, змейка на половине его
+    let (board_w, board_h) = (width - 2, height - 2);
+    let mut game = serpentine(board_w, board_h, board_w as usize * board_h as usize / 2);
+    let mut terminal = Terminal::new(TestBackend::new(width, height)).expect("TestBackend не падает");
+    measure(&format!("кадр {width}×{height}"), rounds, || {
+        terminal
+            .draw(|f| {
+                let block = Block::default().borders(Borders::ALL);
+                f.render_stateful_widget(GameWidget::new(&theme).grid(true).block(block), f.area(), &mut game);
+            })
+            .expect("TestBackend не падает");
+        black_box(terminal.backend().buffer());
+    });
+}
+
+fn main() {
+    // 120 свободных клеток перед головой: 100 шагов проходят без смерти и без еды
+    let (width, height) = (240, 60);
+    let short = serpentine(width, height, 3);
+    let long = serpentine(width, height, 40 * width as usize + 120);
+    for (name, start) in [("100 шагов, змейка 3", &short), ("100 шагов, змейка 9 720", &long)] {
+        measure(name, 100, || {
+            let mut game = serpentine(start.width, start.height, start.snake().len());
+            for _ in 0..100 {
+                black_box(game.step());
+            }
+            debug_assert!(game.game_over.is_none());
+        });
+    }
+
+    // Свободно пять клеток из 1 920: случайные попытки почти всегда мимо
+    let mut full = serpentine(80, 24, 80 * 24 - 5);
+    measure("еда на поле 80×24, свободно 5", 1000, || {
+        black_box(full.spawn_food());
+    });
+
+    render(80, 24, 500);
+    render(240, 60, 100);
+}
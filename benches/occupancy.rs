@@ -0,0 +1,57 @@
+//! Проверка клетки на змейку: перебор змейки против сетки занятых клеток.
+//! Змейка из 500 клеток на поле 200×60. Замер свой, без criterion (офлайн его не
+//! достать): медиана по сериям из `common`. Запуск: `cargo bench --bench occupancy`.
+
+mod common;
+
+use std::{collections::VecDeque, hint::black_box};
+
+use common::measure;
+use zmiy::game::{DirectionSnake, Game, Point};
+
+const WIDTH: u16 = 200;
+const HEIGHT: u16 = 60;
+const LENGTH: usize = 500;
+
+/// Змейка змейкой по верхним рядам: голова в конце, смотрит вправо на свободный ряд
+fn serpentine() -> Vec<Point> {
+    let mut cells: Vec<Point> = (0..LENGTH as u16)
+        .map(|i| {
+            let (row, col) = (i / WIDTH, i % WIDTH);
+            Point { x: if row % 2 == 0 { col } else { WIDTH - 1 - col }, y: row }
+        })
+        .collect();
+    cells.reverse();
+    cells
+}
+
+fn main() {
+    let snake = serpentine();
+    let deque: VecDeque<Point> = snake.iter().copied().collect();
+    let mut game = Game::with_seed(WIDTH, HEIGHT, 1);
+    game.set_snake(snake.iter().copied());
+    game.food = Point { x: WIDTH - 1, y: HEIGHT - 1 };
+    let cells: Vec<Point> = (0..HEIGHT).flat_map(|y| (0..WIDTH).map(move |x| Point { x, y })).collect();
+
+    println!("змейка {LENGTH} клеток, поле {WIDTH}×{HEIGHT}");
+    let scan = measure("все клетки поля, перебор змейки", 20, || {
+        black_box(cells.iter().filter(|p| deque.contains(p)).count());
+    });
+    let grid = measure("все клетки поля, сетка", 20, || {
+        black_box(cells.iter().filter(|p| game.is_snake(**p)).count());
+    });
+    println!("{:<40} {:>11.0}×", "быстрее", scan.as_secs_f64() / grid.as_secs_f64());
+
+    measure("to_text", 20, || {
+        black_box(game.to_text());
+    });
+    measure("100 шагов", 200, || {
+        let mut game = Game::with_seed(WIDTH, HEIGHT, 1);
+        game.set_snake(snake.iter().copied());
+        game.food = Point { x: WIDTH - 1, y: HEIGHT - 1 };
+        game.dir = DirectionSnake::Right;
+        for _ in 0..100 {
+            black_box(game.step());
+        }
+    });
+}
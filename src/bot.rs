@@ -0,0 +1,209 @@
+//! Внешний бот для `--bot-io`: отдельная программа, которая ведёт змейку.
+//!
+//! Перед каждым шагом игра пишет боту в stdin строку JSON с состоянием:
+//!
+//! ```text
+//! {"tick":12,"width":40,"height":20,"score":3,"dir":"RIGHT","snake":[[5,5],[4,5]],"food":[3,4]}
+//! ```
+//!
+//! и ждёт в ответ одну строку: `UP`, `DOWN`, `LEFT`, `RIGHT` или `PASS` (ехать прямо).
+//! Не успевший ответить бот пропускает ход, а его запоздалый ответ выбрасывается.
+//! Всё, что бот пишет в stderr, уходит в журнал.
+
+use std::{
+    fmt::{self, Write as _},
+    io::{self, BufRead, BufReader, Write},
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+    thread,
+    time::Duration,
+};
+
+use crate::{game::{DirectionSnake, Game}, log_warn};
+
+/// Сколько ждать ответа бота по умолчанию
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_millis(200);
+
+/// Почему бот больше не может играть
+#[derive(Debug)]
+pub enum BotError {
+    /// Команда не запустилась
+    Spawn(io::Error),
+    /// Бот закрыл stdin или завершился
+    Exited,
+    /// Ответ не из протокола
+    BadReply(String),
+}
+
+impl fmt::Display for BotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BotError::Spawn(e) => write!(f, "бот не запустился: {e}"),
+            BotError::Exited => write!(f, "бот завершился"),
+            BotError::BadReply(line) => {
+                write!(f, "непонятный ответ бота {line:?}: ожидается UP, DOWN, LEFT, RIGHT или PASS")
+            }
+        }
+    }
+}
+
+impl std::error::Error for BotError {}
+
+/// Направление в протоколе
+fn dir_name(dir: DirectionSnake) -> &'static str {
+    match dir {
+        DirectionSnake::Up => "UP",
+        DirectionSnake::Down => "DOWN",
+        DirectionSnake::Left => "LEFT",
+        DirectionSnake::Right => "RIGHT",
+    }
+}
+
+/// Ответ бота: поворот или `None` для `PASS`
+pub fn parse_reply(line: &str) -> Result<Option<DirectionSnake>, BotError> {
+    match line.trim() {
+        "UP" => Ok(Some(DirectionSnake::Up)),
+        "DOWN" => Ok(Some(DirectionSnake::Down)),
+        "LEFT" => Ok(Some(DirectionSnake::Left)),
+        "RIGHT" => Ok(Some(DirectionSnake::Right)),
+        "PASS" => Ok(None),
+        other => Err(BotError::BadReply(other.to_string())),
+    }
+}
+
+/// Состояние для бота одной строкой JSON, без перевода строки
+pub fn state_json(game: &Game, tick: u64) -> String {
+    let mut out = format!(
+        "{{\"tick\":{tick},\"width\":{},\"height\":{},\"score\":{},\"dir\":\"{}\",\"snake\":[",
+        game.width,
+        game.height,
+        game.score,
+        dir_name(game.dir)
+    );
+    for (i, p) in game.snake().iter().enumerate() {
+        let _ = write!(out, "{}[{},{}]", if i > 0 { "," } else { "" }, p.x, p.y);
+    }
+    let _ = write!(out, "],\"food\":[{},{}]}}", game.food.x, game.food.y);
+    out
+}
+
+/// Запущенный бот
+pub struct Bot {
+    child: Child,
+    stdin: ChildStdin,
+    replies: Receiver<String>,
+    timeout: Duration,
+    /// Сколько ответов опоздало: их надо выбросить, прежде чем ждать свежий
+    late: usize,
+}
+
+impl Bot {
+    /// Запускает команду через `sh -c`, как в терминале
+    pub fn spawn(command: &str, timeout: Duration) -> Result<Self, BotError> {
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(BotError::Spawn)?;
+        let stdin = child.stdin.take().expect("stdin бота перехвачен");
+        let stdout = child.stdout.take().expect("stdout бота перехвачен");
+        let stderr = child.stderr.take().expect("stderr бота перехвачен");
+
+        // Ответы читаются в отдельном потоке, чтобы ждать их с таймаутом
+        let (tx, replies) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else { break };
+                if tx.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        // stderr бота в терминал не пускаем: он испортил бы картинку
+        thread::spawn(move || {
+            for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+                log_warn!("бот: {line}");
+            }
+        });
+        Ok(Self { child, stdin, replies, timeout, late: 0 })
+    }
+
+    /// Отдаёт боту состояние и ждёт ход. Опоздавший бот пропускает ход.
+    pub fn decide(&mut self, game: &Game, tick: u64) -> Result<Option<DirectionSnake>, BotError> {
+        writeln!(self.stdin, "{}", state_json(game, tick)).and_then(|()| self.stdin.flush()).map_err(|_| BotError::Exited)?;
+        loop {
+            match self.replies.recv_timeout(self.timeout) {
+                Ok(_) if self.late > 0 => self.late -= 1,
+                Ok(line) => return parse_reply(&line),
+                Err(RecvTimeoutError::Timeout) => {
+                    self.late += 1;
+                    return Ok(None);
+                }
+                Err(RecvTimeoutError::Disconnected) => return Err(BotError::Exited),
+            }
+        }
+    }
+}
+
+impl Drop for Bot {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Point;
+
+    #[test]
+    fn state_line() {
+        let mut game = Game::with_seed(10, 6, 1);
+        game.set_snake([Point { x: 5, y: 3 }, Point { x: 4, y: 3 }]);
+        game.food = Point { x: 1, y: 2 };
+        game.score = 1;
+        assert_eq!(
+            state_json(&game, 7),
+            r#"{"tick":7,"width":10,"height":6,"score":1,"dir":"RIGHT","snake":[[5,3],[4,3]],"food":[1,2]}"#
+        );
+    }
+
+    #[test]
+    fn replies() {
+        assert_eq!(parse_reply("UP\n").unwrap(), Some(DirectionSnake::Up));
+        assert_eq!(parse_reply("PASS").unwrap(), None);
+        assert_eq!(
+            parse_reply("up").unwrap_err().to_string(),
+            "непонятный ответ бота \"up\": ожидается UP, DOWN, LEFT, RIGHT или PASS"
+        );
+    }
+
+    #[test]
+    fn talks_to_a_process() {
+        // Бот на sh: на каждую строку состояния отвечает DOWN
+        let mut bot = Bot::spawn("while read -r line; do echo DOWN; done", Duration::from_secs(5)).unwrap();
+        let game = Game::with_seed(10, 6, 1);
+        assert_eq!(bot.decide(&game, 0).unwrap(), Some(DirectionSnake::Down));
+        assert_eq!(bot.decide(&game, 1).unwrap(), Some(DirectionSnake::Down));
+
+        let mut bot = Bot::spawn("read -r line; echo JUMP", Duration::from_secs(5)).unwrap();
+        assert!(matches!(bot.decide(&game, 0), Err(BotError::BadReply(_))));
+
+        let mut bot = Bot::spawn("exit 0", Duration::from_secs(5)).unwrap();
+        assert!(matches!(bot.decide(&game, 0), Err(BotError::Exited)));
+    }
+
+    #[test]
+    fn slow_bot_skips_a_turn() {
+        let mut bot = Bot::spawn("read -r l; sleep 0.3; echo UP; read -r l; echo LEFT", Duration::from_millis(50)).unwrap();
+        let game = Game::with_seed(10, 6, 1);
+        assert_eq!(bot.decide(&game, 0).unwrap(), None);
+        // Запоздавший UP выбрасывается, ход берётся из свежего ответа
+        bot.timeout = Duration::from_secs(5);
+        assert_eq!(bot.decide(&game, 1).unwrap(), Some(DirectionSnake::Left));
+    }
+}
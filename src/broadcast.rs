@@ -0,0 +1,301 @@
+//! Трансляция партии зрителям: `--broadcast адрес` у игрока и `--spectate адрес` у зрителя.
+//!
+//! Кадры и приветствие те же, что в игре вдвоём (см. [`crate::versus`]), зритель
+//! здоровается как `watch`. Дальше хост только шлёт снимки, по полю на строку:
+//!
+//! ```text
+//! watch 120                номер шага
+//! board 40 20
+//! food 3 4
+//! bonus 7 2                синяя еда, только в режиме «две еды»
+//! bomb 9 1 42              бомба и сколько шагов она ещё лежит, только если она есть
+//! boss 6 2 3               левый верхний угол босса и сколько касаний ему осталось
+//! caterpillar 5 3 6 3      звенья гусеницы от конца к концу, только если она есть
+//! obstacles 1 1 1 2        сброшенные хвосты, только если они есть
+//! walls 20 0 20 1          стены уровня, только если партия идёт на уровне
+//! fast 3 4 4 4             клетки быстрой зоны, только если зоны есть
+//! slow 9 9                 клетки медленной зоны, только если зоны есть
+//! score 5
+//! dir R
+//! state play               play, pause или конец: wall, self, board, full, obstacle, bomb
+//! snake 5 5 4 5 3 5        клетки змейки от головы к хвосту
+//! ```
+//!
+//! Каждому зрителю пишет свой поток с очередью на один кадр. Если зритель не успевает
+//! читать, новые кадры для него выбрасываются, а игра не ждёт никого.
+
+use std::{
+    collections::VecDeque,
+    net::TcpListener,
+    sync::{
+        mpsc::{self, SyncSender, TrySendError},
+        Arc,
+    },
+    thread,
+};
+
+use crate::{
+    game::{Bomb, Boss, DeathCause, DirectionSnake, Game, Point, Zone},
+    log_info,
+    versus::{self, write_frame, NetError, Seat},
+};
+
+/// Снимок для зрителей
+pub fn snapshot(game: &Game, tick: u64, paused: bool) -> String {
+    let state = match game.game_over {
+        None if paused => "pause",
+        None => "play",
+        Some(DeathCause::Wall) => "wall",
+        Some(DeathCause::SelfCollision) => "self",
+        Some(DeathCause::BoardTooSmall) => "board",
+        Some(DeathCause::BoardFull) => "full",
+        Some(DeathCause::Obstacle) => "obstacle",
+        Some(DeathCause::Bomb) => "bomb",
+    };
+    let mut out = format!(
+        "watch {tick}\nboard {} {}\nfood {} {}\nscore {}\ndir {}\nstate {state}\n",
+        game.width,
+        game.height,
+        game.food.x,
+        game.food.y,
+        game.score,
+        game.dir.code()
+    );
+    if let Some(bonus) = game.bonus {
+        out.push_str(&format!("bonus {} {}\n", bonus.x, bonus.y));
+    }
+    if let Some(bomb) = game.bomb {
+        out.push_str(&format!("bomb {} {} {}\n", bomb.at.x, bomb.at.y, bomb.left));
+    }
+    if let Some(boss) = game.boss {
+        out.push_str(&format!("boss {} {} {}\n", boss.at.x, boss.at.y, boss.hits));
+    }
+    if !game.caterpillar.is_empty() {
+        out.push_str("caterpillar");
+        for p in &game.caterpillar {
+            out.push_str(&format!(" {} {}", p.x, p.y));
+        }
+        out.push('\n');
+    }
+    if !game.obstacles().is_empty() {
+        out.push_str("obstacles");
+        for p in game.obstacles() {
+            out.push_str(&format!(" {} {}", p.x, p.y));
+        }
+        out.push('\n');
+    }
+    for (key, cells) in [("walls", game.walls()), ("fast", game.zones(Zone::Fast)), ("slow", game.zones(Zone::Slow))] {
+        if !cells.is_empty() {
+            out.push_str(key);
+            for p in cells {
+                out.push_str(&format!(" {} {}", p.x, p.y));
+            }
+            out.push('\n');
+        }
+    }
+    out.push_str("snake");
+    for p in game.snake() {
+        out.push_str(&format!(" {} {}", p.x, p.y));
+    }
+    out.push('\n');
+    out
+}
+
+/// Что видит зритель
+pub struct Watched {
+    pub game: Game,
+    pub tick: u64,
+    pub paused: bool,
+}
+
+/// Разбирает снимок хоста
+pub fn parse_snapshot(text: &str) -> Result<Watched, NetError> {
+    let bad = || NetError::BadMessage(text.lines().next().unwrap_or_default().to_string());
+    let (mut tick, mut board, mut food, mut score, mut dir, mut state, mut snake) = (None, None, None, None, None, None, None);
+    let (mut bonus, mut bomb, mut boss, mut caterpillar) = (None, None, None, None);
+    let (mut obstacles, mut walls, mut fast, mut slow) = (None, None, None, None);
+    for line in text.lines() {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let nums: Option<Vec<u16>> = words.iter().skip(1).map(|w| w.parse().ok()).collect();
+        let points = || -> Option<VecDeque<Point>> {
+            let nums = nums.clone()?;
+            nums.len().is_multiple_of(2).then(|| nums.chunks(2).map(|c| Point { x: c[0], y: c[1] }).collect())
+        };
+        match words.as_slice() {
+            ["watch", n] => tick = n.parse::<u64>().ok(),
+            ["board", _, _] => board = points().and_then(|p| p.front().copied()),
+            ["food", _, _] => food = points().and_then(|p| p.front().copied()),
+            ["bonus", _, _] => bonus = points().and_then(|p| p.front().copied()),
+            ["bomb", x, y, left] => {
+                let (Ok(x), Ok(y), Ok(left)) = (x.parse(), y.parse(), left.parse()) else {
+                    return Err(bad());
+                };
+                bomb = Some(Bomb { at: Point { x, y }, left });
+            }
+            ["boss", x, y, hits] => {
+                let (Ok(x), Ok(y), Ok(hits)) = (x.parse(), y.parse(), hits.parse()) else {
+                    return Err(bad());
+                };
+                boss = Some(Boss { at: Point { x, y }, hits });
+            }
+            ["caterpillar", _, ..] => caterpillar = Some(points().ok_or_else(bad)?),
+            ["obstacles", _, ..] => obstacles = Some(points().ok_or_else(bad)?),
+            ["walls", _, ..] => walls = Some(points().ok_or_else(bad)?),
+            ["fast", _, ..] => fast = Some(points().ok_or_else(bad)?),
+            ["slow", _, ..] => slow = Some(points().ok_or_else(bad)?),
+            ["score", n] => score = n.parse::<usize>().ok(),
+            ["dir", d] => dir = DirectionSnake::from_code(d),
+            ["state", s] => state = Some(s.to_string()),
+            ["snake", _, ..] => snake = points(),
+            _ => return Err(bad()),
+        }
+    }
+    let board = board.ok_or_else(bad)?;
+    let mut game = Game::with_seed(board.x, board.y, 0);
+    game.food = food.ok_or_else(bad)?;
+    game.bonus = bonus;
+    game.bomb = bomb;
+    game.boss = boss;
+    game.caterpillar = caterpillar.map(Vec::from).unwrap_or_default();
+    game.set_obstacles(obstacles.unwrap_or_default());
+    game.set_walls(walls.unwrap_or_default());
+    game.set_zones(Zone::Fast, fast.unwrap_or_default());
+    game.set_zones(Zone::Slow, slow.unwrap_or_default());
+    game.score = score.ok_or_else(bad)?;
+    game.dir = dir.ok_or_else(bad)?;
+    game.set_snake(snake.ok_or_else(bad)?);
+    let state = state.ok_or_else(bad)?;
+    game.game_over = match state.as_str() {
+        "play" | "pause" => None,
+        "wall" => Some(DeathCause::Wall),
+        "self" => Some(DeathCause::SelfCollision),
+        "board" => Some(DeathCause::BoardTooSmall),
+        "full" => Some(DeathCause::BoardFull),
+        "obstacle" => Some(DeathCause::Obstacle),
+        "bomb" => Some(DeathCause::Bomb),
+        _ => return Err(bad()),
+    };
+    if !game.snake().iter().all(|p| p.x < game.width && p.y < game.height) {
+        return Err(bad());
+    }
+    Ok(Watched { game, tick: tick.ok_or_else(bad)?, paused: state == "pause" })
+}
+
+/// Открытая трансляция
+pub struct Broadcast {
+    listener: TcpListener,
+    /// Очереди потоков, пишущих зрителям
+    spectators: Vec<SyncSender<Arc<str>>>,
+}
+
+impl Broadcast {
+    pub fn listen(addr: &str) -> std::io::Result<Self> {
+        Ok(Self { listener: versus::listen(addr)?, spectators: Vec::new() })
+    }
+
+    /// Принимает всех, кто подключился с прошлого раза, и возвращает их число.
+    /// Приветствие идёт в потоке зрителя, поэтому медленный зритель игру не задерживает.
+    pub fn accept(&mut self) -> usize {
+        let mut joined = 0;
+        while let Ok((mut stream, addr)) = self.listener.accept() {
+            let (tx, frames) = mpsc::sync_channel::<Arc<str>>(1);
+            self.spectators.push(tx);
+            joined += 1;
+            thread::spawn(move || {
+                if let Err(e) = versus::handshake(&mut stream, Seat::Spectator) {
+                    log_info!("зритель {addr} не подключился: {e}");
+                    return;
+                }
+                log_info!("зритель {addr} подключился");
+                for frame in frames {
+                    if write_frame(&mut stream, &frame).is_err() {
+                        break;
+                    }
+                }
+                log_info!("зритель {addr} отключился");
+            });
+        }
+        joined
+    }
+
+    /// Рассылает снимок. Кому кадр некуда положить, тот его пропускает;
+    /// ушедшие зрители убираются из списка.
+    pub fn send(&mut self, snapshot: &str) {
+        let frame: Arc<str> = Arc::from(snapshot);
+        self.spectators.retain(|tx| !matches!(tx.try_send(frame.clone()), Err(TrySendError::Disconnected(_))));
+    }
+
+    pub fn spectators(&self) -> usize {
+        self.spectators.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::versus::connect;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn snapshot_round_trip() {
+        let mut game = Game::with_seed(12, 8, 4);
+        game.step();
+        game.step();
+        let text = snapshot(&game, 2, true);
+        let watched = parse_snapshot(&text).unwrap();
+        assert_eq!((watched.tick, watched.paused), (2, true));
+        assert_eq!(watched.game.snake(), game.snake());
+        assert_eq!((watched.game.food, watched.game.dir), (game.food, game.dir));
+        assert_eq!(watched.game.bonus, None);
+        game.bonus = Some(Point { x: 7, y: 2 });
+        assert_eq!(parse_snapshot(&snapshot(&game, 2, false)).unwrap().game.bonus, game.bonus);
+        game.set_obstacles([Point { x: 1, y: 1 }, Point { x: 1, y: 2 }]);
+        assert_eq!(parse_snapshot(&snapshot(&game, 2, false)).unwrap().game.obstacles(), game.obstacles());
+        game.set_walls([Point { x: 3, y: 0 }, Point { x: 3, y: 1 }]);
+        assert_eq!(parse_snapshot(&snapshot(&game, 2, false)).unwrap().game.walls(), game.walls());
+        game.set_zones(Zone::Slow, [Point { x: 5, y: 5 }]);
+        let watched = parse_snapshot(&snapshot(&game, 2, false)).unwrap().game;
+        assert_eq!((watched.zones(Zone::Fast), watched.zones(Zone::Slow)), (&[][..], game.zones(Zone::Slow)));
+
+        game.bomb = Some(Bomb { at: Point { x: 9, y: 1 }, left: 42 });
+        assert_eq!(parse_snapshot(&snapshot(&game, 2, false)).unwrap().game.bomb, game.bomb);
+        game.boss = Some(Boss { at: Point { x: 6, y: 2 }, hits: 2 });
+        assert_eq!(parse_snapshot(&snapshot(&game, 2, false)).unwrap().game.boss, game.boss);
+        game.caterpillar = vec![Point { x: 5, y: 3 }, Point { x: 6, y: 3 }];
+        assert_eq!(parse_snapshot(&snapshot(&game, 2, false)).unwrap().game.caterpillar, game.caterpillar);
+
+        game.game_over = Some(DeathCause::Bomb);
+        assert_eq!(parse_snapshot(&snapshot(&game, 3, false)).unwrap().game.game_over, Some(DeathCause::Bomb));
+        assert!(parse_snapshot("watch 1\nboard 4 4\n").is_err());
+        assert!(parse_snapshot(&text.replace("snake", "snake 99 99")).is_err());
+    }
+
+    #[test]
+    fn slow_spectators_do_not_block() {
+        let mut broadcast = Broadcast::listen("127.0.0.1:0").unwrap();
+        let addr = broadcast.listener.local_addr().unwrap().to_string();
+        let watcher = thread::spawn(move || connect(&addr, Seat::Spectator));
+        let started = Instant::now();
+        while broadcast.accept() == 0 {
+            assert!(started.elapsed() < Duration::from_secs(5));
+        }
+        let watcher = watcher.join().unwrap().unwrap();
+        // Зритель ничего не читает, а рассылка всё равно не задерживается
+        let big = "x".repeat(64 * 1024);
+        let started = Instant::now();
+        for _ in 0..200 {
+            broadcast.send(&big);
+        }
+        assert!(started.elapsed() < Duration::from_secs(1));
+        assert_eq!(broadcast.spectators(), 1);
+
+        // Ушедший зритель пропадает из списка
+        drop(watcher);
+        let started = Instant::now();
+        while broadcast.spectators() > 0 {
+            broadcast.send(&big);
+            assert!(started.elapsed() < Duration::from_secs(5));
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+}
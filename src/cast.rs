@@ -0,0 +1,167 @@
+//! Запись сеанса в формате asciinema v2 (`--record-cast файл.cast`).
+//!
+//! Всё, что игра пишет в терминал, по-прежнему уходит в терминал, а заодно копится
+//! до сброса вывода. Каждый сброс — это готовый кадр, он попадает в файл одной строкой
+//! `[секунды, "o", "вывод"]`. Первая строка файла — заголовок с размером терминала;
+//! если терминал меняет размер, в файл пишется событие `"r"`.
+//!
+//! ```text
+//! {"version":2,"width":80,"height":24,"timestamp":1700000000,"title":"zmiy","env":{"TERM":"xterm-256color"}}
+//! [0.000000,"o","\u001b[H\u001b[2J"]
+//! [0.016512,"o","\u001b[1;1H┌──…"]
+//! [3.204113,"r","100x30"]
+//! ```
+
+use std::{
+    fs,
+    io::{self, BufWriter, Write},
+    path::Path,
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{dump::json_string, log_warn};
+
+/// Файл записи: заголовок и события с моментами от начала записи
+pub struct Cast<O: Write> {
+    out: O,
+    start: Instant,
+    size: (u16, u16),
+}
+
+impl<O: Write> Cast<O> {
+    /// Пишет заголовок и очистку экрана: запись начинается с чистого экрана,
+    /// как игра — с альтернативного
+    pub fn start(mut out: O, size: (u16, u16), now: Instant) -> io::Result<Self> {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        let term = std::env::var("TERM").unwrap_or_default();
+        writeln!(
+            out,
+            "{{\"version\":2,\"width\":{},\"height\":{},\"timestamp\":{timestamp},\"title\":\"zmiy\",\"env\":{{\"TERM\":{}}}}}",
+            size.0,
+            size.1,
+            json_string(&term)
+        )?;
+        let mut cast = Self { out, start: now, size };
+        cast.event(now, "o", "\x1b[H\x1b[2J")?;
+        Ok(cast)
+    }
+
+    fn event(&mut self, at: Instant, kind: &str, data: &str) -> io::Result<()> {
+        let secs = at.saturating_duration_since(self.start).as_secs_f64();
+        writeln!(self.out, "[{secs:.6},\"{kind}\",{}]", json_string(data))
+    }
+
+    /// Вывод в терминал в момент `at`
+    pub fn output(&mut self, at: Instant, data: &[u8]) -> io::Result<()> {
+        self.event(at, "o", &String::from_utf8_lossy(data))
+    }
+
+    /// Размер терминала в момент `at`; событие пишется, только если он изменился
+    pub fn resize(&mut self, at: Instant, size: (u16, u16)) -> io::Result<()> {
+        if size == self.size {
+            return Ok(());
+        }
+        self.size = size;
+        self.event(at, "r", &format!("{}x{}", size.0, size.1))
+    }
+}
+
+/// Вывод в терминал, который заодно пишется в `.cast`, если запись включена.
+/// Ошибка записи в файл не мешает игре: запись останавливается, а ошибка печатается
+/// в stderr, когда писатель удаляется. Поэтому он должен жить дольше полноэкранного
+/// режима, иначе сообщение останется на альтернативном экране.
+pub struct CastWriter<W: Write> {
+    inner: W,
+    cast: Option<Cast<BufWriter<fs::File>>>,
+    /// Вывод с прошлого сброса
+    pending: Vec<u8>,
+    error: Option<io::Error>,
+}
+
+impl<W: Write> CastWriter<W> {
+    /// Без записи: вывод просто проходит в терминал
+    pub fn new(inner: W) -> Self {
+        Self { inner, cast: None, pending: Vec::new(), error: None }
+    }
+
+    /// С записью в `path`; размер — текущий размер терминала
+    pub fn recording(inner: W, path: &Path, size: (u16, u16)) -> io::Result<Self> {
+        let cast = Cast::start(BufWriter::new(fs::File::create(path)?), size, Instant::now())?;
+        Ok(Self { inner, cast: Some(cast), pending: Vec::new(), error: None })
+    }
+
+    fn record(&mut self) -> io::Result<()> {
+        let Some(cast) = self.cast.as_mut() else {
+            return Ok(());
+        };
+        let now = Instant::now();
+        if let Ok(size) = crossterm::terminal::size() {
+            cast.resize(now, size)?;
+        }
+        cast.output(now, &self.pending)?;
+        // Кадр сразу на диск: оборванный сеанс тоже остаётся годной записью
+        cast.out.flush()
+    }
+}
+
+impl<W: Write> Write for CastWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        if self.cast.is_some() {
+            self.pending.extend_from_slice(&buf[..written]);
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()?;
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        if let Err(e) = self.record() {
+            log_warn!("запись .cast остановлена: {e}");
+            self.cast = None;
+            self.error = Some(e);
+        }
+        self.pending.clear();
+        Ok(())
+    }
+}
+
+impl<W: Write> Drop for CastWriter<W> {
+    fn drop(&mut self) {
+        if let Some(cast) = self.cast.as_mut()
+            && let Err(e) = cast.out.flush()
+        {
+            log_warn!("запись .cast не дописана: {e}");
+            self.error = Some(e);
+        }
+        if let Some(e) = &self.error {
+            eprintln!("Запись .cast прервана: {e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn header_and_events() {
+        let start = Instant::now();
+        let mut out = Vec::new();
+        let mut cast = Cast::start(&mut out, (80, 24), start).unwrap();
+        cast.output(start + Duration::from_millis(16), "\x1b[1;1H┌\"\\".as_bytes()).unwrap();
+        cast.resize(start + Duration::from_millis(20), (80, 24)).unwrap();
+        cast.resize(start + Duration::from_millis(1500), (100, 30)).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert!(lines[0].starts_with("{\"version\":2,\"width\":80,\"height\":24,\"timestamp\":"), "{}", lines[0]);
+        assert_eq!(&lines[1..], [
+            "[0.000000,\"o\",\"\\u001b[H\\u001b[2J\"]",
+            "[0.016000,\"o\",\"\\u001b[1;1H┌\\\"\\\\\"]",
+            "[1.500000,\"r\",\"100x30\"]",
+        ]);
+    }
+}
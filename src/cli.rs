@@ -0,0 +1,554 @@
+//! Параметры командной строки. Всё, что задаётся при запуске, собирается в `Options`.
+
+use std::{fmt, path::PathBuf, time::Duration};
+
+use crate::{
+    game::{Game, Mode},
+    level::Level,
+    profile::{self, NAME_MAX},
+    sim::POLICIES,
+    theme::{BorderStyle, ColorSupport},
+};
+
+/// Справка для `--help`
+pub const USAGE: &str = "\
+Змейка для терминала
+
+Использование: zmiy [ПАРАМЕТРЫ]
+
+Игра:
+  --speed N           скорость змейки, клеток в секунду (1–100)
+  --size ШxВ          поле фиксированного размера вместо всего терминала
+  --seed N            сид генератора еды: одинаковый сид — одинаковая еда
+  --length N          длина змейки в начале партии (1–10)
+  --growth N          на сколько клеток змейка растёт за еду (0–5); 0 — не растёт
+  --mode РЕЖИМ        режим игры: classic, two-food (две еды: красная растит,
+                      синяя даёт 2 очка без роста), two-rooms (две комнаты
+                      с проходом между ними), nokia (как на старом телефоне:
+                      поле 20x11, скорость 1–9, еда стоит столько очков, какая
+                      скорость) или endless (каждые 20 еды правила мутируют:
+                      бомбы, ускорение, управление наоборот...); режим можно
+                      выбрать и на заставке клавишей Tab
+  --level ФАЙЛ        играть уровень из файла .zlv: поле со стенами-комнатами
+                      (на заставке: E — нарисовать уровень, L — свои уровни)
+  --ghost             показать на поле призрак лучшей записанной партии (см. --record)
+
+Вид:
+  --color ПАЛИТРА     truecolor, 256 или 16 (по умолчанию — по терминалу)
+  --no-color          без цветов, как при NO_COLOR
+  --ascii             только ASCII-символы на поле и в его рамке
+  --border СТИЛЬ      рамка поля: plain, rounded, double, thick или none
+
+Файлы:
+  --config ФАЙЛ       файл настроек вместо ~/.config/zmiy/config.toml
+  --profile ИМЯ       играть профилем ИМЯ: свои настройки, рекорды, статистика,
+                      сохранение и повторы; нового профиля заводится папка
+  --profiles          перед игрой открыть экран профилей: выбрать, создать,
+                      переименовать или удалить
+  --write-default-config
+                      напечатать файл настроек по умолчанию и выйти
+  --record            записывать партии в ~/.local/share/zmiy/replays
+  --play ФАЙЛ         посмотреть повтор
+  --record-cast ФАЙЛ  записать всё, что видно на экране, в ФАЙЛ для asciinema play
+  --dump-on-death     при смерти змейки писать состояние партии в zmiy-dump-*.json
+                      (в игре то же делает F9)
+  --debug             писать журнал в ~/.local/state/zmiy/zmiy.log
+                      (подробность задаёт ZMIY_LOG=info|debug|trace);
+                      в игре F8 включает пошаговый режим, «.» делает шаг
+
+По сети:
+  --host АДРЕС        открыть игру, например 0.0.0.0:7777, и ждать соперника;
+                      перед партией можно дать фору: длину, скорость или щит
+  --join АДРЕС        подключиться к игре, например 192.168.1.5:7777
+  --broadcast АДРЕС   показывать свою игру зрителям, например :7878
+  --spectate АДРЕС    смотреть чужую игру, например 192.168.1.5:7878
+
+Бот:
+  --bot-io            змейку ведёт внешняя программа: состояние в её stdin строкой JSON,
+                      ответ — строка UP, DOWN, LEFT, RIGHT или PASS
+  --bot-cmd КОМАНДА   команда бота, например \"python mybot.py\"
+  --bot-timeout МС    сколько ждать ответа, потом ход пропускается (по умолчанию 200)
+  --control СОКЕТ     команды через Unix-сокет, например /tmp/zmiy.sock: pause, resume,
+                      dir up|down|left|right, restart, state, quit
+
+Без терминала:
+  --simulate N        сыграть N партий встроенной стратегией и напечатать сводку
+  --policy ИМЯ        стратегия для --simulate: greedy
+  --stats             напечатать лучший счёт, число партий за сегодня
+                      и статистику за всё время
+  --json              сводка --simulate или --stats одной строкой JSON
+
+  --help              эта справка
+  --version           версия
+";
+
+/// Параметры запуска
+#[derive(Default, Debug, PartialEq)]
+pub struct Options {
+    /// Интервал между тиками из `--speed`
+    pub tick: Option<Duration>,
+    /// Размер поля из `--size`; `None` — поле во весь терминал
+    pub size: Option<(u16, u16)>,
+    pub seed: Option<u64>,
+    /// Длина змейки в начале партии из `--length`
+    pub length: Option<usize>,
+    /// Рост за еду из `--growth`
+    pub growth: Option<usize>,
+    pub mode: Mode,
+    /// Уровень из `--level` или встроенный уровень режима; размер поля — его
+    pub level: Option<Level>,
+    pub color: Option<ColorSupport>,
+    pub no_color: bool,
+    pub ascii: bool,
+    pub border: Option<BorderStyle>,
+    pub config: Option<PathBuf>,
+    /// Профиль из `--profile`; `None` — выбранный на экране профилей или по умолчанию
+    pub profile: Option<String>,
+    /// Открыть экран профилей перед игрой
+    pub profiles: bool,
+    pub write_default_config: bool,
+    /// Напечатать статистику за всё время
+    pub stats: bool,
+    pub record: bool,
+    /// Показывать призрак лучшего повтора
+    pub ghost: bool,
+    /// Писать дамп партии при смерти змейки
+    pub dump_on_death: bool,
+    pub play: Option<PathBuf>,
+    /// Файл записи экрана для asciinema
+    pub record_cast: Option<PathBuf>,
+    pub debug: bool,
+    /// Сколько партий сыграть без терминала
+    pub simulate: Option<u64>,
+    /// Стратегия для `--simulate`; `None` — первая из `sim::POLICIES`
+    pub policy: Option<String>,
+    /// Сводка `--simulate` или `--stats` одной строкой JSON
+    pub json: bool,
+    /// Адрес, на котором ждать соперника
+    pub host: Option<String>,
+    /// Адрес хоста, к которому подключиться
+    pub join: Option<String>,
+    /// Адрес, на котором ждать зрителей
+    pub broadcast: Option<String>,
+    /// Адрес трансляции, которую смотреть
+    pub spectate: Option<String>,
+    pub bot_io: bool,
+    /// Команда внешнего бота для `--bot-io`
+    pub bot_cmd: Option<String>,
+    pub bot_timeout: Option<Duration>,
+    /// Unix-сокет для команд управления
+    pub control: Option<PathBuf>,
+    pub help: bool,
+    pub version: bool,
+    /// `--panic-test`: паника сразу после входа в полноэкранный режим, чтобы проверить,
+    /// что терминал восстанавливается. Есть только в отладочной сборке.
+    pub panic_test: bool,
+}
+
+/// Ошибка в параметрах командной строки
+#[derive(Debug, PartialEq, Eq)]
+pub struct CliError(pub String);
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (см. zmiy --help)", self.0)
+    }
+}
+
+impl std::error::Error for CliError {}
+
+/// Наименьшее поле, на котором можно играть
+pub const MIN_SIZE: (u16, u16) = (5, 5);
+
+/// Уровень и размер поля, которые режим задаёт сам
+fn own_board(mode: Mode) -> (Option<Level>, Option<(u16, u16)>) {
+    let level = Level::built_in(mode);
+    let size = level.as_ref().map(|l| (l.board.x, l.board.y)).or(mode.board());
+    (level, size)
+}
+
+fn parse_size(value: &str) -> Option<(u16, u16)> {
+    let (w, h) = value.split_once(['x', 'х', 'X', '×'])?;
+    Some((w.trim().parse().ok()?, h.trim().parse().ok()?))
+}
+
+impl Options {
+    /// Разбирает параметры (без имени программы). Значения пишутся как `--name value` или `--name=value`.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, CliError> {
+        let mut options = Options::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let (name, inline) = match arg.split_once('=') {
+                Some((name, value)) if name.starts_with("--") => (name.to_string(), Some(value.to_string())),
+                _ => (arg.clone(), None),
+            };
+            let mut value = || {
+                inline.clone().or_else(|| args.next()).ok_or_else(|| CliError(format!("{name}: нужно значение")))
+            };
+            let bad = |expected: &str| CliError(format!("{name}: ожидается {expected}"));
+            match name.as_str() {
+                "--speed" => {
+                    let speed: f64 = value()?.parse().map_err(|_| bad("число"))?;
+                    if !(1.0..=100.0).contains(&speed) {
+                        return Err(bad("от 1 до 100 клеток в секунду"));
+                    }
+                    // Целые миллисекунды: так интервал пишется в сохранения и повторы,
+                    // а от него зависит цена еды с очками за риск
+                    options.tick = Some(Duration::from_millis((1000.0 / speed).round() as u64));
+                }
+                "--size" => {
+                    let (w, h) = parse_size(&value()?).ok_or_else(|| bad("размер вида 40x20"))?;
+                    if w < MIN_SIZE.0 || h < MIN_SIZE.1 {
+                        return Err(bad(&format!("поле не меньше {}x{}", MIN_SIZE.0, MIN_SIZE.1)));
+                    }
+                    options.size = Some((w, h));
+                }
+                "--seed" => options.seed = Some(value()?.parse().map_err(|_| bad("целое число"))?),
+                "--length" => {
+                    let length = value()?.parse().ok().filter(|n| (1..=Game::MAX_START_LENGTH).contains(n)).ok_or_else(|| bad("от 1 до 10"))?;
+                    options.length = Some(length);
+                }
+                "--growth" => {
+                    let growth = value()?.parse().ok().filter(|&n| n <= Game::MAX_GROWTH_PER_FOOD).ok_or_else(|| bad("от 0 до 5"))?;
+                    options.growth = Some(growth);
+                }
+                "--mode" => {
+                    options.mode = Mode::from_arg(&value()?).ok_or_else(|| bad(&format!("один из режимов: {}", Mode::ARGS.join(", "))))?;
+                }
+                "--level" => {
+                    let path = PathBuf::from(value()?);
+                    options.level = Some(Level::load(&path).map_err(|e| CliError(format!("{name}: {}: {e}", path.display())))?);
+                }
+                "--color" => options.color = Some(ColorSupport::parse(&value()?).ok_or_else(|| bad("truecolor, 256 или 16"))?),
+                "--border" => {
+                    options.border = Some(BorderStyle::parse(&value()?).ok_or_else(|| bad("plain, rounded, double, thick или none"))?);
+                }
+                "--config" => options.config = Some(PathBuf::from(value()?)),
+                "--profile" => {
+                    let name = value()?;
+                    profile::check_name(&name).map_err(|_| bad(&format!("имя от 1 до {NAME_MAX} букв, цифр, «-» или «_»")))?;
+                    options.profile = Some(name);
+                }
+                "--play" => options.play = Some(PathBuf::from(value()?)),
+                "--record-cast" => options.record_cast = Some(PathBuf::from(value()?)),
+                "--control" => options.control = Some(PathBuf::from(value()?)),
+                "--host" => options.host = Some(value()?),
+                "--join" => options.join = Some(value()?),
+                "--broadcast" => options.broadcast = Some(value()?),
+                "--spectate" => options.spectate = Some(value()?),
+                "--bot-cmd" => options.bot_cmd = Some(value()?),
+                "--bot-timeout" => {
+                    let ms = value()?.parse().ok().filter(|ms| (1..=10_000).contains(ms)).ok_or_else(|| bad("от 1 до 10000 мс"))?;
+                    options.bot_timeout = Some(Duration::from_millis(ms));
+                }
+                "--simulate" => {
+                    let games = value()?.parse().ok().filter(|&n| n > 0).ok_or_else(|| bad("число партий больше нуля"))?;
+                    options.simulate = Some(games);
+                }
+                "--policy" => {
+                    let name = value()?;
+                    if !POLICIES.contains(&name.as_str()) {
+                        return Err(bad(&format!("одна из стратегий: {}", POLICIES.join(", "))));
+                    }
+                    options.policy = Some(name);
+                }
+                "--no-color" | "--ascii" | "--record" | "--ghost" | "--profiles" | "--dump-on-death" | "--debug" | "--json" | "--bot-io" | "--write-default-config"
+                | "--stats" | "--help" | "-h" | "--version" | "--panic-test"
+                    if inline.is_some() =>
+                {
+                    return Err(CliError(format!("{name}: значение не нужно")));
+                }
+                "--no-color" => options.no_color = true,
+                "--ascii" => options.ascii = true,
+                "--record" => options.record = true,
+                "--ghost" => options.ghost = true,
+                "--profiles" => options.profiles = true,
+                "--dump-on-death" => options.dump_on_death = true,
+                "--debug" => options.debug = true,
+                "--json" => options.json = true,
+                "--bot-io" => options.bot_io = true,
+                "--write-default-config" => options.write_default_config = true,
+                "--stats" => options.stats = true,
+                "--help" | "-h" => options.help = true,
+                "--version" => options.version = true,
+                "--panic-test" if cfg!(debug_assertions) => options.panic_test = true,
+                _ => return Err(CliError(format!("неизвестный параметр {arg}"))),
+            }
+        }
+        if options.level.is_some() && options.mode != Mode::Classic {
+            return Err(CliError("--level и --mode вместе не работают: у уровня свои правила".to_string()));
+        }
+        if options.level.is_none() {
+            options.level = Level::built_in(options.mode);
+        }
+        if let Some(level) = &options.level {
+            if options.size.is_some() {
+                return Err(CliError(format!("--size не нужен: поле уровня «{}» — {}x{}", level.name, level.board.x, level.board.y)));
+            }
+            options.size = Some((level.board.x, level.board.y));
+        }
+        if let Some((w, h)) = options.mode.board() {
+            if options.size.is_some() {
+                return Err(CliError(format!("--size не нужен: поле режима «{}» — {w}x{h}", options.mode.name())));
+            }
+            options.size = Some((w, h));
+        }
+        if options.record && options.play.is_some() {
+            return Err(CliError("--record и --play вместе не работают".to_string()));
+        }
+        let modes = [
+            options.play.is_some(),
+            options.host.is_some(),
+            options.join.is_some(),
+            options.spectate.is_some(),
+            options.bot_io,
+            options.simulate.is_some(),
+        ];
+        if modes.into_iter().filter(|&on| on).count() > 1 {
+            return Err(CliError("--play, --host, --join, --spectate, --bot-io и --simulate вместе не работают".to_string()));
+        }
+        if options.broadcast.is_some() && modes[..4].contains(&true) {
+            return Err(CliError("--broadcast показывает только обычную игру".to_string()));
+        }
+        if options.control.is_some() && !cfg!(unix) {
+            return Err(CliError("--control есть только в Unix".to_string()));
+        }
+        if options.control.is_some() && (modes[..4].contains(&true) || options.simulate.is_some()) {
+            return Err(CliError("--control управляет только обычной игрой".to_string()));
+        }
+        if options.profiles && options.profile.is_some() {
+            return Err(CliError("--profile и --profiles вместе не работают".to_string()));
+        }
+        if options.profiles && (modes.contains(&true) || options.stats) {
+            return Err(CliError("--profiles открывает экран профилей только перед обычной игрой".to_string()));
+        }
+        if options.ghost && modes.contains(&true) {
+            return Err(CliError("--ghost показывает призрак только в обычной игре".to_string()));
+        }
+        if options.record && (options.host.is_some() || options.join.is_some()) {
+            return Err(CliError("--record в игре по сети не работает".to_string()));
+        }
+        if options.simulate.is_none() && options.policy.is_some() {
+            return Err(CliError("--policy работает только с --simulate".to_string()));
+        }
+        if options.simulate.is_none() && !options.stats && options.json {
+            return Err(CliError("--json работает только с --simulate или --stats".to_string()));
+        }
+        if options.bot_io != options.bot_cmd.is_some() || (options.bot_timeout.is_some() && !options.bot_io) {
+            return Err(CliError("--bot-io и --bot-cmd задаются вместе: --bot-io --bot-cmd \"python mybot.py\"".to_string()));
+        }
+        Ok(options)
+    }
+
+    /// Обычная игра: не просмотр повтора, не игра по сети, не бот и не симуляция
+    pub fn solo(&self) -> bool {
+        self.play.is_none()
+            && self.host.is_none()
+            && self.join.is_none()
+            && self.spectate.is_none()
+            && !self.bot_io
+            && self.simulate.is_none()
+    }
+
+    /// Название режима в таблице рекордов и файлах: у уровня — его название
+    pub fn mode_name(&self) -> &str {
+        self.level.as_ref().map_or(self.mode.name(), |level| &level.name)
+    }
+
+    /// Проверяет, что поле `--size` с рамкой и строкой статуса помещается в терминал
+    pub fn check_terminal(&self, columns: u16, rows: u16) -> Result<(), CliError> {
+        match self.size {
+            Some((w, h)) if (w * self.mode.cell_width()).saturating_add(2) > columns || h.saturating_add(3) > rows => {
+                Err(CliError(format!(
+                    "--size {w}x{h} не помещается в терминал {columns}x{rows}: нужно хотя бы {}x{}",
+                    w * self.mode.cell_width() + 2,
+                    h + 3
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Режим можно сменить на заставке: уровень и размер поля не заданы в командной строке
+    pub fn can_switch_mode(&self) -> bool {
+        (self.level.clone(), self.size) == own_board(self.mode)
+    }
+
+    /// Меняет режим, как `--mode`: уровень и поле становятся теми, что у нового режима.
+    /// Если режим сменить нельзя ([`Self::can_switch_mode`]), возвращает `false`.
+    pub fn switch_mode(&mut self, mode: Mode) -> bool {
+        if !self.can_switch_mode() {
+            return false;
+        }
+        (self.level, self.size) = own_board(mode);
+        self.mode = mode;
+        true
+    }
+
+    /// Играть уровень, как `--level`: режим классический, поле — поле уровня.
+    /// Как и [`Self::switch_mode`], работает, только если режим можно сменить.
+    pub fn use_level(&mut self, level: Level) -> bool {
+        if !self.switch_mode(Mode::Classic) {
+            return false;
+        }
+        self.size = Some((level.board.x, level.board.y));
+        self.level = Some(level);
+        true
+    }
+
+    /// Новая партия с сидом, скоростью и длиной змейки из параметров. `speed` и `length` —
+    /// интервал и длина из настроек, если в командной строке и уровне они не заданы.
+    pub fn new_game(&self, width: u16, height: u16, speed: Option<Duration>, length: usize) -> Game {
+        let (width, height) = self.size.unwrap_or((width, height));
+        let mut game = match self.seed {
+            Some(seed) => Game::with_seed(width, height, seed),
+            None => Game::new(width, height),
+        };
+        // Скорость уровня важнее настроек, но не `--speed`
+        let level_speed = self.level.as_ref().and_then(|level| level.speed);
+        game.base_tick = self.tick.or(level_speed).or(speed).unwrap_or(Game::BASE_TICK);
+        self.mode.setup(&mut game);
+        if let Some(level) = &self.level {
+            level.apply(&mut game);
+        }
+        game.set_start_length(self.length.unwrap_or(length));
+        game
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options, CliError> {
+        Options::parse(args.iter().map(|a| a.to_string()))
+    }
+
+    #[test]
+    fn parses_options() {
+        let options = parse(&["--speed", "10", "--size=30x15", "--seed", "7", "--ascii", "--border", "none", "--debug"]).unwrap();
+        assert_eq!(options.tick, Some(Duration::from_millis(100)));
+        // Интервал округляется до целых миллисекунд
+        assert_eq!(parse(&["--speed", "7"]).unwrap().tick, Some(Duration::from_millis(143)));
+        assert_eq!(options.size, Some((30, 15)));
+        assert_eq!(options.seed, Some(7));
+        assert!(options.ascii && options.debug);
+        assert_eq!(parse(&["--panic-test"]).is_ok_and(|o| o.panic_test), cfg!(debug_assertions));
+        assert!(parse(&["--stats"]).unwrap().stats);
+        assert_eq!(parse(&["--record-cast", "run.cast"]).unwrap().record_cast, Some(PathBuf::from("run.cast")));
+        assert_eq!(options.border, Some(BorderStyle::None));
+        assert_eq!(options.mode, Mode::Classic);
+        assert_eq!(parse(&[]).unwrap(), Options::default());
+    }
+
+    #[test]
+    fn readable_errors() {
+        let err = |args: &[&str]| parse(args).unwrap_err().0;
+        assert_eq!(err(&["--speed"]), "--speed: нужно значение");
+        assert_eq!(err(&["--speed", "0"]), "--speed: ожидается от 1 до 100 клеток в секунду");
+        assert_eq!(err(&["--size", "big"]), "--size: ожидается размер вида 40x20");
+        assert_eq!(err(&["--size", "2x2"]), "--size: ожидается поле не меньше 5x5");
+        assert_eq!(err(&["--length", "11"]), "--length: ожидается от 1 до 10");
+        assert_eq!(err(&["--growth", "-1"]), "--growth: ожидается от 0 до 5");
+        assert_eq!(parse(&["--growth", "0"]).unwrap().growth, Some(0));
+        assert_eq!(err(&["--mode", "wrap"]), "--mode: ожидается один из режимов: classic, two-food, two-rooms, nokia, endless");
+        assert_eq!(err(&["--fast"]), "неизвестный параметр --fast");
+        assert_eq!(err(&["--ascii=yes"]), "--ascii: значение не нужно");
+        assert_eq!(err(&["--record", "--play", "x.zrp"]), "--record и --play вместе не работают");
+        assert_eq!(err(&["--simulate", "0"]), "--simulate: ожидается число партий больше нуля");
+        assert_eq!(err(&["--simulate", "5", "--policy", "smart"]), "--policy: ожидается одна из стратегий: greedy");
+        assert_eq!(err(&["--host", ":7777", "--join", "x:7777"]), "--play, --host, --join, --spectate, --bot-io и --simulate вместе не работают");
+        assert_eq!(err(&["--broadcast", ":7878", "--join", "x:7777"]), "--broadcast показывает только обычную игру");
+        assert_eq!(err(&["--control", "/tmp/z.sock", "--host", ":7777"]), "--control управляет только обычной игрой");
+        assert_eq!(err(&["--ghost", "--bot-io", "--bot-cmd", "./bot"]), "--ghost показывает призрак только в обычной игре");
+        assert_eq!(err(&["--profile", "../x"]), "--profile: ожидается имя от 1 до 16 букв, цифр, «-» или «_»");
+        assert_eq!(err(&["--profile", "оля", "--profiles"]), "--profile и --profiles вместе не работают");
+        assert_eq!(err(&["--profiles", "--stats"]), "--profiles открывает экран профилей только перед обычной игрой");
+        assert_eq!(parse(&["--profile=оля"]).unwrap().profile.as_deref(), Some("оля"));
+        assert_eq!(err(&["--json"]), "--json работает только с --simulate или --stats");
+        assert_eq!(err(&["--policy", "greedy"]), "--policy работает только с --simulate");
+        assert!(parse(&["--stats", "--json"]).unwrap().json);
+        assert_eq!(err(&["--bot-io"]), "--bot-io и --bot-cmd задаются вместе: --bot-io --bot-cmd \"python mybot.py\"");
+        assert_eq!(err(&["--bot-cmd", "./bot"]), "--bot-io и --bot-cmd задаются вместе: --bot-io --bot-cmd \"python mybot.py\"");
+    }
+
+    #[test]
+    fn size_must_fit_terminal() {
+        let options = parse(&["--size", "40x20"]).unwrap();
+        assert!(options.check_terminal(42, 23).is_ok());
+        assert_eq!(
+            options.check_terminal(41, 30).unwrap_err().0,
+            "--size 40x20 не помещается в терминал 41x30: нужно хотя бы 42x23"
+        );
+        // Клетки режима «нокиа» вдвое шире
+        let nokia = parse(&["--mode", "nokia"]).unwrap();
+        assert!(nokia.check_terminal(42, 14).is_ok() && nokia.check_terminal(41, 14).is_err());
+    }
+
+    #[test]
+    fn nokia_mode_has_its_own_board() {
+        let mut options = parse(&["--mode", "nokia", "--seed", "3"]).unwrap();
+        assert_eq!(options.size, Some(Mode::NOKIA_BOARD));
+        let parse_err = parse(&["--mode", "nokia", "--size", "30x15"]).unwrap_err().0;
+        assert_eq!(parse_err, "--size не нужен: поле режима «нокиа» — 20x11");
+        // Скорость — уровень от 1 до 9, еда стоит столько очков, какой уровень
+        options.tick = Some(Mode::NOKIA_TICKS[1]);
+        let game = options.new_game(80, 24, None, 1);
+        assert_eq!((game.width, game.height, game.score_multiplier), (20, 11, 2));
+    }
+
+    #[test]
+    fn mode_switches_with_its_board() {
+        let mut options = parse(&[]).unwrap();
+        assert!(options.switch_mode(Mode::Nokia));
+        assert_eq!((options.mode, options.size), (Mode::Nokia, Some((20, 11))));
+        assert!(options.switch_mode(Mode::TwoRooms));
+        assert_eq!((options.size, options.level.as_ref().map(|l| l.name.as_str())), (Some((40, 20)), Some("две комнаты")));
+        assert!(options.switch_mode(Mode::Classic));
+        assert_eq!((options.size, options.level.is_some()), (None, false));
+        // Поле из командной строки режим не меняет
+        let mut sized = parse(&["--size", "30x15"]).unwrap();
+        assert!(!sized.switch_mode(Mode::Nokia));
+        assert_eq!((sized.mode, sized.size), (Mode::Classic, Some((30, 15))));
+        // Нарисованный уровень играется классикой на своём поле
+        let level = Level::built_in(Mode::TwoRooms).unwrap();
+        assert!(!sized.use_level(level.clone()));
+        assert!(options.switch_mode(Mode::Nokia) && options.use_level(Level { speed: Some(Duration::from_millis(70)), ..level }));
+        assert_eq!((options.mode, options.size, options.mode_name()), (Mode::Classic, Some((40, 20)), "две комнаты"));
+        // Скорость уровня важнее скорости из настроек
+        assert_eq!(options.new_game(80, 24, Some(Duration::from_millis(50)), 3).base_tick, Duration::from_millis(70));
+    }
+
+    #[test]
+    fn new_game_uses_seed_and_size() {
+        let options = parse(&["--seed", "3", "--size", "12x8"]).unwrap();
+        let game = options.new_game(80, 24, Some(Duration::from_millis(50)), 3);
+        assert_eq!((game.width, game.height, game.seed), (12, 8, 3));
+        assert_eq!((game.base_tick, game.snake().len()), (Duration::from_millis(50), 3));
+        // `--length` важнее длины из настроек
+        let options = parse(&["--seed", "3", "--size", "12x8", "--length", "5"]).unwrap();
+        assert_eq!(options.new_game(80, 24, None, 3).snake().len(), 5);
+    }
+
+    #[test]
+    fn level_sets_the_board() {
+        let options = parse(&["--mode", "two-rooms", "--seed", "3"]).unwrap();
+        assert_eq!((options.size, options.mode_name()), (Some((40, 20)), "две комнаты"));
+        let game = options.new_game(80, 24, None, 1);
+        assert_eq!((game.width, game.height, game.walls().len()), (40, 20, 19));
+        assert_eq!(parse(&[]).unwrap().mode_name(), "классика");
+
+        let err = |args: &[&str]| parse(args).unwrap_err().0;
+        assert_eq!(err(&["--mode", "two-rooms", "--size", "30x15"]), "--size не нужен: поле уровня «две комнаты» — 40x20");
+        assert!(err(&["--level", "/нет/такого.zlv"]).starts_with("--level: /нет/такого.zlv: "));
+
+        let path = std::env::temp_dir().join(format!("zmiy-cli-level-{}.zlv", std::process::id()));
+        let rows: String = (0..5).map(|y| if y == 0 { "row #....\n" } else { "row .....\n" }).collect();
+        std::fs::write(&path, format!("zmiy-level 1\nname угол\nboard 5 5\n{rows}")).unwrap();
+        let level = path.to_str().unwrap();
+        let options = parse(&["--level", level]).unwrap();
+        assert_eq!((options.size, options.mode_name()), (Some((5, 5)), "угол"));
+        assert_eq!(err(&["--level", level, "--mode", "two-food"]), "--level и --mode вместе не работают: у уровня свои правила");
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,626 @@
+//! Файл настроек `~/.config/zmiy/config.toml`.
+//!
+//! Понимается подмножество TOML, которого хватает для настроек: пары `ключ = значение`
+//! со строками в кавычках, целыми числами и `true`/`false`, секция `[keys]` и комментарии `#`.
+//! Все поля необязательны: чего нет в файле, берётся по умолчанию.
+//!
+//! Пока игра идёт, файл перечитывается, если изменился (см. [`Watcher`]): палитра,
+//! рамка, звук, сетка, шкала длины и клавиши меняются сразу, а скорость и правило
+//! «скорость от длины» — со следующей партии.
+
+use std::{
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+
+use crossterm::event::KeyCode;
+
+use crate::{
+    game::Game,
+    input::{key_label, parse_key, Action, Keymap, ACTION_NAMES},
+    paths::config_dir,
+    settings::Settings,
+    theme::{BorderStyle, ColorSupport},
+};
+
+/// Файл настроек по умолчанию
+pub fn path() -> Option<PathBuf> {
+    Some(config_dir()?.join("config.toml"))
+}
+
+/// Файл настроек со всеми полями и значениями по умолчанию, для `--write-default-config`
+pub const DEFAULT: &str = r#"# Настройки змейки. Все поля необязательны.
+
+# Интервал между шагами змейки в миллисекундах; меньше — быстрее
+tick_ms = 120
+# Скорость от длины: каждая клетка змейки сверх первой укорачивает шаг на 1,5 мс,
+# но не меньше чем до 40 мс. Укоротившаяся змейка снова замедляется.
+length_speedup = false
+# Дальняя еда: из скольких случайных свободных клеток еда выбирает самую далёкую
+# от головы (1–16). 1 — еда появляется где угодно; больше — дальше и сложнее.
+food_candidates = 1
+# Рост за еду (0–5 клеток). 0 — змейка не растёт вовсе: игра только на очки
+# и скорость, поле не заполняется. Перекрывается --growth.
+growth_per_food = 1
+# Длина змейки в начале партии (1–10): тело тянется по прямой за головой
+# и обрезается, если до стены не хватает места. Перекрывается --length.
+start_length = 1
+# Затмения: раз в 300 шагов поле гаснет на 5 шагов, видны только голова и рамка.
+# За 3 шага до этого рамка мигает.
+blackouts = false
+# Зоны скорости: на поле появляются пятна, где шаг на 30% короче или длиннее.
+# На уровне со своими зонами пятна не добавляются.
+speed_zones = false
+# Бомбы: со счёта 10 вместе с едой появляется красная @. Съесть её — конец партии,
+# а через 60 шагов она пропадает сама и перед этим мигает.
+bombs = false
+# Очки за риск: еда стоит 120 мс / текущий шаг с округлением вверх. На скорости
+# вдвое выше обычной — 2 очка за еду, в быстрой зоне и с длинной змейкой — больше.
+risk_scoring = false
+# Бонус у края: за каждые 40 шагов головой в клетке у внешней стены — очко.
+# Смерть обнуляет накопленные шаги.
+edge_bonus = false
+# Боссы: каждые 50 очков вместо еды появляется босс 2×2. Его съедают за три касания
+# головой, заходя в него снаружи; за него 10 очков и 3 клетки роста.
+boss_food = false
+# Гусеницы: иногда вместе с едой появляется прямая линия из 4 звеньев. Съешь их
+# по порядку с любого конца — по очку за звено и ещё 3 сверху. Укус в середину
+# рассыпает остальные звенья.
+caterpillars = false
+# Таяние счёта: каждые 100 шагов без еды счёт уменьшается на очко, но не ниже нуля.
+# Пока счёт тает, рядом с ним стрелка ↓.
+score_decay = false
+# След слизи: клетка, которую освободил хвост, 20 шагов остаётся скользкой. Голова
+# на слизи не гибнет, но следующий шаг вдвое дольше.
+slime_trail = false
+# Порталы: иногда вместе с едой появляется портал. Вошедшая в него змейка целиком
+# переносится в случайное свободное место не ближе 5 клеток и вытягивается в линию.
+teleports = false
+# Щиты: иногда вместе с едой появляется щит. Подобранный щит спасает от одного
+# столкновения: змейка вместо гибели шаг стоит на месте. Щит бывает только один.
+shields = false
+# Удвоители: иногда вместе с едой появляется удвоитель. Подобранный удвоитель на 100
+# шагов удваивает все очки; следующий продлевает удвоение ещё на 100 шагов.
+doubling = false
+# Заморозки: иногда вместе с едой появляется заморозка, если на поле есть бомбы или
+# убегающая еда. Подобранная заморозка на 50 шагов останавливает их; змейка, голод
+# и таяние счёта идут как обычно.
+freezing = false
+# Случайное начало: змейка начинает в случайной клетке не ближе 5 к стене и смотрит
+# в случайную сторону. С одним сидом (--seed) начало одно и то же.
+random_start = false
+
+# Палитра: "truecolor", "256" или "16". Без этого поля определяется по терминалу.
+# color = "256"
+
+# Рамка поля: "plain", "rounded", "double", "thick" или "none"
+border = "plain"
+
+# Звук через терминальный звонок
+sound = false
+# Фоновая сетка из точек
+grid = false
+# Шкала длины змейки в строке статуса
+length_gauge = true
+# Без анимаций
+reduced_motion = false
+
+# Адрес онлайн-таблицы: результаты, попавшие в таблицу рекордов, уходят туда
+# POST-запросом. Без адреса ничего не отправляется. Нужна сборка с --features leaderboard.
+# leaderboard_url = "http://example.org/zmiy/scores"
+
+# Клавиши: одна буква, "up", "down", "left", "right", "esc", "space",
+# "enter", "tab", "backspace" или "f1".."f12". Каждому действию — одна клавиша.
+[keys]
+up = "up"
+down = "down"
+left = "left"
+right = "right"
+pause = "esc"
+restart = "space"
+shed = "x"
+sound = "s"
+grid = "g"
+mutations = "m"
+scores = "h"
+screenshot = "f12"
+dump = "f9"
+report = "f10"
+help = "f1"
+"#;
+
+/// Как часто проверять, не изменился ли файл настроек
+pub const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Настройки из файла; `None` — поля в файле нет
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct Config {
+    pub tick: Option<Duration>,
+    /// Правило «скорость от длины»
+    pub length_speedup: Option<bool>,
+    /// Сколько клеток на выбор у еды (см. [`Game::food_candidates`])
+    pub food_candidates: Option<u32>,
+    /// Рост за еду (см. [`Game::growth_per_food`])
+    pub growth_per_food: Option<usize>,
+    /// Длина змейки в начале партии (см. [`Game::set_start_length`])
+    pub start_length: Option<usize>,
+    /// Затмения поля (см. [`Game::blackout`])
+    pub blackouts: Option<bool>,
+    /// Случайные пятна зон скорости (см. [`Game::scatter_zones`])
+    pub speed_zones: Option<bool>,
+    /// Бомбы рядом с едой (см. [`Game::bombs`])
+    pub bombs: Option<bool>,
+    /// Очки за риск (см. [`Game::food_value`])
+    pub risk_scoring: Option<bool>,
+    /// Бонус за игру у края (см. [`Game::edge_bonus`])
+    pub edge_bonus: Option<bool>,
+    /// Боссы каждые 50 очков (см. [`Game::bosses`])
+    pub boss_food: Option<bool>,
+    /// Гусеницы рядом с едой (см. [`Game::caterpillars`])
+    pub caterpillars: Option<bool>,
+    /// Таяние счёта без еды (см. [`Game::score_decay`])
+    pub score_decay: Option<bool>,
+    /// След слизи за хвостом (см. [`Game::slime_trail`])
+    pub slime_trail: Option<bool>,
+    /// Порталы рядом с едой (см. [`Game::teleports`])
+    pub teleports: Option<bool>,
+    /// Щиты рядом с едой (см. [`Game::shields`])
+    pub shields: Option<bool>,
+    /// Удвоители очков рядом с едой (см. [`Game::doubling`])
+    pub doubling: Option<bool>,
+    /// Заморозки рядом с едой (см. [`Game::freezing`])
+    pub freezing: Option<bool>,
+    /// Случайное начало змейки (см. [`Game::random_start`])
+    pub random_start: Option<bool>,
+    pub color: Option<ColorSupport>,
+    pub border: Option<BorderStyle>,
+    pub sound: Option<bool>,
+    pub grid: Option<bool>,
+    pub length_gauge: Option<bool>,
+    pub reduced_motion: Option<bool>,
+    /// Адрес онлайн-таблицы; `None` — отправка выключена
+    pub leaderboard_url: Option<String>,
+    /// Переназначенные клавиши в порядке файла
+    pub keys: Vec<(Action, KeyCode)>,
+}
+
+/// Ошибка в файле настроек
+#[derive(Debug, PartialEq, Eq)]
+pub struct ConfigError {
+    /// Номер строки с единицы; 0 — ошибка не в строке, а в самом файле
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.line == 0 {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "строка {}: {}", self.line, self.message)
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// Значение из файла
+enum Value {
+    Str(String),
+    Int(i64),
+    Bool(bool),
+}
+
+impl Value {
+    fn parse(raw: &str) -> Option<Value> {
+        match raw {
+            "true" => return Some(Value::Bool(true)),
+            "false" => return Some(Value::Bool(false)),
+            _ => {}
+        }
+        if let Some(inner) = raw.strip_prefix('"').and_then(|r| r.strip_suffix('"')) {
+            let mut out = String::new();
+            let mut chars = inner.chars();
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' => match chars.next()? {
+                        'n' => out.push('\n'),
+                        't' => out.push('\t'),
+                        c @ ('"' | '\\') => out.push(c),
+                        _ => return None,
+                    },
+                    '"' => return None,
+                    c => out.push(c),
+                }
+            }
+            return Some(Value::Str(out));
+        }
+        raw.replace('_', "").parse().ok().map(Value::Int)
+    }
+
+    fn kind(&self) -> &'static str {
+        match self {
+            Value::Str(_) => "строка",
+            Value::Int(_) => "число",
+            Value::Bool(_) => "true/false",
+        }
+    }
+}
+
+/// Убирает комментарий `#`, если он не внутри строки
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            '#' if !in_string => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+impl Config {
+    pub fn parse(text: &str) -> Result<Self, ConfigError> {
+        let mut config = Config::default();
+        let mut section = String::new();
+        for (i, raw) in text.lines().enumerate() {
+            let n = i + 1;
+            let err = |message: String| ConfigError { line: n, message };
+            let line = strip_comment(raw).trim();
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                section = name.trim().to_string();
+                if section != "keys" {
+                    return Err(err(format!("неизвестная секция [{section}]")));
+                }
+                continue;
+            }
+            let Some((key, raw_value)) = line.split_once('=') else {
+                return Err(err("ожидается ключ = значение".to_string()));
+            };
+            let key = key.trim();
+            let value = Value::parse(raw_value.trim())
+                .ok_or_else(|| err(format!("{key}: не удалось прочитать значение {}", raw_value.trim())))?;
+            let field = if section.is_empty() { key.to_string() } else { format!("{section}.{key}") };
+            let mismatch = |expected: &str| err(format!("{field}: ожидается {expected}, а здесь {}", value.kind()));
+            let bad = |expected: &str| err(format!("{field}: ожидается {expected}"));
+
+            if section == "keys" {
+                let action = ACTION_NAMES
+                    .iter()
+                    .find(|(name, _)| *name == key)
+                    .map(|(_, action)| *action)
+                    .ok_or_else(|| err(format!("неизвестное действие {field}")))?;
+                let Value::Str(name) = &value else { return Err(mismatch("строка")) };
+                let code = parse_key(name).ok_or_else(|| bad("название клавиши"))?;
+                config.keys.push((action, code));
+                continue;
+            }
+            match (key, &value) {
+                ("tick_ms", Value::Int(ms)) => {
+                    let ms = u64::try_from(*ms).ok().filter(|ms| (10..=2000).contains(ms)).ok_or_else(|| bad("от 10 до 2000"))?;
+                    config.tick = Some(Duration::from_millis(ms));
+                }
+                ("color", Value::Str(name)) => {
+                    config.color = Some(ColorSupport::parse(name).ok_or_else(|| bad("\"truecolor\", \"256\" или \"16\""))?);
+                }
+                ("border", Value::Str(name)) => {
+                    config.border = Some(BorderStyle::parse(name).ok_or_else(|| bad("plain, rounded, double, thick или none"))?);
+                }
+                ("length_speedup", Value::Bool(b)) => config.length_speedup = Some(*b),
+                ("blackouts", Value::Bool(b)) => config.blackouts = Some(*b),
+                ("speed_zones", Value::Bool(b)) => config.speed_zones = Some(*b),
+                ("bombs", Value::Bool(b)) => config.bombs = Some(*b),
+                ("risk_scoring", Value::Bool(b)) => config.risk_scoring = Some(*b),
+                ("edge_bonus", Value::Bool(b)) => config.edge_bonus = Some(*b),
+                ("boss_food", Value::Bool(b)) => config.boss_food = Some(*b),
+                ("caterpillars", Value::Bool(b)) => config.caterpillars = Some(*b),
+                ("score_decay", Value::Bool(b)) => config.score_decay = Some(*b),
+                ("slime_trail", Value::Bool(b)) => config.slime_trail = Some(*b),
+                ("teleports", Value::Bool(b)) => config.teleports = Some(*b),
+                ("shields", Value::Bool(b)) => config.shields = Some(*b),
+                ("doubling", Value::Bool(b)) => config.doubling = Some(*b),
+                ("freezing", Value::Bool(b)) => config.freezing = Some(*b),
+                ("random_start", Value::Bool(b)) => config.random_start = Some(*b),
+                ("growth_per_food", Value::Int(n)) => {
+                    let n = usize::try_from(*n)
+                        .ok()
+                        .filter(|&n| n <= Game::MAX_GROWTH_PER_FOOD)
+                        .ok_or_else(|| bad("от 0 до 5"))?;
+                    config.growth_per_food = Some(n);
+                }
+                ("start_length", Value::Int(n)) => {
+                    let n = usize::try_from(*n)
+                        .ok()
+                        .filter(|n| (1..=Game::MAX_START_LENGTH).contains(n))
+                        .ok_or_else(|| bad("от 1 до 10"))?;
+                    config.start_length = Some(n);
+                }
+                ("food_candidates", Value::Int(n)) => {
+                    let n = u32::try_from(*n).ok().filter(|n| (1..=16).contains(n)).ok_or_else(|| bad("от 1 до 16"))?;
+                    config.food_candidates = Some(n);
+                }
+                ("sound", Value::Bool(b)) => config.sound = Some(*b),
+                ("grid", Value::Bool(b)) => config.grid = Some(*b),
+                ("length_gauge", Value::Bool(b)) => config.length_gauge = Some(*b),
+                ("reduced_motion", Value::Bool(b)) => config.reduced_motion = Some(*b),
+                ("leaderboard_url", Value::Str(url)) => config.leaderboard_url = Some(url.clone()).filter(|u| !u.is_empty()),
+                ("tick_ms" | "food_candidates" | "growth_per_food" | "start_length", _) => return Err(mismatch("число")),
+                ("color" | "border" | "leaderboard_url", _) => return Err(mismatch("строка")),
+                (
+                    "length_speedup" | "blackouts" | "speed_zones" | "bombs" | "risk_scoring" | "edge_bonus" | "boss_food"
+                    | "caterpillars" | "score_decay" | "slime_trail" | "teleports" | "shields" | "doubling" | "freezing" | "random_start" | "sound"
+                    | "grid" | "length_gauge" | "reduced_motion",
+                    _,
+                ) => {
+                    return Err(mismatch("true/false"));
+                }
+                _ => return Err(err(format!("неизвестное поле {field}"))),
+            }
+        }
+        Ok(config)
+    }
+
+    /// Читает файл. Если `required` не задан, отсутствующий файл — просто настройки по умолчанию.
+    pub fn load(path: &Path, required: bool) -> Result<Self, ConfigError> {
+        match fs::read_to_string(path) {
+            Ok(text) => Self::parse(&text),
+            Err(e) if e.kind() == io::ErrorKind::NotFound && !required => Ok(Self::default()),
+            Err(e) => Err(ConfigError { line: 0, message: e.to_string() }),
+        }
+    }
+
+    /// Шаг правила «скорость от длины» для новой партии; `None` — правило выключено
+    pub fn length_speedup(&self) -> Option<Duration> {
+        self.length_speedup.unwrap_or(false).then_some(Game::LENGTH_SPEEDUP)
+    }
+
+    /// Сколько клеток на выбор у еды в новой партии
+    pub fn food_candidates(&self) -> u32 {
+        self.food_candidates.unwrap_or(1)
+    }
+
+    /// Длина змейки в начале новой партии
+    pub fn start_length(&self) -> usize {
+        self.start_length.unwrap_or(1)
+    }
+
+    /// Переносит заданные поля в настройки
+    pub fn apply(&self, settings: &mut Settings) {
+        let fields = [
+            (self.sound, &mut settings.sound),
+            (self.grid, &mut settings.grid),
+            (self.length_gauge, &mut settings.length_gauge),
+            (self.reduced_motion, &mut settings.reduced_motion),
+        ];
+        for (value, field) in fields {
+            if let Some(value) = value {
+                *field = value;
+            }
+        }
+        if let Some(border) = self.border {
+            settings.border = border;
+        }
+        if let Some(growth) = self.growth_per_food {
+            settings.growth_per_food = growth;
+        }
+    }
+
+    /// Переносит в настройки только поля, изменившиеся по сравнению с `old`: переключённое
+    /// в игре, например звук, остаётся как есть, пока его не поменяли в файле. Поле, убранное
+    /// из файла, возвращается к значению по умолчанию.
+    pub fn apply_changes(&self, old: &Config, settings: &mut Settings) -> Reload {
+        let defaults = Settings::default();
+        let fields = [
+            (self.sound, old.sound, defaults.sound, &mut settings.sound),
+            (self.grid, old.grid, defaults.grid, &mut settings.grid),
+            (self.length_gauge, old.length_gauge, defaults.length_gauge, &mut settings.length_gauge),
+            (self.reduced_motion, old.reduced_motion, defaults.reduced_motion, &mut settings.reduced_motion),
+        ];
+        for (new, old, default, field) in fields {
+            if new != old {
+                *field = new.unwrap_or(default);
+            }
+        }
+        if self.border != old.border {
+            settings.border = self.border.unwrap_or(defaults.border);
+        }
+        if self.growth_per_food != old.growth_per_food {
+            settings.growth_per_food = self.growth_per_food.unwrap_or(defaults.growth_per_food);
+        }
+        Reload {
+            color: self.color != old.color,
+            keys: self.keys != old.keys,
+            tick: self.tick != old.tick || self.length_speedup != old.length_speedup,
+        }
+    }
+
+    /// Таблица клавиш с заменами из файла
+    pub fn keymap(&self) -> Result<Keymap, ConfigError> {
+        let mut keymap = Keymap::default();
+        for &(action, key) in &self.keys {
+            keymap.rebind(action, key).map_err(|other| ConfigError {
+                line: 0,
+                message: format!(
+                    "[keys]: клавиша {} уже занята действием {}",
+                    key_label(key),
+                    ACTION_NAMES.iter().find(|(_, a)| *a == other).map_or("?", |(name, _)| name)
+                ),
+            })?;
+        }
+        Ok(keymap)
+    }
+}
+
+/// Что ещё поменялось в перечитанном файле, кроме настроек из [`Settings`]
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct Reload {
+    /// Палитра: тему надо построить заново
+    pub color: bool,
+    /// Клавиши: таблицу надо построить заново
+    pub keys: bool,
+    /// Скорость или правило «скорость от длины»: идущую партию они не меняют
+    pub tick: bool,
+}
+
+/// Следит за файлом настроек по времени изменения, проверяя его раз в [`WATCH_INTERVAL`]
+pub struct Watcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    next_check: Instant,
+}
+
+impl Watcher {
+    pub fn new(path: PathBuf, now: Instant) -> Self {
+        let modified = modified(&path);
+        Self { path, modified, next_check: now + WATCH_INTERVAL }
+    }
+
+    /// Сколько осталось до следующей проверки
+    pub fn until_next(&self, now: Instant) -> Duration {
+        self.next_check.saturating_duration_since(now)
+    }
+
+    /// Перечитанный файл, если он изменился с прошлой проверки. Ошибка в клавишах —
+    /// тоже ошибка файла: негодные настройки не должны заменить прежние. Удалённый
+    /// файл не считается изменением, игра остаётся с тем, что было.
+    pub fn poll(&mut self, now: Instant) -> Option<Result<Config, ConfigError>> {
+        if now < self.next_check {
+            return None;
+        }
+        self.next_check = now + WATCH_INTERVAL;
+        let modified = modified(&self.path);
+        if modified == self.modified {
+            return None;
+        }
+        self.modified = modified;
+        modified?;
+        Some(Config::load(&self.path, true).and_then(|config| config.keymap().map(|_| config)))
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_file_parses() {
+        let config = Config::parse(DEFAULT).unwrap();
+        assert_eq!(config.tick, Some(Duration::from_millis(120)));
+        assert_eq!(config.border, Some(BorderStyle::Plain));
+        assert_eq!(config.length_gauge, Some(true));
+        assert!(config.color.is_none());
+        assert!(config.leaderboard_url.is_none());
+        assert_eq!(config.length_speedup(), None);
+        assert_eq!(Config::parse("length_speedup = true").unwrap().length_speedup(), Some(Game::LENGTH_SPEEDUP));
+        assert_eq!(config.food_candidates(), 1);
+        assert_eq!(config.blackouts, Some(false));
+        assert_eq!(config.speed_zones, Some(false));
+        assert_eq!(config.bombs, Some(false));
+        assert_eq!(config.risk_scoring, Some(false));
+        assert_eq!(config.edge_bonus, Some(false));
+        assert_eq!(config.boss_food, Some(false));
+        assert_eq!(config.caterpillars, Some(false));
+        assert_eq!(config.score_decay, Some(false));
+        assert_eq!(config.slime_trail, Some(false));
+        assert_eq!(config.teleports, Some(false));
+        assert_eq!(config.shields, Some(false));
+        assert_eq!(config.doubling, Some(false));
+        assert_eq!(config.freezing, Some(false));
+        assert_eq!(config.random_start, Some(false));
+        assert_eq!(Config::parse("food_candidates = 4").unwrap().food_candidates(), 4);
+        assert_eq!(config.start_length(), 1);
+        assert_eq!(config.growth_per_food, Some(1));
+        assert_eq!(Config::parse("start_length = 5").unwrap().start_length(), 5);
+        // Файл по умолчанию оставляет привычные клавиши
+        let keymap = config.keymap().unwrap();
+        assert_eq!(keymap.action(KeyCode::F(1)), Some(Action::Help));
+        assert_eq!(keymap.action(KeyCode::Char(' ')), Some(Action::Restart));
+    }
+
+    #[test]
+    fn partial_file_and_keys() {
+        let config = Config::parse("grid = true # сетка\n\n[keys]\nup = \"w\"\nleft = \"a\"\n").unwrap();
+        let mut settings = Settings::default();
+        config.apply(&mut settings);
+        assert!(settings.grid);
+        assert!(settings.length_gauge);
+        let keymap = config.keymap().unwrap();
+        assert_eq!(keymap.action(KeyCode::Char('w')), Some(Action::Move(crate::game::DirectionSnake::Up)));
+        assert_eq!(keymap.action(KeyCode::Up), None);
+    }
+
+    #[test]
+    fn errors_name_line_and_field() {
+        let err = |text| Config::parse(text).err().unwrap().to_string();
+        assert_eq!(err("sound = true\ngrid = 1\n"), "строка 2: grid: ожидается true/false, а здесь число");
+        assert_eq!(err("\n\nspeed = 5"), "строка 3: неизвестное поле speed");
+        assert_eq!(err("tick_ms = 0"), "строка 1: tick_ms: ожидается от 10 до 2000");
+        assert_eq!(err("food_candidates = 17"), "строка 1: food_candidates: ожидается от 1 до 16");
+        assert_eq!(err("growth_per_food = 6"), "строка 1: growth_per_food: ожидается от 0 до 5");
+        assert_eq!(err("start_length = 0"), "строка 1: start_length: ожидается от 1 до 10");
+        assert_eq!(err("[keys]\njump = \"j\""), "строка 2: неизвестное действие keys.jump");
+        assert_eq!(err("[colors]"), "строка 1: неизвестная секция [colors]");
+        assert_eq!(err("border = \"wavy\""), "строка 1: border: ожидается plain, rounded, double, thick или none");
+        assert_eq!(err("color = 256"), "строка 1: color: ожидается строка, а здесь число");
+        let clash = Config::parse("[keys]\nup = \"s\"").unwrap().keymap().err().unwrap();
+        assert_eq!(clash.to_string(), "[keys]: клавиша S уже занята действием sound");
+    }
+
+    #[test]
+    fn reload_applies_only_changed_fields() {
+        let old = Config::parse("sound = true\ngrid = true\ntick_ms = 100\n").unwrap();
+        let mut settings = Settings::default();
+        old.apply(&mut settings);
+        // Звук выключили в игре: файл его не трогал, значит и перечитывание не трогает
+        settings.sound = false;
+        let new = Config::parse("sound = true\nborder = \"double\"\ntick_ms = 80\ngrowth_per_food = 0\n").unwrap();
+        let reload = new.apply_changes(&old, &mut settings);
+        assert_eq!(reload, Reload { color: false, keys: false, tick: true });
+        let faster = Config::parse("sound = true\ngrid = true\ntick_ms = 100\nlength_speedup = true\n").unwrap();
+        assert!(faster.apply_changes(&old, &mut Settings::default()).tick);
+        assert!(!settings.sound);
+        // Сетку убрали из файла: она вернулась к умолчанию
+        assert!(!settings.grid);
+        assert_eq!((settings.border, settings.growth_per_food), (BorderStyle::Double, 0));
+    }
+
+    #[test]
+    fn watcher_sees_changes_and_keeps_errors_apart() {
+        let path = std::env::temp_dir().join(format!("zmiy-config-{}.toml", std::process::id()));
+        fs::write(&path, "grid = true\n").unwrap();
+        let start = Instant::now();
+        let mut watcher = Watcher::new(path.clone(), start);
+        let later = |n: u32| start + WATCH_INTERVAL * n;
+        assert_eq!(watcher.poll(later(1)), None);
+
+        // Время изменения задаём явно: файловая система может не различить две записи подряд
+        let touch = |text: &str, secs: u64| {
+            fs::write(&path, text).unwrap();
+            let file = fs::File::options().write(true).open(&path).unwrap();
+            file.set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(secs)).unwrap();
+        };
+        touch("grid = false\n", 100);
+        assert_eq!(watcher.poll(start), None, "проверка не раньше интервала");
+        assert_eq!(watcher.poll(later(2)).unwrap().unwrap().grid, Some(false));
+        assert_eq!(watcher.poll(later(3)), None);
+        touch("[keys]\nup = \"s\"\n", 200);
+        assert_eq!(watcher.poll(later(4)).unwrap().unwrap_err().line, 0);
+        touch("grid = 1\n", 300);
+        assert_eq!(watcher.poll(later(5)).unwrap().unwrap_err().line, 1);
+
+        fs::remove_file(&path).unwrap();
+        assert_eq!(watcher.poll(later(6)), None);
+    }
+}
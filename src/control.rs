@@ -0,0 +1,224 @@
+//! Управление игрой через Unix-сокет: `--control /tmp/zmiy.sock`.
+//!
+//! Клиент шлёт команды по одной на строку и на каждую получает строку ответа:
+//!
+//! ```text
+//! pause                    ok
+//! resume                   ok
+//! dir up                   ok            (up, down, left или right)
+//! restart                  ok            только после конца партии
+//! state                    {"state":"play","tick":12,"width":40,...}
+//! quit                     ok            и игра закрывается
+//! hello                    error: неизвестная команда hello
+//! ```
+//!
+//! Доступ проверяется только правами на файл сокета: он создаётся с правами 0600,
+//! то есть подключиться может лишь тот же пользователь. Клиентов слушают отдельные
+//! потоки, а игра забирает готовые команды без ожидания. Файл сокета удаляется,
+//! когда [`Control`] удаляется, в том числе при выходе по ошибке.
+
+use std::{
+    fs, io,
+    io::{BufRead, BufReader, Write},
+    os::unix::{
+        fs::{FileTypeExt, PermissionsExt},
+        net::{UnixListener, UnixStream},
+    },
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+    time::Duration,
+};
+
+use crate::{
+    bot,
+    game::{DirectionSnake, Game},
+    log_info,
+};
+
+/// Сколько ждать, пока клиент примет ответ; дольше игра не ждёт
+const REPLY_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Команда клиента
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Command {
+    Pause,
+    Resume,
+    Dir(DirectionSnake),
+    Restart,
+    State,
+    Quit,
+}
+
+/// Разбирает строку команды; ошибка — текст для ответа клиенту
+pub fn parse_command(line: &str) -> Result<Command, String> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let command = match words.as_slice() {
+        ["pause"] => Command::Pause,
+        ["resume"] => Command::Resume,
+        ["dir", dir] => Command::Dir(match *dir {
+            "up" => DirectionSnake::Up,
+            "down" => DirectionSnake::Down,
+            "left" => DirectionSnake::Left,
+            "right" => DirectionSnake::Right,
+            other => return Err(format!("неизвестное направление {other}: ожидается up, down, left или right")),
+        }),
+        ["restart"] => Command::Restart,
+        ["state"] => Command::State,
+        ["quit"] => Command::Quit,
+        [] => return Err("пустая команда".to_string()),
+        _ => return Err(format!("неизвестная команда {}", line.trim())),
+    };
+    Ok(command)
+}
+
+/// Состояние для команды `state`: как у бота (см. [`bot::state_json`]) и поле `state`:
+/// `splash`, `countdown`, `play`, `pause` или `over`
+pub fn state_json(game: Option<&Game>, tick: u64, state: &str) -> String {
+    match game {
+        // Без игры нечего описывать, кроме того, что её ещё нет
+        None => format!("{{\"state\":\"{state}\"}}"),
+        Some(game) => format!("{{\"state\":\"{state}\",{}", &bot::state_json(game, tick)[1..]),
+    }
+}
+
+/// Команда, которую ждёт ответ
+pub struct Request {
+    pub command: Command,
+    stream: UnixStream,
+}
+
+impl Request {
+    /// Отвечает клиенту одной строкой. Ушедший клиент ответа просто не получит.
+    pub fn reply(mut self, text: &str) {
+        let _ = writeln!(self.stream, "{text}");
+    }
+}
+
+/// Открытый сокет управления
+pub struct Control {
+    listener: UnixListener,
+    path: PathBuf,
+    requests: Receiver<Request>,
+    sender: Sender<Request>,
+}
+
+impl Control {
+    /// Создаёт сокет с правами только для владельца. Оставшийся от упавшей игры файл
+    /// заменяется; сокет, который кто-то слушает, и обычный файл не трогаются.
+    pub fn listen(path: &Path) -> io::Result<Self> {
+        if let Ok(meta) = fs::symlink_metadata(path) {
+            if !meta.file_type().is_socket() {
+                return Err(io::Error::new(io::ErrorKind::AlreadyExists, "файл уже есть и это не сокет"));
+            }
+            if UnixStream::connect(path).is_ok() {
+                return Err(io::Error::new(io::ErrorKind::AddrInUse, "сокет уже слушает другая игра"));
+            }
+            fs::remove_file(path)?;
+        }
+        let listener = UnixListener::bind(path)?;
+        let (sender, requests) = mpsc::channel();
+        // Сразу создаём значение: если дальше что-то не выйдет, файл удалится
+        let control = Self { listener, path: path.to_path_buf(), requests, sender };
+        fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+        control.listener.set_nonblocking(true)?;
+        Ok(control)
+    }
+
+    /// Принимает новых клиентов и возвращает команды, пришедшие с прошлого раза
+    pub fn poll(&mut self) -> Vec<Request> {
+        while let Ok((stream, _)) = self.listener.accept() {
+            let sender = self.sender.clone();
+            thread::spawn(move || serve(stream, sender));
+        }
+        self.requests.try_iter().collect()
+    }
+}
+
+impl Drop for Control {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Читает команды клиента; непонятные отвергает сам, остальные передаёт игре
+fn serve(stream: UnixStream, sender: Sender<Request>) {
+    log_info!("клиент управления подключился");
+    let Ok(reader) = stream.try_clone() else {
+        return;
+    };
+    // Поток клиента блокирующий, даже если сокет игры — нет
+    let _ = stream.set_nonblocking(false);
+    let _ = stream.set_write_timeout(Some(REPLY_TIMEOUT));
+    for line in BufReader::new(reader).lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        match parse_command(&line) {
+            Ok(command) => {
+                let Ok(stream) = stream.try_clone() else {
+                    break;
+                };
+                if sender.send(Request { command, stream }).is_err() {
+                    break;
+                }
+            }
+            Err(e) => {
+                let _ = writeln!(&stream, "error: {e}");
+            }
+        }
+    }
+    log_info!("клиент управления отключился");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    #[test]
+    fn parses_commands() {
+        assert_eq!(parse_command("pause"), Ok(Command::Pause));
+        assert_eq!(parse_command("  dir left "), Ok(Command::Dir(DirectionSnake::Left)));
+        assert_eq!(parse_command("state"), Ok(Command::State));
+        assert_eq!(parse_command("dir north"), Err("неизвестное направление north: ожидается up, down, left или right".to_string()));
+        assert_eq!(parse_command("jump"), Err("неизвестная команда jump".to_string()));
+    }
+
+    #[test]
+    fn state_carries_status() {
+        let game = Game::with_seed(10, 6, 1);
+        let json = state_json(Some(&game), 3, "pause");
+        assert!(json.starts_with("{\"state\":\"pause\",\"tick\":3,\"width\":10,"), "{json}");
+        assert_eq!(state_json(None, 0, "splash"), "{\"state\":\"splash\"}");
+    }
+
+    #[test]
+    fn commands_arrive_and_socket_is_removed() {
+        let path = std::env::temp_dir().join(format!("zmiy-control-{}.sock", std::process::id()));
+        let mut control = Control::listen(&path).unwrap();
+        assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        // Второй игре тот же сокет не достаётся
+        assert_eq!(Control::listen(&path).err().map(|e| e.kind()), Some(io::ErrorKind::AddrInUse));
+
+        let mut client = UnixStream::connect(&path).unwrap();
+        client.write_all(b"fly\ndir up\n").unwrap();
+        // Клиента принимает poll, поэтому ответы ждём только после него
+        let started = Instant::now();
+        let request = loop {
+            if let Some(request) = control.poll().pop() {
+                break request;
+            }
+            assert!(started.elapsed() < Duration::from_secs(5));
+            thread::sleep(Duration::from_millis(5));
+        };
+        assert_eq!(request.command, Command::Dir(DirectionSnake::Up));
+        request.reply("ok");
+        let mut replies = BufReader::new(client).lines();
+        assert_eq!(replies.next().unwrap().unwrap(), "error: неизвестная команда fly");
+        assert_eq!(replies.next().unwrap().unwrap(), "ok");
+
+        drop(control);
+        assert!(!path.exists());
+    }
+}
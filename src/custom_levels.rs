@@ -0,0 +1,210 @@
+//! Экран «Свои уровни»: файлы из папки уровней (см. [`crate::level::dir`]) со свойствами
+//! из заголовка. Уровень отсюда можно сыграть или удалить.
+//!
+//! Файл, который не читается, остаётся в списке со своей ошибкой, а уровень больше
+//! терминала сыграть нельзя: поле не прокручивается, и его край был бы не виден.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Rect},
+    style::{Modifier, Style},
+    text::Line,
+    widgets::{Block, Borders, Clear, Row, StatefulWidget, Table, TableState, Widget},
+};
+
+use crate::{
+    level::{self, Level},
+    stars::{self, Stars},
+    theme::Theme,
+};
+
+/// Файл уровня в списке
+pub struct Entry {
+    pub path: PathBuf,
+    /// Уровень или почему он не прочитался
+    pub level: Result<Level, String>,
+}
+
+impl Entry {
+    /// Имя файла без папки
+    pub fn file_name(&self) -> String {
+        self.path.file_name().map_or_else(String::new, |name| name.to_string_lossy().into_owned())
+    }
+
+    /// Уровень, если его можно сыграть в терминале `columns`×`rows`: с рамкой и строкой
+    /// статуса, как у поля `--size`
+    pub fn playable(&self, columns: u16, rows: u16) -> Result<&Level, String> {
+        let level = self.level.as_ref().map_err(Clone::clone)?;
+        let (w, h) = (level.board.x, level.board.y);
+        if w.saturating_add(2) > columns || h.saturating_add(3) > rows {
+            return Err(format!("поле {w}x{h} не помещается в терминал {columns}x{rows}"));
+        }
+        Ok(level)
+    }
+}
+
+/// Список своих уровней и выбранная строка
+pub struct LevelList {
+    entries: Vec<Entry>,
+    pub state: TableState,
+    /// Итог последнего действия: почему уровень не сыграть или не удалить
+    pub note: Option<String>,
+}
+
+impl LevelList {
+    /// Читает все файлы `.zlv` из `dir` по порядку имён. Папки может ещё не быть.
+    pub fn load(dir: &Path) -> io::Result<Self> {
+        let mut entries = Vec::new();
+        match fs::read_dir(dir) {
+            Ok(files) => {
+                for file in files {
+                    let path = file?.path();
+                    if path.extension().is_none_or(|ext| ext != level::EXTENSION) {
+                        continue;
+                    }
+                    let level = Level::load(&path).map_err(|e| e.to_string());
+                    entries.push(Entry { path, level });
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(Self { entries, state: TableState::default().with_selected(Some(0)), note: None })
+    }
+
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    pub fn selected(&self) -> Option<&Entry> {
+        self.entries.get(self.state.selected().unwrap_or(0))
+    }
+
+    pub fn up(&mut self) {
+        let selected = self.state.selected().unwrap_or(0);
+        self.state.select(Some(selected.saturating_sub(1)));
+        self.note = None;
+    }
+
+    pub fn down(&mut self) {
+        let selected = self.state.selected().unwrap_or(0);
+        self.state.select(Some((selected + 1).min(self.entries.len().saturating_sub(1))));
+        self.note = None;
+    }
+
+    /// Удаляет файл выбранного уровня и убирает его из списка
+    pub fn delete(&mut self) -> io::Result<()> {
+        let selected = self.state.selected().unwrap_or(0);
+        let Some(entry) = self.entries.get(selected) else {
+            return Ok(());
+        };
+        fs::remove_file(&entry.path)?;
+        self.entries.remove(selected);
+        self.state.select(Some(selected.min(self.entries.len().saturating_sub(1))));
+        Ok(())
+    }
+}
+
+/// Всё, что нужно для отрисовки списка
+pub struct LevelListView<'a> {
+    pub list: &'a mut LevelList,
+    pub theme: &'a Theme,
+    /// Лучшие звёзды за уровни
+    pub stars: &'a Stars,
+}
+
+impl Widget for LevelListView<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let theme = self.theme;
+        let dash = || "-".to_string();
+        let rows: Vec<Row> = self
+            .list
+            .entries
+            .iter()
+            .map(|entry| match &entry.level {
+                Ok(level) => Row::new(vec![
+                    level.name.clone(),
+                    level.author.clone().unwrap_or_else(dash),
+                    format!("{}x{}", level.board.x, level.board.y),
+                    level.speed.map_or_else(dash, |speed| format!("{} мс", speed.as_millis())),
+                    level.par().to_string(),
+                    self.stars.get(level).map_or_else(dash, stars::format),
+                ]),
+                Err(e) => Row::new(vec![entry.file_name(), e.clone()]).style(Style::default().fg(theme.danger)),
+            })
+            .collect();
+        let mut block = Block::default()
+            .borders(Borders::ALL)
+            .title(" Свои уровни ")
+            .title_bottom(Line::from(" ↑↓ - выбор, Enter - играть, Del - удалить, ESC - назад "))
+            .border_style(Style::default().fg(theme.border))
+            .style(Style::default().bg(theme.popup_bg).fg(theme.text));
+        if let Some(note) = &self.list.note {
+            block = block.title_bottom(Line::styled(format!(" {note} "), Style::default().fg(theme.accent)).right_aligned());
+        } else if self.list.entries.is_empty() {
+            block = block.title_bottom(Line::from(" Уровней нет: нарисуйте их в редакторе (E) ").right_aligned());
+        }
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Min(12),
+                Constraint::Length(12),
+                Constraint::Length(7),
+                Constraint::Length(8),
+                Constraint::Length(4),
+                Constraint::Length(6),
+            ],
+        )
+        .header(
+            Row::new(vec!["Название", "Автор", "Поле", "Скорость", "Пар", "Звёзды"])
+                .style(Style::default().fg(theme.accent).add_modifier(Modifier::BOLD)),
+        )
+        .block(block)
+        .row_highlight_style(Style::default().fg(theme.popup_bg).bg(theme.accent));
+
+        Clear.render(area, buf);
+        StatefulWidget::render(table, area, buf, &mut self.list.state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Уровень 7×5 с заголовком `header`
+    fn level(header: &str) -> String {
+        format!("zmiy-level 3\nname тест\n{header}board 7 5\n{}", "row .......\n".repeat(5))
+    }
+
+    #[test]
+    fn lists_levels_with_their_errors() {
+        let dir = std::env::temp_dir().join(format!("zmiy-custom-levels-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        assert!(LevelList::load(&dir).unwrap().entries().is_empty());
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("1.zlv"), level("author кто-то\nspeed 80\ntarget 30\n")).unwrap();
+        fs::write(dir.join("2.zlv"), level("target много\n")).unwrap();
+        fs::write(dir.join("3.txt"), level("")).unwrap();
+
+        let mut list = LevelList::load(&dir).unwrap();
+        let [good, bad] = list.entries() else { panic!("два уровня") };
+        let parsed = good.level.as_ref().unwrap();
+        assert_eq!((parsed.author.as_deref(), parsed.target), (Some("кто-то"), Some(30)));
+        assert_eq!((bad.file_name().as_str(), bad.level.as_ref().err().map(String::as_str)), ("2.zlv", Some("ошибка в строке 3")));
+        assert!(good.playable(9, 8).is_ok());
+        assert_eq!(good.playable(8, 8).err().as_deref(), Some("поле 7x5 не помещается в терминал 8x8"));
+
+        list.down();
+        list.delete().unwrap();
+        assert_eq!(list.entries().len(), 1);
+        assert_eq!(list.selected().map(Entry::file_name).as_deref(), Some("1.zlv"));
+        assert!(!dir.join("2.zlv").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,108 @@
+//! Полное состояние партии одной строкой JSON: F9 в игре и `--dump-on-death`.
+//!
+//! Годится для отчёта об ошибке: по сиду и состоянию генератора партию можно
+//! восстановить и продолжить с того же места.
+//!
+//! ```text
+//! {"version":"0.1.0","mode":"классика","tick":12,"elapsed_ms":1500,"width":40,"height":20,
+//!  "score":1,"dir":"R","food":[3,4],"snake":[[5,5],[4,5]],"game_over":null,
+//!  "food_per_level":null,"seed":1234,"rng":987654321,"base_tick_ms":120,
+//!  "history":{"stride":1,"since_last":0,"points":[0,1]}}
+//! ```
+
+use std::{
+    fmt::Write as _,
+    fs, io,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::game::Game;
+
+/// Экранирует строку для JSON
+pub fn json_string(text: &str) -> String {
+    let mut out = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if u32::from(c) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", u32::from(c));
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Состояние партии в JSON. Генератор еды перезапускается, как при сохранении,
+/// чтобы его состояние уместилось в одно число `rng`.
+pub fn to_json(game: &mut Game, mode: &str, tick: u64, elapsed: Duration) -> String {
+    let rng = game.reseed();
+    let mut out = format!(
+        "{{\"version\":\"{}\",\"mode\":{},\"tick\":{tick},\"elapsed_ms\":{},\"width\":{},\"height\":{},\
+         \"score\":{},\"dir\":\"{}\",\"food\":[{},{}],\"snake\":[",
+        env!("CARGO_PKG_VERSION"),
+        json_string(mode),
+        elapsed.as_millis(),
+        game.width,
+        game.height,
+        game.score,
+        game.dir.code(),
+        game.food.x,
+        game.food.y
+    );
+    for (i, p) in game.snake().iter().enumerate() {
+        let _ = write!(out, "{}[{},{}]", if i > 0 { "," } else { "" }, p.x, p.y);
+    }
+    let game_over = game.game_over.map_or("null".to_string(), |cause| format!("\"{}\"", cause.code()));
+    let food_per_level = game.food_per_level.map_or("null".to_string(), |n| n.to_string());
+    let points: Vec<String> = game.history.points.iter().map(u64::to_string).collect();
+    let _ = write!(
+        out,
+        "],\"game_over\":{game_over},\"food_per_level\":{food_per_level},\"seed\":{},\"rng\":{rng},\
+         \"base_tick_ms\":{},\"history\":{{\"stride\":{},\"since_last\":{},\"points\":[{}]}}}}",
+        game.seed,
+        game.base_tick.as_millis(),
+        game.history.stride,
+        game.history.since_last,
+        points.join(",")
+    );
+    out
+}
+
+/// Пишет дамп в `zmiy-dump-<время>.json` в текущей папке и возвращает имя файла
+pub fn save(game: &mut Game, mode: &str, tick: u64, elapsed: Duration) -> io::Result<String> {
+    let stamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis();
+    let path = format!("zmiy-dump-{stamp}.json");
+    fs::write(&path, to_json(game, mode, tick, elapsed) + "\n")?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{DeathCause, Point};
+
+    #[test]
+    fn dump_fields() {
+        let mut game = Game::with_seed(10, 6, 7);
+        game.set_snake([Point { x: 5, y: 3 }, Point { x: 4, y: 3 }]);
+        game.food = Point { x: 1, y: 2 };
+        game.score = 1;
+        game.game_over = Some(DeathCause::SelfCollision);
+        let json = to_json(&mut game, "классика \"x\"", 9, Duration::from_millis(1500));
+        assert!(json.starts_with("{\"version\":\""), "{json}");
+        assert!(json.contains("\"mode\":\"классика \\\"x\\\"\",\"tick\":9,\"elapsed_ms\":1500"), "{json}");
+        assert!(json.contains("\"dir\":\"R\",\"food\":[1,2],\"snake\":[[5,3],[4,3]],\"game_over\":\"self_collision\""), "{json}");
+        assert!(json.contains("\"food_per_level\":null,\"seed\":7,"), "{json}");
+        assert!(json.ends_with("\"history\":{\"stride\":1,\"since_last\":0,\"points\":[]}}"), "{json}");
+        // Генератор, запущенный с записанного состояния, даёт ту же еду
+        let rng: u64 = json.split("\"rng\":").nth(1).unwrap().split(',').next().unwrap().parse().unwrap();
+        let mut restored = Game::with_seed(10, 6, rng);
+        restored.set_snake(game.snake().iter().copied());
+        game.spawn_food();
+        restored.spawn_food();
+        assert_eq!(game.food, restored.food);
+    }
+}
@@ -0,0 +1,323 @@
+//! Редактор поля: перед партией на пустом поле расставляют стены, а потом играют
+//! на нём или сохраняют его файлом уровня (см. [`crate::level`]).
+//!
+//! Стена ставится и убирается в клетке под курсором. Поле проверяется так же, как
+//! уровень из файла, поэтому сыграть или сохранить можно только годное поле.
+//! У уровня из редактора есть название, автор, рекомендуемая скорость и цель.
+
+use std::{
+    io,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    style::{Modifier, Style},
+    text::Line,
+    widgets::{Block, Borders, Paragraph, Widget},
+};
+
+use crate::{
+    cli::MIN_SIZE,
+    game::{DirectionSnake, Point},
+    level::{self, Level, LevelError},
+    paths,
+    text_input::TextInput,
+    theme::Theme,
+    widget::centered,
+};
+
+/// Поле редактора, если терминал позволяет: как у встроенного уровня
+pub const BOARD: (u16, u16) = (40, 20);
+/// Название нарисованного уровня, пока его не сменили
+pub const NAME: &str = "своё поле";
+/// Самое длинное название уровня
+pub const NAME_MAX: usize = 24;
+/// Самая высокая рекомендуемая скорость, клеток в секунду
+pub const MAX_SPEED: u16 = 30;
+/// На сколько меняется цель уровня за одно нажатие
+pub const TARGET_STEP: usize = 10;
+
+/// Поле со стенами и курсор
+pub struct Editor {
+    board: Point,
+    cursor: Point,
+    /// Стены по рядам сверху вниз
+    walls: Vec<Point>,
+    pub name: String,
+    pub author: Option<String>,
+    /// Рекомендуемая скорость в клетках в секунду; `None` — любая
+    speed: Option<u16>,
+    target: Option<usize>,
+    /// Ввод нового названия
+    pub naming: Option<TextInput>,
+    /// Итог последнего действия: ошибка проверки или куда сохранён файл
+    pub note: Option<String>,
+}
+
+impl Editor {
+    /// Пустое поле `width`×`height`, курсор — там, где начнёт змейка
+    pub fn new(width: u16, height: u16) -> Self {
+        let board = Point { x: width, y: height };
+        let [start, _] = level::start_cells(board);
+        Self {
+            board,
+            cursor: start,
+            walls: Vec::new(),
+            name: NAME.to_string(),
+            author: None,
+            speed: None,
+            target: None,
+            naming: None,
+            note: None,
+        }
+    }
+
+    /// Пустое поле [`BOARD`], уменьшенное до терминала `columns`×`rows`: вокруг поля рамка,
+    /// под ним четыре строки: свойства уровня, сообщение и подсказки
+    pub fn fit(columns: u16, rows: u16) -> Self {
+        let width = BOARD.0.min(columns.saturating_sub(2)).max(MIN_SIZE.0);
+        let height = BOARD.1.min(rows.saturating_sub(6)).max(MIN_SIZE.1);
+        Self::new(width, height)
+    }
+
+    pub fn board(&self) -> Point {
+        self.board
+    }
+
+    pub fn cursor(&self) -> Point {
+        self.cursor
+    }
+
+    pub fn is_wall(&self, p: Point) -> bool {
+        self.walls.contains(&p)
+    }
+
+    /// Сдвигает курсор на клетку; у края поля он стоит
+    pub fn move_cursor(&mut self, dir: DirectionSnake) {
+        let Point { x, y } = self.cursor;
+        self.cursor = match dir {
+            DirectionSnake::Up => Point { x, y: y.saturating_sub(1) },
+            DirectionSnake::Down => Point { x, y: (y + 1).min(self.board.y - 1) },
+            DirectionSnake::Left => Point { x: x.saturating_sub(1), y },
+            DirectionSnake::Right => Point { x: (x + 1).min(self.board.x - 1), y },
+        };
+    }
+
+    /// Ставит стену под курсором или убирает её
+    pub fn toggle(&mut self) {
+        match self.walls.iter().position(|&p| p == self.cursor) {
+            Some(i) => {
+                self.walls.remove(i);
+            }
+            None => {
+                self.walls.push(self.cursor);
+                self.walls.sort_by_key(|p| (p.y, p.x));
+            }
+        }
+        self.note = None;
+    }
+
+    /// Меняет рекомендуемую скорость на клетку в секунду; ниже одной — любая скорость
+    pub fn change_speed(&mut self, faster: bool) {
+        let speed = self.speed.unwrap_or(0);
+        let speed = if faster { (speed + 1).min(MAX_SPEED) } else { speed.saturating_sub(1) };
+        self.speed = (speed > 0).then_some(speed);
+    }
+
+    /// Меняет цель на [`TARGET_STEP`]; ниже шага — цели нет
+    pub fn change_target(&mut self, more: bool) {
+        let target = self.target.unwrap_or(0);
+        let target = if more { target + TARGET_STEP } else { target.saturating_sub(TARGET_STEP) };
+        self.target = (target > 0).then_some(target);
+    }
+
+    /// Начинает ввод названия с нынешнего
+    pub fn start_naming(&mut self) {
+        self.naming = Some(TextInput::new(&self.name, NAME_MAX));
+    }
+
+    /// Заканчивает ввод названия; пустое название не меняет прежнего
+    pub fn finish_naming(&mut self) {
+        if let Some(input) = self.naming.take() {
+            let name = input.value().trim().to_string();
+            if !name.is_empty() {
+                self.name = name;
+            }
+        }
+    }
+
+    /// Нарисованный уровень, если на нём можно играть
+    pub fn level(&self) -> Result<Level, LevelError> {
+        Level {
+            name: self.name.clone(),
+            board: self.board,
+            walls: self.walls.clone(),
+            fast: Vec::new(),
+            slow: Vec::new(),
+            author: self.author.clone(),
+            // Целые миллисекунды, как у `--speed`
+            speed: self.speed.map(|speed| Duration::from_millis((1000.0 / f64::from(speed)).round() as u64)),
+            target: self.target,
+        }
+        .check()
+    }
+}
+
+/// Пишет уровень в `dir/<время>.zlv` и возвращает путь к файлу
+pub fn save(level: &Level, dir: &Path) -> io::Result<PathBuf> {
+    let stamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis();
+    let path = dir.join(format!("{stamp}.{}", level::EXTENSION));
+    paths::write_atomic(&path, level.to_text().as_bytes())?;
+    Ok(path)
+}
+
+/// Всё, что нужно для отрисовки редактора
+pub struct EditorView<'a> {
+    pub editor: &'a Editor,
+    pub theme: &'a Theme,
+    pub ascii: bool,
+}
+
+impl Widget for EditorView<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let area = area.intersection(buf.area);
+        let (editor, theme) = (self.editor, self.theme);
+        let Point { x: width, y: height } = editor.board;
+        let help_width = HELP.iter().map(|l| l.chars().count() as u16).max().unwrap_or(0);
+        let area = centered(area, (width + 2).max(help_width), height + 6);
+        let [board_area, info_area, note_area, help_area] = Layout::vertical([
+            Constraint::Length(height + 2),
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Length(2),
+        ])
+        .areas(area);
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(format!(" Редактор поля: {} ", editor.name))
+            .border_style(Style::default().fg(theme.border));
+        let board_area = centered(board_area, width + 2, height + 2);
+        let inner = block.inner(board_area);
+        block.render(board_area, buf);
+
+        let [start, food] = level::start_cells(editor.board);
+        for y in 0..height.min(inner.height) {
+            for x in 0..width.min(inner.width) {
+                let p = Point { x, y };
+                let (symbol, mut style) = if editor.is_wall(p) {
+                    (if self.ascii { "%" } else { "█" }, Style::default().fg(theme.wall))
+                } else if p == start {
+                    ("O", Style::default().fg(theme.snake))
+                } else if p == food {
+                    ("*", Style::default().fg(theme.food))
+                } else {
+                    (if self.ascii { "." } else { "·" }, Style::default().fg(theme.muted).add_modifier(Modifier::DIM))
+                };
+                if p == editor.cursor {
+                    style = style.add_modifier(Modifier::REVERSED);
+                }
+                buf[(inner.x + x, inner.y + y)].set_symbol(symbol).set_style(style);
+            }
+        }
+
+        let text = Style::default().fg(theme.text);
+        let speed = editor.speed.map_or("любая".to_string(), |speed| format!("{speed} кл/с"));
+        let target = editor.target.map_or("нет".to_string(), |target| target.to_string());
+        Paragraph::new(Line::styled(format!("Скорость: {speed}   Цель: {target}"), text).centered()).render(info_area, buf);
+        if let Some(input) = &editor.naming {
+            let mut line = input.line(text);
+            line.spans.insert(0, "Название: ".into());
+            Paragraph::new(line.centered()).render(note_area, buf);
+        } else if let Some(note) = &editor.note {
+            Paragraph::new(Line::styled(note.as_str(), Style::default().fg(theme.accent)).centered()).render(note_area, buf);
+        }
+        let help: Vec<Line> = HELP.iter().map(|l| Line::styled(*l, text).centered()).collect();
+        Paragraph::new(help).render(help_area, buf);
+    }
+}
+
+/// Подсказка под полем редактора
+const HELP: [&str; 2] = [
+    "стрелки - курсор, Space - стена, Enter - играть, S - сохранить, Esc - назад",
+    "N - название, +/- - скорость, [/] - цель",
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn walls_are_toggled_under_the_cursor() {
+        let mut editor = Editor::new(7, 5);
+        assert_eq!(editor.cursor(), Point { x: 3, y: 2 });
+        editor.move_cursor(DirectionSnake::Right);
+        editor.toggle();
+        editor.move_cursor(DirectionSnake::Up);
+        editor.toggle();
+        assert!(editor.is_wall(Point { x: 4, y: 1 }) && editor.is_wall(Point { x: 4, y: 2 }));
+        // Стены в уровне — по рядам сверху вниз
+        assert_eq!(editor.level().unwrap().walls, [Point { x: 4, y: 1 }, Point { x: 4, y: 2 }]);
+        editor.toggle();
+        assert!(!editor.is_wall(Point { x: 4, y: 1 }));
+        // У края курсор стоит
+        for _ in 0..10 {
+            editor.move_cursor(DirectionSnake::Up);
+            editor.move_cursor(DirectionSnake::Right);
+        }
+        assert_eq!(editor.cursor(), Point { x: 6, y: 0 });
+    }
+
+    #[test]
+    fn drawn_board_is_checked() {
+        let mut editor = Editor::new(7, 5);
+        // Стена на клетке, где начинает змейка
+        editor.toggle();
+        assert_eq!(editor.level(), Err(LevelError::StartBlocked));
+        editor.toggle();
+        // Угол отрезан стенами
+        for dir in [DirectionSnake::Up, DirectionSnake::Up, DirectionSnake::Right, DirectionSnake::Right] {
+            editor.move_cursor(dir);
+        }
+        editor.toggle();
+        editor.move_cursor(DirectionSnake::Right);
+        editor.move_cursor(DirectionSnake::Down);
+        editor.toggle();
+        assert_eq!(editor.level(), Err(LevelError::Closed));
+    }
+
+    #[test]
+    fn fits_the_terminal() {
+        assert_eq!(Editor::fit(200, 60).board(), Point { x: BOARD.0, y: BOARD.1 });
+        assert_eq!(Editor::fit(30, 15).board(), Point { x: 28, y: 9 });
+        assert_eq!(Editor::fit(3, 3).board(), Point { x: MIN_SIZE.0, y: MIN_SIZE.1 });
+    }
+
+    #[test]
+    fn saved_level_loads() {
+        let dir = std::env::temp_dir().join(format!("zmiy-editor-{}", std::process::id()));
+        let mut editor = Editor::new(7, 5);
+        editor.move_cursor(DirectionSnake::Right);
+        editor.toggle();
+        editor.start_naming();
+        editor.naming.as_mut().unwrap().insert('!');
+        editor.finish_naming();
+        editor.author = Some("тест".to_string());
+        for _ in 0..8 {
+            editor.change_speed(true);
+        }
+        editor.change_speed(false);
+        editor.change_target(true);
+        let level = editor.level().unwrap();
+        assert_eq!(
+            (level.name.as_str(), level.speed, level.target),
+            ("своё поле!", Some(Duration::from_millis(143)), Some(TARGET_STEP))
+        );
+        let path = save(&level, &dir).unwrap();
+        assert_eq!(path.extension().unwrap(), level::EXTENSION);
+        assert_eq!(Level::load(&path).unwrap(), level);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,147 @@
+//! Призрак: лучшая записанная партия (`--record`) идёт по полю рядом с текущей.
+//!
+//! Призрак — это воспроизведение повтора, которое отстаёт от игры не больше чем на тик:
+//! после каждого шага игры он делает столько же шагов. С тем же сидом и тем же полем
+//! у призрака и игрока одна и та же еда, пока кто-то из них её не съест. Закончив
+//! партию, призрак остаётся на месте гибели.
+
+use std::{
+    collections::VecDeque,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    game::Point,
+    log_warn,
+    replay::{self, Playback, Replay},
+};
+
+/// Лучший законченный повтор режима `mode` из папки `dir`, с сидом `seed`, если он задан.
+/// Нечитаемые файлы пропускаются: призрак — не повод мешать игре.
+pub fn best(dir: &Path, mode: &str, seed: Option<u64>) -> io::Result<Option<(PathBuf, Replay)>> {
+    let mut best: Option<(PathBuf, Replay)> = None;
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    for entry in entries {
+        let path = entry?.path();
+        if path.extension().is_none_or(|ext| ext != replay::EXTENSION) {
+            continue;
+        }
+        let read = fs::read_to_string(&path).map_err(|e| e.to_string());
+        let replay = match read.and_then(|text| Replay::parse(&text).map_err(|e| e.to_string())) {
+            Ok(replay) => replay,
+            Err(e) => {
+                log_warn!("повтор {} пропущен: {e}", path.display());
+                continue;
+            }
+        };
+        let Some(outcome) = replay.outcome else {
+            continue;
+        };
+        if replay.mode != mode || seed.is_some_and(|seed| seed != replay.seed) {
+            continue;
+        }
+        if best.as_ref().is_none_or(|(_, b)| b.outcome.is_some_and(|o| o.score < outcome.score)) {
+            best = Some((path, replay));
+        }
+    }
+    Ok(best)
+}
+
+/// Призрак на поле
+pub struct Ghost {
+    playback: Playback,
+    score: usize,
+}
+
+impl Ghost {
+    pub fn new(replay: Replay) -> Self {
+        let score = replay.outcome.map_or(0, |o| o.score);
+        Self { playback: Playback::new(replay), score }
+    }
+
+    /// Итоговый счёт призрака
+    pub fn score(&self) -> usize {
+        self.score
+    }
+
+    /// Сид повтора: с ним у игры та же еда, что у призрака
+    pub fn seed(&self) -> u64 {
+        self.playback.game.seed
+    }
+
+    /// Догоняет игру, сделавшую `tick` шагов
+    pub fn sync(&mut self, tick: u64) {
+        while self.playback.tick() < tick && !self.playback.finished() {
+            self.playback.step();
+        }
+    }
+
+    /// Клетки змейки-призрака, от головы к хвосту
+    pub fn cells(&self) -> &VecDeque<Point> {
+        self.playback.game.snake()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        game::{DirectionSnake, Game},
+        replay::Recorder,
+    };
+
+    /// Повтор партии на поле 20×10: поворот вниз на тике `turn`, итог со счётом `score`
+    fn recording(seed: u64, mode: &str, turn: u64, score: Option<usize>) -> String {
+        let game = Game::with_seed(20, 10, seed);
+        let mut out = Vec::new();
+        let mut rec = Recorder::start(&mut out, &game, mode).unwrap();
+        rec.turn(turn, DirectionSnake::Down).unwrap();
+        if let Some(score) = score {
+            rec.finish(turn + 3, score).unwrap();
+        }
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn picks_best_finished_run_of_mode() {
+        let dir = std::env::temp_dir().join(format!("zmiy-ghost-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        assert!(best(&dir, "классика", None).unwrap().is_none());
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("1.zrp"), recording(1, "классика", 2, Some(5))).unwrap();
+        fs::write(dir.join("2.zrp"), recording(2, "классика", 2, Some(9))).unwrap();
+        // Незаконченная, чужого режима, не повтор и битая — не в счёт
+        fs::write(dir.join("3.zrp"), recording(3, "классика", 2, None)).unwrap();
+        fs::write(dir.join("4.zrp"), recording(4, "другой", 2, Some(50))).unwrap();
+        fs::write(dir.join("5.txt"), recording(5, "классика", 2, Some(70))).unwrap();
+        fs::write(dir.join("6.zrp"), "мусор").unwrap();
+
+        let (path, replay) = best(&dir, "классика", None).unwrap().unwrap();
+        assert_eq!((path, replay.seed), (dir.join("2.zrp"), 2));
+        let (_, replay) = best(&dir, "классика", Some(1)).unwrap().unwrap();
+        assert_eq!(replay.seed, 1);
+        assert!(best(&dir, "классика", Some(7)).unwrap().is_none());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn follows_the_game_tick() {
+        let replay = Replay::parse(&recording(1, "классика", 2, Some(5))).unwrap();
+        let mut ghost = Ghost::new(replay);
+        assert_eq!((ghost.score(), ghost.seed()), (5, 1));
+        let start = ghost.cells()[0];
+        ghost.sync(2);
+        assert_eq!(ghost.cells()[0], Point { x: start.x + 2, y: start.y });
+        // Поворот вниз записан на тике 2
+        ghost.sync(3);
+        assert_eq!(ghost.cells()[0], Point { x: start.x + 2, y: start.y + 1 });
+        // Повтор кончился на тике 5: дальше призрак стоит
+        ghost.sync(100);
+        assert_eq!(ghost.cells()[0], Point { x: start.x + 2, y: start.y + 3 });
+    }
+}
@@ -0,0 +1,171 @@
+use std::time::Duration;
+
+use ratatui::{
+    style::Style,
+    text::{Line, Span},
+};
+
+use crate::game::Mutation;
+
+/// Поле строки статуса: полный и сокращённый вид, приоритет (0 — самое важное)
+pub struct HudField {
+    pub full: String,
+    pub short: String,
+    pub style: Style,
+    pub priority: u8,
+}
+
+impl HudField {
+    pub fn new(full: String, short: String, style: Style, priority: u8) -> Self {
+        Self { full, short, style, priority }
+    }
+}
+
+/// Время в виде мм:сс
+pub fn format_duration(d: Duration) -> String {
+    let secs = d.as_secs();
+    format!("{:02}:{:02}", secs / 60, secs % 60)
+}
+
+/// Разделитель между полями
+const SEPARATOR: &str = "  ";
+
+/// Собирает строку статуса шириной не больше `width`.
+///
+/// Сначала сокращаются, а потом и убираются поля с наименьшим приоритетом.
+/// Поле с приоритетом 0 никогда не убирается.
+pub fn fit(fields: &[HudField], width: u16) -> Line<'static> {
+    #[derive(Clone, Copy, PartialEq)]
+    enum Shown {
+        Full,
+        Short,
+        Hidden,
+    }
+
+    let mut shown = vec![Shown::Full; fields.len()];
+    let total = |shown: &[Shown]| {
+        let widths: Vec<usize> = fields
+            .iter()
+            .zip(shown)
+            .filter_map(|(f, s)| match s {
+                Shown::Full => Some(f.full.chars().count()),
+                Shown::Short => Some(f.short.chars().count()),
+                Shown::Hidden => None,
+            })
+            .collect();
+        widths.iter().sum::<usize>() + SEPARATOR.len() * widths.len().saturating_sub(1)
+    };
+
+    // От наименее важного к самому важному
+    let mut order: Vec<usize> = (0..fields.len()).collect();
+    order.sort_by_key(|&i| std::cmp::Reverse(fields[i].priority));
+
+    for step in [Shown::Short, Shown::Hidden] {
+        for &i in &order {
+            if total(&shown) <= width as usize {
+                break;
+            }
+            if step == Shown::Hidden && fields[i].priority == 0 {
+                continue;
+            }
+            shown[i] = step;
+        }
+    }
+
+    let mut spans = Vec::new();
+    for (field, s) in fields.iter().zip(&shown) {
+        let text = match s {
+            Shown::Full => field.full.clone(),
+            Shown::Short => field.short.clone(),
+            Shown::Hidden => continue,
+        };
+        if !spans.is_empty() {
+            spans.push(Span::raw(SEPARATOR));
+        }
+        spans.push(Span::styled(text, field.style));
+    }
+    Line::from(spans)
+}
+
+/// Действующие мутации бесконечного режима по строке на правило в порядке выпадения:
+/// повторы сливаются в одну строку со счётом, а зеркальное управление показывается,
+/// только пока оно идёт, с оставшимися шагами
+pub fn mutation_lines(mutations: &[Mutation], mirrored: u64) -> Vec<String> {
+    let mut seen: Vec<(Mutation, usize)> = Vec::new();
+    for &m in mutations {
+        match seen.iter_mut().find(|(other, _)| *other == m) {
+            Some((_, count)) => *count += 1,
+            None => seen.push((m, 1)),
+        }
+    }
+    seen.into_iter()
+        .filter(|&(m, _)| m != Mutation::Mirrored || mirrored > 0)
+        .map(|(m, count)| match (m, count) {
+            (Mutation::Mirrored, _) => format!("{} ({mirrored})", m.describe()),
+            (_, 1) => m.describe().to_string(),
+            (_, n) => format!("{} ×{n}", m.describe()),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::{backend::TestBackend, widgets::Paragraph, Terminal};
+
+    fn fields() -> Vec<HudField> {
+        let f = |full: &str, short: &str, priority| HudField::new(full.into(), short.into(), Style::default(), priority);
+        vec![
+            f("Счёт: 123", "S:123", 0),
+            f("Длина: 45", "L:45", 1),
+            f("Лучший: 100 +23", "B:100+23", 3),
+            f("8.3 кл/с", "8.3", 4),
+            f("12:34", "12:34", 2),
+        ]
+    }
+
+    fn render(width: u16) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(width, 1)).unwrap();
+        let line = fit(&fields(), width);
+        assert!(line.width() <= width as usize, "ширина {width}: {}", line.width());
+        terminal.draw(|f| f.render_widget(Paragraph::new(line), f.area())).unwrap();
+        terminal.backend().buffer().content().iter().map(|c| c.symbol()).collect::<String>()
+    }
+
+    #[test]
+    fn wide_terminal_shows_everything() {
+        let row = render(120);
+        for text in ["Счёт: 123", "Длина: 45", "Лучший: 100 +23", "8.3 кл/с", "12:34"] {
+            assert!(row.contains(text), "{row}");
+        }
+    }
+
+    #[test]
+    fn eighty_and_sixty_columns_fit_in_full() {
+        for width in [80, 60] {
+            let row = render(width);
+            assert!(row.contains("Счёт: 123") && row.contains("8.3 кл/с"), "{row}");
+        }
+    }
+
+    #[test]
+    fn forty_columns_abbreviate_low_priority_first() {
+        let row = render(40);
+        assert!(row.contains("Счёт: 123") && row.contains("12:34"), "{row}");
+        assert!(row.contains("B:100+23") && row.contains("L:45"), "{row}");
+        assert!(!row.contains("кл/с"), "{row}");
+    }
+
+    #[test]
+    fn repeated_mutations_share_a_line() {
+        let mutations = [Mutation::Faster, Mutation::Bombs, Mutation::Mirrored, Mutation::Faster];
+        assert_eq!(mutation_lines(&mutations, 12), ["Змейка быстрее ×2", "Появились бомбы", "Управление наоборот (12)"]);
+        assert_eq!(mutation_lines(&mutations, 0).len(), 2);
+    }
+
+    #[test]
+    fn score_is_never_dropped() {
+        let line = fit(&fields(), 4);
+        assert!(line.to_string().contains("S:123"));
+    }
+}
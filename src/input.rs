@@ -0,0 +1,189 @@
+use crossterm::event::KeyCode;
+use ratatui::{
+    style::Style,
+    text::{Line, Span},
+};
+
+use crate::{game::DirectionSnake, theme::Theme};
+
+/// Что делает клавиша. Смысл некоторых действий зависит от состояния игры.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Action {
+    Move(DirectionSnake),
+    /// Пауза, снятие паузы, на экране конца игры — выход
+    Pause,
+    /// Рестарт после конца игры, пропуск анимации смерти
+    Restart,
+    /// Сбросить заднюю половину змейки препятствием
+    Shed,
+    ToggleSound,
+    ToggleGrid,
+    /// Свернуть или развернуть список мутаций бесконечного режима
+    ToggleMutations,
+    Help,
+    Screenshot,
+    /// Полное состояние партии в JSON-файл
+    Dump,
+    /// Отчёт о проблеме: состояние, журнал, настройки и сведения о системе
+    Report,
+    /// Таблица рекордов (с экрана конца игры или паузы)
+    HighScores,
+}
+
+#[derive(Clone, Copy)]
+pub struct Binding {
+    pub key: KeyCode,
+    pub action: Action,
+    pub description: &'static str,
+}
+
+/// Привязки по умолчанию. Справка строится по живой таблице `Keymap`.
+pub const KEYMAP: &[Binding] = &[
+    Binding { key: KeyCode::Up, action: Action::Move(DirectionSnake::Up), description: "вверх" },
+    Binding { key: KeyCode::Down, action: Action::Move(DirectionSnake::Down), description: "вниз" },
+    Binding { key: KeyCode::Left, action: Action::Move(DirectionSnake::Left), description: "влево" },
+    Binding { key: KeyCode::Right, action: Action::Move(DirectionSnake::Right), description: "вправо" },
+    Binding { key: KeyCode::Esc, action: Action::Pause, description: "пауза / продолжить / выход" },
+    Binding { key: KeyCode::Char(' '), action: Action::Restart, description: "рестарт" },
+    Binding { key: KeyCode::Char('x'), action: Action::Shed, description: "сбросить хвост (−3 очка)" },
+    Binding { key: KeyCode::Char('s'), action: Action::ToggleSound, description: "звук вкл/выкл" },
+    Binding { key: KeyCode::Char('g'), action: Action::ToggleGrid, description: "сетка вкл/выкл" },
+    Binding { key: KeyCode::Char('m'), action: Action::ToggleMutations, description: "список мутаций" },
+    Binding { key: KeyCode::Char('h'), action: Action::HighScores, description: "таблица рекордов" },
+    Binding { key: KeyCode::F(12), action: Action::Screenshot, description: "снимок поля в файл" },
+    Binding { key: KeyCode::F(9), action: Action::Dump, description: "состояние партии в JSON" },
+    Binding { key: KeyCode::F(10), action: Action::Report, description: "отчёт о проблеме" },
+    Binding { key: KeyCode::F(1), action: Action::Help, description: "справка" },
+    Binding { key: KeyCode::Char('?'), action: Action::Help, description: "справка" },
+];
+
+/// Имена действий в файле настроек, в порядке `KEYMAP`
+pub const ACTION_NAMES: &[(&str, Action)] = &[
+    ("up", Action::Move(DirectionSnake::Up)),
+    ("down", Action::Move(DirectionSnake::Down)),
+    ("left", Action::Move(DirectionSnake::Left)),
+    ("right", Action::Move(DirectionSnake::Right)),
+    ("pause", Action::Pause),
+    ("restart", Action::Restart),
+    ("shed", Action::Shed),
+    ("sound", Action::ToggleSound),
+    ("grid", Action::ToggleGrid),
+    ("mutations", Action::ToggleMutations),
+    ("scores", Action::HighScores),
+    ("screenshot", Action::Screenshot),
+    ("dump", Action::Dump),
+    ("report", Action::Report),
+    ("help", Action::Help),
+];
+
+/// Подпись клавиши для справки
+pub fn key_label(key: KeyCode) -> String {
+    match key {
+        KeyCode::Up => "↑".to_string(),
+        KeyCode::Down => "↓".to_string(),
+        KeyCode::Left => "←".to_string(),
+        KeyCode::Right => "→".to_string(),
+        KeyCode::Esc => "ESC".to_string(),
+        KeyCode::Enter => "Enter".to_string(),
+        KeyCode::Tab => "Tab".to_string(),
+        KeyCode::Backspace => "Bksp".to_string(),
+        KeyCode::Char(' ') => "Пробел".to_string(),
+        KeyCode::Char(c) => c.to_uppercase().to_string(),
+        KeyCode::F(n) => format!("F{n}"),
+        other => format!("{other:?}"),
+    }
+}
+
+/// Клавиша по имени из файла настроек: одна буква, стрелка (`up`), `esc`, `space`, `enter`, `tab` или `f1`..`f12`
+pub fn parse_key(name: &str) -> Option<KeyCode> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(KeyCode::Char(c.to_lowercase().next().unwrap_or(c)));
+    }
+    let lower = name.to_lowercase();
+    let key = match lower.as_str() {
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "esc" => KeyCode::Esc,
+        "space" => KeyCode::Char(' '),
+        "enter" => KeyCode::Enter,
+        "tab" => KeyCode::Tab,
+        "backspace" => KeyCode::Backspace,
+        _ => {
+            let n: u8 = lower.strip_prefix('f')?.parse().ok()?;
+            return (1..=12).contains(&n).then_some(KeyCode::F(n));
+        }
+    };
+    Some(key)
+}
+
+/// Живая таблица привязок: `KEYMAP` с заменами из настроек
+#[derive(Clone)]
+pub struct Keymap {
+    bindings: Vec<Binding>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self { bindings: KEYMAP.to_vec() }
+    }
+}
+
+impl Keymap {
+    pub fn action(&self, key: KeyCode) -> Option<Action> {
+        self.bindings.iter().find(|b| b.key == key).map(|b| b.action)
+    }
+
+    /// Назначает действию одну клавишу вместо прежних. Клавиша, занятая другим действием,
+    /// не переназначается: возвращается это действие.
+    pub fn rebind(&mut self, action: Action, key: KeyCode) -> Result<(), Action> {
+        if let Some(other) = self.action(key).filter(|other| *other != action) {
+            return Err(other);
+        }
+        let Some(first) = self.bindings.iter().position(|b| b.action == action) else {
+            return Ok(());
+        };
+        self.bindings[first].key = key;
+        let mut i = 0;
+        self.bindings.retain(|b| {
+            i += 1;
+            i - 1 == first || b.action != action
+        });
+        Ok(())
+    }
+
+    /// Строки со всеми привязками для справки
+    pub fn lines(&self, theme: &Theme) -> Vec<Line<'static>> {
+        let text = Style::default().fg(theme.text);
+        self.bindings
+            .iter()
+            .map(|b| Line::from(Span::styled(format!("{:>6}  {}", key_label(b.key), b.description), text)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rebind_replaces_all_keys_of_action() {
+        let mut keymap = Keymap::default();
+        keymap.rebind(Action::Help, KeyCode::Char('j')).unwrap();
+        assert_eq!(keymap.action(KeyCode::Char('j')), Some(Action::Help));
+        assert_eq!(keymap.action(KeyCode::F(1)), None);
+        assert_eq!(keymap.action(KeyCode::Char('?')), None);
+        assert_eq!(keymap.rebind(Action::Help, KeyCode::Char('s')), Err(Action::ToggleSound));
+    }
+
+    #[test]
+    fn parses_key_names() {
+        assert_eq!(parse_key("W"), Some(KeyCode::Char('w')));
+        assert_eq!(parse_key("space"), Some(KeyCode::Char(' ')));
+        assert_eq!(parse_key("F5"), Some(KeyCode::F(5)));
+        assert_eq!(parse_key("f13"), None);
+        assert_eq!(parse_key("ctrl"), None);
+    }
+}
@@ -0,0 +1,316 @@
+//! Отправка результатов в онлайн-таблицу. Выключена, пока в настройках не задан
+//! `leaderboard_url`, а сетевой клиент есть только в сборке с `--features leaderboard`.
+//!
+//! Результат, попавший в таблицу рекордов, уходит POST-запросом одной строкой JSON:
+//!
+//! ```text
+//! {"name":"Оля","score":57,"mode":"классика","seed":1234,"duration_ms":61000,"version":"0.1.0"}
+//! ```
+//!
+//! Отправкой занимается отдельный поток, так что игра не ждёт сети. Неотправленное
+//! копится в `<данные>/leaderboard-queue.txt` по строке на результат и уходит при
+//! следующем запуске. Клиент понимает только `http://`: для HTTPS нужна библиотека TLS,
+//! которой в сборке нет. Поэтому с адресом `http://` игра при запуске и в каждом
+//! сообщении об отправке предупреждает, что имя, счёт и сид уходят без шифрования
+//! (см. [`warning`]).
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+    time::Duration,
+};
+
+use crate::{
+    dump::json_string,
+    log_info, log_warn,
+    paths::{data_dir, write_atomic},
+};
+
+/// Сколько ждать сервер на каждом шаге: подключение, отправка, ответ
+pub const TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Очередь неотправленных результатов: `<данные>/leaderboard-queue.txt`
+pub fn queue_path() -> Option<PathBuf> {
+    Some(data_dir()?.join("leaderboard-queue.txt"))
+}
+
+/// Результат для отправки
+pub struct Submission {
+    pub name: String,
+    pub score: usize,
+    pub mode: String,
+    pub seed: u64,
+    pub duration: Duration,
+}
+
+impl Submission {
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"name\":{},\"score\":{},\"mode\":{},\"seed\":{},\"duration_ms\":{},\"version\":\"{}\"}}",
+            json_string(&self.name),
+            self.score,
+            json_string(&self.mode),
+            self.seed,
+            self.duration.as_millis(),
+            env!("CARGO_PKG_VERSION")
+        )
+    }
+}
+
+/// Очередь из файла; нет файла — пустая
+pub fn load_queue(path: &Path) -> io::Result<Vec<String>> {
+    match fs::read_to_string(path) {
+        Ok(text) => Ok(text.lines().filter(|l| !l.trim().is_empty()).map(str::to_string).collect()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Сохраняет очередь; пустая очередь удаляет файл
+pub fn store_queue(path: &Path, queue: &[String]) -> io::Result<()> {
+    if queue.is_empty() {
+        return match fs::remove_file(path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        };
+    }
+    let text: String = queue.iter().map(|line| format!("{line}\n")).collect();
+    write_atomic(path, text.as_bytes())
+}
+
+/// Чем кончилась попытка отправки
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Status {
+    /// Отправлено столько результатов, очередь пуста
+    Sent(usize),
+    /// Сервер недоступен или ответил ошибкой; в очереди ждёт столько результатов
+    Queued(usize, String),
+}
+
+impl Status {
+    /// Текст всплывающего сообщения
+    pub fn message(&self) -> String {
+        match self {
+            Status::Sent(1) => "Результат отправлен в онлайн-таблицу".to_string(),
+            Status::Sent(n) => format!("Отправлено в онлайн-таблицу: {n}"),
+            Status::Queued(n, e) => format!("Онлайн-таблица недоступна ({e}), в очереди: {n}"),
+        }
+    }
+}
+
+/// Отправляет очередь по порядку, пока сервер принимает. Отправленное из очереди убирается.
+pub fn flush(queue: &mut Vec<String>, mut post: impl FnMut(&str) -> Result<(), String>) -> Status {
+    let mut sent = 0;
+    while let Some(body) = queue.first() {
+        if let Err(e) = post(body) {
+            return Status::Queued(queue.len(), e);
+        }
+        queue.remove(0);
+        sent += 1;
+    }
+    Status::Sent(sent)
+}
+
+/// Отправка в фоне: поток держит очередь и сообщает, чем кончилась каждая попытка
+pub struct Uploader {
+    jobs: Sender<String>,
+    statuses: Receiver<Status>,
+}
+
+impl Uploader {
+    /// Запускает поток отправки. Сначала он пробует отправить то, что осталось с прошлого
+    /// запуска. `post` — сам запрос; в игре это [`post`] на адрес из настроек.
+    pub fn start(queue_path: Option<PathBuf>, post: impl Fn(&str) -> Result<(), String> + Send + 'static) -> Self {
+        let (jobs, job_rx) = mpsc::channel::<String>();
+        let (status_tx, statuses) = mpsc::channel();
+        thread::spawn(move || {
+            let mut queue = match queue_path.as_deref().map(load_queue) {
+                Some(Ok(queue)) => queue,
+                Some(Err(e)) => {
+                    log_warn!("очередь онлайн-таблицы не прочитана: {e}");
+                    Vec::new()
+                }
+                None => Vec::new(),
+            };
+            let mut pending = !queue.is_empty();
+            loop {
+                if pending {
+                    let status = flush(&mut queue, &post);
+                    log_info!("онлайн-таблица: {status:?}");
+                    if let Some(path) = &queue_path
+                        && let Err(e) = store_queue(path, &queue)
+                    {
+                        log_warn!("очередь онлайн-таблицы не сохранена: {e}");
+                    }
+                    if status_tx.send(status).is_err() {
+                        break;
+                    }
+                }
+                let Ok(body) = job_rx.recv() else {
+                    break;
+                };
+                queue.push(body);
+                pending = true;
+            }
+        });
+        Self { jobs, statuses }
+    }
+
+    /// Ставит результат в очередь на отправку
+    pub fn submit(&self, submission: &Submission) {
+        let _ = self.jobs.send(submission.to_json());
+    }
+
+    /// Итоги попыток с прошлого раза
+    pub fn poll(&self) -> Vec<Status> {
+        self.statuses.try_iter().collect()
+    }
+}
+
+/// POST с телом JSON на `http://хост[:порт]/путь`; успех — ответ 2xx.
+/// Каждый шаг ждёт не дольше [`TIMEOUT`].
+#[cfg(feature = "leaderboard")]
+pub fn post(url: &str, body: &str) -> Result<(), String> {
+    use std::{
+        io::{Read, Write},
+        net::{TcpStream, ToSocketAddrs},
+    };
+
+    let (host, path) = parse_url(url)?;
+    let addr = host
+        .to_socket_addrs()
+        .map_err(|e| format!("{host}: {e}"))?
+        .next()
+        .ok_or_else(|| format!("{host}: адрес не найден"))?;
+    let mut stream = TcpStream::connect_timeout(&addr, TIMEOUT).map_err(|e| e.to_string())?;
+    stream.set_read_timeout(Some(TIMEOUT)).map_err(|e| e.to_string())?;
+    stream.set_write_timeout(Some(TIMEOUT)).map_err(|e| e.to_string())?;
+    write!(
+        stream,
+        "POST {path} HTTP/1.1\r\nHost: {host}\r\nUser-Agent: zmiy/{}\r\nContent-Type: application/json\r\n\
+         Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        env!("CARGO_PKG_VERSION"),
+        body.len()
+    )
+    .map_err(|e| e.to_string())?;
+    // Хватит строки статуса: `HTTP/1.1 201 Created`
+    let mut head = [0; 64];
+    let n = stream.read(&mut head).map_err(|e| e.to_string())?;
+    let status = String::from_utf8_lossy(&head[..n]);
+    match status.split_whitespace().nth(1) {
+        Some(code) if code.starts_with('2') => Ok(()),
+        Some(code) => Err(format!("сервер ответил {code}")),
+        None => Err("сервер не ответил".to_string()),
+    }
+}
+
+/// Предупреждение для адреса без шифрования; `None`, если адрес не `http://`
+pub fn warning(url: &str) -> Option<&'static str> {
+    url.starts_with("http://").then_some("без шифрования: имя, счёт и сид видны в сети")
+}
+
+/// Хост с портом и путь из адреса таблицы
+pub fn parse_url(url: &str) -> Result<(String, String), String> {
+    if url.starts_with("https://") {
+        return Err("HTTPS не поддерживается: в сборке нет TLS, нужен адрес http://".to_string());
+    }
+    let rest = url.strip_prefix("http://").ok_or("адрес должен начинаться с http://")?;
+    let (host, path) = rest.split_once('/').map_or((rest, "/".to_string()), |(h, p)| (h, format!("/{p}")));
+    if host.is_empty() {
+        return Err("в адресе нет хоста".to_string());
+    }
+    let host = if host.contains(':') { host.to_string() } else { format!("{host}:80") };
+    Ok((host, path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn submission() -> Submission {
+        Submission { name: "Оля \"О\"".into(), score: 57, mode: "классика".into(), seed: 1234, duration: Duration::from_secs(61) }
+    }
+
+    #[test]
+    fn submission_json() {
+        assert_eq!(
+            submission().to_json(),
+            format!(
+                "{{\"name\":\"Оля \\\"О\\\"\",\"score\":57,\"mode\":\"классика\",\"seed\":1234,\"duration_ms\":61000,\"version\":\"{}\"}}",
+                env!("CARGO_PKG_VERSION")
+            )
+        );
+    }
+
+    #[test]
+    fn urls() {
+        assert_eq!(parse_url("http://example.org/api/scores"), Ok(("example.org:80".into(), "/api/scores".into())));
+        assert_eq!(parse_url("http://127.0.0.1:8080"), Ok(("127.0.0.1:8080".into(), "/".into())));
+        assert!(parse_url("https://example.org").unwrap_err().contains("HTTPS"));
+        assert!(parse_url("ftp://example.org").is_err());
+        assert!(warning("http://example.org").is_some());
+        assert_eq!(warning("https://example.org"), None);
+    }
+
+    #[test]
+    fn queue_survives_failures() {
+        let mut queue = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        let mut accepted = 1;
+        let status = flush(&mut queue, |_| {
+            if accepted == 0 {
+                return Err("нет сети".to_string());
+            }
+            accepted -= 1;
+            Ok(())
+        });
+        assert_eq!(status, Status::Queued(2, "нет сети".to_string()));
+        assert_eq!(queue, ["b", "c"]);
+        assert_eq!(flush(&mut queue, |_| Ok(())), Status::Sent(2));
+
+        let path = std::env::temp_dir().join(format!("zmiy-leaderboard-{}.txt", std::process::id()));
+        store_queue(&path, &["{\"score\":1}".to_string()]).unwrap();
+        assert_eq!(load_queue(&path).unwrap(), ["{\"score\":1}"]);
+        store_queue(&path, &[]).unwrap();
+        assert!(!path.exists());
+        assert!(load_queue(&path).unwrap().is_empty());
+    }
+
+    #[test]
+    fn uploader_sends_the_old_queue_first() {
+        let path = std::env::temp_dir().join(format!("zmiy-leaderboard-up-{}.txt", std::process::id()));
+        store_queue(&path, &["старое".to_string()]).unwrap();
+        let (tx, sent) = mpsc::channel();
+        let uploader = Uploader::start(Some(path.clone()), move |body| {
+            tx.send(body.to_string()).unwrap();
+            Ok(())
+        });
+        uploader.submit(&submission());
+        assert_eq!(sent.recv_timeout(Duration::from_secs(5)).unwrap(), "старое");
+        assert!(sent.recv_timeout(Duration::from_secs(5)).unwrap().contains("\"score\":57"));
+        assert!(!path.exists());
+    }
+
+    #[cfg(feature = "leaderboard")]
+    #[test]
+    fn posts_over_http() {
+        use std::{
+            io::{BufRead, BufReader, Write},
+            net::TcpListener,
+        };
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/scores", listener.local_addr().unwrap());
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request = String::new();
+            reader.read_line(&mut request).unwrap();
+            (&stream).write_all(b"HTTP/1.1 201 Created\r\nContent-Length: 0\r\n\r\n").unwrap();
+            request
+        });
+        assert_eq!(post(&url, "{}"), Ok(()));
+        assert_eq!(server.join().unwrap(), "POST /scores HTTP/1.1\r\n");
+    }
+}
@@ -0,0 +1,299 @@
+//! Уровни: поле своего размера со стенами внутри, которые делят его на комнаты.
+//!
+//! Формат текстовый, как у сохранений, а поле нарисовано рядами:
+//!
+//! ```text
+//! zmiy-level 3             сигнатура и версия формата
+//! name две комнаты         название: под ним идут рекорды, сохранения и повторы
+//! board 40 20              размер поля
+//! author Оля               автор (необязательно)
+//! speed 100                интервал между шагами в мс, с которым уровень задуман
+//!                          (необязательно; `--speed` важнее)
+//! target 50                счёт, который надо набрать (необязательно)
+//! row ...++....#.....--...  ряды поля сверху вниз: # — стена, . — свободная клетка,
+//!                          + — быстрая зона, - — медленная (см. [`Zone`])
+//! ```
+//!
+//! Уровни старых форматов читаются через [`crate::persist`].
+//!
+//! Змейка начинает в середине поля, первая еда стоит в трети его ширины и высоты,
+//! поэтому эти клетки должны быть свободны. Все свободные клетки связаны между собой:
+//! в каждую комнату ведёт проход хотя бы в одну клетку. Стены занимают не больше
+//! половины поля.
+//!
+//! Уровень можно нарисовать и в игре: см. [`crate::editor`].
+//!
+//! Уровень пройден, когда счёт дошёл до его пара — цели из заголовка, а без неё
+//! очка за каждые [`PAR_CELLS`] свободных клеток. За прохождение дают звёзды
+//! (см. [`crate::stars`]).
+
+use std::{
+    collections::VecDeque,
+    fmt, fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use crate::{
+    cli::MIN_SIZE,
+    game::{Game, Mode, Point, Zone},
+    paths::data_dir,
+    persist::{self, FormatError},
+};
+
+/// Версия формата уровня
+pub const FORMAT_VERSION: u32 = 3;
+/// Первое слово файла
+pub const SIGNATURE: &str = "zmiy-level";
+/// Расширение файлов уровней
+pub const EXTENSION: &str = "zlv";
+
+/// Какая доля поля в процентах может быть занята стенами
+pub const MAX_WALLS_PERCENT: usize = 50;
+/// На сколько свободных клеток приходится очко пара у уровня без цели
+pub const PAR_CELLS: usize = 10;
+/// Сколько времени даётся на очко пара
+pub const PAR_TIME_PER_POINT: Duration = Duration::from_secs(3);
+
+/// Встроенный уровень режима «две комнаты»
+const TWO_ROOMS: &str = include_str!("../levels/two-rooms.zlv");
+
+/// Прочитанный файл уровня
+pub use crate::persist::Level;
+
+/// Папка уровней, нарисованных в редакторе: `<данные>/levels`
+pub fn dir() -> Option<PathBuf> {
+    Some(data_dir()?.join("levels"))
+}
+
+/// Клетки, которые на поле `board` должны быть свободны: начало змейки и первая еда
+pub fn start_cells(board: Point) -> [Point; 2] {
+    [Point { x: board.x / 2, y: board.y / 2 }, Point { x: board.x / 3, y: board.y / 3 }]
+}
+
+/// Почему уровень не удалось загрузить
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum LevelError {
+    /// Файл не прочитался
+    Io(String),
+    /// Файл не в формате уровня
+    Format(FormatError),
+    /// Поле меньше [`MIN_SIZE`]
+    TooSmall,
+    /// Стена в клетке, где начинает змейка или стоит первая еда
+    StartBlocked,
+    /// Не до всех свободных клеток можно дойти от начала
+    Closed,
+    /// Стены занимают больше [`MAX_WALLS_PERCENT`] поля
+    Crowded,
+}
+
+impl fmt::Display for LevelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LevelError::Io(e) => write!(f, "{e}"),
+            LevelError::Format(FormatError::Foreign) => write!(f, "это не файл уровня"),
+            LevelError::Format(e) => write!(f, "{e}"),
+            LevelError::TooSmall => write!(f, "поле уровня меньше {}x{}", MIN_SIZE.0, MIN_SIZE.1),
+            LevelError::StartBlocked => write!(f, "стена там, где начинает змейка или стоит первая еда"),
+            LevelError::Closed => write!(f, "в уровне есть комната без прохода"),
+            LevelError::Crowded => write!(f, "стены занимают больше {MAX_WALLS_PERCENT}% поля"),
+        }
+    }
+}
+
+impl std::error::Error for LevelError {}
+
+impl From<FormatError> for LevelError {
+    fn from(e: FormatError) -> Self {
+        LevelError::Format(e)
+    }
+}
+
+impl Level {
+    /// Читает уровень и проверяет, что на нём можно играть
+    pub fn parse(text: &str) -> Result<Self, LevelError> {
+        persist::read_level(text)?.check()
+    }
+
+    /// Проверяет, что на уровне можно играть: см. правила в описании модуля
+    pub fn check(self) -> Result<Self, LevelError> {
+        let level = self;
+        let Point { x: width, y: height } = level.board;
+        if width < MIN_SIZE.0 || height < MIN_SIZE.1 {
+            return Err(LevelError::TooSmall);
+        }
+        if level.walls.len() * 100 > width as usize * height as usize * MAX_WALLS_PERCENT {
+            return Err(LevelError::Crowded);
+        }
+        let wall = |p: &Point| level.walls.contains(p);
+        let [start, food] = start_cells(level.board);
+        if wall(&start) || wall(&food) {
+            return Err(LevelError::StartBlocked);
+        }
+        // Обход в ширину от начала змейки должен найти все свободные клетки
+        let index = |p: Point| p.y as usize * width as usize + p.x as usize;
+        let mut seen = vec![false; width as usize * height as usize];
+        for p in &level.walls {
+            seen[index(*p)] = true;
+        }
+        seen[index(start)] = true;
+        let mut queue = VecDeque::from([start]);
+        while let Some(Point { x, y }) = queue.pop_front() {
+            let around = [
+                x.checked_sub(1).map(|x| Point { x, y }),
+                (x + 1 < width).then(|| Point { x: x + 1, y }),
+                y.checked_sub(1).map(|y| Point { x, y }),
+                (y + 1 < height).then(|| Point { x, y: y + 1 }),
+            ];
+            for p in around.into_iter().flatten() {
+                if !seen[index(p)] {
+                    seen[index(p)] = true;
+                    queue.push_back(p);
+                }
+            }
+        }
+        if seen.contains(&false) {
+            return Err(LevelError::Closed);
+        }
+        Ok(level)
+    }
+
+    /// Загружает уровень из файла
+    pub fn load(path: &Path) -> Result<Self, LevelError> {
+        let text = fs::read_to_string(path).map_err(|e| LevelError::Io(e.to_string()))?;
+        Self::parse(&text)
+    }
+
+    /// Встроенный уровень режима; `None`, если режим играется на пустом поле
+    pub fn built_in(mode: Mode) -> Option<Self> {
+        match mode {
+            Mode::TwoRooms => Some(Self::parse(TWO_ROOMS).expect("встроенный уровень читается")),
+            Mode::Classic | Mode::TwoFood | Mode::Nokia | Mode::Endless => None,
+        }
+    }
+
+    /// Счёт, до которого уровень надо пройти: цель или очко на [`PAR_CELLS`] свободных клеток
+    pub fn par(&self) -> usize {
+        let free = self.board.x as usize * self.board.y as usize - self.walls.len();
+        self.target.unwrap_or(free / PAR_CELLS).max(1)
+    }
+
+    /// Время, за которое уровень проходят на три звезды
+    pub fn par_time(&self) -> Duration {
+        PAR_TIME_PER_POINT * self.par() as u32
+    }
+
+    /// Хеш раскладки уровня: поля, стен и зон, но не названия и заголовка. По нему
+    /// запоминаются звёзды, поэтому переименованный уровень остаётся тем же.
+    pub fn layout_hash(&self) -> u64 {
+        const PRIME: u64 = 0x0100_0000_01b3;
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        let mut feed = |bytes: &[u8]| {
+            for &b in bytes {
+                hash = (hash ^ u64::from(b)).wrapping_mul(PRIME);
+            }
+        };
+        feed(&self.board.x.to_le_bytes());
+        feed(&self.board.y.to_le_bytes());
+        for cells in [&self.walls, &self.fast, &self.slow] {
+            feed(&(cells.len() as u64).to_le_bytes());
+            for p in cells {
+                feed(&p.x.to_le_bytes());
+                feed(&p.y.to_le_bytes());
+            }
+        }
+        hash
+    }
+
+    /// Ставит стены и зоны скорости уровня на поле партии
+    pub fn apply(&self, game: &mut Game) {
+        game.set_walls(self.walls.iter().copied());
+        game.set_zones(Zone::Fast, self.fast.iter().copied());
+        game.set_zones(Zone::Slow, self.slow.iter().copied());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Уровень 7×5 с рядами `rows`
+    fn level(rows: [&str; 5]) -> String {
+        let rows: String = rows.iter().map(|r| format!("row {r}\n")).collect();
+        format!("zmiy-level 3\nname тест\nboard 7 5\n{rows}")
+    }
+
+    #[test]
+    fn built_in_two_rooms() {
+        let level = Level::built_in(Mode::TwoRooms).unwrap();
+        assert_eq!((level.name.as_str(), level.board), (Mode::TwoRooms.name(), Point { x: 40, y: 20 }));
+        // Одна стена во всю высоту с проходом в одну клетку
+        assert_eq!(level.walls.len(), 19);
+        assert!(level.walls.iter().all(|p| p.x == 26));
+        assert!(Level::built_in(Mode::Classic).is_none());
+    }
+
+    #[test]
+    fn rooms_are_parsed_and_checked() {
+        let two_rooms = level(["....#..", "....#..", "....#..", ".......", "....#.."]);
+        let parsed = Level::parse(&two_rooms).unwrap();
+        assert_eq!(parsed.walls, (0..5).filter(|&y| y != 3).map(|y| Point { x: 4, y }).collect::<Vec<_>>());
+        let mut game = Game::with_seed(7, 5, 1);
+        parsed.apply(&mut game);
+        assert!(game.is_wall(Point { x: 4, y: 0 }) && !game.is_wall(Point { x: 4, y: 3 }));
+
+        let zoned = Level::parse(&level(["++.....", ".......", ".......", "....--.", "......."])).unwrap();
+        assert_eq!((zoned.fast.len(), &zoned.slow[..], zoned.walls.len()), (2, &[Point { x: 4, y: 3 }, Point { x: 5, y: 3 }][..], 0));
+        zoned.apply(&mut game);
+        assert_eq!((game.zone(Point { x: 1, y: 0 }), game.zone(Point { x: 5, y: 3 })), (Some(Zone::Fast), Some(Zone::Slow)));
+        // В формате 1 зон ещё не было
+        let old = level(["++.....", ".......", ".......", ".......", "......."]).replacen("zmiy-level 3", "zmiy-level 1", 1);
+        assert_eq!(Level::parse(&old), Err(LevelError::Format(FormatError::BadLine(4))));
+
+        let closed = level(["....#..", "....#..", "....#..", "....#..", "....#.."]);
+        assert_eq!(Level::parse(&closed), Err(LevelError::Closed));
+        let blocked = level([".......", ".......", "...#...", ".......", "......."]);
+        assert_eq!(Level::parse(&blocked), Err(LevelError::StartBlocked));
+        let short = level([".......", ".......", ".....", ".......", "......."]);
+        assert_eq!(Level::parse(&short), Err(LevelError::Format(FormatError::BadLine(6))));
+        let odd = level([".......", ".......", "...x...", ".......", "......."]);
+        assert_eq!(Level::parse(&odd), Err(LevelError::Format(FormatError::BadLine(6))));
+        let missing = two_rooms.replacen("row .......\n", "", 1);
+        assert_eq!(Level::parse(&missing), Err(LevelError::Format(FormatError::MissingField("row"))));
+        let tiny = "zmiy-level 3\nname тест\nboard 3 3\nrow ...\nrow ...\nrow ...\n";
+        assert_eq!(Level::parse(tiny), Err(LevelError::TooSmall));
+        assert_eq!(LevelError::Format(FormatError::Foreign).to_string(), "это не файл уровня");
+    }
+
+    #[test]
+    fn written_level_reads_back() {
+        let text = level(["#+.....", "#......", ".......", "....--.", "......#"]);
+        let parsed = Level::parse(&text).unwrap();
+        assert_eq!(parsed.to_text(), text);
+        // Необязательные строки заголовка пишутся после поля
+        let described = text.replacen("board 7 5\n", "board 7 5\nauthor кто-то\nspeed 80\ntarget 30\n", 1);
+        let parsed = Level::parse(&described).unwrap();
+        assert_eq!((parsed.author.as_deref(), parsed.speed, parsed.target), (Some("кто-то"), Some(Duration::from_millis(80)), Some(30)));
+        assert_eq!(parsed.to_text(), described);
+        let malformed = text.replacen("board 7 5\n", "board 7 5\nspeed быстро\n", 1);
+        assert_eq!(Level::parse(&malformed), Err(LevelError::Format(FormatError::BadLine(4))));
+        // Стены больше чем на половине поля
+        let crowded = level(["#######", "#######", "##.....", ".......", "#######"]);
+        assert_eq!(Level::parse(&crowded), Err(LevelError::Crowded));
+    }
+
+    #[test]
+    fn par_and_layout_hash() {
+        let two_rooms = Level::built_in(Mode::TwoRooms).unwrap();
+        assert_eq!((two_rooms.par(), two_rooms.par_time()), (78, Duration::from_secs(234)));
+        let aimed = Level { target: Some(5), ..two_rooms.clone() };
+        assert_eq!(aimed.par(), 5);
+        // Заголовок не меняет раскладку, стена — меняет
+        let renamed = Level { name: "другое".to_string(), author: Some("кто-то".to_string()), ..aimed };
+        assert_eq!(renamed.layout_hash(), two_rooms.layout_hash());
+        let mut walled = two_rooms.clone();
+        walled.walls.pop();
+        assert_ne!(walled.layout_hash(), two_rooms.layout_hash());
+    }
+}
@@ -0,0 +1,41 @@
+//! Змейка для терминала: игровая логика и всё, что нужно для её отрисовки.
+//! Бинарник `zmiy` — только настройка терминала и главный цикл.
+
+pub mod bot;
+pub mod broadcast;
+pub mod cast;
+pub mod cli;
+pub mod config;
+#[cfg(unix)]
+pub mod control;
+pub mod custom_levels;
+pub mod dump;
+pub mod editor;
+pub mod ghost;
+pub use zmiy_core::game;
+pub mod hud;
+pub mod input;
+pub mod leaderboard;
+pub mod level;
+pub mod lifetime;
+pub mod log;
+pub mod pause;
+pub mod paths;
+pub mod persist;
+pub mod profile;
+pub mod replay;
+pub mod report;
+pub mod save;
+pub mod scores;
+pub mod settings;
+pub mod sim;
+pub mod sound;
+pub mod splash;
+pub mod stars;
+pub mod term;
+pub mod text_input;
+pub mod theme;
+pub mod ticker;
+pub mod toast;
+pub mod versus;
+pub mod widget;
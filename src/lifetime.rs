@@ -0,0 +1,355 @@
+//! Статистика за всё время: сколько сыграно партий, съедено еды, проведено времени
+//! в игре и отчего змейка погибала. Хранится в `<данные>/stats.txt` и обновляется
+//! в конце каждой партии; показывается на вкладке «Статистика» меню паузы, а сводка
+//! с лучшим счётом и партиями за сегодня — по `--stats` (и `--stats --json`).
+//!
+//! ```text
+//! zmiy-stats 2
+//! games 12
+//! food 340
+//! played_ms 3600000
+//! longest 57
+//! wall 5
+//! self_collision 6
+//! board_too_small 0
+//! board_full 1
+//! day 19650
+//! games_day 3
+//! ```
+//!
+//! `day` — день последней партии (номер дня UTC с начала эпохи Unix), `games_day` — сколько
+//! партий сыграно в этот день. В формате 1 этих полей не было.
+
+use std::{
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use crate::{
+    dump::json_string,
+    game::{DeathCause, Game},
+    paths::{data_dir, write_atomic},
+    scores::HighScores,
+};
+
+/// Версия формата файла статистики
+pub const FORMAT_VERSION: u32 = 2;
+/// Первое слово файла
+pub const SIGNATURE: &str = "zmiy-stats";
+
+/// Файл статистики: `<данные>/stats.txt`
+pub fn path() -> Option<PathBuf> {
+    Some(data_dir()?.join("stats.txt"))
+}
+
+/// Почему файл статистики не удалось прочитать
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum LifetimeError {
+    /// Файл не прочитался
+    Io(String),
+    /// Нет сигнатуры `zmiy-stats`
+    NotStats,
+    /// Формат другой версии
+    UnsupportedFormat(u32),
+    /// Непонятная строка, номер с единицы
+    BadLine(usize),
+}
+
+impl fmt::Display for LifetimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LifetimeError::Io(e) => write!(f, "{e}"),
+            LifetimeError::NotStats => write!(f, "это не файл статистики"),
+            LifetimeError::UnsupportedFormat(v) => {
+                write!(f, "формат статистики версии {v} не поддерживается (нужна {FORMAT_VERSION})")
+            }
+            LifetimeError::BadLine(n) => write!(f, "ошибка в строке {n}"),
+        }
+    }
+}
+
+impl std::error::Error for LifetimeError {}
+
+/// Итоги всех законченных партий
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+pub struct Lifetime {
+    pub games: u64,
+    /// Съедено еды за все партии
+    pub food: u64,
+    pub played: Duration,
+    /// Самая длинная змейка
+    pub longest: u64,
+    /// Сколько партий закончилось каждой причиной, в порядке [`DeathCause::ALL`]
+    pub endings: [u64; DeathCause::ALL.len()],
+    /// День последней партии: номер дня UTC с начала эпохи Unix
+    pub day: u64,
+    /// Партий в день `day`
+    pub games_day: u64,
+}
+
+impl Lifetime {
+    /// Учитывает партию, законченную в `date` (секунды с начала эпохи Unix)
+    pub fn record(&mut self, game: &Game, played: Duration, date: u64) {
+        let day = date / 86_400;
+        if day != self.day {
+            self.day = day;
+            self.games_day = 0;
+        }
+        self.games_day += 1;
+        self.games += 1;
+        self.food += game.score as u64;
+        self.played += played;
+        self.longest = self.longest.max(game.snake().len() as u64);
+        if let Some(cause) = game.game_over
+            && let Some(i) = DeathCause::ALL.iter().position(|c| *c == cause)
+        {
+            self.endings[i] += 1;
+        }
+    }
+
+    /// Сколько партий сыграно в день, куда попадает `date`
+    pub fn games_on(&self, date: u64) -> u64 {
+        if date / 86_400 == self.day { self.games_day } else { 0 }
+    }
+
+    /// Подписи и значения для экрана и `--stats`
+    pub fn lines(&self) -> Vec<(&'static str, String)> {
+        let mut lines = vec![
+            ("Сыграно партий", group_digits(self.games)),
+            ("Съедено еды", group_digits(self.food)),
+            ("Время в игре", format_long_duration(self.played)),
+            ("Самая длинная змейка", group_digits(self.longest)),
+        ];
+        lines.extend(DeathCause::ALL.iter().zip(self.endings).map(|(c, n)| (c.describe(), group_digits(n))));
+        lines
+    }
+
+    /// Статистика текстом: подпись и значение, выровненные в две колонки
+    pub fn report(&self) -> String {
+        columns(&self.lines())
+    }
+
+    /// Статистика в формате файла: сигнатура, затем по полю на строку
+    pub fn to_text(&self) -> String {
+        let mut text = format!(
+            "{SIGNATURE} {FORMAT_VERSION}\ngames {}\nfood {}\nplayed_ms {}\nlongest {}\n",
+            self.games,
+            self.food,
+            self.played.as_millis(),
+            self.longest
+        );
+        for (cause, n) in DeathCause::ALL.iter().zip(self.endings) {
+            text.push_str(&format!("{} {n}\n", cause.code()));
+        }
+        text.push_str(&format!("day {}\ngames_day {}\n", self.day, self.games_day));
+        text
+    }
+
+    /// Разбирает файл формата 1 или 2. Отсутствующие поля считаются нулями.
+    pub fn parse(text: &str) -> Result<Self, LifetimeError> {
+        let mut lines = text.lines();
+        let format = match lines.next().map(|l| l.split_whitespace().collect::<Vec<_>>()) {
+            Some(words) if words.first() == Some(&SIGNATURE) => words.get(1).and_then(|v| v.parse().ok()),
+            _ => return Err(LifetimeError::NotStats),
+        };
+        let format = match format {
+            Some(v @ 1..=FORMAT_VERSION) => v,
+            Some(other) => return Err(LifetimeError::UnsupportedFormat(other)),
+            None => return Err(LifetimeError::BadLine(1)),
+        };
+        let mut stats = Self::default();
+        for (i, line) in lines.enumerate().filter(|(_, l)| !l.trim().is_empty()) {
+            let bad = || LifetimeError::BadLine(i + 2);
+            let [key, value] = line.split_whitespace().collect::<Vec<_>>()[..] else {
+                return Err(bad());
+            };
+            let value: u64 = value.parse().map_err(|_| bad())?;
+            match key {
+                "games" => stats.games = value,
+                "food" => stats.food = value,
+                "played_ms" => stats.played = Duration::from_millis(value),
+                "longest" => stats.longest = value,
+                "day" if format >= 2 => stats.day = value,
+                "games_day" if format >= 2 => stats.games_day = value,
+                code => {
+                    let cause = DeathCause::from_code(code).ok_or_else(bad)?;
+                    let i = DeathCause::ALL.iter().position(|c| *c == cause).unwrap_or_default();
+                    stats.endings[i] = value;
+                }
+            }
+        }
+        Ok(stats)
+    }
+
+    /// Загружает статистику; если файла ещё нет — пустая
+    pub fn load(path: &Path) -> Result<Self, LifetimeError> {
+        match fs::read_to_string(path) {
+            Ok(text) => Self::parse(&text),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(LifetimeError::Io(e.to_string())),
+        }
+    }
+
+    pub fn store(&self, path: &Path) -> io::Result<()> {
+        write_atomic(path, self.to_text().as_bytes())
+    }
+}
+
+/// Подписи и значения в две колонки, подписи с двоеточием
+fn columns(lines: &[(&str, String)]) -> String {
+    let width = lines.iter().map(|(label, _)| label.chars().count()).max().unwrap_or(0) + 1;
+    lines.iter().map(|(label, value)| format!("{:<width$} {value}\n", format!("{label}:"), width = width)).collect()
+}
+
+/// Сводка для `--stats`: лучший счёт из таблицы рекордов, партии за сегодня
+/// и статистика за всё время
+pub struct Summary<'a> {
+    pub stats: &'a Lifetime,
+    /// Лучший счёт и режим, в котором он набран
+    pub best: Option<(usize, &'a str)>,
+    pub games_today: u64,
+}
+
+impl<'a> Summary<'a> {
+    /// Сводка на момент `now` (секунды с начала эпохи Unix)
+    pub fn new(stats: &'a Lifetime, scores: &'a HighScores, now: u64) -> Self {
+        Self { stats, best: scores.top().map(|e| (e.score, e.mode.as_str())), games_today: stats.games_on(now) }
+    }
+
+    pub fn to_text(&self) -> String {
+        let best = self.best.map_or("нет".to_string(), |(score, mode)| format!("{} ({mode})", group_digits(score as u64)));
+        let mut lines = vec![("Лучший счёт", best), ("Сегодня партий", group_digits(self.games_today))];
+        lines.extend(self.stats.lines());
+        columns(&lines)
+    }
+
+    /// Сводка одной строкой JSON
+    pub fn to_json(&self) -> String {
+        let (best, mode) = match self.best {
+            Some((score, mode)) => (score.to_string(), json_string(mode)),
+            None => ("null".to_string(), "null".to_string()),
+        };
+        let deaths: Vec<String> =
+            DeathCause::ALL.iter().zip(self.stats.endings).map(|(c, n)| format!("\"{}\":{n}", c.code())).collect();
+        format!(
+            "{{\"best_score\":{best},\"best_mode\":{mode},\"games_today\":{},\"games\":{},\"played_ms\":{},\
+             \"food\":{},\"longest\":{},\"deaths\":{{{}}}}}",
+            self.games_today,
+            self.stats.games,
+            self.stats.played.as_millis(),
+            self.stats.food,
+            self.stats.longest,
+            deaths.join(",")
+        )
+    }
+}
+
+/// Число с разрядами через пробел: `1 234 567`
+pub fn group_digits(n: u64) -> String {
+    let digits = n.to_string();
+    let mut out = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(' ');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Длительность двумя старшими единицами: `45 с`, `12 мин 5 с`, `3 ч 25 мин`, `2 дн 4 ч`
+pub fn format_long_duration(d: Duration) -> String {
+    let secs = d.as_secs();
+    let (days, hours, minutes, seconds) = (secs / 86_400, secs / 3600 % 24, secs / 60 % 60, secs % 60);
+    if days > 0 {
+        format!("{} дн {hours} ч", group_digits(days))
+    } else if hours > 0 {
+        format!("{hours} ч {minutes} мин")
+    } else if minutes > 0 {
+        format!("{minutes} мин {seconds} с")
+    } else {
+        format!("{seconds} с")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Point;
+
+    /// 2023-11-14 00:00 UTC
+    const DAY: u64 = 19_675 * 86_400;
+
+    #[test]
+    fn record_and_round_trip() {
+        let mut stats = Lifetime::default();
+        let mut game = Game::with_seed(10, 6, 1);
+        game.set_snake([Point { x: 3, y: 1 }, Point { x: 2, y: 1 }, Point { x: 1, y: 1 }]);
+        game.score = 2;
+        game.game_over = Some(DeathCause::Wall);
+        stats.record(&game, Duration::from_secs(30), DAY);
+        game.score = 5;
+        game.game_over = Some(DeathCause::SelfCollision);
+        stats.record(&game, Duration::from_millis(4500), DAY + 60);
+        assert_eq!((stats.games, stats.food, stats.longest), (2, 7, 3));
+        assert_eq!(stats.played, Duration::from_millis(34_500));
+        assert_eq!(stats.endings, [1, 1, 0, 0, 0, 0]);
+        assert_eq!((stats.games_on(DAY + 3600), stats.games_on(DAY + 86_400)), (2, 0));
+        assert_eq!(Lifetime::parse(&stats.to_text()), Ok(stats.clone()));
+        // Первая партия нового дня начинает счёт заново
+        stats.record(&game, Duration::from_secs(1), DAY + 86_400);
+        assert_eq!((stats.games, stats.games_on(DAY + 86_400)), (3, 1));
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(Lifetime::parse("zmiy-scores 1\n"), Err(LifetimeError::NotStats));
+        assert_eq!(Lifetime::parse("zmiy-stats 3\n"), Err(LifetimeError::UnsupportedFormat(3)));
+        // Файл формата 1 читается, но полей дня в нём быть не может
+        assert_eq!(Lifetime::parse("zmiy-stats 1\ngames 4\n").unwrap().games, 4);
+        assert_eq!(Lifetime::parse("zmiy-stats 1\nday 5\n"), Err(LifetimeError::BadLine(2)));
+        assert_eq!(Lifetime::parse("zmiy-stats 1\ngames 3\nlava 1\n"), Err(LifetimeError::BadLine(3)));
+        assert_eq!(Lifetime::parse("zmiy-stats 1\nfood -1\n"), Err(LifetimeError::BadLine(2)));
+        // Недостающие поля — нули
+        assert_eq!(Lifetime::parse("zmiy-stats 1\n\ngames 3\n").unwrap().games, 3);
+    }
+
+    #[test]
+    fn human_formats() {
+        assert_eq!(group_digits(0), "0");
+        assert_eq!(group_digits(999), "999");
+        assert_eq!(group_digits(1000), "1 000");
+        assert_eq!(group_digits(1_234_567), "1 234 567");
+        assert_eq!(format_long_duration(Duration::from_secs(45)), "45 с");
+        assert_eq!(format_long_duration(Duration::from_secs(12 * 60 + 5)), "12 мин 5 с");
+        assert_eq!(format_long_duration(Duration::from_secs(3 * 3600 + 25 * 60 + 9)), "3 ч 25 мин");
+        assert_eq!(format_long_duration(Duration::from_secs(2 * 86_400 + 4 * 3600)), "2 дн 4 ч");
+
+        let report = Lifetime { games: 1500, ..Default::default() }.report();
+        assert!(report.starts_with("Сыграно партий:               1 500\n"), "{report}");
+        assert!(report.contains("Поле стало слишком маленьким: 0\n"), "{report}");
+    }
+
+    #[test]
+    fn summary_for_the_prompt() {
+        let mut scores = HighScores::default();
+        let stats = Lifetime { games: 12, games_day: 3, day: DAY / 86_400, played: Duration::from_secs(3600), ..Default::default() };
+        let empty = Summary::new(&stats, &scores, DAY + 100);
+        assert!(empty.to_text().starts_with("Лучший счёт:                  нет\nСегодня партий:               3\n"), "{}", empty.to_text());
+        assert!(empty.to_json().starts_with("{\"best_score\":null,\"best_mode\":null,\"games_today\":3,\"games\":12,\"played_ms\":3600000,"));
+
+        scores.insert(crate::scores::ScoreEntry {
+            name: "x".into(),
+            score: 1234,
+            length: 1235,
+            mode: "классика".into(),
+            date: DAY,
+            duration: Duration::from_secs(60),
+        });
+        let summary = Summary::new(&stats, &scores, DAY + 86_400);
+        assert!(summary.to_text().starts_with("Лучший счёт:                  1 234 (классика)\nСегодня партий:               0\n"), "{}", summary.to_text());
+        assert!(summary.to_json().starts_with("{\"best_score\":1234,\"best_mode\":\"классика\",\"games_today\":0,"));
+        assert!(summary.to_json().ends_with("\"deaths\":{\"wall\":0,\"self_collision\":0,\"board_too_small\":0,\"board_full\":0,\"obstacle\":0,\"bomb\":0}}"));
+    }
+}
@@ -0,0 +1,222 @@
+//! Журнал для разбора проблем: `~/.local/state/zmiy/zmiy.log`.
+//!
+//! Включается `--debug` или переменной `ZMIY_LOG=error|warn|info|debug|trace`. Пишется только
+//! в файл: пока открыт полноэкранный режим, вывод в stdout/stderr испортил бы картинку.
+//! Файл больше [`MAX_SIZE`] переименовывается в `zmiy.log.1`, и журнал начинается заново.
+
+use std::{
+    fmt,
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{paths::state_dir, scores::format_date};
+
+/// Размер файла, после которого журнал начинается заново
+pub const MAX_SIZE: u64 = 4 * 1024 * 1024;
+
+/// Файл журнала по умолчанию
+pub fn path() -> Option<PathBuf> {
+    Some(state_dir()?.join("zmiy.log"))
+}
+
+/// Важность записи: чем дальше, тем подробнее
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Level {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl Level {
+    /// Уровень из `ZMIY_LOG`; `off` и пустая строка — журнал выключен
+    pub fn parse(name: &str) -> Option<Option<Self>> {
+        Some(Some(match name.trim().to_ascii_lowercase().as_str() {
+            "" | "off" => return Some(None),
+            "error" => Level::Error,
+            "warn" => Level::Warn,
+            "info" => Level::Info,
+            "debug" => Level::Debug,
+            "trace" => Level::Trace,
+            _ => return None,
+        }))
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Level::Error => "ERROR",
+            Level::Warn => "WARN ",
+            Level::Info => "INFO ",
+            Level::Debug => "DEBUG",
+            Level::Trace => "TRACE",
+        }
+    }
+}
+
+/// Открытый файл журнала
+pub struct Logger {
+    path: PathBuf,
+    file: File,
+    level: Level,
+    max_size: u64,
+    /// Сколько байт уже в файле
+    size: u64,
+}
+
+impl Logger {
+    /// Открывает журнал на дозапись; слишком большой файл сразу откладывается в `.1`
+    pub fn open(path: &Path, level: Level, max_size: u64) -> io::Result<Self> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let size = fs::metadata(path).map_or(0, |m| m.len());
+        if size >= max_size {
+            fs::rename(path, rotated(path))?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let size = file.metadata()?.len();
+        Ok(Self { path: path.to_path_buf(), file, level, max_size, size })
+    }
+
+    pub fn enabled(&self, level: Level) -> bool {
+        level <= self.level
+    }
+
+    /// Дописывает строку `<время> <уровень> <сообщение>`
+    pub fn write(&mut self, level: Level, message: fmt::Arguments) -> io::Result<()> {
+        if !self.enabled(level) {
+            return Ok(());
+        }
+        let line = format!("{} {} {message}\n", timestamp(), level.label());
+        if self.size > 0 && self.size + line.len() as u64 > self.max_size {
+            fs::rename(&self.path, rotated(&self.path))?;
+            self.file = File::create(&self.path)?;
+            self.size = 0;
+        }
+        self.file.write_all(line.as_bytes())?;
+        self.size += line.len() as u64;
+        Ok(())
+    }
+}
+
+/// Имя отложенного файла: `zmiy.log` → `zmiy.log.1`
+fn rotated(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".1");
+    PathBuf::from(name)
+}
+
+/// Время UTC с миллисекундами
+fn timestamp() -> String {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = now.as_secs();
+    let (h, m, s) = (secs / 3600 % 24, secs / 60 % 60, secs % 60);
+    format!("{} {h:02}:{m:02}:{s:02}.{:03}", format_date(secs), now.subsec_millis())
+}
+
+static LOGGER: Mutex<Option<Logger>> = Mutex::new(None);
+
+/// Включает журнал программы. До вызова и после ошибки записи макросы ничего не делают.
+pub fn init(logger: Logger) {
+    *LOGGER.lock().unwrap_or_else(|e| e.into_inner()) = Some(logger);
+}
+
+/// Файл журнала программы, если он включён
+pub fn current_path() -> Option<PathBuf> {
+    LOGGER.lock().unwrap_or_else(|e| e.into_inner()).as_ref().map(|l| l.path.clone())
+}
+
+/// Пишет в журнал программы, если он включён. Ошибка записи выключает журнал:
+/// сообщить о ней всё равно некуда.
+pub fn write(level: Level, message: fmt::Arguments) {
+    let mut logger = LOGGER.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(l) = logger.as_mut()
+        && l.write(level, message).is_err()
+    {
+        *logger = None;
+    }
+}
+
+/// Запись об ошибке, которую игра пережила
+#[macro_export]
+macro_rules! log_error {
+    ($($arg:tt)*) => { $crate::log::write($crate::log::Level::Error, format_args!($($arg)*)) };
+}
+
+#[macro_export]
+macro_rules! log_warn {
+    ($($arg:tt)*) => { $crate::log::write($crate::log::Level::Warn, format_args!($($arg)*)) };
+}
+
+#[macro_export]
+macro_rules! log_info {
+    ($($arg:tt)*) => { $crate::log::write($crate::log::Level::Info, format_args!($($arg)*)) };
+}
+
+#[macro_export]
+macro_rules! log_debug {
+    ($($arg:tt)*) => { $crate::log::write($crate::log::Level::Debug, format_args!($($arg)*)) };
+}
+
+/// Самые подробные записи, например каждое нажатие клавиши
+#[macro_export]
+macro_rules! log_trace {
+    ($($arg:tt)*) => { $crate::log::write($crate::log::Level::Trace, format_args!($($arg)*)) };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_log(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("zmiy-log-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir.join("zmiy.log")
+    }
+
+    #[test]
+    fn parses_levels() {
+        assert_eq!(Level::parse("debug"), Some(Some(Level::Debug)));
+        assert_eq!(Level::parse("TRACE"), Some(Some(Level::Trace)));
+        assert_eq!(Level::parse("off"), Some(None));
+        assert_eq!(Level::parse("громко"), None);
+        assert!(Level::Trace > Level::Debug);
+    }
+
+    #[test]
+    fn filters_by_level() {
+        let path = temp_log("level");
+        let mut logger = Logger::open(&path, Level::Info, MAX_SIZE).unwrap();
+        logger.write(Level::Warn, format_args!("размер {}", 7)).unwrap();
+        logger.write(Level::Trace, format_args!("клавиша")).unwrap();
+        let text = fs::read_to_string(&path).unwrap();
+        assert_eq!(text.lines().count(), 1);
+        assert!(text.trim_end().ends_with("WARN  размер 7"), "{text}");
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn rotates_when_full() {
+        let path = temp_log("rotate");
+        let mut logger = Logger::open(&path, Level::Trace, 200).unwrap();
+        for i in 0..10 {
+            logger.write(Level::Debug, format_args!("запись {i}")).unwrap();
+        }
+        let current = fs::metadata(&path).unwrap().len();
+        let old = fs::read_to_string(rotated(&path)).unwrap();
+        assert!(current <= 200);
+        assert!(old.len() <= 200 && old.contains("DEBUG"));
+        assert!(fs::read_to_string(&path).unwrap().contains("запись 9"));
+
+        // Переполненный файл откладывается уже при открытии
+        fs::write(&path, vec![b'x'; 300]).unwrap();
+        let logger = Logger::open(&path, Level::Trace, 200).unwrap();
+        assert_eq!(logger.size, 0);
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
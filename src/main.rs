@@ -1,333 +1,2415 @@
-use crossterm::{
-    event::{self, Event, KeyCode, KeyEvent, KeyEventKind},
-    execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
-};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
 use ratatui::{
-    backend::CrosstermBackend,
-    style::{Color, Style},
+    backend::{Backend, CrosstermBackend},
+    layout::{Alignment, Constraint, Layout, Rect},
+    style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Paragraph},
+    widgets::{Block, Borders, Clear, Gauge, Paragraph, Wrap},
     Terminal,
 };
+#[cfg(unix)]
+use zmiy::control::{self, Command, Control};
+use zmiy::{
+    bot::{self, Bot, BotError},
+    broadcast::{self, Broadcast, Watched},
+    cast::CastWriter,
+    game::{DirectionSnake, FoodKind, Game, GameEvent, Mode, Mutation, Point, Zone},
+    hud::{self, format_duration, HudField},
+    cli::{Options, USAGE},
+    config::{self, Config},
+    custom_levels::{LevelList, LevelListView},
+    dump,
+    editor::{self, Editor, EditorView},
+    ghost::{self, Ghost},
+    input::{Action, Keymap},
+    leaderboard::{self, Submission, Uploader},
+    level,
+    lifetime::{self, Lifetime, Summary},
+    log::{self, Level, Logger},
+    log_debug, log_error, log_info, log_trace, log_warn,
+    pause::{PauseAction, PauseMenu, PauseTab, PauseView},
+    paths,
+    profile::{Choice, ProfileMenu, ProfileView, Profiles},
+    replay::{self, Diverged, Playback, Recorder, Replay},
+    report::{self, Report},
+    save::{self, SavedGame},
+    scores::{self, HighScores, ScoreEntry, ScoresView},
+    settings::Settings,
+    sim::{self, POLICIES},
+    sound::Bell,
+    splash::Splash,
+    stars::{self, Stars},
+    term::{self, TerminalGuard},
+    text_input::TextInput,
+    theme::{ColorSupport, Theme, ASCII_BORDER},
+    ticker::Ticker,
+    toast::Toasts,
+    versus::{self, ClientMessage, Handicap, NetError, Outcome, Peer, Seat, Versus, VersusSetup, VersusWidget, CLIENT, HOST},
+    widget::{centered, game_over_popup, Celebration, Dim, GameWidget, Popup, CELEBRATION_FRAMES, DEATH_FRAMES},
+};
+
 use std::{
-    collections::VecDeque,
+    fs,
+    path::{Path, PathBuf},
     io::{self},
-    sync::mpsc::{self, TryRecvError},
-    thread,
-    time::{Duration, Instant},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
-#[derive(Clone, Copy, PartialEq, Eq)]
-enum DirectionSnake {
-    Up,
-    Down,
-    Left,
-    Right,
-}
-
-#[derive(Clone, Copy, PartialEq, Eq)]
-struct Point {
-    x: u16,
-    y: u16,
-}
-
-struct Game {
-    snake: VecDeque<Point>,
-    dir: DirectionSnake,
-    food: Point,
-    width: u16,
-    height: u16,
-    game_over: bool,
-    score: usize,
-}
-
-impl Game {
-    fn new(width: u16, height: u16) -> Self {
-        let mut snake = VecDeque::new();
-        let start = Point { x: width / 2, y: height / 2 };
-        snake.push_back(start);
-        let food = Point { x: width / 3, y: height / 3 };
-        Self {
-            snake,
-            dir: DirectionSnake::Right,
-            food,
-            width,
-            height,
-            game_over: false,
-            score: 0,
-        }
-    }
-
-    fn step(&mut self) {
-        if self.game_over { return; }
-        let mut new_head = *self.snake.front().unwrap();
-        match self.dir {
-            DirectionSnake::Up => {
-                if new_head.y == 0 {
-                    self.game_over = true;
-                    return;
+/// Обратный отсчёт 3…2…1 перед стартом или продолжением игры.
+/// Пока он идёт, `step()` не вызывается, а нажатые стрелки запоминаются.
+struct Countdown {
+    started: Instant,
+    queued: Option<DirectionSnake>,
+}
+
+impl Countdown {
+    const STEPS: u32 = 3;
+    const STEP: Duration = Duration::from_millis(333);
+
+    fn new() -> Self {
+        Self { started: Instant::now(), queued: None }
+    }
+
+    /// Текущая цифра отсчёта или `None`, если отсчёт закончился
+    /// (в том числе досрочно — нажатием стрелки)
+    fn remaining(&self) -> Option<u32> {
+        if self.queued.is_some() {
+            return None;
+        }
+        let passed = (self.started.elapsed().as_millis() / Self::STEP.as_millis()) as u32;
+        (passed < Self::STEPS).then(|| Self::STEPS - passed)
+    }
+}
+
+/// Анимация смерти: сегменты краснеют и по очереди угасают от хвоста к голове.
+/// Идёт по кадрам, см. `widget::DEATH_FRAMES`.
+struct Dying {
+    started_frame: u64,
+}
+
+impl Dying {
+    fn new(frame: u64) -> Self {
+        Self { started_frame: frame }
+    }
+
+    /// Сколько кадров прошло с гибели
+    fn frames(&self, frame: u64) -> u64 {
+        frame.wrapping_sub(self.started_frame)
+    }
+
+    fn finished(&self, frame: u64) -> bool {
+        self.frames(frame) >= DEATH_FRAMES
+    }
+}
+
+/// Замедление после прохода вплотную ([`GameEvent::NearMiss`]): несколько следующих
+/// шагов идут вдвое медленнее, а клетка, мимо которой прошла голова, подсвечена
+struct SlowMo {
+    cell: Point,
+    ticks_left: u64,
+}
+
+/// Сколько шагов длится замедление после прохода вплотную
+const SLOWMO_TICKS: u64 = 3;
+
+/// Перенос через портал ([`GameEvent::Teleported`]): прежнее тело ещё видно и уходит
+/// в портал, пока новое проступает на месте. Идёт по кадрам, см. `widget::WARP_FRAMES`.
+struct Warp {
+    /// Прежнее тело от портала к хвосту
+    from: Vec<Point>,
+    started_frame: u64,
+}
+
+/// Время активной игры: без пауз, отсчёта и экрана конца игры.
+#[derive(Default)]
+struct PlayClock {
+    total: Duration,
+    running_since: Option<Instant>,
+}
+
+impl PlayClock {
+    /// Запускает или останавливает часы. Повторные вызовы с тем же значением ничего не меняют.
+    fn set_running(&mut self, running: bool) {
+        match (running, self.running_since) {
+            (true, None) => self.running_since = Some(Instant::now()),
+            (false, Some(since)) => {
+                self.total += since.elapsed();
+                self.running_since = None;
+            }
+            _ => {}
+        }
+    }
+
+    fn elapsed(&self) -> Duration {
+        self.total + self.running_since.map_or(Duration::ZERO, |since| since.elapsed())
+    }
+}
+
+/// Сохраняет снимок поля в `zmiy-screenshot-<время>.txt` в текущей папке и возвращает имя файла
+fn save_screenshot(game: &Game, elapsed: Duration) -> io::Result<String> {
+    let stamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    let path = format!("zmiy-screenshot-{stamp}.txt");
+    let header = format!(
+        "Счёт: {}  Длина: {}  Время: {}\n",
+        game.score,
+        game.snake().len(),
+        format_duration(elapsed)
+    );
+    fs::write(&path, header + &game.to_text())?;
+    Ok(path)
+}
+
+/// Пишет дамп партии и сообщает, куда
+fn save_dump(game: &mut Game, mode: &str, ticks: u64, elapsed: Duration, toasts: &mut Toasts) {
+    match dump::save(game, mode, ticks, elapsed) {
+        Ok(path) => {
+            log_info!("дамп партии: {path}");
+            toasts.push(format!("Дамп: {path}"));
+        }
+        Err(e) => {
+            log_warn!("дамп не сохранён: {e}");
+            toasts.push(format!("Ошибка дампа: {e}"));
+        }
+    }
+}
+
+/// Терминал без цветов: `NO_COLOR` или `TERM=dumb`.
+/// Тусклые стили там недоступны, поэтому декоративные элементы не рисуем.
+fn no_color() -> bool {
+    std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty())
+        || std::env::var_os("TERM").is_some_and(|t| t == "dumb")
+}
+
+/// Повтор, который пишется в файл
+type ReplayFile = Recorder<io::BufWriter<fs::File>>;
+
+/// Начинает запись партии в `<данные>/replays/<время>.zrp`
+fn start_recording(game: &Game, mode: &str) -> io::Result<ReplayFile> {
+    let dir = replay::dir().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "неизвестна домашняя папка"))?;
+    fs::create_dir_all(&dir)?;
+    let stamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis();
+    let file = fs::File::create(dir.join(format!("{stamp}.{}", replay::EXTENSION)))?;
+    Recorder::start(io::BufWriter::new(file), game, mode)
+}
+
+/// Дописывает событие в повтор, если он пишется. При ошибке записи повтор бросается.
+fn log_replay(recorder: &mut Option<ReplayFile>, toasts: &mut Toasts, write: impl FnOnce(&mut ReplayFile) -> io::Result<()>) {
+    if let Some(rec) = recorder.as_mut()
+        && let Err(e) = write(rec)
+    {
+        log_warn!("запись повтора прервана: {e}");
+        *recorder = None;
+        toasts.push("Запись повтора прервана");
+    }
+}
+
+/// Тема режима: у «нокиа» своя, зелёная, у остальных — обычная
+fn mode_theme(mode: Mode, support: ColorSupport) -> Theme {
+    if mode == Mode::Nokia { Theme::nokia(support) } else { Theme::new(support) }
+}
+
+/// Справка: клавиши из живой таблицы привязок, правила и обозначения
+fn help_popup<'a>(theme: &'a Theme, keymap: &Keymap, debug: bool) -> Popup<'a> {
+    let header = Style::default().fg(theme.accent);
+    let text = Style::default().fg(theme.text);
+    let mut lines = vec![Line::from(Span::styled("Клавиши", header))];
+    lines.extend(keymap.lines(theme));
+    // Клавиши отладки не переназначаются и есть не во всякой сборке
+    if debug {
+        lines.push(Line::from(Span::styled(format!("{:>6}  пошаговый режим вкл/выкл", "F8"), text)));
+        lines.push(Line::from(Span::styled(format!("{:>6}  шаг в пошаговом режиме", "."), text)));
+    }
+    lines.push(Line::default());
+    lines.push(Line::from(Span::styled("Правила", header)));
+    lines.push(Line::from(Span::styled("Ешь еду, чтобы расти и набирать очки.", text)));
+    lines.push(Line::from(Span::styled("Нельзя врезаться в стены и в себя.", text)));
+    lines.push(Line::default());
+    lines.push(Line::from(Span::styled("Обозначения", header)));
+    lines.push(Line::from(vec![Span::styled("     O", Style::default().fg(theme.snake)), Span::styled("  голова", text)]));
+    lines.push(Line::from(vec![Span::styled("     o", Style::default().fg(theme.snake)), Span::styled("  тело", text)]));
+    lines.push(Line::from(vec![Span::styled("     *", Style::default().fg(theme.food)), Span::styled("  еда", text)]));
+    lines.push(Line::default());
+    lines.push(Line::from(Span::styled("↑↓ - прокрутка, любая клавиша - закрыть", text)));
+    Popup::new(theme, lines)
+}
+
+/// Каждые столько сегментов — новая отметка длины
+const LENGTH_MILESTONE: usize = 10;
+/// При меньшей ширине шкала длины не рисуется
+const MIN_GAUGE_WIDTH: u16 = 8;
+
+/// Шкала длины змейки до следующей отметки, например «23/30». Змейка без роста
+/// до отметки не дойдёт, и шкала показывает только длину.
+fn length_gauge(theme: &Theme, len: usize, grows: bool) -> Gauge<'static> {
+    if !grows {
+        return Gauge::default()
+            .gauge_style(Style::default().fg(theme.snake).bg(theme.popup_bg))
+            .ratio(0.0)
+            .label(format!("{len}, без роста"));
+    }
+    let next = (len / LENGTH_MILESTONE + 1) * LENGTH_MILESTONE;
+    let done = len % LENGTH_MILESTONE;
+    Gauge::default()
+        .gauge_style(Style::default().fg(theme.snake).bg(theme.popup_bg))
+        .ratio(done as f64 / LENGTH_MILESTONE as f64)
+        .label(format!("{len}/{next}"))
+}
+
+/// Диапазон скоростей (клеток в секунду) для цвета от «медленно» до «быстро»
+const SPEED_RANGE: (f64, f64) = (4.0, 20.0);
+
+/// Цвет скорости: от зелёного (медленно) к красному (быстро)
+fn speed_color(theme: &Theme, cells_per_sec: f64) -> Color {
+    let (slow, fast) = SPEED_RANGE;
+    let t = ((cells_per_sec - slow) / (fast - slow)).clamp(0.0, 1.0);
+    theme.speed[(t * (theme.speed.len() - 1) as f64).round() as usize]
+}
+
+/// Шкала до следующего уровня, например «ещё 2 до ур. 4»
+fn level_gauge(theme: &Theme, level: usize, done: usize, threshold: usize) -> Gauge<'static> {
+    Gauge::default()
+        .gauge_style(Style::default().fg(theme.record).bg(theme.popup_bg))
+        .ratio(done as f64 / threshold.max(1) as f64)
+        .label(format!("ещё {} до ур. {}", threshold - done, level + 1))
+}
+
+/// Имя игрока для таблицы рекордов: профиль, `$USER` или «игрок»
+fn player_name() -> String {
+    if let Some(name) = paths::profile() {
+        return name.to_string();
+    }
+    std::env::var("USER").ok().filter(|n| !n.is_empty()).unwrap_or_else(|| "игрок".to_string())
+}
+
+/// Самое длинное имя в таблице рекордов
+const NAME_MAX: usize = 12;
+
+/// Ввод имени для результата, попавшего в таблицу рекордов
+fn name_popup<'a>(theme: &'a Theme, score: usize, input: &TextInput) -> Popup<'a> {
+    let text = Style::default().fg(theme.text);
+    Popup::new(theme, vec![
+        Line::styled("Результат попал в таблицу!", Style::default().fg(theme.record).add_modifier(Modifier::BOLD)),
+        Line::styled(format!("Счёт: {score}"), Style::default().fg(theme.accent)),
+        Line::default(),
+        Line::styled("Ваше имя:", text),
+        input.line(text),
+        Line::default(),
+        Line::styled("Enter - сохранить, ESC - пропустить", text),
+    ])
+}
+
+/// Итог пройденного уровня
+struct LevelResult {
+    earned: u8,
+    /// Лучшие звёзды до этой партии
+    previous: Option<u8>,
+    elapsed: Duration,
+}
+
+/// Уровень пройден: звёзды этой партии и прежний лучший итог
+fn level_popup<'a>(theme: &'a Theme, result: &LevelResult) -> Popup<'a> {
+    let text = Style::default().fg(theme.text);
+    Popup::new(theme, vec![
+        Line::styled("Уровень пройден!", Style::default().fg(theme.record).add_modifier(Modifier::BOLD)),
+        Line::styled(stars::format(result.earned), Style::default().fg(theme.record)),
+        Line::styled(format!("Время: {}", format_duration(result.elapsed)), Style::default().fg(theme.accent)),
+        Line::styled(format!("Прежний лучший: {}", result.previous.map_or("нет".to_string(), stars::format)), text),
+        Line::default(),
+        Line::styled("Любая клавиша - играть дальше", text),
+    ])
+}
+
+/// Минимальный размер терминала, при котором в игру можно играть
+const MIN_WIDTH: u16 = 20;
+const MIN_HEIGHT: u16 = 10;
+
+/// Скорости просмотра повтора
+const PLAYBACK_SPEEDS: [f64; 4] = [0.5, 1.0, 2.0, 4.0];
+
+/// Читает повтор для `--play`
+fn load_replay(path: &Path) -> Result<Replay, String> {
+    let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    Replay::parse(&text).map_err(|e| e.to_string())
+}
+
+/// Просмотр повтора: пробел — пауза, ←→ — скорость, ESC — выход. Расхождение с контрольными
+/// суммами останавливает просмотр и возвращается, чтобы напечатать его после выхода
+/// из полноэкранного режима.
+fn play_replay(
+    terminal: &mut Terminal<impl Backend>,
+    theme: &Theme,
+    mut playback: Playback,
+) -> io::Result<Option<Diverged>> {
+    let mut speed = 1;
+    let mut paused = false;
+    let mut ticker = Ticker::new(Instant::now(), playback.game.tick_rate().div_f64(PLAYBACK_SPEEDS[speed]));
+    let started = Instant::now();
+    loop {
+        let frame = frame_since(started);
+        let warnings = playback.warnings();
+        let mut status = format!(
+            "Тик {}/{}  Скорость: {}×  Счёт: {}",
+            playback.tick(),
+            playback.total(),
+            PLAYBACK_SPEEDS[speed],
+            playback.game.score
+        );
+        if playback.finished() {
+            status.push_str("  Конец");
+        } else if paused {
+            status.push_str("  Пауза");
+        }
+        terminal.draw(|f| {
+            let [notes, arena, status_area] = Layout::vertical([
+                Constraint::Length(warnings.len() as u16),
+                Constraint::Min(0),
+                Constraint::Length(1),
+            ])
+            .areas(f.area());
+            let lines: Vec<Line> =
+                warnings.into_iter().map(|w| Line::styled(w, Style::default().fg(theme.danger))).collect();
+            f.render_widget(Paragraph::new(lines), notes);
+            let block = Block::default()
+                .borders(Borders::ALL)
+                .title("Повтор (Пробел - пауза, ←→ - скорость, ESC - выход)");
+            let cell_width = Mode::from_name(playback.mode()).unwrap_or_default().cell_width();
+            let game = &mut playback.game;
+            // Поле повтора рисуем в его записанном размере, а не во весь терминал
+            let board = ratatui::layout::Rect {
+                width: arena.width.min(game.width * cell_width + 2),
+                height: arena.height.min(game.height + 2),
+                ..arena
+            };
+            let overtime = game.overtime && game.game_over.is_none();
+            let widget = GameWidget::new(theme).block(block).frame(frame).overtime(overtime).wide(cell_width > 1);
+            f.render_stateful_widget(widget, board, game);
+            f.render_widget(Paragraph::new(status).style(Style::default().fg(theme.text)), status_area);
+        })?;
+
+        let running = !paused && !playback.finished();
+        let wait = if running { ticker.until_next(Instant::now()).min(MAX_WAIT) } else { MAX_WAIT };
+        if let Some(KeyEvent { code, kind: KeyEventKind::Release, .. }) = next_key(wait)? {
+            match code {
+                KeyCode::Char(' ') => paused = !paused,
+                KeyCode::Left => speed = speed.saturating_sub(1),
+                KeyCode::Right => speed = (speed + 1).min(PLAYBACK_SPEEDS.len() - 1),
+                KeyCode::Esc => break,
+                _ => {}
+            }
+        }
+
+        let interval = playback.game.tick_rate().div_f64(PLAYBACK_SPEEDS[speed]);
+        if !paused && !playback.finished() && ticker.due(Instant::now(), interval) {
+            playback.step();
+            if let Some(diverged) = playback.diverged() {
+                return Ok(Some(diverged));
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Экран профилей перед игрой, в своём полноэкранном режиме
+fn pick_profile(profiles: &Profiles, theme: &Theme) -> io::Result<Choice> {
+    let mut terminal = Terminal::new(CrosstermBackend::new(io::stdout()))?;
+    let _guard = TerminalGuard::enter()?;
+    let mut menu = ProfileMenu::new(profiles);
+    loop {
+        terminal.draw(|f| f.render_widget(ProfileView { menu: &menu, theme }, f.area()))?;
+        if let Some(KeyEvent { code, kind: KeyEventKind::Release, .. }) = next_key(MAX_WAIT)? {
+            match menu.key(code, profiles) {
+                Choice::Stay => {}
+                choice => return Ok(choice),
+            }
+        }
+    }
+}
+
+/// Дольше этого цикл не ждёт клавишу: анимации и сообщения должны обновляться
+const MAX_WAIT: Duration = Duration::from_millis(30);
+/// Сколько ждать клавишу в игре по сети: сообщения соперника проверяются так же часто
+const NET_WAIT: Duration = Duration::from_millis(10);
+/// Длина кадра анимаций. Кадры считаются по часам, а не по оборотам цикла,
+/// поэтому анимации идут с одной скоростью, как бы часто ни приходили события.
+const FRAME: Duration = Duration::from_millis(10);
+
+/// Номер кадра анимаций с момента `start`
+fn frame_since(start: Instant) -> u64 {
+    (start.elapsed().as_millis() / FRAME.as_millis()) as u64
+}
+
+/// Ждёт событие терминала не дольше `timeout`; без `timeout` — сколько понадобится
+fn next_event(timeout: Option<Duration>) -> io::Result<Option<Event>> {
+    if let Some(timeout) = timeout
+        && !event::poll(timeout)?
+    {
+        return Ok(None);
+    }
+    event::read().map(Some)
+}
+
+/// Ждёт событие терминала не дольше `timeout` и возвращает клавишу, если это была она.
+/// Остальные события (размер окна, фокус) только прерывают ожидание: следующий оборот
+/// цикла сразу перерисует экран под новый размер.
+fn next_key(timeout: Duration) -> io::Result<Option<KeyEvent>> {
+    match next_event(Some(timeout))? {
+        Some(Event::Key(key)) => Ok(Some(key)),
+        _ => Ok(None),
+    }
+}
+
+/// Сторона в игре по сети
+enum Role {
+    /// Ждём соперника на этом сокете; строка — адрес для подсказки
+    Host(std::net::TcpListener, String),
+    /// Подключены к хосту по адресу
+    Join(Peer, String),
+}
+
+/// Пауза перед стартом партии по сети, чтобы оба игрока успели увидеть поле
+const VERSUS_START: Duration = Duration::from_secs(1);
+/// Как часто клиент пробует переподключиться
+const RECONNECT_EVERY: Duration = Duration::from_secs(1);
+
+/// Игра вдвоём. У хоста партия считается здесь же, клиент только рисует снимки хоста.
+/// Возвращает ошибку протокола, с которой игра остановилась.
+fn play_versus(
+    terminal: &mut Terminal<impl Backend>,
+    theme: &Theme,
+    keymap: &Keymap,
+    role: Role,
+    (width, height): (u16, u16),
+    tick: Duration,
+) -> io::Result<Option<NetError>> {
+    let (listener, mut peer, addr, me) = match role {
+        Role::Host(listener, addr) => (Some(listener), None, addr, HOST),
+        Role::Join(peer, addr) => (None, Some(peer), addr, CLIENT),
+    };
+    let mut game = listener.as_ref().map(|_| Versus::new(width, height, Game::new(width, height).seed));
+    // Хост сначала выбирает фору; новые партии идут с той же, пока её не поменяют
+    let mut setup = listener.as_ref().map(|_| VersusSetup::default());
+    let mut handicaps = [Handicap::default(); 2];
+    // Последний применённый номер поворота клиента и последний отправленный
+    let (mut applied_seq, mut sent_seq) = (0u64, 0u64);
+    let mut start_at = Instant::now() + VERSUS_START;
+    let mut last_tick = Instant::now();
+    let mut last_attempt = Instant::now();
+    // Что случилось с соединением: показывается, пока ждём соперника
+    let mut note: Option<String> = None;
+    // Хост должен разослать снимок: после шага, подключения или новой партии
+    let mut changed = true;
+    loop {
+        terminal.draw(|f| {
+            let [arena, status_area] = Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(f.area());
+            if let Some(game) = &game {
+                let block = Block::default().borders(Borders::ALL).title("Игра по сети (ESC - выход)");
+                let board = ratatui::layout::Rect {
+                    width: arena.width.min(game.width + 2),
+                    height: arena.height.min(game.height + 2),
+                    ..arena
+                };
+                f.render_widget(VersusWidget { theme, game, me, block: Some(block) }, board);
+                let shield = |i: usize| if game.shields[i] { " (щит)" } else { "" };
+                let status = format!("Вы: {}{}  Соперник: {}{}", game.scores[me], shield(me), game.scores[1 - me], shield(1 - me));
+                f.render_widget(Paragraph::new(status).style(Style::default().fg(theme.text)), status_area);
+            }
+            let text = Style::default().fg(theme.text);
+            let mut lines = Vec::new();
+            if let Some(setup) = &setup {
+                lines.push(Line::from(Span::styled("Фора", Style::default().fg(theme.accent).add_modifier(Modifier::BOLD))));
+                for row in 0..VersusSetup::ROWS {
+                    if row % 3 == 0 {
+                        lines.push(Line::from(Span::styled(if row / 3 == me { "Вы" } else { "Соперник" }, text)));
+                    }
+                    let (label, value) = setup.row_text(row);
+                    let style = if row == setup.row { Style::default().fg(theme.popup_bg).bg(theme.accent) } else { text };
+                    lines.push(Line::from(Span::styled(format!("  {label}: {value}"), style)));
+                }
+                lines.push(Line::default());
+                lines.push(Line::from(Span::styled("↑↓ - выбор, ←→ - изменить, Enter - играть, ESC - выход", text)));
+            } else if peer.is_none() {
+                let waiting = if me == HOST { format!("Ждём соперника на {addr}…") } else { format!("Нет связи с {addr}, переподключаемся…") };
+                lines.push(Line::from(Span::styled(waiting, Style::default().fg(theme.accent))));
+                if let Some(note) = &note {
+                    lines.push(Line::from(Span::styled(note.clone(), Style::default().fg(theme.danger))));
                 }
-                new_head.y -= 1;
+                lines.push(Line::from(Span::styled("ESC - выход", text)));
+            } else if let Some(outcome) = game.as_ref().and_then(|g| g.outcome) {
+                let (title, color) = match outcome {
+                    Outcome::Won(i) if i == me => ("Победа!", theme.record),
+                    Outcome::Won(_) => ("Поражение", theme.danger),
+                    Outcome::Draw => ("Ничья", theme.accent),
+                };
+                lines.push(Line::from(Span::styled(title, Style::default().fg(color))));
+                // Счёт с форой — не то же, что счёт на равных: итог её называет
+                if let Some(game) = &game
+                    && game.handicaps != [Handicap::default(); 2]
+                {
+                    let (mine, theirs) = (game.handicaps[me].describe(), game.handicaps[1 - me].describe());
+                    lines.push(Line::from(Span::styled(format!("Фора: у вас {mine}, у соперника {theirs}"), text)));
+                }
+                let keys = if me == HOST { "Пробел - ещё раз, Tab - фора, ESC - выход" } else { "Пробел - ещё раз, ESC - выход" };
+                lines.push(Line::from(Span::styled(keys, text)));
             }
-            DirectionSnake::Down => {
-                new_head.y += 1;
-                if new_head.y >= self.height {
-                    self.game_over = true;
-                    return;
+            if !lines.is_empty() {
+                f.render_widget(Dim(theme), arena);
+                f.render_widget(Popup::new(theme, lines), arena);
+            }
+        })?;
+
+        // Свои клавиши: у хоста поворот сразу, у клиента — сообщением хосту
+        match next_key(NET_WAIT)? {
+            Some(KeyEvent { code: KeyCode::Esc, kind: KeyEventKind::Release, .. }) => return Ok(None),
+            Some(KeyEvent { code, kind: KeyEventKind::Release, .. }) if let Some(form) = setup.as_mut() => match code {
+                KeyCode::Up => form.up(),
+                KeyCode::Down => form.down(),
+                KeyCode::Left => form.change(false),
+                KeyCode::Right => form.change(true),
+                KeyCode::Enter => {
+                    handicaps = form.handicaps;
+                    log_info!("фора: {} / {}", handicaps[HOST].describe(), handicaps[CLIENT].describe());
+                    game = Some(Versus::with_handicaps(width, height, Game::new(width, height).seed, handicaps));
+                    setup = None;
+                    start_at = Instant::now() + VERSUS_START;
+                    changed = true;
                 }
+                _ => {}
+            },
+            Some(KeyEvent { code: KeyCode::Tab, kind: KeyEventKind::Release, .. })
+                if me == HOST && game.as_ref().is_some_and(|g| g.outcome.is_some()) =>
+            {
+                setup = Some(VersusSetup { handicaps, row: 0 });
             }
-            DirectionSnake::Left => {
-                if new_head.x == 0 {
-                    self.game_over = true;
-                    return;
+            Some(KeyEvent { code, kind: KeyEventKind::Release, .. }) => {
+                let over = game.as_ref().is_some_and(|g| g.outcome.is_some());
+                match (keymap.action(code), game.as_mut(), peer.as_mut()) {
+                    (Some(Action::Move(dir)), Some(game), _) if me == HOST => game.change_dir(HOST, dir),
+                    (Some(Action::Move(dir)), _, Some(peer)) => {
+                        sent_seq += 1;
+                        let _ = peer.send(&versus::dir_message(sent_seq, dir));
+                    }
+                    (Some(Action::Restart), Some(game), _) if me == HOST && over => {
+                        *game = Versus::with_handicaps(width, height, Game::new(width, height).seed, handicaps);
+                        start_at = Instant::now() + VERSUS_START;
+                        changed = true;
+                    }
+                    (Some(Action::Restart), _, Some(peer)) if over => {
+                        let _ = peer.send("restart");
+                    }
+                    _ => {}
                 }
-                new_head.x -= 1;
             }
-            DirectionSnake::Right => {
-                new_head.x += 1;
-                if new_head.x >= self.width {
-                    self.game_over = true;
-                    return;
+            _ => {}
+        }
+
+        // Сообщения соперника; закрытое соединение — ждём нового
+        while let Some(p) = peer.as_ref() {
+            let text = match p.poll() {
+                Ok(Some(text)) => text,
+                Ok(None) => break,
+                Err(_) => {
+                    log_info!("соперник отключился");
+                    peer = None;
+                    break;
+                }
+            };
+            if me == CLIENT {
+                match Versus::from_snapshot(&text) {
+                    Ok(snapshot) => game = Some(snapshot),
+                    Err(e) => return Ok(Some(e)),
+                }
+                continue;
+            }
+            let Some(game) = game.as_mut() else { break };
+            match ClientMessage::parse(&text) {
+                Ok(ClientMessage::Dir(seq, dir)) if seq > applied_seq => {
+                    applied_seq = seq;
+                    game.change_dir(CLIENT, dir);
+                }
+                Ok(ClientMessage::Dir(..)) => {}
+                Ok(ClientMessage::Restart) if game.outcome.is_some() && setup.is_none() => {
+                    *game = Versus::with_handicaps(width, height, Game::new(width, height).seed, handicaps);
+                    start_at = Instant::now() + VERSUS_START;
+                    changed = true;
+                }
+                Ok(ClientMessage::Restart) => {}
+                Err(e) => {
+                    log_warn!("соперник отключён: {e}");
+                    note = Some(e.to_string());
+                    peer = None;
                 }
             }
         }
-        if self.snake.contains(&new_head) {
-            self.game_over = true;
-            return;
+
+        match (&listener, &peer) {
+            (Some(listener), None) => match versus::accept(listener) {
+                Ok(Some(new)) => {
+                    log_info!("соперник подключился");
+                    peer = Some(new);
+                    applied_seq = 0;
+                    note = None;
+                    start_at = Instant::now() + VERSUS_START;
+                    changed = true;
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    log_warn!("соперник не подключился: {e}");
+                    note = Some(e.to_string());
+                }
+            },
+            (None, None) if last_attempt.elapsed() >= RECONNECT_EVERY => {
+                last_attempt = Instant::now();
+                match versus::connect(&addr, Seat::Player) {
+                    Ok(new) => {
+                        log_info!("снова подключились к {addr}");
+                        peer = Some(new);
+                        note = None;
+                    }
+                    // Другая версия у хоста не пройдёт и со второй попытки
+                    Err(e @ NetError::Rejected(_)) => return Ok(Some(e)),
+                    Err(e) => note = Some(e.to_string()),
+                }
+            }
+            _ => {}
         }
-        self.snake.push_front(new_head);
-        if new_head == self.food {
-            self.score += 1;
-            self.spawn_food();
-        } else {
-            self.snake.pop_back();
+
+        // Хост шагает только с подключённым соперником и рассылает снимок после каждого шага
+        if me == HOST
+            && let (Some(game), Some(p)) = (game.as_mut(), peer.as_mut())
+        {
+            if setup.is_none() && Instant::now() >= start_at && last_tick.elapsed() >= tick {
+                game.step();
+                last_tick = Instant::now();
+                changed = true;
+            }
+            if changed {
+                changed = false;
+                if let Err(e) = p.send(&game.snapshot()) {
+                    log_info!("соперник отключился: {e}");
+                    peer = None;
+                }
+            }
         }
     }
+}
 
-    fn spawn_food(&mut self) {
-        use rand::{Rng, SeedableRng};
-        use std::time::{SystemTime, UNIX_EPOCH};
+/// Как часто трансляция шлёт снимок, даже если игра стоит: зритель видит, что связь жива
+const BROADCAST_HEARTBEAT: Duration = Duration::from_millis(250);
+/// Как часто проверять сокет управления, когда игра стоит
+#[cfg(unix)]
+const CONTROL_POLL: Duration = Duration::from_millis(50);
 
-        // Используем системное время как источник энтропии для сида
-        let seed = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .expect("Ошибка получения времени")
-            .as_nanos() as u64;
+/// Просмотр чужой игры: рисует последний снимок хоста, клавиши игры не действуют.
+/// Возвращает ошибку протокола, с которой просмотр остановился.
+fn play_spectate(
+    terminal: &mut Terminal<impl Backend>,
+    theme: &Theme,
+    peer: Peer,
+    addr: &str,
+) -> io::Result<Option<NetError>> {
+    let mut peer = Some(peer);
+    let mut watched: Option<Watched> = None;
+    let mut last_attempt = Instant::now();
+    let mut note: Option<String> = None;
+    let started = Instant::now();
+    loop {
+        let frame = frame_since(started);
+        terminal.draw(|f| {
+            let [arena, status_area] = Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(f.area());
+            if let Some(watched) = watched.as_mut() {
+                let block = Block::default()
+                    .borders(Borders::ALL)
+                    .title(Line::from(vec![
+                        Span::styled(" ЗРИТЕЛЬ ", Style::default().fg(theme.accent).add_modifier(Modifier::REVERSED)),
+                        Span::raw(format!(" {addr} (ESC - выход)")),
+                    ]));
+                let game = &mut watched.game;
+                let board = ratatui::layout::Rect {
+                    width: arena.width.min(game.width + 2),
+                    height: arena.height.min(game.height + 2),
+                    ..arena
+                };
+                f.render_stateful_widget(GameWidget::new(theme).block(block).frame(frame), board, game);
+                let mut status = format!("Счёт: {}  Шаг: {}", game.score, watched.tick);
+                if game.game_over.is_some() {
+                    status.push_str("  Конец");
+                } else if watched.paused {
+                    status.push_str("  Пауза");
+                }
+                f.render_widget(Paragraph::new(status).style(Style::default().fg(theme.text)), status_area);
+            }
+            if peer.is_none() || watched.is_none() {
+                let waiting = if peer.is_none() { "Трансляция прервалась, переподключаемся…" } else { "Ждём первый кадр…" };
+                let mut lines = vec![Line::from(Span::styled(waiting, Style::default().fg(theme.accent)))];
+                if let Some(note) = &note {
+                    lines.push(Line::from(Span::styled(note.clone(), Style::default().fg(theme.danger))));
+                }
+                lines.push(Line::from(Span::styled("ESC - выход", Style::default().fg(theme.text))));
+                f.render_widget(Dim(theme), arena);
+                f.render_widget(Popup::new(theme, lines), arena);
+            }
+        })?;
 
-        // Создаем генератор с уникальным сидом
-        let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+        if let Some(KeyEvent { code: KeyCode::Esc | KeyCode::Char('q'), kind: KeyEventKind::Release, .. }) = next_key(NET_WAIT)? {
+            return Ok(None);
+        }
 
-        loop {
-            let x = rng.random_range(0..self.width);
-            let y = rng.random_range(0..self.height);
-            let p = Point { x, y };
-            if !self.snake.contains(&p) {
-                self.food = p;
-                break;
+        // Важен только последний снимок: все накопившиеся разбираются, рисуется свежий
+        while let Some(p) = peer.as_ref() {
+            match p.poll() {
+                Ok(Some(text)) => match broadcast::parse_snapshot(&text) {
+                    Ok(snapshot) => watched = Some(snapshot),
+                    Err(e) => return Ok(Some(e)),
+                },
+                Ok(None) => break,
+                Err(_) => {
+                    log_info!("трансляция {addr} прервалась");
+                    peer = None;
+                }
+            }
+        }
+        if peer.is_none() && last_attempt.elapsed() >= RECONNECT_EVERY {
+            last_attempt = Instant::now();
+            match versus::connect(addr, Seat::Spectator) {
+                Ok(new) => {
+                    log_info!("снова смотрим {addr}");
+                    peer = Some(new);
+                    note = None;
+                }
+                Err(e @ NetError::Rejected(_)) => return Ok(Some(e)),
+                Err(e) => note = Some(e.to_string()),
             }
         }
     }
+}
+
+/// Файл настроек: `--config PATH`, который обязан быть, или файл по умолчанию
+fn config_path(options: &Options) -> Option<(PathBuf, bool)> {
+    match &options.config {
+        Some(path) => Some((path.clone(), true)),
+        None => Some((config::path()?, false)),
+    }
+}
+
+/// Настройки из `--config PATH` или из файла по умолчанию, если он есть
+fn load_config(options: &Options) -> Result<Config, String> {
+    let Some((path, required)) = config_path(options) else {
+        return Ok(Config::default());
+    };
+    Config::load(&path, required).map_err(|e| format!("Ошибка в настройках {}: {e}", path.display()))
+}
 
-    fn change_dir(&mut self, dir: DirectionSnake) {
-        // Если длина змейки 1 — разрешаем любое направление
-        if self.snake.len() == 1 {
-            self.dir = dir;
-            return;
+/// Поток отправки в онлайн-таблицу на `url`
+#[cfg(feature = "leaderboard")]
+fn start_uploader(url: &str) -> Result<Uploader, String> {
+    use zmiy::leaderboard::{parse_url, post, queue_path};
+
+    parse_url(url)?;
+    let url = url.to_string();
+    Ok(Uploader::start(queue_path(), move |body| post(&url, body)))
+}
+
+#[cfg(not(feature = "leaderboard"))]
+fn start_uploader(_url: &str) -> Result<Uploader, String> {
+    Err("игра собрана без сети, нужна сборка с --features leaderboard".to_string())
+}
+
+/// Включает журнал по `ZMIY_LOG` или `--debug`; `--debug` без `ZMIY_LOG` — уровень debug
+fn start_log(options: &Options) -> Result<(), String> {
+    let level = match std::env::var("ZMIY_LOG") {
+        Ok(name) => Level::parse(&name)
+            .ok_or_else(|| format!("ZMIY_LOG={name}: ожидается off, error, warn, info, debug или trace"))?,
+        Err(_) => None,
+    };
+    let Some(level) = level.or(options.debug.then_some(Level::Debug)) else {
+        return Ok(());
+    };
+    let path = log::path().ok_or("Журнал не включён: неизвестна домашняя папка")?;
+    let logger = Logger::open(&path, level, log::MAX_SIZE)
+        .map_err(|e| format!("Не удалось открыть журнал {}: {e}", path.display()))?;
+    log::init(logger);
+    Ok(())
+}
+
+/// Поле для `--simulate`, если размер не задан
+const SIMULATION_SIZE: (u16, u16) = (40, 20);
+
+/// `--simulate N`: партии без терминала и сводка в stdout
+fn simulate(options: &Options, games: u64) {
+    let name = options.policy.as_deref().unwrap_or(POLICIES[0]);
+    let mut policy = sim::policy(name).expect("имя стратегии проверено при разборе параметров");
+    let (width, height) = options.size.unwrap_or(SIMULATION_SIZE);
+    let seed = options.seed.unwrap_or_else(|| Game::new(width, height).seed);
+    let stats = sim::simulate(games, width, height, seed, name, policy.as_mut());
+    log_info!("--simulate: {}", stats.to_json());
+    if options.json {
+        println!("{}", stats.to_json());
+    } else {
+        print!("{}", stats.to_text());
+    }
+}
+
+/// Правила новой партии из файла настроек и настроек игрока: одни и те же для первой
+/// партии и для каждой следующей. Новое правило из настроек добавляется только сюда.
+fn configure(game: &mut Game, config: &Config, settings: &Settings) {
+    game.length_speedup = config.length_speedup();
+    game.food_candidates = config.food_candidates();
+    game.blackouts = config.blackouts.unwrap_or(false);
+    game.bombs = config.bombs.unwrap_or(false);
+    game.risk_scoring = config.risk_scoring.unwrap_or(false);
+    game.edge_bonus = config.edge_bonus.unwrap_or(false);
+    game.bosses = config.boss_food.unwrap_or(false);
+    game.caterpillars = config.caterpillars.unwrap_or(false);
+    game.score_decay = config.score_decay.unwrap_or(false);
+    game.slime_trail = config.slime_trail.unwrap_or(false);
+    game.teleports = config.teleports.unwrap_or(false);
+    game.shields = config.shields.unwrap_or(false);
+    game.doubling = config.doubling.unwrap_or(false);
+    game.freezing = config.freezing.unwrap_or(false);
+    game.growth_per_food = settings.growth_per_food;
+    scatter_zones(game, config);
+    if config.random_start == Some(true) {
+        game.random_start();
+    }
+}
+
+/// Пятна зон скорости из настроек, если у уровня нет своих зон
+fn scatter_zones(game: &mut Game, config: &Config) {
+    if config.speed_zones == Some(true) && Zone::ALL.into_iter().all(|zone| game.zones(zone).is_empty()) {
+        game.scatter_zones();
+    }
+}
+
+/// Сообщает об ошибке запуска до включения полноэкранного режима и завершает программу
+fn exit_with(message: impl std::fmt::Display) -> ! {
+    eprintln!("{message}");
+    std::process::exit(1);
+}
+
+fn main() {
+    // Терминал к этому моменту уже восстановлен: его возвращает `TerminalGuard` внутри `run`
+    if let Err(e) = run() {
+        log_error!("ошибка терминала: {e}");
+        exit_with(format!("Ошибка терминала: {e}"));
+    }
+}
+
+fn run() -> io::Result<()> {
+    let mut options = Options::parse(std::env::args().skip(1)).unwrap_or_else(|e| exit_with(e));
+    if options.help {
+        print!("{USAGE}");
+        return Ok(());
+    }
+    if options.version {
+        println!("zmiy {}", env!("CARGO_PKG_VERSION"));
+        return Ok(());
+    }
+    if options.write_default_config {
+        print!("{}", config::DEFAULT);
+        return Ok(());
+    }
+    // Профиль выбирается раньше всего, что читает файлы: настроек, рекордов, статистики
+    let profiles = Profiles::open();
+    let mut new_profile = None;
+    if let Some(name) = &options.profile {
+        let Some(profiles) = &profiles else {
+            exit_with("Профили недоступны: неизвестна домашняя папка");
+        };
+        if !profiles.exists(name) {
+            profiles.create(name).unwrap_or_else(|e| exit_with(format!("Профиль {name} не создан: {e}")));
+            new_profile = Some(name.clone());
         }
-        // Не даём развернуться на 180
-        match (self.dir, dir) {
-            (DirectionSnake::Up, DirectionSnake::Down) => {}
-            (DirectionSnake::Down, DirectionSnake::Up) => {}
-            (DirectionSnake::Left, DirectionSnake::Right) => {}
-            (DirectionSnake::Right, DirectionSnake::Left) => {}
-            _ => self.dir = dir,
+        paths::set_profile(name);
+    }
+    // `--stats` только читает файлы и печатает сводку: терминал не переключается
+    if options.stats {
+        let stats = match lifetime::path().as_deref().map(Lifetime::load) {
+            Some(Ok(stats)) => stats,
+            Some(Err(e)) => exit_with(format!("Статистика не загружена: {e}")),
+            None => Lifetime::default(),
+        };
+        let scores = match scores::path().as_deref().map(HighScores::load) {
+            Some(Ok(scores)) => scores,
+            Some(Err(e)) => exit_with(format!("Рекорды не загружены: {e}")),
+            None => HighScores::default(),
+        };
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        let summary = Summary::new(&stats, &scores, now);
+        if options.json {
+            println!("{}", summary.to_json());
+        } else {
+            print!("{}", summary.to_text());
         }
+        return Ok(());
     }
-}
+    start_log(&options).unwrap_or_else(|e| exit_with(e));
+    log_info!("zmiy {} запущен: {options:?}", env!("CARGO_PKG_VERSION"));
+    if let Some(games) = options.simulate {
+        simulate(&options, games);
+        return Ok(());
+    }
+    // Экран профилей: по `--profiles`, а без `--profile` — сам, если профили уже заведены
+    if options.solo()
+        && options.profile.is_none()
+        && let Some(profiles) = &profiles
+        && (options.profiles || profiles.list().is_ok_and(|names| !names.is_empty()))
+    {
+        match pick_profile(profiles, &Theme::new(options.color.unwrap_or_else(ColorSupport::detect)))? {
+            Choice::Play(Some(name)) => paths::set_profile(&name),
+            Choice::Play(None) | Choice::Stay => {}
+            Choice::Quit => return Ok(()),
+        }
+    }
+    if let Some(name) = paths::profile() {
+        log_info!("профиль {name}");
+    }
+    if options.size.is_some() {
+        let (columns, rows) = crossterm::terminal::size()?;
+        options.check_terminal(columns, rows).unwrap_or_else(|e| exit_with(e));
+    }
+    let mut config = load_config(&options).unwrap_or_else(|e| {
+        eprintln!("{e}");
+        std::process::exit(1);
+    });
+    let mut keymap = config.keymap().unwrap_or_else(|e| {
+        eprintln!("Ошибка в настройках: {e}");
+        std::process::exit(1);
+    });
+    // Настройки попадут в отчёт о сбое, если игра упадёт
+    report::set_config(config_path(&options).map(|(path, _)| path));
 
-fn main() -> Result<(), io::Error> {
-    enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen)?;
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
+    // `--play файл.zrp`: вместо игры — просмотр повтора
+    let playback = match &options.play {
+        Some(path) => match load_replay(path) {
+            Ok(replay) => Some(Playback::new(replay)),
+            Err(e) => exit_with(format!("Не удалось открыть повтор {}: {e}", path.display())),
+        },
+        None => None,
+    };
 
-    let (tx, rx) = mpsc::channel();
-    thread::spawn(move || {
-        loop {
-            if event::poll(Duration::from_millis(10)).unwrap() {
-                if let Event::Key(key) = event::read().unwrap() {
-                    tx.send(key).unwrap();
-                }
-            }
+    // `--bot-io`: змейку ведёт внешняя программа, клавиши направления не действуют
+    let mut bot = options.bot_cmd.as_deref().map(|command| {
+        Bot::spawn(command, options.bot_timeout.unwrap_or(bot::DEFAULT_TIMEOUT)).unwrap_or_else(|e| exit_with(e))
+    });
+    // Ошибка протокола останавливает игру и печатается после выхода из полноэкранного режима
+    let mut bot_error: Option<BotError> = None;
+    // `--host`/`--join`: вдвоём по сети. Сокет открывается до полноэкранного режима,
+    // чтобы ошибка адреса напечаталась обычным текстом.
+    let role = match (&options.host, &options.join) {
+        (Some(addr), _) => {
+            let listener = versus::listen(addr).unwrap_or_else(|e| exit_with(format!("Не удалось открыть {addr}: {e}")));
+            Some(Role::Host(listener, addr.clone()))
+        }
+        (None, Some(addr)) => {
+            let peer = versus::connect(addr, Seat::Player).unwrap_or_else(|e| exit_with(format!("Не удалось подключиться к {addr}: {e}")));
+            Some(Role::Join(peer, addr.clone()))
         }
+        (None, None) => None,
+    };
+    // `--spectate`: только смотреть чужую игру
+    let spectate = options.spectate.as_deref().map(|addr| {
+        let peer = versus::connect(addr, Seat::Spectator)
+            .unwrap_or_else(|e| exit_with(format!("Не удалось подключиться к трансляции {addr}: {e}")));
+        (peer, addr)
     });
+    // `--broadcast`: своя игра видна зрителям
+    let mut broadcast = options.broadcast.as_deref().map(|addr| {
+        Broadcast::listen(addr).unwrap_or_else(|e| exit_with(format!("Не удалось открыть {addr}: {e}")))
+    });
+
+    // `--record-cast`: экран пишется ещё и в файл. Терминал создаётся до полноэкранного
+    // режима, чтобы удалиться после него: ошибка записи печатается уже в обычный экран.
+    let output = match &options.record_cast {
+        Some(path) => CastWriter::recording(io::stdout(), path, crossterm::terminal::size()?)
+            .unwrap_or_else(|e| exit_with(format!("Не удалось создать {}: {e}", path.display()))),
+        None => CastWriter::new(io::stdout()),
+    };
+    let mut terminal = Terminal::new(CrosstermBackend::new(output))?;
+    term::install_panic_hook();
+    let guard = TerminalGuard::enter()?;
+    if options.panic_test {
+        panic!("проверка восстановления терминала (--panic-test)");
+    }
+
 
     // let width = 30;
     // let height = 20;
     // let mut game = Game::new(width, height);
     // Вместо фиксированных размеров, инициализируем после первого draw
     let mut game: Option<Game> = None;
-    let mut last_tick = Instant::now();
-    let tick_rate = Duration::from_millis(120);
+    let mut ticker = Ticker::new(Instant::now(), Game::BASE_TICK);
+    // Пошаговый режим (F8, «.» — шаг) есть в отладочной сборке и с `--debug`
+    let debug_keys = options.debug || cfg!(debug_assertions);
+    // Повороты, принятые в пошаговом режиме после последнего шага
+    let mut pending_turns: Vec<DirectionSnake> = Vec::new();
 
     let mut paused = false;
+    let mut settings = Settings::default();
+    // Флаги командной строки важнее файла настроек
+    config.apply(&mut settings);
+    if let Some(border) = options.border {
+        settings.border = border;
+    }
+    if let Some(growth) = options.growth {
+        settings.growth_per_food = growth;
+    }
+    let no_color = options.no_color || no_color();
+    let mut support = options.color.or(config.color).unwrap_or_else(ColorSupport::detect);
+    let mut theme = mode_theme(options.mode, support);
+    // Режим можно сменить на заставке, поэтому его название — не заимствование из `options`
+    let mut mode = options.mode_name().to_string();
+    // Скорость из `--speed`: к ней возвращаемся, уйдя на заставке из режима «нокиа»
+    let speed_arg = options.tick;
+    log_info!(
+        "терминал {:?}, палитра {:?}, без цветов: {no_color}, настройки: {settings:?}",
+        crossterm::terminal::size().ok(),
+        support
+    );
+    if let Some(playback) = playback {
+        let theme = mode_theme(Mode::from_name(playback.mode()).unwrap_or_default(), support);
+        let diverged = play_replay(&mut terminal, &theme, playback)?;
+        drop(guard);
+        if let Some(e) = diverged {
+            log_warn!("{e}");
+            exit_with(format!("Просмотр остановлен: {e}"));
+        }
+        return Ok(());
+    }
+    if let Some(role) = role {
+        // Поле хоста — весь терминал без рамки и строки статуса; клиент берёт размер у хоста
+        let (columns, rows) = crossterm::terminal::size()?;
+        let size = options.size.unwrap_or((columns.saturating_sub(2).max(5), rows.saturating_sub(3).max(5)));
+        let tick = options.tick.or(config.tick).unwrap_or(Game::BASE_TICK);
+        let result = play_versus(&mut terminal, &theme, &keymap, role, size, tick)?;
+        drop(guard);
+        if let Some(e) = result {
+            exit_with(format!("Игра по сети остановлена: {e}"));
+        }
+        return Ok(());
+    }
+    if let Some((peer, addr)) = spectate {
+        let result = play_spectate(&mut terminal, &theme, peer, addr)?;
+        drop(guard);
+        if let Some(e) = result {
+            exit_with(format!("Просмотр остановлен: {e}"));
+        }
+        return Ok(());
+    }
+    let mut bell = Bell::default();
+    let mut countdown: Option<Countdown> = None;
+    let mut dying: Option<Dying> = None;
+    let mut slowmo: Option<SlowMo> = None;
+    let mut warp: Option<Warp> = None;
+    let mut clock = PlayClock::default();
+    // Лучший счёт с момента запуска (только в памяти)
+    let mut session_best: usize = 0;
+    let mut toasts = Toasts::default();
+    // Открытая справка и её прокрутка
+    let mut help: Option<u16> = None;
+    // Список мутаций бесконечного режима развёрнут поверх поля
+    let mut mutations_open = true;
+    // Заметки о проблемах при запуске: показываются, когда закроется заставка
+    let mut startup_notes: Vec<String> = Vec::new();
+    if let Some(name) = new_profile {
+        startup_notes.push(format!("Создан профиль {name}"));
+    }
+    let scores_path = scores::path();
+    let mut high_scores = match scores_path.as_deref().map(HighScores::load) {
+        Some(Ok(scores)) => scores,
+        Some(Err(e)) => {
+            log_warn!("рекорды не загружены: {e}");
+            startup_notes.push(format!("Рекорды не загружены: {e}"));
+            HighScores::default()
+        }
+        None => HighScores::default(),
+    };
+    let lifetime_path = lifetime::path();
+    let mut lifetime = match lifetime_path.as_deref().map(Lifetime::load) {
+        Some(Ok(stats)) => stats,
+        Some(Err(e)) => {
+            log_warn!("статистика не загружена: {e}");
+            startup_notes.push(format!("Статистика не загружена: {e}"));
+            Lifetime::default()
+        }
+        None => Lifetime::default(),
+    };
+    // Лучшие звёзды за уровни; `level_result` — итог пройденного уровня, пока он на экране
+    let stars_path = stars::path();
+    let mut level_stars = match stars_path.as_deref().map(Stars::load) {
+        Some(Ok(stars)) => stars,
+        Some(Err(e)) => {
+            log_warn!("звёзды не загружены: {e}");
+            startup_notes.push(format!("Звёзды не загружены: {e}"));
+            Stars::default()
+        }
+        None => Stars::default(),
+    };
+    let mut level_result: Option<LevelResult> = None;
+    let mut level_done = false;
+    // Открытая таблица рекордов
+    let mut scores_view: Option<ScoresView> = None;
+    // Место текущей партии в таблице её режима; `recorded` — итог партии уже обработан
+    let mut last_rank: Option<usize> = None;
+    let mut recorded = false;
+    // Ввод имени для результата, попавшего в таблицу; запись добавляется по Enter
+    let mut name_prompt: Option<TextInput> = None;
+    // Партия побила рекорд таблицы; праздник начинается с первым кадром экрана конца игры
+    let mut new_record = false;
+    let mut celebration_start: Option<u64> = None;
+    let mut pause_menu = PauseMenu::default();
+    // Заставка до первого нажатия
+    let mut splash = true;
+    // Редактор поля и список своих уровней, открытые с заставки
+    let mut board_editor: Option<Editor> = None;
+    let mut level_list: Option<LevelList> = None;
+    // `--record`: каждая партия пишется в файл повтора
+    let record = options.record;
+    let mut recorder: Option<ReplayFile> = None;
+    // Сколько шагов сделала текущая партия
+    let mut ticks: u64 = 0;
+    // Сохранённая партия, которую можно продолжить с заставки
+    let save_path = save::path();
+    let mut saved = match save_path.as_deref().and_then(SavedGame::load) {
+        Some(Ok(saved)) => Some(saved),
+        Some(Err(e)) => {
+            log_warn!("сохранение не загружено: {e}");
+            startup_notes.push(format!("Сохранение не загружено: {e}"));
+            None
+        }
+        None => None,
+    };
+    // `--ghost`: рядом идёт лучшая записанная партия режима. Без `--seed` игра берёт её сид,
+    // чтобы еда появлялась там же, где у призрака.
+    let best_run = if options.ghost {
+        match replay::dir().map(|dir| ghost::best(&dir, &mode, options.seed)) {
+            Some(Ok(Some((path, replay)))) => {
+                log_info!("призрак: {}", path.display());
+                Some(replay)
+            }
+            Some(Ok(None)) | None => {
+                startup_notes.push("Призрака нет: запишите партию этого режима с --record".to_string());
+                None
+            }
+            Some(Err(e)) => {
+                log_warn!("повторы для призрака не прочитаны: {e}");
+                startup_notes.push(format!("Призрак не загружен: {e}"));
+                None
+            }
+        }
+    } else {
+        None
+    };
+    if let Some(replay) = &best_run {
+        options.seed.get_or_insert(replay.seed);
+    }
+    let mut ghost: Option<Ghost> = None;
+    // Онлайн-таблица: включается адресом в настройках
+    let uploader = config.leaderboard_url.as_deref().and_then(|url| match start_uploader(url) {
+        Ok(uploader) => {
+            if let Some(warning) = leaderboard::warning(url) {
+                log_warn!("онлайн-таблица {url}: {warning}");
+                startup_notes.push(format!("Онлайн-таблица {warning}"));
+            }
+            Some(uploader)
+        }
+        Err(e) => {
+            log_warn!("онлайн-таблица выключена: {e}");
+            startup_notes.push(format!("Онлайн-таблица выключена: {e}"));
+            None
+        }
+    });
+    // Адрес, с которым запущен поток отправки: перечитанные настройки его не меняют
+    let upload_warning = config.leaderboard_url.as_deref().and_then(leaderboard::warning).filter(|_| uploader.is_some());
+
+    // Начало отсчёта кадров для анимаций, не зависит от тиков игры
+    let started = Instant::now();
+    // Экран устарел: было событие, и кадр надо нарисовать, даже если ничего не движется
+    let mut dirty = true;
+    // Когда трансляция последний раз слала снимок и какого шага
+    let mut last_broadcast: Option<(Instant, u64)> = None;
+    // Файл настроек перечитывается, когда меняется
+    let mut config_watcher = config_path(&options).map(|(path, _)| config::Watcher::new(path, Instant::now()));
+    // `--control`: команды через Unix-сокет. Файл сокета удаляется вместе с `control`.
+    #[cfg(unix)]
+    let mut control = match options.control.as_deref().map(Control::listen) {
+        Some(Ok(control)) => Some(control),
+        Some(Err(e)) => {
+            drop(guard);
+            exit_with(format!("Не удалось открыть сокет управления: {e}"));
+        }
+        None => None,
+    };
 
     loop {
-        terminal.draw(|f| {
-            let size = f.area();
-            // Размеры поля = размер терминала минус рамка (по 2 по x и y)
-            let width = size.width.saturating_sub(2);
-            let height = size.height.saturating_sub(2); // исправлено: только рамка
-
-            // Инициализация игры если ещё не была
-            if game.is_none() {
-                game.replace(Game::new(width, height));
-            }
-            let game = game.as_mut().unwrap();
-
-            // Если размеры изменились (resize терминала) — обновляем размеры поля, сохраняем прогресс, ставим на паузу
-            if game.width != width || game.height != height {
-                // Проверяем, помещается ли змейка и еда в новые размеры
-                let snake_fits = game.snake.iter().all(|p| p.x < width && p.y < height);
-                let food_fits = game.food.x < width && game.food.y < height;
-                game.width = width;
-                game.height = height;
-                if !snake_fits || !food_fits {
-                    game.game_over = true;
-                }
-                paused = true;
-            }
-
-            // Рисуем рамку поля
-            let block = Block::default().borders(Borders::ALL).title("Змейка (ESC - пауза, пробел - рестарт)");
-            f.render_widget(block, size);
-
-            // Игровое поле (без границ, только змейка и еда)
-            let mut rows = Vec::new();
-            for y in 0..game.height {
-                let mut line = Vec::new();
-                for x in 0..game.width {
-                    let p = Point { x, y };
-                    if game.snake.front().unwrap() == &p {
-                        line.push(Span::styled("O", Style::default().fg(Color::Green)));
-                    } else if game.snake.contains(&p) {
-                        line.push(Span::styled("o", Style::default().fg(Color::Green)));
-                    } else if game.food == p {
-                        line.push(Span::styled("*", Style::default().fg(Color::Red)));
-                    } else {
-                        line.push(Span::raw(" "));
+        let frame = frame_since(started);
+        toasts.expire();
+        let running = game.as_ref().is_some_and(|g| g.game_over.is_none())
+            && !paused
+            && countdown.is_none()
+            && level_result.is_none();
+        let celebrating = new_record
+            && game.as_ref().is_some_and(|g| g.game_over.is_some())
+            && celebration_start.is_none_or(|s| frame - s < CELEBRATION_FRAMES);
+        // Пока что-то движется, кадр рисуется каждый оборот; иначе — только после событий
+        let animating = running || countdown.is_some() || dying.is_some() || celebrating || !toasts.is_empty();
+        if dirty || animating {
+            terminal.draw(|f| {
+                let size = f.area();
+                if let Some(editor) = &board_editor {
+                    f.render_widget(EditorView { editor, theme: &theme, ascii: options.ascii }, size);
+                    return;
+                }
+                if let Some(list) = level_list.as_mut() {
+                    f.render_widget(LevelListView { list, theme: &theme, stars: &level_stars }, size);
+                    return;
+                }
+                if splash {
+                    let speed_level = (options.mode == Mode::Nokia)
+                        .then(|| Mode::nokia_level(options.tick.or(config.tick).unwrap_or(Game::BASE_TICK)));
+                    let splash = Splash::new(&theme)
+                        .saved(saved.is_some())
+                        .ghost(best_run.as_ref().and_then(|r| r.outcome).map(|o| o.score))
+                        .mode((!options.ghost && options.can_switch_mode()).then_some(mode.as_str()))
+                        .speed_level(speed_level)
+                        .editor(!options.ghost && options.can_switch_mode());
+                    f.render_widget(splash, size);
+                    return;
+                }
+
+                // Слишком маленький терминал — вместо игры показываем заглушку,
+                // а идущую игру ставим на паузу до увеличения окна
+                if size.width < MIN_WIDTH || size.height < MIN_HEIGHT {
+                    if game.as_ref().is_some_and(|g| g.game_over.is_none()) {
+                        paused = true;
                     }
+                    let msg = Paragraph::new(format!(
+                        "Терминал слишком мал: нужно минимум {MIN_WIDTH}×{MIN_HEIGHT}, сейчас {}×{}",
+                        size.width, size.height
+                    ))
+                    .alignment(Alignment::Center)
+                    .wrap(Wrap { trim: true });
+                    let area = ratatui::layout::Rect {
+                        y: size.y + size.height / 3,
+                        height: size.height - size.height / 3,
+                        ..size
+                    };
+                    f.render_widget(msg, area);
+                    return;
                 }
-                rows.push(Line::from(line));
-            }
-            // Смещаем игровое поле на +1 по x и +1 по y, чтобы оно было внутр�� рамки
-            let area = ratatui::layout::Rect {
-                x: size.x + 1,
-                y: size.y + 1,
-                width: game.width,
-                height: game.height,
-            };
-            let para = Paragraph::new(rows);
-            f.render_widget(para, area);
-
-            // Счёт внизу по центру (ровно под рамкой)
-            let score_str = format!("Счёт: {}", game.score);
-            let score_span = Span::styled(&score_str, Style::default().fg(Color::Yellow));
-            let score_line = Line::from(score_span);
-            let score_para = Paragraph::new(score_line);
-            let score_x = size.x + (size.width / 2).saturating_sub((score_str.len() / 2) as u16);
-            let score_y = size.y + game.height + 1; // теперь ровно под рамкой
-            f.render_widget(
-                score_para,
-                ratatui::layout::Rect {
-                    x: score_x,
-                    y: score_y,
-                    width: score_str.len() as u16,
-                    height: 1,
-                },
-            );
-
-            if game.game_over {
-                let over = Paragraph::new(vec![
-                    Line::from(Span::styled("Игра окончена!", Style::default().fg(Color::Red))),
-                    Line::from(Span::styled("Пробел - рестарт", Style::default().fg(Color::White))),
-                    Line::from(Span::styled("ESC - выход", Style::default().fg(Color::White))),
-                ]);
-                let area = ratatui::layout::Rect {
-                    x: size.x + (size.width / 2) - 11,
-                    y: size.y + game.height / 2,
-                    width: 22,
-                    height: 4,
+
+                // Поле с рамкой сверху, строка статуса снизу.
+                // Размеры поля — всё, что осталось внутри рамки.
+                let [arena, status] = Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(size);
+                // Без рамки поле занимает всю область целиком
+                let block = settings.border.border_type().map(|border_type| {
+                    let block = Block::default()
+                        .borders(Borders::ALL)
+                        .border_type(border_type)
+                        .title(match paths::profile() {
+                            Some(name) => format!("Змейка [{name}] (ESC - пауза, F1 - справка)"),
+                            None => "Змейка (ESC - пауза, F1 - справка)".to_string(),
+                        });
+                    if options.ascii { block.border_set(ASCII_BORDER) } else { block }
+                });
+                // Поле фиксированного размера (`--size`) стоит по центру, иначе занимает всё место
+                let arena = match options.size {
+                    Some((w, h)) => {
+                        let frame = if block.is_some() { 2 } else { 0 };
+                        centered(arena, w * options.mode.cell_width() + frame, h + frame)
+                    }
+                    None => arena,
+                };
+                let inner = block.as_ref().map_or(arena, |b| b.inner(arena));
+                let (width, height) = (inner.width, inner.height);
+
+                // Инициализация игры если ещё не была, с отсчётом перед первым стартом
+                if game.is_none() {
+                    let new = game.insert(options.new_game(width, height, config.tick, config.start_length()));
+                    configure(new, &config, &settings);
+                    countdown = Some(Countdown::new());
+                    ticks = 0;
+                    ghost = best_run.clone().map(Ghost::new);
+                    if record {
+                        recorder = start_recording(new, &mode)
+                            .inspect_err(|e| {
+                                log_warn!("повтор не записывается: {e}");
+                                toasts.push("Не удалось начать запись повтора");
+                            })
+                            .ok();
+                    }
+                }
+                let Some(game) = game.as_mut() else { return };
+
+                // Если размеры изменились (resize терминала) — обновляем размеры поля, сохраняем прогресс, ставим на паузу.
+                // Поле фиксированного размера не меняется, а в тесном терминале просто обрезается.
+                if options.size.is_none() && (game.width != width || game.height != height) {
+                    log_debug!("поле {}x{} → {width}x{height}", game.width, game.height);
+                    game.resize(width, height);
+                    log_replay(&mut recorder, &mut toasts, |rec| rec.resize(ticks, width, height));
+                    paused = true;
+                }
+
+                let mut board = GameWidget::new(&theme).grid(settings.grid && !no_color).plain(no_color).ascii(options.ascii);
+                if let Some(block) = block {
+                    board = board.block(block);
+                }
+                // Еда пульсирует только в идущей игре, чтобы на паузе кадры не менялись
+                if !settings.reduced_motion && running {
+                    board = board.frame(frame);
+                }
+                if let Some(d) = &dying {
+                    board = board.dying(d.frames(frame));
+                }
+                if let Some(ghost) = &ghost {
+                    board = board.ghost(ghost.cells());
+                }
+                board = board
+                    .blackout(game.blackout())
+                    .overtime(game.overtime && game.game_over.is_none())
+                    .wide(options.mode.cell_width() > 1);
+                if !settings.reduced_motion {
+                    board = board.near_miss(slowmo.as_ref().map(|s| s.cell));
+                    if let Some(w) = &warp {
+                        board = board.warp(&w.from, frame.wrapping_sub(w.started_frame));
+                    }
+                }
+                f.render_stateful_widget(board, arena, game);
+                if mutations_open && dying.is_none() {
+                    let lines = hud::mutation_lines(&game.mutations, game.mirrored);
+                    if !lines.is_empty() {
+                        let width = lines.iter().map(|l| l.chars().count() as u16).max().unwrap_or(0) + 2;
+                        let area = Rect { width: width.min(inner.width), height: (lines.len() as u16 + 2).min(inner.height), ..inner };
+                        let list = Paragraph::new(lines.into_iter().map(Line::from).collect::<Vec<_>>())
+                            .style(Style::default().fg(theme.text).bg(theme.popup_bg))
+                            .block(Block::bordered().title(" Мутации ").border_style(Style::default().fg(theme.border)));
+                        f.render_widget(Clear, area);
+                        f.render_widget(list, area);
+                    }
+                }
+
+                // Строка статуса: поля по приоритету сокращаются и убираются на узком терминале,
+                // а шкалы рисуются только на оставшемся месте
+                let best = if game.score > session_best {
+                    // Текущая партия обгоняет лучший счёт сессии — подсвечиваем и показываем разницу
+                    let delta = game.score - session_best;
+                    HudField::new(
+                        format!("Лучший: {session_best} +{delta}"),
+                        format!("B:{session_best}+{delta}"),
+                        Style::default().fg(theme.record).add_modifier(Modifier::BOLD),
+                        3,
+                    )
+                } else {
+                    HudField::new(format!("Лучший: {session_best}"), format!("B:{session_best}"), Style::default().fg(theme.text), 3)
                 };
-                f.render_widget(over, area);
-            } else if paused {
-                let pause = Paragraph::new(vec![
-                    Line::from(Span::styled("Пауза", Style::default().fg(Color::Yellow))),
-                    Line::from(Span::styled("ESC - продолжить", Style::default().fg(Color::White))),
-                ]);
-                let area = ratatui::layout::Rect {
-                    x: size.x + (size.width / 2) - 9,
-                    y: size.y + game.height / 2,
-                    width: 18,
-                    height: 3,
+                // Рекорд режима из таблицы; законченная партия уже в ней, поэтому сравниваем с идущей
+                let record = high_scores.best(&mode).map_or(0, |e| e.score);
+                let record = if game.score > record && !recorded {
+                    let delta = game.score - record;
+                    HudField::new(
+                        format!("Рекорд: {record} +{delta}"),
+                        format!("R:{record}+{delta}"),
+                        Style::default().fg(theme.record).add_modifier(Modifier::BOLD),
+                        3,
+                    )
+                } else {
+                    HudField::new(format!("Рекорд: {record}"), format!("R:{record}"), Style::default().fg(theme.text), 3)
                 };
-                f.render_widget(pause, area);
-            }
-        })?;
+                let speed = game.cells_per_sec();
+                let time = format_duration(clock.elapsed());
+                // Пока счёт тает без еды, рядом с ним стрелка
+                let decay = if game.decaying() { "↓" } else { "" };
+                let mut fields = vec![
+                    HudField::new(
+                        format!("Счёт: {}{decay}", game.score),
+                        format!("S:{}{decay}", game.score),
+                        Style::default().fg(theme.accent),
+                        0,
+                    ),
+                    HudField::new(format!("Длина: {}", game.snake().len()), format!("L:{}", game.snake().len()), Style::default().fg(theme.text), 1),
+                    best,
+                    record,
+                    HudField::new(format!("{speed:.1} кл/с"), format!("{speed:.1}"), Style::default().fg(speed_color(&theme, speed)), 4),
+                    HudField::new(time.clone(), time, Style::default().fg(theme.text), 2),
+                ];
+                // В режиме «две еды» — сколько съедено каждой
+                if game.bonus.is_some() {
+                    let (normal, bonus) = (game.eaten(FoodKind::Normal), game.eaten(FoodKind::Bonus));
+                    fields.push(HudField::new(
+                        format!("Красная: {normal} Синяя: {bonus}"),
+                        format!("{normal}/{bonus}"),
+                        Style::default().fg(theme.bonus_food),
+                        3,
+                    ));
+                }
+                // Пока у змейки есть щит — значок щита
+                if game.shield > 0 {
+                    let icon = if options.ascii { "S" } else { "◈" };
+                    fields.push(HudField::new(format!("{icon} Щит"), icon.to_string(), Style::default().fg(theme.shield), 1));
+                }
+                // Пока очки удвоены — сколько шагов удвоению осталось
+                if game.doubled > 0 {
+                    let left = game.doubled;
+                    fields.push(HudField::new(format!("Очки ×2: {left}"), format!("×2:{left}"), Style::default().fg(theme.doubler), 1));
+                }
+                // Пока мир заморожен — сколько шагов заморозке осталось
+                if game.world_frozen() {
+                    let (left, icon) = (game.frozen, if options.ascii { "F" } else { "❄" });
+                    fields.push(HudField::new(format!("{icon} Заморозка: {left}"), format!("{icon}{left}"), Style::default().fg(theme.frozen), 1));
+                }
+                // С очками за риск — сколько сейчас стоит еда
+                if game.risk_scoring {
+                    let value = game.food_value();
+                    fields.push(HudField::new(format!("Еда: ×{value}"), format!("×{value}"), Style::default().fg(theme.food), 2));
+                }
+                // В бесконечном режиме — сколько раз мутировали правила и сколько еды до следующей мутации
+                if game.mutating {
+                    let (count, due) = (game.mutations.len(), game.mutation_due);
+                    fields.push(HudField::new(
+                        format!("Мутаций: {count}, следующая через {due}"),
+                        format!("M:{count}/{due}"),
+                        Style::default().fg(theme.accent),
+                        2,
+                    ));
+                }
+                // С бонусом у края — сколько шагов накоплено к следующему очку
+                if let Some((done, needed)) = game.edge_progress() {
+                    fields.push(HudField::new(
+                        format!("У края: {done}/{needed}"),
+                        format!("E:{done}"),
+                        Style::default().fg(theme.text),
+                        3,
+                    ));
+                }
+                // В пошаговом режиме — номер шага и повороты, которые войдут в следующий
+                if ticker.is_manual() {
+                    let turns: String = pending_turns.iter().map(|dir| dir.code()).collect();
+                    fields.push(HudField::new(
+                        format!("Шаг {ticks}, ждут: {}", if turns.is_empty() { "—" } else { turns.as_str() }),
+                        format!("#{ticks} {turns}"),
+                        Style::default().fg(theme.accent),
+                        1,
+                    ));
+                }
+                // С `--debug` — сколько тиков в секунду выходит на самом деле
+                if options.debug
+                    && let Some(rate) = ticker.measured_rate()
+                {
+                    fields.push(HudField::new(format!("тиков/с {rate:.2}"), format!("t{rate:.1}"), Style::default().fg(theme.muted), 5));
+                }
+                let score = hud::fit(&fields, status.width);
+                let score_width = score.width() as u16;
+                // Шкалы после счёта: уровень (только в режимах с уровнями) и длина; лишние не рисуются
+                let mut gauges = Vec::new();
+                if let Some((done, threshold)) = game.level_progress() {
+                    gauges.push(level_gauge(&theme, game.level(), done, threshold));
+                }
+                if settings.length_gauge {
+                    gauges.push(length_gauge(&theme, game.snake().len(), game.growth_per_food > 0));
+                }
+                let room = status.width.saturating_sub(score_width);
+                gauges.truncate((room / (MIN_GAUGE_WIDTH + 1)) as usize);
+                if gauges.is_empty() {
+                    f.render_widget(Paragraph::new(score).alignment(Alignment::Center), status);
+                } else {
+                    let [score_area, _, gauges_area] =
+                        Layout::horizontal([Constraint::Length(score_width), Constraint::Length(1), Constraint::Min(0)])
+                            .areas(status);
+                    f.render_widget(Paragraph::new(score), score_area);
+                    let areas = Layout::horizontal(gauges.iter().map(|_| Constraint::Fill(1))).spacing(1).split(gauges_area);
+                    for (gauge, area) in gauges.into_iter().zip(areas.iter()) {
+                        f.render_widget(gauge, *area);
+                    }
+                }
+
+                // Таблица рекордов закрывает поле целиком
+                if let Some(view) = scores_view.as_mut() {
+                    f.render_widget(Dim(&theme), arena);
+                    f.render_widget(view.widget(&high_scores, &theme), centered(arena, 60, arena.height));
+                    f.render_widget(toasts.view(&theme), inner);
+                    return;
+                }
 
-        // Обработка ввода
-        match rx.try_recv() {
-            Ok(KeyEvent { code, modifiers: _, kind, .. }) => {
+                // Справка поверх всего остального, прокрутка ограничивается размером окна
+                if let Some(scroll) = help.as_mut() {
+                    f.render_widget(Dim(&theme), arena);
+                    let popup = help_popup(&theme, &keymap, debug_keys);
+                    *scroll = (*scroll).min(popup.max_scroll(arena));
+                    f.render_widget(popup.scroll(*scroll), arena);
+                    f.render_widget(toasts.view(&theme), inner);
+                    return;
+                }
+
+                // Во время анимации смерти поверх поля ничего не рисуем
+                let overlay = dying.is_none();
+                if overlay && (game.game_over.is_some() || paused) {
+                    f.render_widget(Dim(&theme), arena);
+                }
+                if overlay && let Some(result) = &level_result {
+                    f.render_widget(Dim(&theme), arena);
+                    f.render_widget(level_popup(&theme, result), arena);
+                } else if overlay && let Some(prompt) = &name_prompt {
+                    f.render_widget(name_popup(&theme, game.score, prompt), arena);
+                } else if overlay && game.game_over.is_some() {
+                    let beaten = last_rank == Some(0) && game.score > 0;
+                    let mut popup = game_over_popup(&theme, game, clock.elapsed(), beaten);
+                    // Новый рекорд: рамка переливается, по краям падают звёздочки
+                    if new_record {
+                        let frames = frame - *celebration_start.get_or_insert(frame);
+                        if frames < CELEBRATION_FRAMES {
+                            let celebration = Celebration { theme: &theme, frames };
+                            popup = popup.border(celebration.color());
+                            f.render_widget(celebration, inner);
+                        }
+                    }
+                    f.render_widget(popup, arena);
+                } else if overlay && paused {
+                    f.render_widget(
+                        PauseView {
+                            menu: &pause_menu,
+                            theme: &theme,
+                            game,
+                            settings: &settings,
+                            keymap: &keymap,
+                            elapsed: clock.elapsed(),
+                            session_best,
+                            lifetime: &lifetime,
+                        },
+                        arena,
+                    );
+                } else if let Some(n) = countdown.as_ref().and_then(Countdown::remaining) {
+                    let digit = Paragraph::new(Line::from(Span::styled(
+                        n.to_string(),
+                        Style::default().fg(theme.accent),
+                    )));
+                    f.render_widget(digit, centered(arena, 1, 1));
+                }
+
+                // Сообщения — самый верхний слой, внутри рамки поля
+                f.render_widget(toasts.view(&theme), inner);
+            })?;
+            // Кадр после конца анимации тоже нужен: на нём видно, чем она кончилась
+            dirty = animating;
+        }
+        // Звонки выводим после кадра, чтобы не смешивать их с выводом ratatui
+        bell.flush(terminal.backend_mut())?;
+
+        // Обработка ввода: клавишу ждём до следующего шага или кадра анимации,
+        // а если ничего не движется — пока она не придёт
+        let mut wait = if running {
+            Some(ticker.until_next(Instant::now()).min(MAX_WAIT))
+        } else if animating {
+            Some(MAX_WAIT)
+        } else {
+            None
+        };
+        // Трансляции нужен пульс, даже когда игра стоит
+        if broadcast.is_some() {
+            wait = Some(wait.map_or(BROADCAST_HEARTBEAT, |w| w.min(BROADCAST_HEARTBEAT)));
+        }
+        // Файл настроек проверяется и тогда, когда клавиш нет
+        if let Some(watcher) = &config_watcher {
+            let check = watcher.until_next(Instant::now());
+            wait = Some(wait.map_or(check, |w| w.min(check)));
+        }
+        // Команды управления приходят не через терминал, поэтому совсем не спим
+        #[cfg(unix)]
+        if control.is_some() {
+            wait = Some(wait.map_or(CONTROL_POLL, |w| w.min(CONTROL_POLL)));
+        }
+        // Рестарт по клавише или команде; выполняется после разбора ввода
+        let mut restart = false;
+        match next_event(wait) {
+            Ok(Some(Event::Key(KeyEvent { code, modifiers, kind, .. }))) => {
+                dirty = true;
+                log_trace!("клавиша {code:?} {modifiers:?} {kind:?}");
                 // Обрабатываем только отпускание клавиши
                 if kind != KeyEventKind::Release {
                     // Игнорируем все события кроме отпускания
                     continue;
                 }
-                let game = game.as_mut().unwrap();
-                if game.game_over {
+                // Редактор поля и свои уровни: Enter играет на выбранном поле,
+                // Esc возвращает на заставку
+                let mut chosen: Option<level::Level> = None;
+                let on_screen = board_editor.is_some() || level_list.is_some();
+                if let Some(editor) = board_editor.as_mut()
+                    && let Some(input) = editor.naming.as_mut()
+                {
                     match code {
-                        KeyCode::Char(' ') => {
-                            // Пересоздаём игру с текущими размерами
-                            *game = Game::new(game.width, game.height);
-                            paused = false;
-                            last_tick = Instant::now();
+                        KeyCode::Char(c) => input.insert(c),
+                        KeyCode::Backspace => input.backspace(),
+                        KeyCode::Delete => input.delete(),
+                        KeyCode::Left => input.left(),
+                        KeyCode::Right => input.right(),
+                        KeyCode::Home => input.home(),
+                        KeyCode::End => input.end(),
+                        KeyCode::Enter => editor.finish_naming(),
+                        KeyCode::Esc => editor.naming = None,
+                        _ => {}
+                    }
+                    continue;
+                }
+                if let Some(editor) = board_editor.as_mut() {
+                    match code {
+                        KeyCode::Up => editor.move_cursor(DirectionSnake::Up),
+                        KeyCode::Down => editor.move_cursor(DirectionSnake::Down),
+                        KeyCode::Left => editor.move_cursor(DirectionSnake::Left),
+                        KeyCode::Right => editor.move_cursor(DirectionSnake::Right),
+                        KeyCode::Char(' ') => editor.toggle(),
+                        KeyCode::Char('n' | 'N') => editor.start_naming(),
+                        KeyCode::Char('+' | '=') => editor.change_speed(true),
+                        KeyCode::Char('-') => editor.change_speed(false),
+                        KeyCode::Char(']') => editor.change_target(true),
+                        KeyCode::Char('[') => editor.change_target(false),
+                        KeyCode::Char('s' | 'S') => {
+                            let saved = editor.level().map_err(|e| e.to_string()).and_then(|level| {
+                                let dir = level::dir().ok_or("неизвестна домашняя папка")?;
+                                editor::save(&level, &dir).map_err(|e| e.to_string())
+                            });
+                            editor.note = Some(match saved {
+                                Ok(path) => {
+                                    log_info!("уровень сохранён: {}", path.display());
+                                    format!("Сохранено: {}", path.display())
+                                }
+                                Err(e) => format!("Не сохранено: {e}"),
+                            });
+                        }
+                        KeyCode::Enter => match editor.level() {
+                            Ok(level) => chosen = Some(level),
+                            Err(e) => editor.note = Some(e.to_string()),
+                        },
+                        KeyCode::Esc => board_editor = None,
+                        _ => {}
+                    }
+                } else if let Some(list) = level_list.as_mut() {
+                    match code {
+                        KeyCode::Up => list.up(),
+                        KeyCode::Down => list.down(),
+                        KeyCode::Enter => {
+                            let size = terminal.size()?;
+                            match list.selected().map(|entry| entry.playable(size.width, size.height)) {
+                                Some(Ok(level)) => chosen = Some(level.clone()),
+                                Some(Err(e)) => list.note = Some(e),
+                                None => {}
+                            }
+                        }
+                        KeyCode::Delete => {
+                            if let Err(e) = list.delete() {
+                                log_warn!("уровень не удалён: {e}");
+                                list.note = Some(format!("Не удалось удалить: {e}"));
+                            }
+                        }
+                        KeyCode::Esc => level_list = None,
+                        _ => {}
+                    }
+                } else if splash
+                    && matches!(code, KeyCode::Char('e' | 'E' | 'l' | 'L'))
+                    && !options.ghost
+                    && options.can_switch_mode()
+                {
+                    if matches!(code, KeyCode::Char('e' | 'E')) {
+                        let size = terminal.size()?;
+                        let mut editor = Editor::fit(size.width, size.height);
+                        editor.author = Some(player_name());
+                        board_editor = Some(editor);
+                    } else {
+                        match level::dir().map(|dir| LevelList::load(&dir)) {
+                            Some(Ok(list)) => level_list = Some(list),
+                            Some(Err(e)) => {
+                                log_warn!("свои уровни не прочитаны: {e}");
+                                startup_notes.push(format!("Свои уровни не прочитаны: {e}"));
+                            }
+                            None => startup_notes.push("Свои уровни не найти: неизвестна домашняя папка".to_string()),
+                        }
+                    }
+                    continue;
+                }
+                if let Some(level) = chosen.take() {
+                    board_editor = None;
+                    level_list = None;
+                    if options.use_level(level) {
+                        mode = options.mode_name().to_string();
+                        theme = mode_theme(options.mode, support);
+                        options.tick = speed_arg;
+                    }
+                    splash = false;
+                    for note in startup_notes.drain(..) {
+                        toasts.push(note);
+                    }
+                }
+                if on_screen {
+                    continue;
+                }
+                // На заставке Tab меняет режим, а в режиме «нокиа» цифры — скорость.
+                // У призрака свой режим, поэтому с ним режим не меняется.
+                if splash && code == KeyCode::Tab {
+                    if !options.ghost && options.switch_mode(options.mode.next()) {
+                        mode = options.mode_name().to_string();
+                        theme = mode_theme(options.mode, support);
+                        options.tick = speed_arg;
+                    }
+                    continue;
+                }
+                if splash
+                    && options.mode == Mode::Nokia
+                    && let KeyCode::Char(digit @ '1'..='9') = code
+                {
+                    options.tick = Some(Mode::NOKIA_TICKS[digit as usize - '1' as usize]);
+                    continue;
+                }
+                // Заставка закрывается любой другой клавишей
+                if splash {
+                    splash = false;
+                    for note in startup_notes.drain(..) {
+                        toasts.push(note);
+                    }
+                    // Сохранение продолжается один раз: файл удаляем сразу
+                    if code == KeyCode::Enter
+                        && let Some(restored) = saved.take()
+                    {
+                        log_info!("продолжается сохранённая партия: счёт {}, шагов {}", restored.game.score, restored.ticks);
+                        let mut restored_game = restored.game;
+                        // Партия продолжается со своей скоростью, если её не задали при запуске
+                        restored_game.base_tick =
+                            options.tick.or(restored.speed).or(config.tick).unwrap_or(Game::BASE_TICK);
+                        restored_game.blackouts =
+                            config.blackouts.unwrap_or(false) || restored_game.mutations.contains(&Mutation::Blackouts);
+                        if Mode::from_name(&restored.mode) == Some(Mode::Nokia) {
+                            restored_game.score_multiplier = Mode::nokia_level(restored_game.base_tick);
+                        }
+                        restored_game.steps = restored.ticks;
+                        game = Some(restored_game);
+                        ticks = restored.ticks;
+                        // У сохранённой партии свой сид: призрак ей не пара
+                        ghost = None;
+                        clock = PlayClock { total: restored.elapsed, running_since: None };
+                        countdown = Some(Countdown::new());
+                        if let Some(path) = &save_path
+                            && let Err(e) = fs::remove_file(path)
+                        {
+                            log_warn!("сохранение не удалено: {e}");
+                            toasts.push(format!("Не удалось удалить сохранение: {e}"));
+                        }
+                    }
+                    continue;
+                }
+                // Игры ещё нет, пока терминал слишком мал
+                let Some(game) = game.as_mut() else {
+                    continue;
+                };
+                // Итог уровня закрывается любой клавишей, и после отсчёта игра идёт дальше
+                if level_result.take().is_some() {
+                    countdown = Some(Countdown::new());
+                    continue;
+                }
+                let action = keymap.action(code);
+                if dying.is_none()
+                    && let Some(prompt) = name_prompt.as_mut()
+                {
+                    match code {
+                        KeyCode::Char(c) => prompt.insert(c),
+                        KeyCode::Backspace => prompt.backspace(),
+                        KeyCode::Delete => prompt.delete(),
+                        KeyCode::Left => prompt.left(),
+                        KeyCode::Right => prompt.right(),
+                        KeyCode::Home => prompt.home(),
+                        KeyCode::End => prompt.end(),
+                        KeyCode::Enter => {
+                            let typed = prompt.value().trim().to_string();
+                            name_prompt = None;
+                            let name = if typed.is_empty() { player_name() } else { typed };
+                            if let Some(uploader) = &uploader {
+                                uploader.submit(&Submission {
+                                    name: name.clone(),
+                                    score: game.score,
+                                    mode: mode.clone(),
+                                    seed: game.seed,
+                                    duration: clock.elapsed(),
+                                });
+                            }
+                            last_rank = high_scores.insert(ScoreEntry {
+                                name,
+                                score: game.score,
+                                length: game.snake().len(),
+                                mode: mode.clone(),
+                                date: SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs(),
+                                duration: clock.elapsed(),
+                            });
+                            if let Some(path) = &scores_path
+                                && let Err(e) = high_scores.store(path)
+                            {
+                                log_warn!("рекорды не сохранены: {e}");
+                                toasts.push(format!("Не удалось сохранить рекорды: {e}"));
+                            }
+                            new_record = last_rank == Some(0) && !settings.reduced_motion;
+                            // Сразу показываем таблицу с новой строкой
+                            scores_view = Some(ScoresView::new(Some(&mode), last_rank));
                         }
-                        KeyCode::Esc => break,
+                        KeyCode::Esc => name_prompt = None,
+                        _ => {}
+                    }
+                } else if let Some(view) = scores_view.as_mut() {
+                    match code {
+                        KeyCode::Up => view.up(1),
+                        KeyCode::Down => view.down(1, &high_scores),
+                        KeyCode::PageUp => view.page_up(),
+                        KeyCode::PageDown => view.page_down(&high_scores),
+                        KeyCode::Tab => view.next_mode(&high_scores),
+                        KeyCode::Esc => scores_view = None,
+                        _ => {}
+                    }
+                } else if let Some(scroll) = help.as_mut() {
+                    // Справку листаем стрелками, любая другая клавиша её закрывает
+                    match code {
+                        KeyCode::Up => *scroll = scroll.saturating_sub(1),
+                        KeyCode::Down => *scroll = scroll.saturating_add(1),
+                        _ => help = None,
+                    }
+                } else if action == Some(Action::ToggleSound) {
+                    // Переключатели работают в любом состоянии
+                    settings.sound = !settings.sound;
+                } else if action == Some(Action::ToggleGrid) {
+                    settings.grid = !settings.grid;
+                } else if action == Some(Action::ToggleMutations) {
+                    mutations_open = !mutations_open;
+                } else if action == Some(Action::Screenshot) {
+                    match save_screenshot(game, clock.elapsed()) {
+                        Ok(path) => toasts.push(format!("Снимок: {path}")),
+                        Err(e) => {
+                            log_warn!("снимок не сохранён: {e}");
+                            toasts.push(format!("Ошибка снимка: {e}"));
+                        }
+                    }
+                } else if debug_keys && code == KeyCode::F(8) && game.game_over.is_none() {
+                    let manual = !ticker.is_manual();
+                    ticker.set_manual(manual, Instant::now(), game.tick_rate());
+                    pending_turns.clear();
+                    toasts.push(if manual { "Пошаговый режим: «.» — шаг, F8 — выход" } else { "Пошаговый режим выключен" });
+                } else if action == Some(Action::Dump) {
+                    save_dump(game, &mode, ticks, clock.elapsed(), &mut toasts);
+                } else if action == Some(Action::Report) {
+                    let state = dump::to_json(game, &mode, ticks, clock.elapsed());
+                    match Report::now(Some(state)).save() {
+                        Ok(dir) => {
+                            log_info!("отчёт о проблеме: {}", dir.display());
+                            toasts.push(format!("Отчёт: {}", dir.display()));
+                        }
+                        Err(e) => {
+                            log_warn!("отчёт не записан: {e}");
+                            toasts.push(format!("Ошибка отчёта: {e}"));
+                        }
+                    }
+                } else if action == Some(Action::Help) {
+                    // Пока открыта справка, игра стоит на паузе
+                    if game.game_over.is_none() {
+                        paused = true;
+                    }
+                    help = Some(0);
+                } else if action == Some(Action::HighScores) && dying.is_none() && (game.game_over.is_some() || paused) {
+                    // С экрана конца игры сразу выделяем только что сыгранную партию
+                    scores_view = Some(if game.game_over.is_some() && last_rank.is_some() {
+                        ScoresView::new(Some(&mode), last_rank)
+                    } else {
+                        ScoresView::new(None, None)
+                    });
+                } else if new_record && celebration_start.is_some_and(|s| frame - s < CELEBRATION_FRAMES) {
+                    // Любая клавиша только останавливает праздничную анимацию
+                    new_record = false;
+                } else if dying.is_some() {
+                    // Анимацию смерти можно пропустить пробелом, остальной ввод игнорируется
+                    if action == Some(Action::Restart) {
+                        dying = None;
+                    }
+                } else if game.game_over.is_some() {
+                    match action {
+                        Some(Action::Restart) => restart = true,
+                        Some(Action::Pause) => break,
                         _ => {}
                     }
                 } else if paused {
                     match code {
-                        KeyCode::Esc => paused = false, // ESC снимает паузу
+                        KeyCode::Esc => {
+                            paused = false; // ESC снимает паузу с любой вкладки
+                            pause_menu = PauseMenu::default();
+                            countdown = Some(Countdown::new());
+                        }
+                        KeyCode::Right | KeyCode::Tab => pause_menu.next_tab(),
+                        KeyCode::Left | KeyCode::BackTab => pause_menu.prev_tab(),
+                        KeyCode::Up => pause_menu.up(),
+                        KeyCode::Down => pause_menu.down(),
+                        KeyCode::Enter if pause_menu.tab == PauseTab::Resume => match pause_menu.choose(game.score, session_best) {
+                            Some(PauseAction::Resume) => {
+                                paused = false;
+                                pause_menu = PauseMenu::default();
+                                countdown = Some(Countdown::new());
+                            }
+                            Some(PauseAction::Restart) => {
+                                log_info!("партия брошена: счёт {}, шагов {ticks}", game.score);
+                                // Брошенная партия тоже в счёт лучшего за сессию
+                                session_best = session_best.max(game.score);
+                                pause_menu = PauseMenu::default();
+                                restart = true;
+                            }
+                            Some(PauseAction::Settings) => pause_menu.tab = PauseTab::Settings,
+                            Some(PauseAction::SaveAndQuit) => {
+                                let stored = save_path
+                                    .as_deref()
+                                    .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "неизвестна домашняя папка"))
+                                    .and_then(|path| save::store(path, game, &mode, clock.elapsed(), ticks));
+                                match stored {
+                                    Ok(()) => {
+                                        log_info!("партия сохранена: счёт {}, шагов {ticks}", game.score);
+                                        break;
+                                    }
+                                    Err(e) => {
+                                        log_warn!("партия не сохранена: {e}");
+                                        toasts.push(format!("Не удалось сохранить: {e}"));
+                                    }
+                                }
+                            }
+                            Some(PauseAction::Quit) => {
+                                log_info!("выход без сохранения: счёт {}, шагов {ticks}", game.score);
+                                break;
+                            }
+                            None => {}
+                        },
+                        KeyCode::Enter | KeyCode::Char(' ') => pause_menu.toggle(&mut settings),
+                        _ => {}
+                    }
+                } else if let Some(cd) = countdown.as_mut() {
+                    // Во время отсчёта стрелка запоминается и сразу запускает игру
+                    match action {
+                        Some(Action::Pause) => paused = true,
+                        Some(Action::Move(dir)) if bot.is_none() => cd.queued = Some(dir),
                         _ => {}
                     }
                 } else {
-                    match code {
-                        KeyCode::Esc => paused = true, // ESC ставит на паузу только если не game_over и не paused
-                        KeyCode::Up => game.change_dir(DirectionSnake::Up),
-                        KeyCode::Down => game.change_dir(DirectionSnake::Down),
-                        KeyCode::Left => game.change_dir(DirectionSnake::Left),
-                        KeyCode::Right => game.change_dir(DirectionSnake::Right),
+                    match action {
+                        Some(Action::Pause) => paused = true, // ESC ставит на паузу только если не game_over и не paused
+                        Some(Action::Move(dir)) if bot.is_none() && game.change_dir(dir) => {
+                            log_replay(&mut recorder, &mut toasts, |rec| rec.turn(ticks, dir));
+                            if ticker.is_manual() {
+                                pending_turns.push(dir);
+                            }
+                        }
+                        None if code == KeyCode::Char('.') => ticker.advance(),
+                        Some(Action::Shed) if bot.is_none() => match game.shed() {
+                            Some(_) => log_replay(&mut recorder, &mut toasts, |rec| rec.shed(ticks)),
+                            None if game.shed_cooldown > 0 => {
+                                toasts.push(format!("До следующего сброса хвоста, шагов: {}", game.shed_cooldown))
+                            }
+                            None => {}
+                        },
                         _ => {}
                     }
                 }
             }
-            Err(TryRecvError::Empty) => {}
-            Err(_) => break,
+            // Размер окна, фокус: экран перерисуется
+            Ok(Some(_)) => dirty = true,
+            Ok(None) => {}
+            // Терминал восстановится, а ошибка напечатается после выхода
+            Err(e) => return Err(e),
+        }
+
+        // Изменённый файл настроек: внешний вид — сразу, скорость — со следующей партии.
+        // Флаги командной строки по-прежнему важнее файла.
+        if let Some(result) = config_watcher.as_mut().and_then(|w| w.poll(Instant::now())) {
+            dirty = true;
+            match result {
+                Ok(new) => {
+                    let reload = new.apply_changes(&config, &mut settings);
+                    if let Some(border) = options.border {
+                        settings.border = border;
+                    }
+                    if let Some(growth) = options.growth {
+                        settings.growth_per_food = growth;
+                    }
+                    if reload.color && options.color.is_none() {
+                        support = new.color.unwrap_or_else(ColorSupport::detect);
+                        theme = mode_theme(options.mode, support);
+                    }
+                    if reload.keys {
+                        keymap = new.keymap().expect("клавиши проверены при чтении");
+                    }
+                    log_info!("настройки перечитаны: {settings:?}");
+                    if reload.tick && options.tick.is_none() && game.is_some() {
+                        toasts.push("Настройки перечитаны, скорость — со следующей партии");
+                    } else {
+                        toasts.push("Настройки перечитаны");
+                    }
+                    config = new;
+                }
+                Err(e) => {
+                    log_warn!("настройки не перечитаны: {e}");
+                    toasts.push(format!("Ошибка в настройках, остались прежние: {e}"));
+                }
+            }
+        }
+
+        if let Some(uploader) = &uploader {
+            for status in uploader.poll() {
+                dirty = true;
+                toasts.push(match upload_warning {
+                    Some(warning) => format!("{} ({warning})", status.message()),
+                    None => status.message(),
+                });
+            }
         }
 
+        // Команды через сокет управления: те же действия, что и с клавиатуры
+        #[cfg(unix)]
+        if let Some(control) = control.as_mut() {
+            let mut quit = false;
+            for request in control.poll() {
+                dirty = true;
+                log_debug!("команда управления {:?}", request.command);
+                let status = match game.as_ref() {
+                    _ if splash => "splash",
+                    None => "small",
+                    Some(g) if g.game_over.is_some() => "over",
+                    Some(_) if paused => "pause",
+                    Some(_) if countdown.is_some() => "countdown",
+                    Some(_) => "play",
+                };
+                let playing = matches!(status, "play" | "countdown" | "pause");
+                let reply = match request.command {
+                    Command::State => control::state_json(game.as_ref(), ticks, status),
+                    Command::Quit => {
+                        quit = true;
+                        "ok".to_string()
+                    }
+                    // С заставки `resume` начинает игру, как любая клавиша
+                    Command::Resume if splash => {
+                        splash = false;
+                        for note in startup_notes.drain(..) {
+                            toasts.push(note);
+                        }
+                        "ok".to_string()
+                    }
+                    _ if !playing && status != "over" => format!("error: игра ещё не началась ({status})"),
+                    Command::Restart if status == "over" => {
+                        restart = true;
+                        "ok".to_string()
+                    }
+                    Command::Restart => "error: партия ещё идёт".to_string(),
+                    _ if !playing => "error: партия окончена".to_string(),
+                    Command::Pause => {
+                        paused = true;
+                        "ok".to_string()
+                    }
+                    Command::Resume => {
+                        if paused {
+                            paused = false;
+                            pause_menu = PauseMenu::default();
+                            help = None;
+                            scores_view = None;
+                            countdown = Some(Countdown::new());
+                        }
+                        "ok".to_string()
+                    }
+                    Command::Dir(_) if bot.is_some() => "error: змейку ведёт бот".to_string(),
+                    Command::Dir(_) if paused => "error: игра на паузе".to_string(),
+                    Command::Dir(dir) => {
+                        if let Some(cd) = countdown.as_mut() {
+                            cd.queued = Some(dir);
+                            "ok".to_string()
+                        } else if game.as_mut().is_some_and(|g| g.change_dir(dir)) {
+                            log_replay(&mut recorder, &mut toasts, |rec| rec.turn(ticks, dir));
+                            "ok".to_string()
+                        } else {
+                            "error: поворот не принят".to_string()
+                        }
+                    }
+                };
+                request.reply(&reply);
+            }
+            if quit {
+                break;
+            }
+        }
+        if restart && let Some(game) = game.as_mut() {
+            // Пересоздаём игру с текущими размерами
+            *game = options.new_game(game.width, game.height, config.tick, config.start_length());
+            configure(game, &config, &settings);
+            ticks = 0;
+            slowmo = None;
+            warp = None;
+            ghost = best_run.clone().map(Ghost::new);
+            if record {
+                recorder = start_recording(game, &mode)
+                    .inspect_err(|e| {
+                        log_warn!("повтор не записывается: {e}");
+                        toasts.push("Не удалось начать запись повтора");
+                    })
+                    .ok();
+            }
+            paused = false;
+            countdown = Some(Countdown::new());
+            clock = PlayClock::default();
+            recorded = false;
+            level_done = false;
+            level_result = None;
+            name_prompt = None;
+            last_rank = None;
+            new_record = false;
+            celebration_start = None;
+        }
+
+        // Отсчёт закончился — применяем запомненное направление и запускаем тики
+        if !paused
+            && let Some(cd) = countdown.take_if(|cd| cd.remaining().is_none())
+        {
+            if let (Some(game), Some(dir)) = (game.as_mut(), cd.queued)
+                && game.change_dir(dir)
+            {
+                log_replay(&mut recorder, &mut toasts, |rec| rec.turn(ticks, dir));
+            }
+            ticker.restart(Instant::now(), game.as_ref().map_or(Game::BASE_TICK, Game::tick_rate));
+        }
+
+        if dying.as_ref().is_some_and(|d| d.finished(frame)) {
+            dying = None;
+        }
+
+        // Часы идут только во время активной игры
+        clock.set_running(
+            game.as_ref().is_some_and(|g| g.game_over.is_none())
+                && !paused
+                && countdown.is_none()
+                && dying.is_none()
+                && level_result.is_none(),
+        );
+
         // step только если игра инициализирована
-        if let Some(game) = game.as_mut() {
-            if !game.game_over && !paused && last_tick.elapsed() >= tick_rate {
-                game.step();
-                last_tick = Instant::now();
+        let now = Instant::now();
+        let late = ticker.late(now);
+        if let Some(game) = game.as_mut()
+            && game.game_over.is_none()
+            && !paused
+            && countdown.is_none()
+            && level_result.is_none()
+            && ticker.due(now, game.tick_rate())
+        {
+            // Тик, опоздавший больше чем на интервал, — признак подвисания
+            if late > game.tick_rate() {
+                log_debug!("тик {ticks} опоздал на {late:?} при интервале {:?}", game.tick_rate());
+            }
+            if let Some(bot) = bot.as_mut() {
+                match bot.decide(game, ticks) {
+                    Ok(Some(dir)) if game.change_dir(dir) => {
+                        log_replay(&mut recorder, &mut toasts, |rec| rec.turn(ticks, dir));
+                    }
+                    Ok(_) => {}
+                    Err(e) => {
+                        bot_error = Some(e);
+                        break;
+                    }
+                }
+            }
+            // Тело до шага нужно анимации портала, если змейка в него войдёт
+            let body: Option<Vec<Point>> = game.portal.map(|_| game.snake().iter().copied().collect());
+            let (score, doubled) = (game.score, game.doubled > 0);
+            let events = game.step();
+            ticks += 1;
+            pending_turns.clear();
+            slowmo = slowmo.filter(|s| s.ticks_left > 1).map(|s| SlowMo { ticks_left: s.ticks_left - 1, ..s });
+            log_replay(&mut recorder, &mut toasts, |rec| rec.step(ticks, game));
+            if let Some(ghost) = ghost.as_mut() {
+                ghost.sync(ticks);
+            }
+            report::remember_state(bot::state_json(game, ticks));
+            for event in events {
+                match event {
+                    GameEvent::Ate { .. } => {
+                        if settings.sound {
+                            bell.ring();
+                        }
+                        if session_best > 0 && game.score == session_best + 1 {
+                            toasts.push("Рекорд сессии!");
+                        }
+                        // Пока очки удвоены, видно, сколько принесла еда
+                        if doubled {
+                            toasts.push(format!("+{}", game.score.saturating_sub(score)));
+                        }
+                    }
+                    GameEvent::Grew(len) if len % LENGTH_MILESTONE == 0 => {
+                        toasts.push(format!("Длина {len}!"));
+                    }
+                    GameEvent::LevelUp(level) => toasts.push(format!("Уровень {level}!")),
+                    GameEvent::Died(cause) => {
+                        // Итог партии попадает в лучший результат сессии сразу, а не с новой партией:
+                        // конец игры и статистика уже показывают его
+                        session_best = session_best.max(game.score);
+                        log_info!("конец игры: {cause:?}, счёт {}, длина {}, шагов {ticks}", game.score, game.snake().len());
+                        if settings.sound {
+                            bell.ring_twice();
+                        }
+                        dying = Some(Dying::new(frame));
+                        slowmo = None;
+                        if options.dump_on_death {
+                            save_dump(game, &mode, ticks, clock.elapsed(), &mut toasts);
+                        }
+                    }
+                    GameEvent::Won => {
+                        session_best = session_best.max(game.score);
+                        log_info!("победа: счёт {}, шагов {ticks}", game.score);
+                        if settings.sound {
+                            bell.ring_twice();
+                        }
+                        toasts.push("Победа!");
+                    }
+                    GameEvent::BossHit { left: 0, .. } => {
+                        if settings.sound {
+                            bell.ring_twice();
+                        }
+                        toasts.push("Босс повержен!");
+                    }
+                    GameEvent::BossHit { left, .. } => {
+                        if settings.sound {
+                            bell.ring();
+                        }
+                        toasts.push(format!("Босс: ещё {left}"));
+                    }
+                    GameEvent::Pellet { left, .. } => {
+                        if settings.sound {
+                            bell.ring();
+                        }
+                        if left == 0 {
+                            toasts.push("Гусеница целиком!");
+                        }
+                    }
+                    GameEvent::Collapsed(_) => toasts.push("Гусеница рассыпалась"),
+                    GameEvent::Overtime => {
+                        log_info!("овертайм: длина {}, шагов {ticks}", game.snake().len());
+                        if settings.sound {
+                            bell.ring_twice();
+                        }
+                        toasts.push("Овертайм! Еда вдвое дороже");
+                    }
+                    GameEvent::Mutated(mutation) => {
+                        log_info!("мутация {mutation:?} на шаге {ticks}");
+                        if settings.sound {
+                            bell.ring_twice();
+                        }
+                        toasts.push(format!("Мутация: {}", mutation.describe()));
+                    }
+                    GameEvent::NearMiss(cell) => {
+                        log_debug!("проход вплотную у {cell:?} на шаге {ticks}");
+                        slowmo = Some(SlowMo { cell, ticks_left: SLOWMO_TICKS });
+                    }
+                    GameEvent::Teleported { from, to } => {
+                        log_debug!("портал {from:?} -> {to:?} на шаге {ticks}");
+                        if settings.sound {
+                            bell.ring();
+                        }
+                        let len = game.snake().len();
+                        let from = std::iter::once(from).chain(body.iter().flatten().copied()).take(len).collect();
+                        warp = Some(Warp { from, started_frame: frame });
+                    }
+                    GameEvent::ShieldPicked(_) => {
+                        if settings.sound {
+                            bell.ring();
+                        }
+                        toasts.push("Щит!");
+                    }
+                    GameEvent::Shielded(cause) => {
+                        log_info!("щит спас от {cause:?} на шаге {ticks}");
+                        if settings.sound {
+                            bell.ring_twice();
+                        }
+                        toasts.push("Щит принял удар");
+                    }
+                    GameEvent::Doubled(_) => {
+                        if settings.sound {
+                            bell.ring();
+                        }
+                        toasts.push(format!("Очки ×2 ещё {} шагов", game.doubled));
+                    }
+                    GameEvent::Froze(_) => {
+                        if settings.sound {
+                            bell.ring();
+                        }
+                        toasts.push(format!("Заморозка ещё {} шагов", game.frozen));
+                    }
+                    GameEvent::Moved(_) | GameEvent::Grew(_) => {}
+                }
+            }
+            // В замедлении следующий шаг, уже назначенный тикером, откладывается ещё на интервал
+            if slowmo.is_some() {
+                ticker.stretch(game.tick_rate());
+            }
+            // Партия закончилась — повтор дописан
+            if game.game_over.is_some()
+                && let Some(rec) = recorder.take()
+                && let Err(e) = rec.finish(ticks, game.score)
+            {
+                log_warn!("запись повтора прервана: {e}");
+                toasts.push("Запись повтора прервана");
+            }
+        }
+        // Счёт дошёл до пара уровня: звёзды даются один раз за партию, и игра ждёт,
+        // пока игрок посмотрит итог
+        if let Some(level) = &options.level
+            && let Some(game) = game.as_ref()
+            && !level_done
+            && game.score >= level.par()
+        {
+            level_done = true;
+            let elapsed = clock.elapsed();
+            let earned = stars::rate(level, game.score, elapsed);
+            let previous = level_stars.record(level, earned);
+            log_info!("уровень «{}» пройден: звёзд {earned}, время {elapsed:?}", level.name);
+            if let Some(path) = &stars_path
+                && let Err(e) = level_stars.store(path)
+            {
+                log_warn!("звёзды не сохранены: {e}");
+                toasts.push(format!("Не удалось сохранить звёзды: {e}"));
+            }
+            if settings.sound {
+                bell.ring_twice();
+            }
+            level_result = Some(LevelResult { earned, previous, elapsed });
+        }
+        // Законченная партия, попадающая в таблицу рекордов, один раз просит имя игрока
+        if let Some(game) = game.as_ref()
+            && game.game_over.is_some()
+            && !recorded
+        {
+            recorded = true;
+            let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+            lifetime.record(game, clock.elapsed(), now);
+            if let Some(path) = &lifetime_path
+                && let Err(e) = lifetime.store(path)
+            {
+                log_warn!("статистика не сохранена: {e}");
+                toasts.push(format!("Не удалось сохранить статистику: {e}"));
+            }
+            if game.score > 0 && high_scores.rank(&mode, game.score).is_some() {
+                name_prompt = Some(TextInput::new(&player_name(), NAME_MAX));
+            }
+        }
+        // Трансляция: новых зрителей принимаем сразу, снимок шлём после шага или для пульса
+        if let Some(broadcast) = broadcast.as_mut() {
+            if broadcast.accept() > 0 {
+                last_broadcast = None;
+                toasts.push(format!("Зритель подключился, всего {}", broadcast.spectators()));
+            }
+            if let Some(game) = game.as_ref()
+                && last_broadcast.is_none_or(|(at, tick)| tick != ticks || at.elapsed() >= BROADCAST_HEARTBEAT)
+            {
+                broadcast.send(&broadcast::snapshot(game, ticks, paused));
+                last_broadcast = Some((Instant::now(), ticks));
             }
         }
-        thread::sleep(Duration::from_millis(10));
     }
 
-    disable_raw_mode()?;
-    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    drop(guard);
+    // Сокет убираем до `exit_with`: после него деструкторы уже не вызываются
+    #[cfg(unix)]
+    drop(control);
+    if let Some(e) = bot_error {
+        log_warn!("{e}");
+        exit_with(format!("Игра остановлена: {e}"));
+    }
+    log_info!("выход");
     Ok(())
 }
@@ -0,0 +1,79 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+/// Профиль, выбранный при запуске; без него — профиль по умолчанию
+static PROFILE: OnceLock<String> = OnceLock::new();
+
+/// Выбирает профиль для всех путей игры (см. [`crate::profile`]). Работает один раз:
+/// профиль не меняется, пока игра запущена.
+pub fn set_profile(name: &str) {
+    let _ = PROFILE.set(name.to_string());
+}
+
+/// Имя выбранного профиля; `None` — профиль по умолчанию
+pub fn profile() -> Option<&'static str> {
+    PROFILE.get().map(String::as_str)
+}
+
+/// Папка выбранного профиля внутри общей папки
+fn in_profile(root: PathBuf) -> PathBuf {
+    match profile() {
+        Some(name) => root.join("profiles").join(name),
+        None => root,
+    }
+}
+
+/// Папка данных выбранного профиля (см. [`data_root`])
+pub fn data_dir() -> Option<PathBuf> {
+    data_root().map(in_profile)
+}
+
+/// Общая папка данных игры: `$XDG_DATA_HOME/zmiy` или `~/.local/share/zmiy`.
+/// `None`, если домашняя папка неизвестна.
+pub fn data_root() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_DATA_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))?;
+    Some(base.join("zmiy"))
+}
+
+/// Записывает файл целиком: сначала во временный рядом, затем переименовывает,
+/// чтобы оборванная запись не испортила прежнее содержимое
+pub fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let tmp = path.with_extension("tmp");
+    let mut file = fs::File::create(&tmp)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    fs::rename(tmp, path)
+}
+
+/// Папка настроек выбранного профиля (см. [`config_root`])
+pub fn config_dir() -> Option<PathBuf> {
+    config_root().map(in_profile)
+}
+
+/// Общая папка настроек: `$XDG_CONFIG_HOME/zmiy` или `~/.config/zmiy`
+pub fn config_root() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(base.join("zmiy"))
+}
+
+/// Папка состояния, например журнала: `$XDG_STATE_HOME/zmiy` или `~/.local/state/zmiy`
+pub fn state_dir() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_STATE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/state")))?;
+    Some(base.join("zmiy"))
+}
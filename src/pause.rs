@@ -0,0 +1,290 @@
+use std::time::Duration;
+
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Tabs, Widget, Wrap},
+};
+
+use crate::{
+    game::Game,
+    hud::format_duration,
+    input::Keymap,
+    lifetime::Lifetime,
+    settings::Settings,
+    theme::Theme,
+    widget::centered,
+};
+
+/// Вкладки меню паузы
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub enum PauseTab {
+    #[default]
+    Resume,
+    Stats,
+    Help,
+    Settings,
+}
+
+impl PauseTab {
+    const ALL: [PauseTab; 4] = [PauseTab::Resume, PauseTab::Stats, PauseTab::Help, PauseTab::Settings];
+
+    fn title(self) -> &'static str {
+        match self {
+            PauseTab::Resume => "Пауза",
+            PauseTab::Stats => "Статистика",
+            PauseTab::Help => "Клавиши",
+            PauseTab::Settings => "Настройки",
+        }
+    }
+
+    fn index(self) -> usize {
+        Self::ALL.iter().position(|t| *t == self).unwrap_or(0)
+    }
+}
+
+/// Действия на вкладке «Пауза», по порядку строк
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PauseAction {
+    Resume,
+    Restart,
+    Settings,
+    SaveAndQuit,
+    Quit,
+}
+
+impl PauseAction {
+    const ALL: [PauseAction; 5] =
+        [PauseAction::Resume, PauseAction::Restart, PauseAction::Settings, PauseAction::SaveAndQuit, PauseAction::Quit];
+
+    fn label(self) -> &'static str {
+        match self {
+            PauseAction::Resume => "Продолжить",
+            PauseAction::Restart => "Начать заново",
+            PauseAction::Settings => "Настройки",
+            PauseAction::SaveAndQuit => "Сохранить и выйти",
+            PauseAction::Quit => "Выйти без сохранения",
+        }
+    }
+
+    /// Действие бросает партию, и со счётом лучше сессионного его нужно подтвердить
+    fn destructive(self) -> bool {
+        matches!(self, PauseAction::Restart | PauseAction::Quit)
+    }
+}
+
+/// Состояние меню паузы: открытая вкладка, выбранное действие и строка настроек
+#[derive(Default)]
+pub struct PauseMenu {
+    pub tab: PauseTab,
+    action: usize,
+    cursor: usize,
+    /// Действие, которое ждёт второго Enter (см. [`PauseMenu::choose`])
+    armed: Option<PauseAction>,
+}
+
+impl PauseMenu {
+    pub fn next_tab(&mut self) {
+        self.tab = PauseTab::ALL[(self.tab.index() + 1) % PauseTab::ALL.len()];
+    }
+
+    pub fn prev_tab(&mut self) {
+        let len = PauseTab::ALL.len();
+        self.tab = PauseTab::ALL[(self.tab.index() + len - 1) % len];
+    }
+
+    /// Строка выше: действия идут по кругу, настройки упираются в первую
+    pub fn up(&mut self) {
+        if self.tab == PauseTab::Resume {
+            let len = PauseAction::ALL.len();
+            self.action = (self.action + len - 1) % len;
+            self.armed = None;
+        } else {
+            self.cursor = self.cursor.saturating_sub(1);
+        }
+    }
+
+    /// Строка ниже: действия идут по кругу, настройки упираются в последнюю
+    pub fn down(&mut self) {
+        if self.tab == PauseTab::Resume {
+            self.action = (self.action + 1) % PauseAction::ALL.len();
+            self.armed = None;
+        } else {
+            self.cursor = (self.cursor + 1).min(SETTINGS.len() - 1);
+        }
+    }
+
+    /// Выбранное действие
+    pub fn selected(&self) -> PauseAction {
+        PauseAction::ALL[self.action]
+    }
+
+    /// Выбирает действие под курсором. Если счёт партии `score` лучше прежнего лучшего
+    /// за сессию `session_best`, действие, которое бросает партию, сначала только просит
+    /// подтверждения, и `None` значит, что выполнять пока нечего.
+    pub fn choose(&mut self, score: usize, session_best: usize) -> Option<PauseAction> {
+        let action = self.selected();
+        if action.destructive() && score > session_best && self.armed != Some(action) {
+            self.armed = Some(action);
+            return None;
+        }
+        self.armed = None;
+        Some(action)
+    }
+
+    /// Переключает выбранную настройку (только на вкладке настроек). Рост за еду
+    /// не переключается, а идёт по кругу от 0 до [`Game::MAX_GROWTH_PER_FOOD`].
+    pub fn toggle(&self, settings: &mut Settings) {
+        if self.tab != PauseTab::Settings {
+            return;
+        }
+        let flag = match self.cursor {
+            0 => &mut settings.sound,
+            1 => &mut settings.grid,
+            2 => &mut settings.length_gauge,
+            3 => &mut settings.reduced_motion,
+            _ => {
+                settings.growth_per_food = (settings.growth_per_food + 1) % (Game::MAX_GROWTH_PER_FOOD + 1);
+                return;
+            }
+        };
+        *flag = !*flag;
+    }
+}
+
+/// Настройки, которые можно переключить прямо из паузы, в том же порядке, что и в `PauseMenu::toggle`
+const SETTINGS: [&str; 5] = ["Звук", "Сетка", "Шкала длины", "Без анимаций", "Рост за еду (со следующей партии)"];
+
+/// Отметки настроек: флажок у переключателей, число у роста за еду
+fn setting_marks(settings: &Settings) -> [String; 5] {
+    let flag = |on: bool| if on { "[x]" } else { "[ ]" }.to_string();
+    [
+        flag(settings.sound),
+        flag(settings.grid),
+        flag(settings.length_gauge),
+        flag(settings.reduced_motion),
+        format!("[{}]", settings.growth_per_food),
+    ]
+}
+
+/// Всё, что нужно для отрисовки меню паузы
+pub struct PauseView<'a> {
+    pub menu: &'a PauseMenu,
+    pub theme: &'a Theme,
+    pub game: &'a Game,
+    pub settings: &'a Settings,
+    pub keymap: &'a Keymap,
+    pub elapsed: Duration,
+    pub session_best: usize,
+    /// Статистика за всё время, без текущей партии
+    pub lifetime: &'a Lifetime,
+}
+
+impl Widget for PauseView<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let theme = self.theme;
+        // Статистике нужно место и для итогов за всё время
+        let height = if self.menu.tab == PauseTab::Stats { 22 } else { 18 };
+        let area = centered(area.intersection(buf.area), 44, height);
+        Clear.render(area, buf);
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title_bottom(Line::from(" ←→/Tab - вкладки, ESC - продолжить "))
+            .border_style(Style::default().fg(theme.border))
+            .style(Style::default().bg(theme.popup_bg).fg(theme.text));
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        let [tabs_area, _, body] =
+            Layout::vertical([Constraint::Length(1), Constraint::Length(1), Constraint::Min(0)]).areas(inner);
+        Tabs::new(PauseTab::ALL.iter().map(|t| t.title()))
+            .select(self.menu.tab.index())
+            .highlight_style(Style::default().fg(theme.accent).add_modifier(Modifier::BOLD))
+            .render(tabs_area, buf);
+
+        let lines = match self.menu.tab {
+            PauseTab::Resume => self.resume_tab(),
+            PauseTab::Stats => self.stats_tab(),
+            PauseTab::Help => self.keymap.lines(theme),
+            PauseTab::Settings => self.settings_tab(),
+        };
+        Paragraph::new(lines).wrap(Wrap { trim: false }).render(body, buf);
+    }
+}
+
+impl PauseView<'_> {
+    fn resume_tab(&self) -> Vec<Line<'static>> {
+        let accent = Style::default().fg(self.theme.accent);
+        let mut lines = vec![
+            Line::from(Span::styled("Игра на паузе", accent)),
+            Line::default(),
+            Line::from(format!("Счёт: {}   Длина: {}", self.game.score, self.game.snake().len())),
+            Line::from(format!("Время: {}", format_duration(self.elapsed))),
+            Line::default(),
+        ];
+        let items = PauseAction::ALL.iter().map(|&action| {
+            let line = Line::from(format!(" {} ", action.label()));
+            if self.menu.armed == Some(action) {
+                line.style(Style::default().fg(self.theme.danger))
+            } else {
+                line
+            }
+        });
+        lines.extend(select(items, self.menu.action, self.theme));
+        lines.push(Line::default());
+        lines.push(if self.menu.armed.is_some() {
+            Line::styled("Это лучший счёт сессии. Enter - ещё раз, если точно", Style::default().fg(self.theme.danger))
+        } else {
+            Line::from("↑↓ - выбор, Enter - выполнить")
+        });
+        lines
+    }
+
+    fn stats_tab(&self) -> Vec<Line<'static>> {
+        let game = self.game;
+        let secs = self.elapsed.as_secs_f64();
+        let per_minute = if secs > 0.0 { game.score as f64 * 60.0 / secs } else { 0.0 };
+        let cells = game.width as usize * game.height as usize;
+        let mut lines = vec![
+            Line::from(format!("Счёт:            {}", game.score)),
+            Line::from(format!("Лучший за сессию: {}", self.session_best.max(game.score))),
+            Line::from(format!("Длина:           {}", game.snake().len())),
+            Line::from(format!("Заполнено поля:  {:.1}%", game.snake().len() as f64 * 100.0 / cells.max(1) as f64)),
+            Line::from(format!("Время:           {}", format_duration(self.elapsed))),
+            Line::from(format!("Еды в минуту:    {per_minute:.1}")),
+            Line::from(format!("Скорость:        {:.1} кл/с", game.cells_per_sec())),
+            Line::default(),
+            Line::from(Span::styled("За всё время", Style::default().fg(self.theme.accent))),
+        ];
+        lines.extend(self.lifetime.lines().into_iter().map(|(label, value)| Line::from(format!("{label}: {value}"))));
+        lines
+    }
+
+    fn settings_tab(&self) -> Vec<Line<'static>> {
+        let marks = setting_marks(self.settings);
+        let items = SETTINGS.iter().zip(marks).map(|(name, mark)| Line::from(format!("{mark} {name}")));
+        let mut lines = select(items, self.menu.cursor, self.theme);
+        lines.push(Line::default());
+        lines.push(Line::from("↑↓ - выбор, Enter - переключить"));
+        lines
+    }
+}
+
+/// Строки списка, где выбранная строка `selected` выделена цветом акцента. Строка,
+/// у которой уже есть свой цвет, выделяется этим цветом вместо акцента.
+fn select<'a>(items: impl IntoIterator<Item = Line<'a>>, selected: usize, theme: &Theme) -> Vec<Line<'a>> {
+    items
+        .into_iter()
+        .enumerate()
+        .map(|(i, line)| {
+            if i == selected {
+                let bg = line.style.fg.unwrap_or(theme.accent);
+                line.style(Style::default().fg(theme.popup_bg).bg(bg))
+            } else {
+                line
+            }
+        })
+        .collect()
+}
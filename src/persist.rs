@@ -0,0 +1,1038 @@
+//! Версии форматов сохранений, повторов и уровней и чтение файлов старых версий.
+//!
+//! Первая строка файла — сигнатура и номер формата, дальше по полю на строку:
+//! имя поля и значения через пробел. Файл любой известной версии читается сразу
+//! в структуру текущего формата. Новые форматы до сих пор только добавляли строки,
+//! поэтому в файле старой версии их просто нет, и поля получают значения по умолчанию.
+//! Строка, которой в формате файла ещё не было, — ошибка: какая строка в каком формате
+//! появилась, записано в таблицах `SAVE_SINCE`, `REPLAY_SINCE` и `LEVEL_SINCE`.
+//! То, что поменялось без новых строк, выставляется по номеру формата. Файл более
+//! новой версии не читается вовсе, чтобы не потерять поля, о которых эта версия
+//! игры не знает.
+//!
+//! Отдельная структура для старой версии нужна, только если формат поменял вид
+//! или смысл уже существующей строки; такого пока не было.
+//!
+//! Как поменять формат: добавить поле в структуру, а его строку — в таблицу
+//! с новым номером формата, поднять `FORMAT_VERSION` и положить пример файла новой
+//! версии в `tests/fixtures`.
+//!
+//! История версий:
+//!
+//! - сохранения: 1 — первая версия; 2 — добавлена скорость змейки (`speed`);
+//!   3 — добавлены сброшенные хвосты (`obstacles`, `shed`); 4 — добавлена дальняя еда
+//!   (`candidates`); 5 — добавлены стены уровня (`walls`); 6 — добавлены зоны скорости
+//!   (`fast`, `slow`); 7 — добавлены бомбы (`bombs`, `bomb`); 8 — добавлены очки за риск
+//!   и скорость от длины (`risk`, `speedup`); 9 — добавлен бонус за игру у края
+//!   (`edge`, `edge_ticks`); 10 — добавлены боссы (`bosses`, `boss`, `growth`);
+//!   11 — добавлены гусеницы (`caterpillars`, `caterpillar`); 12 — добавлен рост за еду (`grow`);
+//!   13 — добавлен овертайм (`overtime`); 14 — добавлены мутации бесконечного режима
+//!   (`mutations`, `mutate`, `mirrored`); 15 — добавлено таяние счёта без еды (`decay`, `idle`);
+//!   16 — добавлен след слизи (`slime`, `slimy`); 17 — добавлены порталы (`teleports`, `portal`);
+//!   18 — добавлены щиты (`shields`, `shield_pickup`, `shield`); 19 — добавлены удвоители
+//!   очков (`doubling`, `doubler`, `doubled`); 20 — добавлены заморозки (`freezing`,
+//!   `freezer`, `frozen`);
+//! - повторы: 1 — первая версия; 2 — добавлена скорость змейки (`speed`);
+//!   3 — добавлены контрольные суммы состояния (`c`); 4 — добавлен сброс хвоста (`x`);
+//!   5 — добавлена дальняя еда (`candidates`); 6 — добавлены стены уровня (`walls`);
+//!   7 — добавлены зоны скорости (`fast`, `slow`); 8 — добавлены бомбы (`bombs`);
+//!   9 — добавлены очки за риск и скорость от длины (`risk`, `speedup`);
+//!   10 — добавлен бонус за игру у края (`edge`); 11 — добавлены боссы (`bosses`);
+//!   12 — добавлены гусеницы (`caterpillars`); 13 — добавлено случайное начало (`start`);
+//!   14 — добавлена длина змейки в начале партии (`length`); 15 — добавлен рост за еду (`grow`);
+//!   16 — добавлен овертайм: строк не прибавилось, но в партиях формата 16 он есть;
+//!   17 — добавлено таяние счёта без еды (`decay`); 18 — добавлен след слизи (`slime`);
+//!   19 — добавлены порталы (`teleports`); 20 — добавлены щиты (`shields`);
+//!   21 — добавлены удвоители очков (`doubling`); 22 — добавлены мутации, которые двигают
+//!   поле: строк не прибавилось, но в партиях формата 22 стены пропускают, а еда убегает на ходу;
+//!   23 — добавлены заморозки (`freezing`);
+//! - уровни: 1 — первая версия; 2 — добавлены зоны скорости (`+` и `-` в рядах);
+//!   3 — добавлены автор, скорость и цель уровня (`author`, `speed`, `target`).
+
+use std::{fmt, str::FromStr, time::Duration};
+
+use crate::{
+    game::{Bomb, Boss, DirectionSnake, Mutation, Point, ScoreHistory},
+    level,
+    replay::{self, Input, Outcome},
+    save,
+};
+
+/// Почему файл не прочитался как файл нужного формата
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum FormatError {
+    /// Первое слово не та сигнатура
+    Foreign,
+    /// Номер формата, которого никогда не было
+    Unsupported(u32),
+    /// Файл записан более новой версией игры
+    Newer(u32),
+    MissingField(&'static str),
+    /// Непонятная строка, номер с единицы
+    BadLine(usize),
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FormatError::Foreign => write!(f, "неизвестный формат файла"),
+            FormatError::Unsupported(v) => write!(f, "формат версии {v} не поддерживается"),
+            FormatError::Newer(v) => write!(f, "файл создан более новой версией zmiy (формат {v})"),
+            FormatError::MissingField(name) => write!(f, "нет поля {name}"),
+            FormatError::BadLine(n) => write!(f, "ошибка в строке {n}"),
+        }
+    }
+}
+
+impl std::error::Error for FormatError {}
+
+/// Поля файла после строки заголовка. Прочитанные поля забираются, а то,
+/// что осталось непрочитанным, — ошибка в строке.
+pub struct Fields<'a> {
+    lines: Vec<(usize, Vec<&'a str>)>,
+}
+
+impl<'a> Fields<'a> {
+    /// Забирает все строки поля и возвращает последнюю: повторённое поле заменяет прежнее
+    fn take(&mut self, key: &str) -> Option<(usize, Vec<&'a str>)> {
+        let mut last = None;
+        self.lines.retain(|(n, words)| {
+            let matches = words[0] == key;
+            if matches {
+                last = Some((*n, words[1..].to_vec()));
+            }
+            !matches
+        });
+        last
+    }
+
+    /// Все строки с одним из имён, в порядке файла
+    fn take_all(&mut self, keys: &[&str]) -> Vec<(usize, Vec<&'a str>)> {
+        let (taken, rest) = self.lines.drain(..).partition(|(_, words)| keys.contains(&words[0]));
+        self.lines = rest;
+        taken
+    }
+
+    /// Поле с ровно одним значением
+    fn value<T: FromStr>(&mut self, key: &str) -> Result<Option<T>, FormatError> {
+        match self.take(key) {
+            Some((n, values)) => match values.as_slice() {
+                [v] => v.parse().map(Some).map_err(|_| FormatError::BadLine(n)),
+                _ => Err(FormatError::BadLine(n)),
+            },
+            None => Ok(None),
+        }
+    }
+
+    /// Поле из нескольких чисел
+    fn numbers<T: FromStr>(&mut self, key: &str) -> Result<Option<(usize, Vec<T>)>, FormatError> {
+        match self.take(key) {
+            Some((n, values)) => {
+                let parsed = values.iter().map(|v| v.parse().map_err(|_| FormatError::BadLine(n))).collect::<Result<_, _>>()?;
+                Ok(Some((n, parsed)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Поле — список клеток, по паре чисел на клетку
+    fn points(&mut self, key: &str) -> Result<Option<Vec<Point>>, FormatError> {
+        match self.numbers::<u16>(key)? {
+            Some((n, coords)) if !coords.len().is_multiple_of(2) => Err(FormatError::BadLine(n)),
+            Some((_, coords)) => Ok(Some(coords.chunks(2).map(|c| Point { x: c[0], y: c[1] }).collect())),
+            None => Ok(None),
+        }
+    }
+
+    /// Поле — одна клетка или размер
+    fn point(&mut self, key: &str) -> Result<Option<Point>, FormatError> {
+        match self.numbers::<u16>(key)? {
+            Some((_, coords)) if coords.len() == 2 => Ok(Some(Point { x: coords[0], y: coords[1] })),
+            Some((n, _)) => Err(FormatError::BadLine(n)),
+            None => Ok(None),
+        }
+    }
+
+    /// Поле-флаг без значений: есть строка — включено
+    fn flag(&mut self, key: &str) -> Result<bool, FormatError> {
+        match self.take(key) {
+            Some((_, values)) if values.is_empty() => Ok(true),
+            Some((n, _)) => Err(FormatError::BadLine(n)),
+            None => Ok(false),
+        }
+    }
+
+    /// Текст до конца строки: слова через один пробел
+    fn text(&mut self, key: &str) -> Option<String> {
+        self.take(key).map(|(_, words)| words.join(" "))
+    }
+
+    /// Строки, которых в формате `format` ещё не было: в `since` — имена строк и форматы,
+    /// в которых они появились. Первая такая строка — ошибка, а не тихо принятое значение.
+    fn reject_newer(&self, format: u32, since: &[(&str, u32)]) -> Result<(), FormatError> {
+        let newer = |key: &str| since.iter().any(|&(name, added)| name == key && added > format);
+        match self.lines.iter().find(|(_, words)| newer(words[0])) {
+            Some((n, _)) => Err(FormatError::BadLine(*n)),
+            None => Ok(()),
+        }
+    }
+
+    /// Все поля прочитаны; первая оставшаяся строка — ошибка
+    fn finish(self) -> Result<(), FormatError> {
+        match self.lines.first() {
+            Some((n, _)) => Err(FormatError::BadLine(*n)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Проверяет сигнатуру и возвращает номер формата и поля файла.
+/// Файл формата новее `current` не читается.
+pub fn read_header<'a>(text: &'a str, signature: &str, current: u32) -> Result<(u32, Fields<'a>), FormatError> {
+    let mut lines = text.lines().enumerate().map(|(i, line)| (i + 1, line.split_whitespace().collect::<Vec<_>>()));
+    let version = match lines.next() {
+        Some((_, words)) if words.first() == Some(&signature) => {
+            words.get(1).and_then(|v| v.parse::<u32>().ok()).ok_or(FormatError::BadLine(1))?
+        }
+        _ => return Err(FormatError::Foreign),
+    };
+    if version == 0 {
+        return Err(FormatError::Unsupported(version));
+    }
+    if version > current {
+        return Err(FormatError::Newer(version));
+    }
+    Ok((version, Fields { lines: lines.filter(|(_, words)| !words.is_empty()).collect() }))
+}
+
+fn required<T>(value: Option<T>, name: &'static str) -> Result<T, FormatError> {
+    value.ok_or(FormatError::MissingField(name))
+}
+
+/// Строки сохранения, которых не было в формате 1, и формат, в котором каждая появилась
+const SAVE_SINCE: &[(&str, u32)] = &[
+    ("speed", 2),
+    ("obstacles", 3),
+    ("shed", 3),
+    ("candidates", 4),
+    ("walls", 5),
+    ("fast", 6),
+    ("slow", 6),
+    ("bombs", 7),
+    ("bomb", 7),
+    ("speedup", 8),
+    ("risk", 8),
+    ("edge", 9),
+    ("edge_ticks", 9),
+    ("bosses", 10),
+    ("boss", 10),
+    ("growth", 10),
+    ("caterpillars", 11),
+    ("caterpillar", 11),
+    ("grow", 12),
+    ("overtime", 13),
+    ("mutations", 14),
+    ("mutate", 14),
+    ("mirrored", 14),
+    ("decay", 15),
+    ("idle", 15),
+    ("slime", 16),
+    ("slimy", 16),
+    ("teleports", 17),
+    ("portal", 17),
+    ("shields", 18),
+    ("shield_pickup", 18),
+    ("shield", 18),
+    ("doubling", 19),
+    ("doubler", 19),
+    ("doubled", 19),
+    ("freezing", 20),
+    ("freezer", 20),
+    ("frozen", 20),
+];
+
+/// Сохранение в текущем формате
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Save {
+    /// Версия игры, записавшей файл
+    pub version: String,
+    pub mode: String,
+    pub board: Point,
+    pub seed: u64,
+    pub rng: u64,
+    pub dir: DirectionSnake,
+    pub food: Point,
+    pub score: usize,
+    pub levels: Option<usize>,
+    pub ticks: u64,
+    pub elapsed: Duration,
+    pub history: ScoreHistory,
+    pub snake: Vec<Point>,
+    /// Интервал между шагами; `None` у сохранений формата 1
+    pub speed: Option<Duration>,
+    /// Клетки сброшенных хвостов; строки нет, если хвост не сбрасывался
+    pub obstacles: Vec<Point>,
+    /// Через сколько шагов снова можно сбросить хвост; строки нет, если уже можно
+    pub shed: u64,
+    /// Из скольких клеток выбирается еда; строки нет, если из одной
+    pub candidates: u32,
+    /// Стены внутри поля; строки нет, если партия идёт не на уровне
+    pub walls: Vec<Point>,
+    /// Клетки быстрой зоны; строки нет, если зон нет
+    pub fast: Vec<Point>,
+    /// Клетки медленной зоны; строки нет, если зон нет
+    pub slow: Vec<Point>,
+    /// Включены ли бомбы; строка `bombs` без значений
+    pub bombs: bool,
+    /// Бомба на поле; строки нет, если её нет
+    pub bomb: Option<Bomb>,
+    /// Шаг правила «скорость от длины» в микросекундах; строки нет, если правило выключено
+    pub speedup: Option<Duration>,
+    /// Включены ли очки за риск; строка `risk` без значений
+    pub risk: bool,
+    /// Включён ли бонус за игру у края; строка `edge` без значений
+    pub edge: bool,
+    /// Шагов у края к следующему очку; строки нет, если их нет
+    pub edge_ticks: u64,
+    /// Включены ли боссы; строка `bosses` без значений
+    pub bosses: bool,
+    /// Босс на поле: `boss x y касаний`
+    pub boss: Option<Boss>,
+    /// Клетки роста, которые змейка ещё не набрала; строки нет, если их нет
+    pub growth: usize,
+    /// Включены ли гусеницы; строка `caterpillars` без значений
+    pub caterpillars: bool,
+    /// Звенья гусеницы на поле от конца к концу; строки нет, если её нет
+    pub caterpillar: Vec<Point>,
+    /// Рост за еду; строки нет, если 1
+    pub grow: usize,
+    /// Партия дошла до овертайма
+    pub overtime: bool,
+    /// Мутации по порядку
+    pub mutations: Vec<Mutation>,
+    /// Сколько еды до следующей мутации; 0 — правила не мутируют
+    pub mutate: usize,
+    /// Сколько ещё шагов управление зеркальное
+    pub mirrored: u64,
+    /// Счёт тает без еды
+    pub decay: bool,
+    /// Шагов с последней еды
+    pub idle: u64,
+    /// След слизи включён
+    pub slime: bool,
+    /// Клетки слизи с оставшимися им шагами, от самой старой к самой свежей
+    pub slimy: Vec<(Point, u64)>,
+    /// Порталы включены
+    pub teleports: bool,
+    /// Портал, если он на поле
+    pub portal: Option<Point>,
+    /// Щиты включены
+    pub shields: bool,
+    /// Щит на поле, если он есть
+    pub shield_pickup: Option<Point>,
+    /// Сколько щитов у змейки
+    pub shield: u8,
+    /// Удвоители включены
+    pub doubling: bool,
+    /// Удвоитель на поле, если он есть
+    pub doubler: Option<Point>,
+    /// Сколько ещё шагов очки удвоены
+    pub doubled: u64,
+    /// Заморозки включены
+    pub freezing: bool,
+    /// Заморозка на поле, если она есть
+    pub freezer: Option<Point>,
+    /// Сколько ещё шагов мир заморожен
+    pub frozen: u64,
+}
+
+impl Save {
+    /// Сохранение формата `format`. Строк, которых в нём ещё не было, в файле быть
+    /// не может, а поля под них получают значения по умолчанию.
+    pub fn from_fields(format: u32, mut fields: Fields) -> Result<Self, FormatError> {
+        fields.reject_newer(format, SAVE_SINCE)?;
+        let version = fields.text("version");
+        let mode = fields.text("mode");
+        let board = fields.point("board")?;
+        let seed = fields.value("seed")?;
+        let rng = fields.value("rng")?;
+        let dir = match fields.take("dir") {
+            Some((n, words)) => Some(words.first().and_then(|d| DirectionSnake::from_code(d)).ok_or(FormatError::BadLine(n))?),
+            None => None,
+        };
+        let food = fields.point("food")?;
+        let score = fields.value("score")?;
+        let levels = fields.value("levels")?;
+        let ticks = fields.value("ticks")?;
+        let elapsed = fields.value("elapsed")?.map(Duration::from_millis);
+        let history = match fields.numbers::<u64>("history")? {
+            Some((n, values)) => {
+                let [stride, since_last, points @ ..] = values.as_slice() else {
+                    return Err(FormatError::BadLine(n));
+                };
+                Some(ScoreHistory {
+                    stride: u32::try_from(*stride).map_err(|_| FormatError::BadLine(n))?,
+                    since_last: u32::try_from(*since_last).map_err(|_| FormatError::BadLine(n))?,
+                    points: points.to_vec(),
+                })
+            }
+            None => None,
+        };
+        let snake = fields.points("snake")?;
+        let speed = fields.value("speed")?.map(Duration::from_millis);
+        let obstacles = fields.points("obstacles")?.unwrap_or_default();
+        let shed = fields.value("shed")?.unwrap_or(0);
+        let candidates = fields.value("candidates")?.unwrap_or(1);
+        let walls = fields.points("walls")?.unwrap_or_default();
+        let fast = fields.points("fast")?.unwrap_or_default();
+        let slow = fields.points("slow")?.unwrap_or_default();
+        let bombs = fields.flag("bombs")?;
+        let bomb = match fields.numbers::<u64>("bomb")? {
+            Some((n, values)) => match values.as_slice() {
+                &[x, y, left] => {
+                    let (Ok(x), Ok(y)) = (u16::try_from(x), u16::try_from(y)) else {
+                        return Err(FormatError::BadLine(n));
+                    };
+                    Some(Bomb { at: Point { x, y }, left })
+                }
+                _ => return Err(FormatError::BadLine(n)),
+            },
+            None => None,
+        };
+        let speedup = fields.value::<u64>("speedup")?.map(Duration::from_micros);
+        let risk = fields.flag("risk")?;
+        let edge = fields.flag("edge")?;
+        let edge_ticks = fields.value("edge_ticks")?.unwrap_or(0);
+        let bosses = fields.flag("bosses")?;
+        let boss = match fields.numbers::<u16>("boss")? {
+            Some((n, values)) => match values.as_slice() {
+                &[x, y, hits] => {
+                    let Ok(hits) = u8::try_from(hits) else {
+                        return Err(FormatError::BadLine(n));
+                    };
+                    Some(Boss { at: Point { x, y }, hits })
+                }
+                _ => return Err(FormatError::BadLine(n)),
+            },
+            None => None,
+        };
+        let growth = fields.value("growth")?.unwrap_or(0);
+        let caterpillars = fields.flag("caterpillars")?;
+        let caterpillar = fields.points("caterpillar")?.unwrap_or_default();
+        let grow = fields.value("grow")?.unwrap_or(1);
+        let overtime = fields.flag("overtime")?;
+        let mutations = match fields.take("mutations") {
+            Some((n, codes)) => codes.iter().map(|c| Mutation::from_code(c).ok_or(FormatError::BadLine(n))).collect::<Result<_, _>>()?,
+            None => Vec::new(),
+        };
+        let mutate = fields.value("mutate")?.unwrap_or(0);
+        let mirrored = fields.value("mirrored")?.unwrap_or(0);
+        let decay = fields.flag("decay")?;
+        let idle = fields.value("idle")?.unwrap_or(0);
+        let slime = fields.flag("slime")?;
+        let slimy = match fields.numbers::<u64>("slimy")? {
+            Some((n, values)) if !values.len().is_multiple_of(3) => return Err(FormatError::BadLine(n)),
+            Some((n, values)) => values
+                .chunks(3)
+                .map(|c| match (u16::try_from(c[0]), u16::try_from(c[1])) {
+                    (Ok(x), Ok(y)) if c[2] > 0 => Ok((Point { x, y }, c[2])),
+                    _ => Err(FormatError::BadLine(n)),
+                })
+                .collect::<Result<_, _>>()?,
+            None => Vec::new(),
+        };
+        let teleports = fields.flag("teleports")?;
+        let portal = fields.point("portal")?;
+        let shields = fields.flag("shields")?;
+        let shield_pickup = fields.point("shield_pickup")?;
+        let shield = fields.value("shield")?.unwrap_or(0);
+        let doubling = fields.flag("doubling")?;
+        let doubler = fields.point("doubler")?;
+        let doubled = fields.value("doubled")?.unwrap_or(0);
+        let freezing = fields.flag("freezing")?;
+        let freezer = fields.point("freezer")?;
+        let frozen = fields.value("frozen")?.unwrap_or(0);
+        fields.finish()?;
+        Ok(Self {
+            version: required(version, "version")?,
+            mode: required(mode, "mode")?,
+            board: required(board, "board")?,
+            seed: required(seed, "seed")?,
+            rng: required(rng, "rng")?,
+            dir: required(dir, "dir")?,
+            food: required(food, "food")?,
+            score: required(score, "score")?,
+            levels,
+            ticks: required(ticks, "ticks")?,
+            elapsed: required(elapsed, "elapsed")?,
+            history: required(history, "history")?,
+            snake: required(snake, "snake")?,
+            // С формата 2 скорость пишется всегда
+            speed: if format >= 2 { Some(required(speed, "speed")?) } else { None },
+            obstacles,
+            shed,
+            candidates,
+            walls,
+            fast,
+            slow,
+            bombs,
+            bomb,
+            speedup,
+            risk,
+            edge,
+            edge_ticks,
+            bosses,
+            boss,
+            growth,
+            caterpillars,
+            caterpillar,
+            grow,
+            overtime,
+            mutations,
+            mutate,
+            mirrored,
+            decay,
+            idle,
+            slime,
+            slimy,
+            teleports,
+            portal,
+            shields,
+            shield_pickup,
+            shield,
+            doubling,
+            doubler,
+            doubled,
+            freezing,
+            freezer,
+            frozen,
+        })
+    }
+
+    /// Текст файла в текущем формате
+    pub fn to_text(&self) -> String {
+        let mut text = format!(
+            "{} {}\nversion {}\nmode {}\nboard {} {}\nseed {}\nrng {}\n",
+            save::SIGNATURE,
+            save::FORMAT_VERSION,
+            self.version,
+            self.mode,
+            self.board.x,
+            self.board.y,
+            self.seed,
+            self.rng
+        );
+        if let Some(speed) = self.speed {
+            text.push_str(&format!("speed {}\n", speed.as_millis()));
+        }
+        text.push_str(&format!("dir {}\nfood {} {}\nscore {}\n", self.dir.code(), self.food.x, self.food.y, self.score));
+        if let Some(n) = self.levels {
+            text.push_str(&format!("levels {n}\n"));
+        }
+        text.push_str(&format!("ticks {}\nelapsed {}\n", self.ticks, self.elapsed.as_millis()));
+        text.push_str(&format!("history {} {}", self.history.stride, self.history.since_last));
+        for p in &self.history.points {
+            text.push_str(&format!(" {p}"));
+        }
+        if !self.obstacles.is_empty() {
+            text.push_str("\nobstacles");
+            for p in &self.obstacles {
+                text.push_str(&format!(" {} {}", p.x, p.y));
+            }
+        }
+        if !self.walls.is_empty() {
+            text.push_str("\nwalls");
+            for p in &self.walls {
+                text.push_str(&format!(" {} {}", p.x, p.y));
+            }
+        }
+        for (key, cells) in [("fast", &self.fast), ("slow", &self.slow)] {
+            if !cells.is_empty() {
+                text.push_str(&format!("\n{key}"));
+                for p in cells {
+                    text.push_str(&format!(" {} {}", p.x, p.y));
+                }
+            }
+        }
+        if self.bombs {
+            text.push_str("\nbombs");
+        }
+        if let Some(bomb) = self.bomb {
+            text.push_str(&format!("\nbomb {} {} {}", bomb.at.x, bomb.at.y, bomb.left));
+        }
+        if let Some(step) = self.speedup {
+            text.push_str(&format!("\nspeedup {}", step.as_micros()));
+        }
+        if self.risk {
+            text.push_str("\nrisk");
+        }
+        if self.edge {
+            text.push_str("\nedge");
+        }
+        if self.edge_ticks > 0 {
+            text.push_str(&format!("\nedge_ticks {}", self.edge_ticks));
+        }
+        if self.bosses {
+            text.push_str("\nbosses");
+        }
+        if let Some(boss) = self.boss {
+            text.push_str(&format!("\nboss {} {} {}", boss.at.x, boss.at.y, boss.hits));
+        }
+        if self.growth > 0 {
+            text.push_str(&format!("\ngrowth {}", self.growth));
+        }
+        if self.grow != 1 {
+            text.push_str(&format!("\ngrow {}", self.grow));
+        }
+        if self.overtime {
+            text.push_str("\novertime");
+        }
+        if !self.mutations.is_empty() {
+            text.push_str("\nmutations");
+            for m in &self.mutations {
+                text.push_str(&format!(" {}", m.code()));
+            }
+        }
+        if self.mutate > 0 {
+            text.push_str(&format!("\nmutate {}", self.mutate));
+        }
+        if self.mirrored > 0 {
+            text.push_str(&format!("\nmirrored {}", self.mirrored));
+        }
+        if self.decay {
+            text.push_str("\ndecay");
+        }
+        if self.idle > 0 {
+            text.push_str(&format!("\nidle {}", self.idle));
+        }
+        if self.slime {
+            text.push_str("\nslime");
+        }
+        if !self.slimy.is_empty() {
+            text.push_str("\nslimy");
+            for (p, left) in &self.slimy {
+                text.push_str(&format!(" {} {} {left}", p.x, p.y));
+            }
+        }
+        if self.teleports {
+            text.push_str("\nteleports");
+        }
+        if let Some(portal) = self.portal {
+            text.push_str(&format!("\nportal {} {}", portal.x, portal.y));
+        }
+        if self.shields {
+            text.push_str("\nshields");
+        }
+        if let Some(p) = self.shield_pickup {
+            text.push_str(&format!("\nshield_pickup {} {}", p.x, p.y));
+        }
+        if self.shield > 0 {
+            text.push_str(&format!("\nshield {}", self.shield));
+        }
+        if self.doubling {
+            text.push_str("\ndoubling");
+        }
+        if let Some(p) = self.doubler {
+            text.push_str(&format!("\ndoubler {} {}", p.x, p.y));
+        }
+        if self.doubled > 0 {
+            text.push_str(&format!("\ndoubled {}", self.doubled));
+        }
+        if self.freezing {
+            text.push_str("\nfreezing");
+        }
+        if let Some(p) = self.freezer {
+            text.push_str(&format!("\nfreezer {} {}", p.x, p.y));
+        }
+        if self.frozen > 0 {
+            text.push_str(&format!("\nfrozen {}", self.frozen));
+        }
+        if self.caterpillars {
+            text.push_str("\ncaterpillars");
+        }
+        if !self.caterpillar.is_empty() {
+            text.push_str("\ncaterpillar");
+            for p in &self.caterpillar {
+                text.push_str(&format!(" {} {}", p.x, p.y));
+            }
+        }
+        if self.candidates != 1 {
+            text.push_str(&format!("\ncandidates {}", self.candidates));
+        }
+        if self.shed > 0 {
+            text.push_str(&format!("\nshed {}", self.shed));
+        }
+        text.push_str("\nsnake");
+        for p in &self.snake {
+            text.push_str(&format!(" {} {}", p.x, p.y));
+        }
+        text.push('\n');
+        text
+    }
+}
+
+/// Читает сохранение любой известной версии
+pub fn read_save(text: &str) -> Result<Save, FormatError> {
+    let (format, fields) = read_header(text, save::SIGNATURE, save::FORMAT_VERSION)?;
+    Save::from_fields(format, fields)
+}
+
+/// Строки повтора, которых не было в формате 1, и формат, в котором каждая появилась
+const REPLAY_SINCE: &[(&str, u32)] = &[
+    ("speed", 2),
+    ("c", 3),
+    ("x", 4),
+    ("candidates", 5),
+    ("walls", 6),
+    ("fast", 7),
+    ("slow", 7),
+    ("bombs", 8),
+    ("speedup", 9),
+    ("risk", 9),
+    ("edge", 10),
+    ("bosses", 11),
+    ("caterpillars", 12),
+    ("start", 13),
+    ("length", 14),
+    ("grow", 15),
+    ("decay", 17),
+    ("slime", 18),
+    ("teleports", 19),
+    ("shields", 20),
+    ("doubling", 21),
+    ("freezing", 23),
+];
+
+/// Повтор в текущем формате
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Replay {
+    /// Версия игры, записавшей повтор
+    pub version: String,
+    pub seed: u64,
+    pub width: u16,
+    pub height: u16,
+    pub mode: String,
+    /// События ввода с тиками, по возрастанию тиков; [`Input::Shed`] — с формата 4
+    pub inputs: Vec<(u64, Input)>,
+    /// `None`, если запись оборвалась до конца партии
+    pub outcome: Option<Outcome>,
+    /// Интервал между шагами; `None` у повторов формата 1
+    pub speed: Option<Duration>,
+    /// Контрольные суммы после шага с этим тиком, по возрастанию тиков (см. [`crate::game::Game::checksum`]).
+    /// В повторах до формата 3 их нет, и такие повторы не проверяются.
+    pub checks: Vec<(u64, u64)>,
+    /// Из скольких клеток выбиралась еда (см. [`crate::game::Game::food_candidates`]);
+    /// 1 у повторов без строки `candidates`
+    pub candidates: u32,
+    /// Стены внутри поля; пусто, если партия шла не на уровне
+    pub walls: Vec<Point>,
+    /// Клетки быстрой зоны; пусто, если зон не было
+    pub fast: Vec<Point>,
+    /// Клетки медленной зоны; пусто, если зон не было
+    pub slow: Vec<Point>,
+    /// Были ли в партии бомбы; строка `bombs` без значений
+    pub bombs: bool,
+    /// Шаг правила «скорость от длины» в микросекундах; строки нет, если правило выключено
+    pub speedup: Option<Duration>,
+    /// Шли ли очки за риск; строка `risk` без значений
+    pub risk: bool,
+    /// Шёл ли бонус за игру у края; строка `edge` без значений
+    pub edge: bool,
+    /// Шли ли боссы; строка `bosses` без значений
+    pub bosses: bool,
+    /// Были ли гусеницы; строка `caterpillars` без значений
+    pub caterpillars: bool,
+    /// Клетка и направление, с которых змейка начала; `None` — посреди поля головой вправо
+    pub start: Option<(Point, DirectionSnake)>,
+    /// Длина змейки в начале партии; строки нет, если 1
+    pub length: usize,
+    /// Рост за еду; строки нет, если 1
+    pub grow: usize,
+    /// Овертайм в партии есть; строки под него нет, он есть во всех повторах с формата 16
+    pub overtime: bool,
+    /// Счёт таял без еды
+    pub decay: bool,
+    /// След слизи был включён
+    pub slime: bool,
+    /// В партии были порталы
+    pub teleports: bool,
+    /// В партии были щиты
+    pub shields: bool,
+    /// В партии были удвоители
+    pub doubling: bool,
+    /// Мутации двигают поле (см. [`crate::game::Game::moving_mutations`]); строки под это нет,
+    /// так во всех повторах с формата 22, а раньше стены не пропускали, а еда не убегала на ходу
+    pub moving_mutations: bool,
+    /// В партии были заморозки
+    pub freezing: bool,
+}
+
+/// События ввода повтора из строк с именами `keys`, в порядке файла. Тики не убывают.
+fn read_inputs(fields: &mut Fields, keys: &[&str]) -> Result<Vec<(u64, Input)>, FormatError> {
+    let mut inputs: Vec<(u64, Input)> = Vec::new();
+    for (n, words) in fields.take_all(keys) {
+        let bad = || FormatError::BadLine(n);
+        let num = |i: usize| words.get(i).and_then(|w| w.parse::<u64>().ok()).ok_or_else(bad);
+        let size = |i: usize| words.get(i).and_then(|w| w.parse::<u16>().ok()).ok_or_else(bad);
+        let input = match words.as_slice() {
+            ["t", _, dir] => (num(1)?, Input::Turn(DirectionSnake::from_code(dir).ok_or_else(bad)?)),
+            ["r", _, _, _] => (num(1)?, Input::Resize { width: size(2)?, height: size(3)? }),
+            ["x", _] => (num(1)?, Input::Shed),
+            _ => return Err(bad()),
+        };
+        if inputs.last().is_some_and(|&(tick, _)| tick > input.0) {
+            return Err(bad());
+        }
+        inputs.push(input);
+    }
+    Ok(inputs)
+}
+
+impl Replay {
+    /// Повтор формата `format`. Строк, которых в нём ещё не было, в файле быть
+    /// не может, а поля под них получают значения по умолчанию.
+    /// Суммы — строки `c тик сумма`, сумма — 16 шестнадцатеричных цифр.
+    pub fn from_fields(format: u32, mut fields: Fields) -> Result<Self, FormatError> {
+        fields.reject_newer(format, REPLAY_SINCE)?;
+        let inputs = read_inputs(&mut fields, &["t", "r", "x"])?;
+        let version = fields.text("version");
+        let seed = fields.value("seed")?;
+        let board = fields.point("board")?;
+        let mode = fields.text("mode");
+        let outcome = match fields.numbers::<u64>("end")? {
+            Some((_, values)) if values.len() == 2 => Some(Outcome { ticks: values[0], score: values[1] as usize }),
+            Some((n, _)) => return Err(FormatError::BadLine(n)),
+            None => None,
+        };
+        let speed = fields.value("speed")?.map(Duration::from_millis);
+        let mut checks: Vec<(u64, u64)> = Vec::new();
+        for (n, words) in fields.take_all(&["c"]) {
+            let check = match words.as_slice() {
+                [_, tick, sum] => tick.parse().ok().zip(u64::from_str_radix(sum, 16).ok()),
+                _ => None,
+            };
+            match check {
+                Some(check) if checks.last().is_none_or(|&(tick, _)| tick < check.0) => checks.push(check),
+                _ => return Err(FormatError::BadLine(n)),
+            }
+        }
+        let candidates = fields.value("candidates")?.unwrap_or(1);
+        let walls = fields.points("walls")?.unwrap_or_default();
+        let fast = fields.points("fast")?.unwrap_or_default();
+        let slow = fields.points("slow")?.unwrap_or_default();
+        let bombs = fields.flag("bombs")?;
+        let speedup = fields.value::<u64>("speedup")?.map(Duration::from_micros);
+        let risk = fields.flag("risk")?;
+        let edge = fields.flag("edge")?;
+        let bosses = fields.flag("bosses")?;
+        let caterpillars = fields.flag("caterpillars")?;
+        let start = match fields.take("start") {
+            Some((n, words)) => match words.as_slice() {
+                [x, y, dir] => {
+                    let at = x.parse().ok().zip(y.parse().ok()).map(|(x, y)| Point { x, y });
+                    Some(at.zip(DirectionSnake::from_code(dir)).ok_or(FormatError::BadLine(n))?)
+                }
+                _ => return Err(FormatError::BadLine(n)),
+            },
+            None => None,
+        };
+        let length = fields.value("length")?.unwrap_or(1);
+        let grow = fields.value("grow")?.unwrap_or(1);
+        let decay = fields.flag("decay")?;
+        let slime = fields.flag("slime")?;
+        let teleports = fields.flag("teleports")?;
+        let shields = fields.flag("shields")?;
+        let doubling = fields.flag("doubling")?;
+        let freezing = fields.flag("freezing")?;
+        fields.finish()?;
+        let board = required(board, "board")?;
+        Ok(Self {
+            version: required(version, "version")?,
+            seed: required(seed, "seed")?,
+            width: board.x,
+            height: board.y,
+            mode: required(mode, "mode")?,
+            inputs,
+            outcome,
+            // С формата 2 скорость пишется всегда
+            speed: if format >= 2 { Some(required(speed, "speed")?) } else { None },
+            checks,
+            candidates,
+            walls,
+            fast,
+            slow,
+            bombs,
+            speedup,
+            risk,
+            edge,
+            bosses,
+            caterpillars,
+            start,
+            length,
+            grow,
+            overtime: format >= 16,
+            decay,
+            slime,
+            teleports,
+            shields,
+            doubling,
+            moving_mutations: format >= 22,
+            freezing,
+        })
+    }
+}
+
+/// Читает повтор любой известной версии
+pub fn read_replay(text: &str) -> Result<Replay, FormatError> {
+    let (format, fields) = read_header(text, replay::SIGNATURE, replay::FORMAT_VERSION)?;
+    Replay::from_fields(format, fields)
+}
+
+/// Клетки рядов уровня — строк `row` сверху вниз — кроме свободных (`.`), с их символами.
+/// Рядов ровно `board.y`, в каждом `board.x` символов из `allowed`.
+fn read_rows(fields: &mut Fields, board: Point, allowed: &[char]) -> Result<Vec<(Point, char)>, FormatError> {
+    let rows = fields.take_all(&["row"]);
+    let mut cells = Vec::new();
+    for (y, (n, words)) in rows.iter().enumerate() {
+        let [_, row] = words.as_slice() else {
+            return Err(FormatError::BadLine(*n));
+        };
+        if y >= board.y as usize || row.chars().count() != board.x as usize {
+            return Err(FormatError::BadLine(*n));
+        }
+        for (x, c) in row.chars().enumerate() {
+            if !allowed.contains(&c) {
+                return Err(FormatError::BadLine(*n));
+            }
+            if c != '.' {
+                cells.push((Point { x: x as u16, y: y as u16 }, c));
+            }
+        }
+    }
+    if rows.len() < board.y as usize {
+        return Err(FormatError::MissingField("row"));
+    }
+    Ok(cells)
+}
+
+/// Клетки рядов с символом `c`
+fn cells_of(cells: &[(Point, char)], c: char) -> Vec<Point> {
+    cells.iter().filter(|&&(_, d)| d == c).map(|&(p, _)| p).collect()
+}
+
+/// Строки уровня, которых не было в формате 1, и формат, в котором каждая появилась
+const LEVEL_SINCE: &[(&str, u32)] = &[("author", 3), ("speed", 3), ("target", 3)];
+
+/// Уровень в текущем формате
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Level {
+    /// Название уровня: под ним идут рекорды, сохранения и повторы
+    pub name: String,
+    pub board: Point,
+    /// Клетки стен, по рядам сверху вниз
+    pub walls: Vec<Point>,
+    /// Клетки быстрой зоны, по рядам сверху вниз
+    pub fast: Vec<Point>,
+    /// Клетки медленной зоны, по рядам сверху вниз
+    pub slow: Vec<Point>,
+    pub author: Option<String>,
+    /// Интервал между шагами, с которым уровень задуман
+    pub speed: Option<Duration>,
+    /// Счёт, которого надо набрать на уровне
+    pub target: Option<usize>,
+}
+
+impl Level {
+    /// Уровень формата `format`. Ряды поля — строки `row`, сверху вниз: `#` — стена,
+    /// `.` — свободная клетка, а с формата 2 ещё `+` — быстрая клетка и `-` — медленная.
+    pub fn from_fields(format: u32, mut fields: Fields) -> Result<Self, FormatError> {
+        fields.reject_newer(format, LEVEL_SINCE)?;
+        let name = fields.text("name");
+        let board = required(fields.point("board")?, "board")?;
+        let allowed: &[char] = if format >= 2 { &['#', '.', '+', '-'] } else { &['#', '.'] };
+        let cells = read_rows(&mut fields, board, allowed)?;
+        let author = fields.text("author");
+        let speed = fields.value("speed")?.map(Duration::from_millis);
+        let target = fields.value("target")?;
+        fields.finish()?;
+        Ok(Self {
+            name: required(name, "name")?,
+            board,
+            walls: cells_of(&cells, '#'),
+            fast: cells_of(&cells, '+'),
+            slow: cells_of(&cells, '-'),
+            author,
+            speed,
+            target,
+        })
+    }
+
+    /// Уровень в текущем формате: ряды рисуются по стенам и зонам
+    pub fn to_text(&self) -> String {
+        let mut text = format!(
+            "{} {}\nname {}\nboard {} {}\n",
+            level::SIGNATURE,
+            level::FORMAT_VERSION,
+            self.name,
+            self.board.x,
+            self.board.y
+        );
+        if let Some(author) = &self.author {
+            text.push_str(&format!("author {author}\n"));
+        }
+        if let Some(speed) = self.speed {
+            text.push_str(&format!("speed {}\n", speed.as_millis()));
+        }
+        if let Some(target) = self.target {
+            text.push_str(&format!("target {target}\n"));
+        }
+        for y in 0..self.board.y {
+            text.push_str("row ");
+            for x in 0..self.board.x {
+                let p = Point { x, y };
+                text.push(if self.walls.contains(&p) {
+                    '#'
+                } else if self.fast.contains(&p) {
+                    '+'
+                } else if self.slow.contains(&p) {
+                    '-'
+                } else {
+                    '.'
+                });
+            }
+            text.push('\n');
+        }
+        text
+    }
+}
+
+/// Читает уровень любой известной версии
+pub fn read_level(text: &str) -> Result<Level, FormatError> {
+    let (format, fields) = read_header(text, level::SIGNATURE, level::FORMAT_VERSION)?;
+    Level::from_fields(format, fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn header_versions() {
+        assert!(matches!(read_header("zmiy-save 1\n", "zmiy-save", 2), Ok((1, _))));
+        assert!(matches!(read_header("zmiy-save 3\n", "zmiy-save", 2), Err(FormatError::Newer(3))));
+        assert!(matches!(read_header("zmiy-save 0\n", "zmiy-save", 2), Err(FormatError::Unsupported(0))));
+        assert!(matches!(read_header("zmiy-save x\n", "zmiy-save", 2), Err(FormatError::BadLine(1))));
+        assert!(matches!(read_header("zmiy-replay 1\n", "zmiy-save", 2), Err(FormatError::Foreign)));
+        assert_eq!(FormatError::Newer(3).to_string(), "файл создан более новой версией zmiy (формат 3)");
+    }
+
+    #[test]
+    fn fields_are_read_once() {
+        let (_, mut fields) = read_header("sig 1\nseed 5\n\nseed 7\nboard 4 3\nodd 1\n", "sig", 1).unwrap();
+        assert_eq!(fields.value::<u64>("seed"), Ok(Some(7)));
+        assert_eq!(fields.point("board"), Ok(Some(Point { x: 4, y: 3 })));
+        assert_eq!(fields.value::<u64>("seed"), Ok(None));
+        // Непрочитанное поле — ошибка в его строке
+        assert!(matches!(fields.finish(), Err(FormatError::BadLine(6))));
+    }
+}
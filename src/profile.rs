@@ -0,0 +1,442 @@
+//! Профили игроков за одной учётной записью: у каждого свои настройки, рекорды,
+//! статистика, сохранение и повторы.
+//!
+//! Профиль по умолчанию живёт там же, где файлы лежали до профилей, остальные — в
+//! подпапках `profiles/<имя>` папок настроек и данных:
+//!
+//! ```text
+//! ~/.config/zmiy/config.toml                   профиль по умолчанию
+//! ~/.local/share/zmiy/scores.txt
+//! ~/.config/zmiy/profiles/оля/config.toml      профиль «оля»
+//! ~/.local/share/zmiy/profiles/оля/scores.txt
+//! ```
+//!
+//! Профиль выбирается один раз при запуске, `--profile` или на экране профилей, и
+//! дальше действует на все пути из [`crate::paths`]. Журнал у всех профилей общий.
+
+use std::{fmt, fs, io, path::PathBuf};
+
+use crossterm::event::KeyCode;
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Widget},
+};
+
+use crate::{
+    paths::{config_root, data_root},
+    text_input::TextInput,
+    theme::Theme,
+    widget::centered,
+};
+
+/// Самое длинное имя профиля в символах
+pub const NAME_MAX: usize = 16;
+
+/// Что не так с профилем
+#[derive(Debug)]
+pub enum ProfileError {
+    /// Имя пустое, длинное или с недопустимыми символами
+    BadName(String),
+    Exists(String),
+    Missing(String),
+    Io(io::Error),
+}
+
+impl fmt::Display for ProfileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProfileError::BadName(name) => write!(
+                f,
+                "неподходящее имя профиля «{name}»: нужно от 1 до {NAME_MAX} букв, цифр, «-» или «_»"
+            ),
+            ProfileError::Exists(name) => write!(f, "профиль {name} уже есть"),
+            ProfileError::Missing(name) => write!(f, "профиля {name} нет"),
+            ProfileError::Io(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for ProfileError {}
+
+impl From<io::Error> for ProfileError {
+    fn from(e: io::Error) -> Self {
+        ProfileError::Io(e)
+    }
+}
+
+/// Проверяет имя профиля: оно же имя папки, поэтому только буквы, цифры, `-` и `_`
+pub fn check_name(name: &str) -> Result<(), ProfileError> {
+    let len = name.chars().count();
+    if (1..=NAME_MAX).contains(&len) && name.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_') {
+        Ok(())
+    } else {
+        Err(ProfileError::BadName(name.to_string()))
+    }
+}
+
+/// Папки, в которых лежат профили: `profiles` в папке настроек и в папке данных
+pub struct Profiles {
+    config: PathBuf,
+    data: PathBuf,
+}
+
+impl Profiles {
+    /// Профили в папках пользователя; `None`, если домашняя папка неизвестна
+    pub fn open() -> Option<Self> {
+        Some(Self::at(config_root()?, data_root()?))
+    }
+
+    /// Профили в заданных папках настроек и данных
+    pub fn at(config: PathBuf, data: PathBuf) -> Self {
+        Self { config: config.join("profiles"), data: data.join("profiles") }
+    }
+
+    fn dirs(&self, name: &str) -> [PathBuf; 2] {
+        [self.config.join(name), self.data.join(name)]
+    }
+
+    pub fn exists(&self, name: &str) -> bool {
+        self.dirs(name).iter().any(|dir| dir.is_dir())
+    }
+
+    /// Имена профилей по алфавиту, без профиля по умолчанию
+    pub fn list(&self) -> io::Result<Vec<String>> {
+        let mut names = Vec::new();
+        for root in [&self.config, &self.data] {
+            let entries = match fs::read_dir(root) {
+                Ok(entries) => entries,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e),
+            };
+            for entry in entries {
+                let entry = entry?;
+                if entry.file_type()?.is_dir()
+                    && let Some(name) = entry.file_name().to_str()
+                    && check_name(name).is_ok()
+                    && !names.iter().any(|n| n == name)
+                {
+                    names.push(name.to_string());
+                }
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    pub fn create(&self, name: &str) -> Result<(), ProfileError> {
+        check_name(name)?;
+        if self.exists(name) {
+            return Err(ProfileError::Exists(name.to_string()));
+        }
+        for dir in self.dirs(name) {
+            fs::create_dir_all(dir)?;
+        }
+        Ok(())
+    }
+
+    pub fn rename(&self, old: &str, new: &str) -> Result<(), ProfileError> {
+        check_name(new)?;
+        if !self.exists(old) {
+            return Err(ProfileError::Missing(old.to_string()));
+        }
+        if old != new && self.exists(new) {
+            return Err(ProfileError::Exists(new.to_string()));
+        }
+        for (from, to) in self.dirs(old).into_iter().zip(self.dirs(new)) {
+            if from.is_dir() {
+                fs::rename(from, to)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Удаляет профиль вместе с его настройками, рекордами и повторами
+    pub fn delete(&self, name: &str) -> Result<(), ProfileError> {
+        if !self.exists(name) {
+            return Err(ProfileError::Missing(name.to_string()));
+        }
+        for dir in self.dirs(name) {
+            if dir.is_dir() {
+                fs::remove_dir_all(dir)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Чем кончилось нажатие на экране профилей
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Choice {
+    /// Остаёмся на экране
+    Stay,
+    /// Играть этим профилем; `None` — профиль по умолчанию
+    Play(Option<String>),
+    Quit,
+}
+
+/// Что сейчас вводится или подтверждается
+enum Editing {
+    New(TextInput),
+    /// Новое имя для профиля
+    Rename(String, TextInput),
+    Delete(String),
+}
+
+/// Экран профилей: список, где первая строка — профиль по умолчанию
+pub struct ProfileMenu {
+    names: Vec<String>,
+    cursor: usize,
+    editing: Option<Editing>,
+    /// Ошибка последнего действия
+    error: Option<String>,
+}
+
+impl ProfileMenu {
+    pub fn new(profiles: &Profiles) -> Self {
+        let mut menu = Self { names: Vec::new(), cursor: 0, editing: None, error: None };
+        menu.reload(profiles);
+        menu
+    }
+
+    fn reload(&mut self, profiles: &Profiles) {
+        match profiles.list() {
+            Ok(names) => self.names = names,
+            Err(e) => self.error = Some(format!("Профили не прочитаны: {e}")),
+        }
+        self.cursor = self.cursor.min(self.names.len());
+    }
+
+    /// Выбранный профиль; `None` — профиль по умолчанию
+    pub fn selected(&self) -> Option<&str> {
+        self.cursor.checked_sub(1).and_then(|i| self.names.get(i)).map(String::as_str)
+    }
+
+    /// Ставит курсор на профиль с этим именем, если он есть
+    pub fn select(&mut self, name: &str) {
+        if let Some(i) = self.names.iter().position(|n| n == name) {
+            self.cursor = i + 1;
+        }
+    }
+
+    /// Обрабатывает клавишу; профили на диске меняются сразу
+    pub fn key(&mut self, code: KeyCode, profiles: &Profiles) -> Choice {
+        match self.editing.take() {
+            Some(Editing::Delete(name)) => {
+                if matches!(code, KeyCode::Char('y' | 'Y' | 'д' | 'Д')) {
+                    self.apply(profiles.delete(&name), profiles, None);
+                }
+            }
+            Some(Editing::New(mut input)) => match code {
+                KeyCode::Enter => {
+                    let name = input.value().trim().to_string();
+                    self.apply(profiles.create(&name), profiles, Some(&name));
+                }
+                KeyCode::Esc => {}
+                code => {
+                    edit(&mut input, code);
+                    self.editing = Some(Editing::New(input));
+                }
+            },
+            Some(Editing::Rename(old, mut input)) => match code {
+                KeyCode::Enter => {
+                    let name = input.value().trim().to_string();
+                    self.apply(profiles.rename(&old, &name), profiles, Some(&name));
+                }
+                KeyCode::Esc => {}
+                code => {
+                    edit(&mut input, code);
+                    self.editing = Some(Editing::Rename(old, input));
+                }
+            },
+            None => {
+                self.error = None;
+                match code {
+                    KeyCode::Up => self.cursor = self.cursor.saturating_sub(1),
+                    KeyCode::Down => self.cursor = (self.cursor + 1).min(self.names.len()),
+                    KeyCode::Enter => return Choice::Play(self.selected().map(str::to_string)),
+                    KeyCode::Esc | KeyCode::Char('q') => return Choice::Quit,
+                    KeyCode::Char('n') => self.editing = Some(Editing::New(TextInput::new("", NAME_MAX))),
+                    KeyCode::Char('r') => match self.selected() {
+                        Some(name) => self.editing = Some(Editing::Rename(name.to_string(), TextInput::new(name, NAME_MAX))),
+                        None => self.error = Some("Профиль по умолчанию не переименовывается".to_string()),
+                    },
+                    KeyCode::Char('d') => match self.selected() {
+                        Some(name) => self.editing = Some(Editing::Delete(name.to_string())),
+                        None => self.error = Some("Профиль по умолчанию не удаляется".to_string()),
+                    },
+                    _ => {}
+                }
+            }
+        }
+        Choice::Stay
+    }
+
+    /// Итог действия: ошибка на экран, при успехе — свежий список и курсор на `select`
+    fn apply(&mut self, result: Result<(), ProfileError>, profiles: &Profiles, select: Option<&str>) {
+        match result {
+            Ok(()) => {
+                self.reload(profiles);
+                if let Some(name) = select {
+                    self.select(name);
+                }
+            }
+            Err(e) => self.error = Some(e.to_string()),
+        }
+    }
+}
+
+/// Правка имени в поле ввода
+fn edit(input: &mut TextInput, code: KeyCode) {
+    match code {
+        KeyCode::Char(c) => input.insert(c),
+        KeyCode::Backspace => input.backspace(),
+        KeyCode::Delete => input.delete(),
+        KeyCode::Left => input.left(),
+        KeyCode::Right => input.right(),
+        KeyCode::Home => input.home(),
+        KeyCode::End => input.end(),
+        _ => {}
+    }
+}
+
+/// Отрисовка экрана профилей
+pub struct ProfileView<'a> {
+    pub menu: &'a ProfileMenu,
+    pub theme: &'a Theme,
+}
+
+impl Widget for ProfileView<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let (menu, theme) = (self.menu, self.theme);
+        let text = Style::default().fg(theme.text);
+        let mut lines: Vec<Line> = std::iter::once("по умолчанию".to_string())
+            .chain(menu.names.iter().cloned())
+            .enumerate()
+            .map(|(i, name)| {
+                if i == menu.cursor {
+                    Line::styled(format!("> {name}"), Style::default().fg(theme.popup_bg).bg(theme.accent))
+                } else {
+                    Line::styled(format!("  {name}"), text)
+                }
+            })
+            .collect();
+        lines.push(Line::default());
+        match &menu.editing {
+            Some(Editing::New(input)) => {
+                lines.push(Line::from(vec![Span::styled("Новый профиль: ", text)]));
+                lines.push(input.line(Style::default().fg(theme.accent)));
+                lines.push(Line::styled("Enter - создать, ESC - отмена", Style::default().fg(theme.muted)));
+            }
+            Some(Editing::Rename(old, input)) => {
+                lines.push(Line::styled(format!("Новое имя для {old}:"), text));
+                lines.push(input.line(Style::default().fg(theme.accent)));
+                lines.push(Line::styled("Enter - переименовать, ESC - отмена", Style::default().fg(theme.muted)));
+            }
+            Some(Editing::Delete(name)) => {
+                lines.push(Line::styled(
+                    format!("Удалить {name} со всеми рекордами и повторами?"),
+                    Style::default().fg(theme.danger).add_modifier(Modifier::BOLD),
+                ));
+                lines.push(Line::styled("Y - удалить, любая другая клавиша - отмена", Style::default().fg(theme.muted)));
+            }
+            None => {
+                lines.push(Line::styled("↑↓ - выбор, Enter - играть", Style::default().fg(theme.muted)));
+                lines.push(Line::styled("N - новый, R - переименовать, D - удалить", Style::default().fg(theme.muted)));
+                lines.push(Line::styled("ESC - выход", Style::default().fg(theme.muted)));
+            }
+        }
+        if let Some(error) = &menu.error {
+            lines.push(Line::styled(error.clone(), Style::default().fg(theme.danger)));
+        }
+
+        let area = area.intersection(buf.area);
+        let area = centered(area, 48.min(area.width), (lines.len() as u16 + 2).min(area.height));
+        Clear.render(area, buf);
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(" Профили ")
+            .border_style(Style::default().fg(theme.border))
+            .style(Style::default().bg(theme.popup_bg).fg(theme.text));
+        Paragraph::new(lines).block(block).render(area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_profiles(tag: &str) -> (PathBuf, Profiles) {
+        let root = std::env::temp_dir().join(format!("zmiy-profiles-{tag}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let profiles = Profiles::at(root.join("config"), root.join("data"));
+        (root, profiles)
+    }
+
+    #[test]
+    fn names() {
+        assert!(check_name("оля").is_ok());
+        assert!(check_name("player_2-b").is_ok());
+        for bad in ["", "../x", "a b", ".hidden", "очень-длинное-имя-профиля"] {
+            assert!(matches!(check_name(bad), Err(ProfileError::BadName(_))), "{bad}");
+        }
+    }
+
+    #[test]
+    fn create_rename_delete() {
+        let (root, profiles) = temp_profiles("ops");
+        assert!(profiles.list().unwrap().is_empty());
+        profiles.create("оля").unwrap();
+        profiles.create("max").unwrap();
+        assert!(matches!(profiles.create("max"), Err(ProfileError::Exists(_))));
+        assert_eq!(profiles.list().unwrap(), ["max", "оля"]);
+
+        fs::write(root.join("data/profiles/оля/scores.txt"), "рекорды").unwrap();
+        profiles.rename("оля", "olya").unwrap();
+        assert_eq!(fs::read_to_string(root.join("data/profiles/olya/scores.txt")).unwrap(), "рекорды");
+        assert!(root.join("config/profiles/olya").is_dir());
+        assert!(matches!(profiles.rename("olya", "max"), Err(ProfileError::Exists(_))));
+
+        profiles.delete("olya").unwrap();
+        assert_eq!(profiles.list().unwrap(), ["max"]);
+        assert!(matches!(profiles.delete("olya"), Err(ProfileError::Missing(_))));
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn menu_manages_profiles() {
+        let (root, profiles) = temp_profiles("menu");
+        let mut menu = ProfileMenu::new(&profiles);
+        assert_eq!(menu.selected(), None);
+        assert!(matches!(menu.key(KeyCode::Char('d'), &profiles), Choice::Stay));
+        assert_eq!(menu.error.as_deref(), Some("Профиль по умолчанию не удаляется"));
+
+        // N, имя, Enter: профиль создан и выбран
+        menu.key(KeyCode::Char('n'), &profiles);
+        for c in "оля".chars() {
+            menu.key(KeyCode::Char(c), &profiles);
+        }
+        menu.key(KeyCode::Enter, &profiles);
+        assert_eq!(menu.selected(), Some("оля"));
+
+        menu.key(KeyCode::Char('r'), &profiles);
+        menu.key(KeyCode::Backspace, &profiles);
+        menu.key(KeyCode::Char('ь'), &profiles);
+        menu.key(KeyCode::Enter, &profiles);
+        assert_eq!(profiles.list().unwrap(), ["оль"]);
+        assert_eq!(menu.key(KeyCode::Enter, &profiles), Choice::Play(Some("оль".to_string())));
+
+        // Удаление только после подтверждения
+        menu.key(KeyCode::Char('d'), &profiles);
+        menu.key(KeyCode::Char('n'), &profiles);
+        assert_eq!(profiles.list().unwrap(), ["оль"]);
+        menu.key(KeyCode::Char('d'), &profiles);
+        menu.key(KeyCode::Char('y'), &profiles);
+        assert!(profiles.list().unwrap().is_empty());
+        assert_eq!(menu.key(KeyCode::Enter, &profiles), Choice::Play(None));
+        assert_eq!(menu.key(KeyCode::Esc, &profiles), Choice::Quit);
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
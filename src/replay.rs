@@ -0,0 +1,540 @@
+//! Запись партий в файлы повторов `.zrp`.
+//!
+//! Формат текстовый, по записи на строку, поля через пробел:
+//!
+//! ```text
+//! zmiy-replay 23           сигнатура и версия формата
+//! version 0.1.0            версия игры, записавшей повтор
+//! seed 1234                сид генератора еды
+//! board 40 20              размер поля в начале партии
+//! mode классика            режим игры
+//! speed 120                интервал между шагами в миллисекундах
+//! candidates 3             из скольких клеток выбирается еда; строки нет, если из одной
+//! walls 20 0 20 1          стены уровня, по паре чисел на клетку; строки нет без уровня
+//! fast 3 4 4 4             клетки быстрой зоны; строки нет, если зон нет
+//! slow 9 9                 клетки медленной зоны; строки нет, если зон нет
+//! bombs                    в партии были бомбы; строки нет, если не было
+//! speedup 1500             шаг скорости от длины в микросекундах; строки нет без правила
+//! risk                     шли очки за риск; строки нет, если не шли
+//! edge                     шёл бонус за игру у края; строки нет, если не шёл
+//! bosses                   в партии были боссы; строки нет, если не было
+//! caterpillars             в партии были гусеницы; строки нет, если не было
+//! decay                    счёт таял без еды; строки нет, если не таял
+//! slime                    змейка оставляла след слизи; строки нет, если не оставляла
+//! teleports                в партии были порталы; строки нет, если не было
+//! shields                  в партии были щиты; строки нет, если не было
+//! doubling                 в партии были удвоители очков; строки нет, если не было
+//! freezing                 в партии были заморозки; строки нет, если не было
+//! start 12 7 U             змейка начала в клетке 12 7 головой вверх; строки нет,
+//!                          если посреди поля головой вправо
+//! length 3                 длина змейки в начале партии; строки нет, если 1
+//! grow 3                   на сколько клеток змейка растёт за еду; строки нет, если на одну
+//! t 15 U                   на тике 15 принят поворот: U, D, L или R
+//! r 40 38 18               на тике 40 поле изменило размер на 38×18
+//! x 57                     на тике 57 змейка сбросила хвост
+//! c 100 5f0e3c2a91d4b877   после шага 100 контрольная сумма состояния такая
+//! end 212 9                итог: всего тиков и счёт; пишется в конце партии
+//! ```
+//!
+//! Тик — число шагов `Game::step`, сделанных до события. Поворот с тиком N
+//! применяется перед шагом номер N. Файл без строки `end` — партия не закончена.
+//! Партии из повторов форматов до 16 играются без овертайма: его тогда ещё не было.
+//!
+//! Контрольная сумма пишется каждые [`CHECK_INTERVAL`] шагов и продолжает предыдущую
+//! (см. [`Game::checksum`]), так что она подтверждает и всю партию до этого шага.
+//! Просмотр сверяет суммы и останавливается на первом расхождении: значит, повтор
+//! записан другой версией игры или файл изменили.
+
+use std::{
+    fmt,
+    io::{self, Write},
+    path::PathBuf,
+};
+
+use crate::{
+    game::{DirectionSnake, Game, GameEvent, Mode, Point, Zone},
+    paths::data_dir,
+    persist::{self, FormatError},
+};
+
+/// Версия формата; файлы старых версий тоже читаются, см. [`crate::persist`]
+pub const FORMAT_VERSION: u32 = 23;
+/// Первое слово файла
+pub const SIGNATURE: &str = "zmiy-replay";
+/// Расширение файлов повторов
+pub const EXTENSION: &str = "zrp";
+/// Через сколько шагов в повтор пишется контрольная сумма
+pub const CHECK_INTERVAL: u64 = 100;
+
+/// Папка повторов `--record`: `<данные>/replays`
+pub fn dir() -> Option<PathBuf> {
+    Some(data_dir()?.join("replays"))
+}
+
+/// Событие ввода, записанное в повтор
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Input {
+    Turn(DirectionSnake),
+    Resize { width: u16, height: u16 },
+    /// Сброс хвоста (см. [`Game::shed`])
+    Shed,
+}
+
+/// Итог записанной партии
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Outcome {
+    pub ticks: u64,
+    pub score: usize,
+}
+
+/// Прочитанный файл повтора любой известной версии
+pub use crate::persist::Replay;
+
+/// Почему файл не удалось прочитать как повтор
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ReplayError {
+    /// Нет сигнатуры `zmiy-replay`
+    NotReplay,
+    /// Формат, которого никогда не было
+    UnsupportedFormat(u32),
+    /// Формат новее, чем умеет эта версия игры
+    Newer(u32),
+    /// Не хватает строки заголовка
+    MissingField(&'static str),
+    /// Непонятная строка, номер с единицы
+    BadLine(usize),
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::NotReplay => write!(f, "это не файл повтора"),
+            ReplayError::UnsupportedFormat(v) => {
+                write!(f, "формат повтора версии {v} не поддерживается")
+            }
+            ReplayError::Newer(v) => {
+                write!(f, "повтор создан более новой версией zmiy (формат {v}, а эта версия читает до {FORMAT_VERSION})")
+            }
+            ReplayError::MissingField(name) => write!(f, "в заголовке нет поля {name}"),
+            ReplayError::BadLine(n) => write!(f, "ошибка в строке {n}"),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+/// Просмотр разошёлся с записью: контрольная сумма после шага с этим тиком не совпала
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Diverged(pub u64);
+
+impl fmt::Display for Diverged {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "повтор разошёлся с записью на тике {} (вероятно, другая версия игры или изменённый файл)", self.0)
+    }
+}
+
+impl std::error::Error for Diverged {}
+
+impl From<FormatError> for ReplayError {
+    fn from(e: FormatError) -> Self {
+        match e {
+            FormatError::Foreign => ReplayError::NotReplay,
+            FormatError::Unsupported(v) => ReplayError::UnsupportedFormat(v),
+            FormatError::Newer(v) => ReplayError::Newer(v),
+            FormatError::MissingField(name) => ReplayError::MissingField(name),
+            FormatError::BadLine(n) => ReplayError::BadLine(n),
+        }
+    }
+}
+
+impl Replay {
+    /// Читает повтор любого известного формата
+    pub fn parse(text: &str) -> Result<Self, ReplayError> {
+        Ok(persist::read_replay(text)?)
+    }
+}
+
+/// Воспроизведение повтора: та же партия с тем же сидом и теми же поворотами
+pub struct Playback {
+    replay: Replay,
+    pub game: Game,
+    tick: u64,
+    /// Индекс следующего непримененного события ввода
+    next: usize,
+    /// Сумма последней сверенной контрольной точки и индекс следующей
+    sum: u64,
+    next_check: usize,
+    diverged: Option<Diverged>,
+}
+
+impl Playback {
+    pub fn new(replay: Replay) -> Self {
+        let mut game = Game::with_seed(replay.width, replay.height, replay.seed);
+        game.base_tick = replay.speed.unwrap_or(Game::BASE_TICK);
+        game.food_candidates = replay.candidates;
+        game.bombs = replay.bombs;
+        game.length_speedup = replay.speedup;
+        game.risk_scoring = replay.risk;
+        game.edge_bonus = replay.edge;
+        game.bosses = replay.bosses;
+        game.caterpillars = replay.caterpillars;
+        game.score_decay = replay.decay;
+        game.slime_trail = replay.slime;
+        game.teleports = replay.teleports;
+        game.shields = replay.shields;
+        game.doubling = replay.doubling;
+        game.freezing = replay.freezing;
+        game.growth_per_food = replay.grow;
+        game.sudden_death = replay.overtime;
+        game.moving_mutations = replay.moving_mutations;
+        game.set_walls(replay.walls.iter().copied());
+        game.set_zones(Zone::Fast, replay.fast.iter().copied());
+        game.set_zones(Zone::Slow, replay.slow.iter().copied());
+        if let Some(mode) = Mode::from_name(&replay.mode) {
+            mode.setup(&mut game);
+        }
+        if replay.start.is_some() || replay.length != 1 {
+            let (at, dir) = replay.start.unwrap_or((game.snake()[0], game.dir));
+            game.place_start(at, dir, replay.length);
+        }
+        Self { replay, game, tick: 0, next: 0, sum: 0, next_check: 0, diverged: None }
+    }
+
+    /// Режим записанной партии
+    pub fn mode(&self) -> &str {
+        &self.replay.mode
+    }
+
+    /// Сколько шагов уже сделано
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// Длина повтора в тиках; у оборванной записи — до последнего события
+    pub fn total(&self) -> u64 {
+        self.replay
+            .outcome
+            .map_or_else(|| self.replay.inputs.last().map_or(0, |&(tick, _)| tick), |o| o.ticks)
+    }
+
+    /// Повтор кончился, змейка погибла или просмотр остановлен на расхождении
+    pub fn finished(&self) -> bool {
+        self.game.game_over.is_some() || self.tick >= self.total() || self.diverged.is_some()
+    }
+
+    /// Где просмотр разошёлся с контрольными суммами записи; дальше он не идёт
+    pub fn diverged(&self) -> Option<Diverged> {
+        self.diverged
+    }
+
+    /// Применяет события ввода этого тика и делает шаг
+    pub fn step(&mut self) -> Vec<GameEvent> {
+        if self.finished() {
+            return Vec::new();
+        }
+        while let Some(&(tick, input)) = self.replay.inputs.get(self.next)
+            && tick <= self.tick
+        {
+            match input {
+                Input::Turn(dir) => {
+                    self.game.change_dir(dir);
+                }
+                Input::Resize { width, height } => self.game.resize(width, height),
+                Input::Shed => {
+                    self.game.shed();
+                }
+            }
+            self.next += 1;
+        }
+        self.tick += 1;
+        let events = self.game.step();
+        if let Some(&(tick, sum)) = self.replay.checks.get(self.next_check)
+            && tick == self.tick
+        {
+            self.sum = self.game.checksum(self.sum);
+            if self.sum != sum {
+                self.diverged = Some(Diverged(tick));
+            }
+            self.next_check += 1;
+        }
+        events
+    }
+
+    /// Предупреждения для зрителя: чужая версия, оборванная запись, расхождение с итогом
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        let ours = env!("CARGO_PKG_VERSION");
+        if self.replay.version != ours {
+            warnings.push(format!(
+                "Повтор записан версией {}, а это {ours}: партия может пойти иначе",
+                self.replay.version
+            ));
+        }
+        match self.replay.outcome {
+            None => warnings.push("Запись оборвана до конца партии".to_string()),
+            Some(outcome) if self.finished() && self.game.score != outcome.score => warnings.push(format!(
+                "Повтор разошёлся с записью: счёт {} вместо {}",
+                self.game.score, outcome.score
+            )),
+            Some(outcome) if self.game.game_over.is_some() && self.tick < outcome.ticks => {
+                warnings.push(format!("Повтор разошёлся с записью: партия кончилась на тике {}", self.tick))
+            }
+            Some(_) => {}
+        }
+        warnings
+    }
+}
+
+/// Пишет повтор по мере игры. Каждая строка сразу сбрасывается на диск,
+/// чтобы оборванная партия тоже осталась в файле.
+pub struct Recorder<W: Write> {
+    out: W,
+    /// Последняя записанная контрольная сумма
+    sum: u64,
+}
+
+impl<W: Write> Recorder<W> {
+    /// Начинает запись: заголовок с сидом и размером поля новой партии
+    pub fn start(mut out: W, game: &Game, mode: &str) -> io::Result<Self> {
+        writeln!(out, "{SIGNATURE} {FORMAT_VERSION}")?;
+        writeln!(out, "version {}", env!("CARGO_PKG_VERSION"))?;
+        writeln!(out, "seed {}", game.seed)?;
+        writeln!(out, "board {} {}", game.width, game.height)?;
+        writeln!(out, "mode {mode}")?;
+        writeln!(out, "speed {}", game.base_tick.as_millis())?;
+        if game.food_candidates != 1 {
+            writeln!(out, "candidates {}", game.food_candidates)?;
+        }
+        if game.bombs {
+            writeln!(out, "bombs")?;
+        }
+        if let Some(step) = game.length_speedup {
+            writeln!(out, "speedup {}", step.as_micros())?;
+        }
+        if game.risk_scoring {
+            writeln!(out, "risk")?;
+        }
+        if game.edge_bonus {
+            writeln!(out, "edge")?;
+        }
+        if game.bosses {
+            writeln!(out, "bosses")?;
+        }
+        if game.caterpillars {
+            writeln!(out, "caterpillars")?;
+        }
+        if game.score_decay {
+            writeln!(out, "decay")?;
+        }
+        if game.slime_trail {
+            writeln!(out, "slime")?;
+        }
+        if game.teleports {
+            writeln!(out, "teleports")?;
+        }
+        if game.shields {
+            writeln!(out, "shields")?;
+        }
+        if game.doubling {
+            writeln!(out, "doubling")?;
+        }
+        if game.freezing {
+            writeln!(out, "freezing")?;
+        }
+        let center = Point { x: game.width / 2, y: game.height / 2 };
+        if let Some(&head) = game.snake().front()
+            && (head, game.dir) != (center, DirectionSnake::Right)
+        {
+            writeln!(out, "start {} {} {}", head.x, head.y, game.dir.code())?;
+        }
+        if game.snake().len() != 1 {
+            writeln!(out, "length {}", game.snake().len())?;
+        }
+        if game.growth_per_food != 1 {
+            writeln!(out, "grow {}", game.growth_per_food)?;
+        }
+        for (key, cells) in [("walls", game.walls()), ("fast", game.zones(Zone::Fast)), ("slow", game.zones(Zone::Slow))] {
+            if !cells.is_empty() {
+                let cells: Vec<String> = cells.iter().map(|p| format!("{} {}", p.x, p.y)).collect();
+                writeln!(out, "{key} {}", cells.join(" "))?;
+            }
+        }
+        out.flush()?;
+        Ok(Self { out, sum: 0 })
+    }
+
+    /// Принятый поворот перед шагом `tick`
+    pub fn turn(&mut self, tick: u64, dir: DirectionSnake) -> io::Result<()> {
+        writeln!(self.out, "t {tick} {}", dir.code())?;
+        self.out.flush()
+    }
+
+    /// Поле изменило размер перед шагом `tick`
+    pub fn resize(&mut self, tick: u64, width: u16, height: u16) -> io::Result<()> {
+        writeln!(self.out, "r {tick} {width} {height}")?;
+        self.out.flush()
+    }
+
+    /// Змейка сбросила хвост перед шагом `tick`
+    pub fn shed(&mut self, tick: u64) -> io::Result<()> {
+        writeln!(self.out, "x {tick}")?;
+        self.out.flush()
+    }
+
+    /// Сделан шаг номер `tick`: каждые [`CHECK_INTERVAL`] шагов пишется контрольная сумма
+    pub fn step(&mut self, tick: u64, game: &Game) -> io::Result<()> {
+        if !tick.is_multiple_of(CHECK_INTERVAL) {
+            return Ok(());
+        }
+        self.sum = game.checksum(self.sum);
+        writeln!(self.out, "c {tick} {:016x}", self.sum)?;
+        self.out.flush()
+    }
+
+    /// Итог партии; после него запись закончена
+    pub fn finish(mut self, ticks: u64, score: usize) -> io::Result<()> {
+        writeln!(self.out, "end {ticks} {score}")?;
+        self.out.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_documented_format() {
+        let game = Game::with_seed(40, 20, 1234);
+        let mut out = Vec::new();
+        let mut rec = Recorder::start(&mut out, &game, "классика").unwrap();
+        rec.turn(15, DirectionSnake::Up).unwrap();
+        rec.resize(40, 38, 18).unwrap();
+        rec.shed(60).unwrap();
+        rec.finish(212, 9).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let expected = format!(
+            "zmiy-replay 23\nversion {}\nseed 1234\nboard 40 20\nmode классика\nspeed 120\nt 15 U\nr 40 38 18\nx 60\nend 212 9\n",
+            env!("CARGO_PKG_VERSION")
+        );
+        assert_eq!(text, expected);
+    }
+
+    #[test]
+    fn parse_round_trip() {
+        let game = Game::with_seed(40, 20, 1234);
+        let mut out = Vec::new();
+        let mut rec = Recorder::start(&mut out, &game, "классика").unwrap();
+        rec.turn(15, DirectionSnake::Up).unwrap();
+        rec.resize(40, 38, 18).unwrap();
+        rec.shed(60).unwrap();
+        rec.finish(212, 9).unwrap();
+        let replay = Replay::parse(std::str::from_utf8(&out).unwrap()).unwrap();
+        assert_eq!(replay.seed, 1234);
+        assert_eq!((replay.width, replay.height), (40, 20));
+        assert_eq!(replay.mode, "классика");
+        assert_eq!(replay.inputs, [
+            (15, Input::Turn(DirectionSnake::Up)),
+            (40, Input::Resize { width: 38, height: 18 }),
+            (60, Input::Shed),
+        ]);
+        assert_eq!(replay.outcome, Some(Outcome { ticks: 212, score: 9 }));
+    }
+
+    #[test]
+    fn rejects_bad_files() {
+        assert_eq!(Replay::parse("hello"), Err(ReplayError::NotReplay));
+        assert_eq!(Replay::parse("zmiy-replay 0\n"), Err(ReplayError::UnsupportedFormat(0)));
+        assert_eq!(Replay::parse("zmiy-replay 24\n"), Err(ReplayError::Newer(24)));
+        assert_eq!(Replay::parse("zmiy-replay 1\nseed 1\n"), Err(ReplayError::MissingField("board")));
+        assert_eq!(Replay::parse("zmiy-replay 1\nt 5 X\n"), Err(ReplayError::BadLine(2)));
+    }
+
+    /// Записывает партию, сыгранную вживую, и проверяет, что повтор приходит к тому же итогу.
+    /// Режим «две еды» ставит синюю еду при старте, и повтор должен поставить её так же,
+    /// как и случайное начало змейки с её длиной и первой едой.
+    #[test]
+    fn playback_matches_live_game() {
+        let turns = [(3, DirectionSnake::Up), (6, DirectionSnake::Left), (9, DirectionSnake::Down)];
+        for (mode, random) in [(Mode::Classic, false), (Mode::TwoFood, false), (Mode::Classic, true), (Mode::TwoFood, true)] {
+            let mut game = Game::with_seed(12, 12, 99);
+            mode.setup(&mut game);
+            game.set_start_length(if random { 3 } else { 1 });
+            if random {
+                game.random_start();
+            }
+            let start = (game.snake()[0], game.dir, game.food);
+            let mut out = Vec::new();
+            let mut rec = Recorder::start(&mut out, &game, mode.name()).unwrap();
+            let mut tick = 0;
+            while game.game_over.is_none() {
+                for &(at, dir) in &turns {
+                    if at == tick && game.change_dir(dir) {
+                        rec.turn(tick, dir).unwrap();
+                    }
+                }
+                game.step();
+                tick += 1;
+                rec.step(tick, &game).unwrap();
+            }
+            rec.finish(tick, game.score).unwrap();
+
+            let replay = Replay::parse(std::str::from_utf8(&out).unwrap()).unwrap();
+            assert_eq!((replay.start.is_some(), replay.length), (random, if random { 3 } else { 1 }));
+            let mut playback = Playback::new(replay);
+            assert_eq!((playback.game.snake()[0], playback.game.dir, playback.game.food), start);
+            while !playback.finished() {
+                playback.step();
+            }
+            assert_eq!(playback.tick(), tick);
+            assert_eq!((playback.game.score, playback.game.bonus), (game.score, game.bonus));
+            assert_eq!(playback.game.snake(), game.snake());
+            assert_eq!(playback.diverged(), None);
+            assert!(playback.warnings().is_empty());
+        }
+    }
+
+    /// Змейка ходит квадратом со стороной 8 клеток, мимо еды, 350 шагов
+    fn square_run() -> String {
+        let mut game = Game::with_seed(40, 20, 7);
+        let mut out = Vec::new();
+        let mut rec = Recorder::start(&mut out, &game, "классика").unwrap();
+        let turns = [DirectionSnake::Down, DirectionSnake::Left, DirectionSnake::Up, DirectionSnake::Right];
+        for tick in 0..350_u64 {
+            if tick > 0 && tick.is_multiple_of(8) {
+                let dir = turns[(tick / 8 - 1) as usize % 4];
+                assert!(game.change_dir(dir));
+                rec.turn(tick, dir).unwrap();
+            }
+            game.step();
+            rec.step(tick + 1, &game).unwrap();
+        }
+        assert!(game.game_over.is_none());
+        rec.finish(350, game.score).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn checksums_catch_changed_inputs() {
+        let text = square_run();
+        let replay = Replay::parse(&text).unwrap();
+        assert_eq!(replay.checks.iter().map(|&(tick, _)| tick).collect::<Vec<_>>(), [100, 200, 300]);
+        let mut playback = Playback::new(replay);
+        while !playback.finished() {
+            playback.step();
+        }
+        assert_eq!((playback.tick(), playback.diverged()), (350, None));
+
+        // Поворот на тике 104 сдвинут на шаг: до тика 200 это незаметно, а там сумма другая
+        let tampered = text.replacen("\nt 104 ", "\nt 105 ", 1);
+        assert_ne!(tampered, text);
+        let mut playback = Playback::new(Replay::parse(&tampered).unwrap());
+        while !playback.finished() {
+            playback.step();
+        }
+        assert_eq!((playback.tick(), playback.diverged()), (200, Some(Diverged(200))));
+        assert_eq!(
+            Diverged(200).to_string(),
+            "повтор разошёлся с записью на тике 200 (вероятно, другая версия игры или изменённый файл)"
+        );
+    }
+}
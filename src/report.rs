@@ -0,0 +1,159 @@
+//! Отчёт о проблеме: F10 в игре и автоматически при панике. Это папка
+//! `zmiy-report-<время>` в текущей папке, которую можно приложить к сообщению об ошибке:
+//!
+//! ```text
+//! zmiy-report-1700000000000/
+//!   system.txt    версия, ОС, терминал, параметры запуска, текст паники
+//!   state.json    состояние партии (как F9; при панике — как для бота, с последнего шага)
+//!   log.txt       последние 200 строк журнала, если он включён
+//!   config.toml   файл настроек, с которым запущена игра
+//! ```
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::log;
+
+/// Сколько последних строк журнала попадает в отчёт
+pub const LOG_LINES: usize = 200;
+
+/// Что известно о запуске, когда случается паника
+struct Context {
+    config: Option<PathBuf>,
+    /// Состояние после последнего шага
+    state: Option<String>,
+}
+
+static CONTEXT: Mutex<Context> = Mutex::new(Context { config: None, state: None });
+
+/// Запоминает файл настроек, чтобы положить его в отчёт
+pub fn set_config(path: Option<PathBuf>) {
+    CONTEXT.lock().unwrap_or_else(|e| e.into_inner()).config = path;
+}
+
+/// Запоминает состояние партии на случай паники
+pub fn remember_state(json: String) {
+    CONTEXT.lock().unwrap_or_else(|e| e.into_inner()).state = Some(json);
+}
+
+/// Содержимое отчёта
+#[derive(Default)]
+pub struct Report {
+    /// Состояние партии в JSON; `None` — партии нет
+    pub state: Option<String>,
+    /// Текст паники, если отчёт пишется из-за неё
+    pub panic: Option<String>,
+    pub config: Option<PathBuf>,
+    pub terminal: Option<(u16, u16)>,
+}
+
+impl Report {
+    /// Отчёт при панике: состояние с последнего шага и запомненный файл настроек
+    pub fn from_panic(message: String) -> Self {
+        // Паника могла случиться, пока этот же поток держал замок: тогда без контекста
+        let (state, config) = match CONTEXT.try_lock() {
+            Ok(context) => (context.state.clone(), context.config.clone()),
+            Err(_) => (None, None),
+        };
+        Self { state, panic: Some(message), config, terminal: crossterm::terminal::size().ok() }
+    }
+
+    /// Отчёт по F10: состояние передаёт игра, файл настроек — запомненный
+    pub fn now(state: Option<String>) -> Self {
+        let config = CONTEXT.lock().unwrap_or_else(|e| e.into_inner()).config.clone();
+        Self { state, panic: None, config, terminal: crossterm::terminal::size().ok() }
+    }
+
+    /// Сведения о системе и запуске
+    pub fn system(&self) -> String {
+        let mut text = format!(
+            "zmiy {}\nОС: {} {}\nTERM: {}\nТерминал: {}\nЗапуск: {}\n",
+            env!("CARGO_PKG_VERSION"),
+            std::env::consts::OS,
+            std::env::consts::ARCH,
+            std::env::var("TERM").unwrap_or_default(),
+            self.terminal.map_or("неизвестно".to_string(), |(w, h)| format!("{w}×{h}")),
+            std::env::args().collect::<Vec<_>>().join(" ")
+        );
+        if let Some(panic) = &self.panic {
+            text.push_str(&format!("\nПаника: {panic}\n"));
+        }
+        text
+    }
+
+    /// Пишет отчёт в папку `dir` и возвращает её
+    pub fn write_to(&self, dir: &Path) -> io::Result<PathBuf> {
+        fs::create_dir_all(dir)?;
+        fs::write(dir.join("system.txt"), self.system())?;
+        if let Some(state) = &self.state {
+            fs::write(dir.join("state.json"), format!("{state}\n"))?;
+        }
+        let log = match log::current_path() {
+            Some(path) => match fs::read_to_string(&path) {
+                Ok(text) => tail(&text, LOG_LINES),
+                Err(e) => format!("журнал {} не прочитан: {e}\n", path.display()),
+            },
+            None => "журнал выключен: запустите игру с --debug или ZMIY_LOG=debug\n".to_string(),
+        };
+        fs::write(dir.join("log.txt"), log)?;
+        // Файла настроек может и не быть: тогда игра шла с настройками по умолчанию
+        if let Some(config) = &self.config
+            && config.exists()
+        {
+            fs::copy(config, dir.join("config.toml"))?;
+        }
+        Ok(dir.to_path_buf())
+    }
+
+    /// Пишет отчёт в `zmiy-report-<время>` в текущей папке
+    pub fn save(&self) -> io::Result<PathBuf> {
+        let stamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis();
+        self.write_to(Path::new(&format!("zmiy-report-{stamp}")))
+    }
+}
+
+/// Последние `n` строк текста
+fn tail(text: &str, n: usize) -> String {
+    let lines: Vec<&str> = text.lines().collect();
+    lines[lines.len().saturating_sub(n)..].iter().map(|l| format!("{l}\n")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundle_has_every_part() {
+        let dir = std::env::temp_dir().join(format!("zmiy-report-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let config = std::env::temp_dir().join(format!("zmiy-report-config-{}.toml", std::process::id()));
+        fs::write(&config, "grid = true\n").unwrap();
+        let report = Report {
+            state: Some("{\"tick\":3}".to_string()),
+            panic: Some("индекс за границей".to_string()),
+            config: Some(config.clone()),
+            terminal: Some((80, 24)),
+        };
+        report.write_to(&dir).unwrap();
+        let system = fs::read_to_string(dir.join("system.txt")).unwrap();
+        assert!(system.starts_with(&format!("zmiy {}\nОС: ", env!("CARGO_PKG_VERSION"))), "{system}");
+        assert!(system.contains("Терминал: 80×24\n"), "{system}");
+        assert!(system.ends_with("\nПаника: индекс за границей\n"), "{system}");
+        assert_eq!(fs::read_to_string(dir.join("state.json")).unwrap(), "{\"tick\":3}\n");
+        assert_eq!(fs::read_to_string(dir.join("config.toml")).unwrap(), "grid = true\n");
+        assert!(dir.join("log.txt").exists());
+        fs::remove_dir_all(&dir).unwrap();
+        fs::remove_file(&config).unwrap();
+    }
+
+    #[test]
+    fn keeps_last_lines() {
+        let text: String = (1..=5).map(|i| format!("строка {i}\n")).collect();
+        assert_eq!(tail(&text, 2), "строка 4\nстрока 5\n");
+        assert_eq!(tail(&text, 10), text);
+    }
+}
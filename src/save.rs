@@ -0,0 +1,384 @@
+//! Сохранение партии, чтобы закрыть терминал и доиграть потом.
+//!
+//! Формат текстовый, как у повторов: по полю на строку, значения через пробел.
+//!
+//! ```text
+//! zmiy-save 20             сигнатура и версия формата
+//! version 0.1.0            версия игры, записавшей файл
+//! mode классика
+//! board 40 20
+//! seed 1234                сид, с которого начиналась партия
+//! rng 987654321            текущее состояние генератора еды
+//! speed 120                интервал между шагами в миллисекундах
+//! dir R
+//! food 3 4
+//! score 5
+//! levels 10                еды на уровень; строки нет, если в режиме нет уровней
+//! ticks 100                шагов с начала партии
+//! elapsed 62000            время игры в миллисекундах
+//! history 1 0 0 1 2 5      шаг и счётчик графика счёта, затем его точки
+//! obstacles 1 1 1 2        сброшенные хвосты; строки нет, если хвост не сбрасывался
+//! walls 20 0 20 1          стены уровня; строки нет, если партия идёт не на уровне
+//! fast 3 4 4 4             клетки быстрой зоны; строки нет, если зон нет
+//! slow 9 9                 клетки медленной зоны; строки нет, если зон нет
+//! bombs                    бомбы включены; строки нет, если выключены
+//! bomb 9 1 42              бомба и сколько шагов она ещё лежит; строки нет, если её нет
+//! speedup 1500             шаг скорости от длины в микросекундах; строки нет без правила
+//! risk                     очки за риск включены; строки нет, если выключены
+//! edge                     бонус за игру у края включён; строки нет, если выключен
+//! edge_ticks 17            шагов у края к следующему очку; строки нет, если их нет
+//! bosses                   боссы включены; строки нет, если выключены
+//! boss 6 2 3               левый верхний угол босса и сколько касаний ему осталось;
+//!                          строки нет, если его нет. Еда лежит под ним, в этом углу
+//! growth 2                 клеток роста, которые змейка ещё не набрала; строки нет, если их нет
+//! grow 3                   на сколько клеток змейка растёт за еду; строки нет, если на одну
+//! overtime                 партия дошла до овертайма; строки нет, если ещё нет
+//! mutations bombs faster   мутации бесконечного режима по порядку; строки нет, если их не было.
+//!                          Проходимые стены и убегающая еда включаются по этому списку
+//! mutate 12                еды до следующей мутации; строки нет, если правила не мутируют
+//! mirrored 30              шагов зеркального управления; строки нет, если оно не включено
+//! decay                    счёт тает без еды; строки нет, если не тает
+//! idle 42                  шагов с последней еды; строки нет, если змейка только что ела
+//! slime                    след слизи включён; строки нет, если выключен
+//! slimy 4 5 19 3 5 20      клетки слизи и сколько шагов им сохнуть; строки нет, если слизи нет
+//! teleports                порталы включены; строки нет, если выключены
+//! portal 8 2               портал; строки нет, если его нет
+//! shields                  щиты включены; строки нет, если выключены
+//! shield_pickup 3 7        щит на поле; строки нет, если его нет
+//! shield 1                 сколько щитов у змейки; строки нет, если ни одного
+//! doubling                 удвоители включены; строки нет, если выключены
+//! doubler 6 4              удвоитель на поле; строки нет, если его нет
+//! doubled 57               сколько ещё шагов очки удвоены; строки нет, если не удвоены
+//! freezing                 заморозки включены; строки нет, если выключены
+//! freezer 3 7              заморозка на поле; строки нет, если её нет
+//! frozen 31                сколько ещё шагов мир заморожен; строки нет, если не заморожен
+//! caterpillars             гусеницы включены; строки нет, если выключены
+//! caterpillar 5 3 6 3      звенья гусеницы от конца к концу; строки нет, если её нет
+//! candidates 3             из скольких клеток выбирается еда; строки нет, если из одной
+//! shed 37                  шагов до следующего сброса; строки нет, если уже можно
+//! snake 5 5 4 5 3 5        клетки змейки от головы к хвосту, по паре чисел
+//! ```
+//!
+//! Сохранения старых форматов читаются через [`crate::persist`].
+
+use std::{
+    fmt, fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use crate::{
+    game::{Game, Mode, Mutation, Point, Zone},
+    paths::{data_dir, write_atomic},
+    persist::{self, FormatError, Save},
+};
+
+/// Версия формата сохранения
+pub const FORMAT_VERSION: u32 = 20;
+/// Первое слово файла
+pub const SIGNATURE: &str = "zmiy-save";
+
+/// Файл сохранения: `<данные>/save.zsv`
+pub fn path() -> Option<PathBuf> {
+    Some(data_dir()?.join("save.zsv"))
+}
+
+/// Сохранённая партия со всем, что нужно, чтобы её продолжить
+pub struct SavedGame {
+    pub game: Game,
+    pub mode: String,
+    pub elapsed: Duration,
+    pub ticks: u64,
+    /// Скорость, с которой партия шла; `None` у сохранений формата 1
+    pub speed: Option<Duration>,
+}
+
+/// Почему сохранение не удалось загрузить
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum SaveError {
+    /// Файл не прочитался
+    Io(String),
+    /// Нет сигнатуры `zmiy-save`
+    NotSave,
+    /// Формат, которого никогда не было
+    UnsupportedFormat(u32),
+    /// Формат новее, чем умеет эта версия игры
+    Newer(u32),
+    /// Не хватает поля
+    MissingField(&'static str),
+    /// Непонятная строка, номер с единицы
+    BadLine(usize),
+    /// Поля прочитались, но вместе не складываются в партию
+    Inconsistent,
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::Io(e) => write!(f, "{e}"),
+            SaveError::NotSave => write!(f, "это не файл сохранения"),
+            SaveError::UnsupportedFormat(v) => {
+                write!(f, "формат сохранения версии {v} не поддерживается")
+            }
+            SaveError::Newer(v) => {
+                write!(f, "сохранение создано более новой версией zmiy (формат {v}, а эта версия читает до {FORMAT_VERSION})")
+            }
+            SaveError::MissingField(name) => write!(f, "нет поля {name}"),
+            SaveError::BadLine(n) => write!(f, "ошибка в строке {n}"),
+            SaveError::Inconsistent => write!(f, "сохранение повреждено"),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+impl From<FormatError> for SaveError {
+    fn from(e: FormatError) -> Self {
+        match e {
+            FormatError::Foreign => SaveError::NotSave,
+            FormatError::Unsupported(v) => SaveError::UnsupportedFormat(v),
+            FormatError::Newer(v) => SaveError::Newer(v),
+            FormatError::MissingField(name) => SaveError::MissingField(name),
+            FormatError::BadLine(n) => SaveError::BadLine(n),
+        }
+    }
+}
+
+impl SavedGame {
+    /// Пишет партию в формате сохранения. Генератор еды при этом перезапускается,
+    /// чтобы его состояние уместилось в одно число.
+    pub fn write(out: &mut impl Write, game: &mut Game, mode: &str, elapsed: Duration, ticks: u64) -> io::Result<()> {
+        let rng = game.reseed();
+        let saved = Save {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            mode: mode.to_string(),
+            board: Point { x: game.width, y: game.height },
+            seed: game.seed,
+            rng,
+            dir: game.dir,
+            food: game.food,
+            score: game.score,
+            levels: game.food_per_level,
+            ticks,
+            elapsed,
+            history: game.history.clone(),
+            snake: game.snake().iter().copied().collect(),
+            speed: Some(game.base_tick),
+            obstacles: game.obstacles().to_vec(),
+            shed: game.shed_cooldown,
+            candidates: game.food_candidates,
+            walls: game.walls().to_vec(),
+            fast: game.zones(Zone::Fast).to_vec(),
+            slow: game.zones(Zone::Slow).to_vec(),
+            bombs: game.bombs,
+            bomb: game.bomb,
+            speedup: game.length_speedup,
+            risk: game.risk_scoring,
+            edge: game.edge_bonus,
+            edge_ticks: game.edge_ticks,
+            bosses: game.bosses,
+            boss: game.boss,
+            growth: game.growth,
+            caterpillars: game.caterpillars,
+            caterpillar: game.caterpillar.clone(),
+            grow: game.growth_per_food,
+            overtime: game.overtime,
+            mutations: game.mutations.clone(),
+            mutate: if game.mutating { game.mutation_due } else { 0 },
+            mirrored: game.mirrored,
+            decay: game.score_decay,
+            idle: game.since_food,
+            slime: game.slime_trail,
+            slimy: game.slime().to_vec(),
+            teleports: game.teleports,
+            portal: game.portal,
+            shields: game.shields,
+            shield_pickup: game.shield_pickup,
+            shield: game.shield,
+            doubling: game.doubling,
+            doubler: game.doubler,
+            doubled: game.doubled,
+            freezing: game.freezing,
+            freezer: game.freezer,
+            frozen: game.frozen,
+        };
+        out.write_all(saved.to_text().as_bytes())
+    }
+
+    pub fn parse(text: &str) -> Result<Self, SaveError> {
+        let saved = persist::read_save(text)?;
+        let mut game = Game::with_seed(saved.board.x, saved.board.y, saved.rng);
+        game.seed = saved.seed;
+        game.dir = saved.dir;
+        game.food = saved.food;
+        game.score = saved.score;
+        game.food_per_level = saved.levels;
+        game.history = saved.history;
+        game.set_snake(saved.snake);
+        game.set_obstacles(saved.obstacles);
+        game.shed_cooldown = saved.shed;
+        game.food_candidates = saved.candidates;
+        game.set_walls(saved.walls);
+        game.set_zones(Zone::Fast, saved.fast);
+        game.set_zones(Zone::Slow, saved.slow);
+        game.bombs = saved.bombs;
+        game.bomb = saved.bomb;
+        game.length_speedup = saved.speedup;
+        game.risk_scoring = saved.risk;
+        game.edge_bonus = saved.edge;
+        game.edge_ticks = saved.edge_ticks;
+        game.bosses = saved.bosses;
+        game.boss = saved.boss;
+        game.growth = saved.growth;
+        game.caterpillars = saved.caterpillars;
+        game.caterpillar = saved.caterpillar;
+        game.growth_per_food = saved.grow;
+        game.overtime = saved.overtime;
+        game.wrapping = saved.mutations.contains(&Mutation::Wrap);
+        game.fleeing = saved.mutations.contains(&Mutation::FleeingFood);
+        game.mutations = saved.mutations;
+        game.mutation_due = saved.mutate;
+        game.mirrored = saved.mirrored;
+        game.score_decay = saved.decay;
+        game.since_food = saved.idle;
+        game.slime_trail = saved.slime;
+        game.set_slime(saved.slimy.iter().copied());
+        game.teleports = saved.teleports;
+        game.portal = saved.portal;
+        game.shields = saved.shields;
+        game.shield_pickup = saved.shield_pickup;
+        game.shield = saved.shield;
+        game.doubling = saved.doubling;
+        game.doubler = saved.doubler;
+        game.doubled = saved.doubled;
+        game.freezing = saved.freezing;
+        game.freezer = saved.freezer;
+        game.frozen = saved.frozen;
+
+        let inside = |p: &Point| p.x < game.width && p.y < game.height;
+        if game.snake().is_empty()
+            || !game.snake().iter().all(inside)
+            || !inside(&game.food)
+            || !game.obstacles().iter().all(inside)
+            || !game.walls().iter().all(inside)
+            || !Zone::ALL.into_iter().all(|zone| game.zones(zone).iter().all(inside))
+            || saved.slimy.iter().any(|(p, left)| !inside(p) || *left > Game::SLIME_TICKS)
+            || game.is_wall(game.food)
+            || game.snake().iter().any(|&p| game.is_wall(p))
+            || game.bomb.is_some_and(|bomb| !inside(&bomb.at) || bomb.left == 0 || game.is_snake(bomb.at))
+            || game.boss.is_some_and(|boss| {
+                !boss.cells().iter().all(inside) || !(1..=Game::BOSS_HITS).contains(&boss.hits) || game.food != boss.at
+            })
+            || !caterpillar_in_line(&game.caterpillar)
+            || game.caterpillar.iter().any(|&p| !inside(&p) || game.is_snake(p) || p == game.food)
+            || game.portal.is_some_and(|p| !inside(&p) || game.is_snake(p) || game.is_wall(p) || p == game.food)
+            || game.shield_pickup.is_some_and(|p| !inside(&p) || game.is_snake(p) || game.is_wall(p) || p == game.food)
+            || saved.shield > Game::MAX_SHIELDS
+            || game.doubler.is_some_and(|p| !inside(&p) || game.is_snake(p) || game.is_wall(p) || p == game.food)
+            || game.freezer.is_some_and(|p| !inside(&p) || game.is_snake(p) || game.is_wall(p) || p == game.food)
+            || game.history.stride == 0
+            || saved.levels == Some(0)
+            || saved.candidates == 0
+            || saved.speed.is_some_and(|speed| speed.is_zero())
+            || saved.speedup.is_some_and(|step| step.is_zero())
+            || saved.edge_ticks >= Game::EDGE_TICKS
+            || saved.grow > Game::MAX_GROWTH_PER_FOOD
+            || saved.mutate > Game::MUTATION_EVERY
+        {
+            return Err(SaveError::Inconsistent);
+        }
+        // Синяя еда в сохранение не пишется: режим ставит её заново
+        if let Some(mode) = Mode::from_name(&saved.mode) {
+            mode.setup(&mut game);
+        }
+        Ok(Self { game, mode: saved.mode, elapsed: saved.elapsed, ticks: saved.ticks, speed: saved.speed })
+    }
+
+    /// Загружает сохранение; `None`, если его нет
+    pub fn load(path: &Path) -> Option<Result<Self, SaveError>> {
+        match fs::read_to_string(path) {
+            Ok(text) => Some(Self::parse(&text)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e) => Some(Err(SaveError::Io(e.to_string()))),
+        }
+    }
+}
+
+/// Сохраняет партию в файл, не портя прежнее сохранение при сбое
+pub fn store(path: &Path, game: &mut Game, mode: &str, elapsed: Duration, ticks: u64) -> io::Result<()> {
+    let mut out = Vec::new();
+    SavedGame::write(&mut out, game, mode, elapsed, ticks)?;
+    write_atomic(path, &out)
+}
+
+/// Звенья гусеницы идут подряд по одной прямой и их не больше [`Game::CATERPILLAR_LEN`]
+fn caterpillar_in_line(cells: &[Point]) -> bool {
+    let step = |a: Point, b: Point| (i32::from(b.x) - i32::from(a.x), i32::from(b.y) - i32::from(a.y));
+    let Some(&[first, second]) = cells.first_chunk() else {
+        return cells.len() <= 1;
+    };
+    let dir = step(first, second);
+    cells.len() <= Game::CATERPILLAR_LEN
+        && dir.0.abs() + dir.1.abs() == 1
+        && cells.windows(2).all(|w| step(w[0], w[1]) == dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{DirectionSnake, GameEvent};
+
+    fn saved(game: &mut Game) -> String {
+        let mut out = Vec::new();
+        SavedGame::write(&mut out, game, "классика", Duration::from_millis(62_000), 100).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn round_trip_continues_identically() {
+        let mut game = Game::with_seed(20, 12, 5);
+        game.food_per_level = Some(4);
+        for _ in 0..7 {
+            game.step();
+        }
+        game.change_dir(DirectionSnake::Down);
+        let text = saved(&mut game);
+        let mut restored = SavedGame::parse(&text).unwrap();
+        assert_eq!(restored.mode, "классика");
+        assert_eq!(restored.elapsed, Duration::from_millis(62_000));
+        assert_eq!(restored.ticks, 100);
+        assert_eq!(restored.speed, Some(Game::BASE_TICK));
+        assert_eq!(restored.game.seed, 5);
+        assert_eq!(restored.game.food_per_level, Some(4));
+        assert_eq!(restored.game.history.points, game.history.points);
+
+        // Дальше обе партии идут одинаково, включая новую еду
+        for _ in 0..20 {
+            restored.game.spawn_food();
+            game.spawn_food();
+            assert_eq!(restored.game.food, game.food);
+            let events: Vec<GameEvent> = game.step();
+            assert_eq!(restored.game.step(), events);
+        }
+        assert_eq!(restored.game.snake(), game.snake());
+    }
+
+    #[test]
+    fn rejects_corrupt_saves() {
+        let mut game = Game::with_seed(20, 12, 5);
+        let text = saved(&mut game);
+        assert!(matches!(SavedGame::parse("мусор"), Err(SaveError::NotSave)));
+        assert!(matches!(SavedGame::parse("zmiy-save 0\n"), Err(SaveError::UnsupportedFormat(0))));
+        assert!(matches!(SavedGame::parse("zmiy-save 21\n"), Err(SaveError::Newer(21))));
+        // Версию игры решает формат файла, а не номер версии в нём
+        let other = text.replace(env!("CARGO_PKG_VERSION"), "0.0.0-old");
+        assert!(SavedGame::parse(&other).is_ok());
+        let truncated: String = text.lines().take(6).map(|l| format!("{l}\n")).collect();
+        assert!(matches!(SavedGame::parse(&truncated), Err(SaveError::MissingField(_))));
+        let garbled = text.replace("score 0", "score много");
+        assert!(matches!(SavedGame::parse(&garbled), Err(SaveError::BadLine(_))));
+        let outside = text.replace("snake 10 6", "snake 50 6");
+        assert!(matches!(SavedGame::parse(&outside), Err(SaveError::Inconsistent)));
+    }
+}
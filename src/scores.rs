@@ -0,0 +1,403 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Rect},
+    style::{Modifier, Style},
+    text::Line,
+    widgets::{Block, Borders, Clear, Row, StatefulWidget, Table, TableState, Widget},
+};
+
+use std::{
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use crate::{
+    hud::format_duration,
+    paths::{data_dir, write_atomic},
+    theme::Theme,
+};
+
+/// Версия формата файла рекордов
+pub const FORMAT_VERSION: u32 = 1;
+/// Первое слово файла
+pub const SIGNATURE: &str = "zmiy-scores";
+
+/// Файл рекордов: `<данные>/scores.txt`
+pub fn path() -> Option<PathBuf> {
+    Some(data_dir()?.join("scores.txt"))
+}
+
+/// Одна запись таблицы рекордов
+#[derive(Clone)]
+pub struct ScoreEntry {
+    pub name: String,
+    pub score: usize,
+    pub length: usize,
+    pub mode: String,
+    /// Время окончания партии, секунды с начала эпохи Unix
+    pub date: u64,
+    /// Сколько длилась партия
+    pub duration: Duration,
+}
+
+/// Почему файл рекордов не удалось прочитать
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ScoresError {
+    /// Файл не прочитался
+    Io(String),
+    /// Нет сигнатуры `zmiy-scores`
+    NotScores,
+    /// Формат другой версии
+    UnsupportedFormat(u32),
+    /// Непонятная строка, номер с единицы
+    BadLine(usize),
+}
+
+impl fmt::Display for ScoresError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScoresError::Io(e) => write!(f, "{e}"),
+            ScoresError::NotScores => write!(f, "это не файл рекордов"),
+            ScoresError::UnsupportedFormat(v) => {
+                write!(f, "формат рекордов версии {v} не поддерживается (нужна {FORMAT_VERSION})")
+            }
+            ScoresError::BadLine(n) => write!(f, "ошибка в строке {n}"),
+        }
+    }
+}
+
+impl std::error::Error for ScoresError {}
+
+/// Таблицы рекордов по режимам: в каждой лучшие `CAP` результатов режима.
+/// Записи всех режимов хранятся вместе, от большего счёта к меньшему.
+#[derive(Default)]
+pub struct HighScores {
+    entries: Vec<ScoreEntry>,
+}
+
+impl HighScores {
+    pub const CAP: usize = 10;
+
+    /// Место (с нуля), которое займёт счёт в таблице режима, или `None`, если он в неё не попадёт.
+    /// При равном счёте более ранняя запись остаётся выше.
+    pub fn rank(&self, mode: &str, score: usize) -> Option<usize> {
+        let pos = self.entries.iter().filter(|e| e.mode == mode && e.score >= score).count();
+        (pos < Self::CAP).then_some(pos)
+    }
+
+    /// Добавляет запись и возвращает её место в таблице режима, если она туда попала
+    pub fn insert(&mut self, entry: ScoreEntry) -> Option<usize> {
+        let rank = self.rank(&entry.mode, entry.score)?;
+        let pos = self.entries.partition_point(|e| e.score >= entry.score);
+        let mode = entry.mode.clone();
+        self.entries.insert(pos, entry);
+        // Из переполненной таблицы режима выпадает последняя запись
+        if self.entries.iter().filter(|e| e.mode == mode).count() > Self::CAP
+            && let Some(last) = self.entries.iter().rposition(|e| e.mode == mode)
+        {
+            self.entries.remove(last);
+        }
+        Some(rank)
+    }
+
+    /// Лучшая запись всех режимов
+    pub fn top(&self) -> Option<&ScoreEntry> {
+        self.entries.first()
+    }
+
+    /// Лучшая запись режима
+    pub fn best(&self, mode: &str) -> Option<&ScoreEntry> {
+        self.entries.iter().find(|e| e.mode == mode)
+    }
+
+    /// Таблица в формате файла: сигнатура, затем по записи на строку,
+    /// поля через табуляцию — счёт, длина, длительность в мс, дата, режим, имя
+    pub fn to_text(&self) -> String {
+        let mut text = format!("{SIGNATURE} {FORMAT_VERSION}\n");
+        for e in &self.entries {
+            let name: String = e.name.chars().map(|c| if c.is_control() { ' ' } else { c }).collect();
+            text.push_str(&format!(
+                "{}\t{}\t{}\t{}\t{}\t{name}\n",
+                e.score,
+                e.length,
+                e.duration.as_millis(),
+                e.date,
+                e.mode
+            ));
+        }
+        text
+    }
+
+    pub fn parse(text: &str) -> Result<Self, ScoresError> {
+        let mut lines = text.lines();
+        let format = match lines.next().map(|l| l.split_whitespace().collect::<Vec<_>>()) {
+            Some(words) if words.first() == Some(&SIGNATURE) => words.get(1).and_then(|v| v.parse().ok()),
+            _ => return Err(ScoresError::NotScores),
+        };
+        match format {
+            Some(FORMAT_VERSION) => {}
+            Some(other) => return Err(ScoresError::UnsupportedFormat(other)),
+            None => return Err(ScoresError::BadLine(1)),
+        }
+        let mut scores = Self::default();
+        for (i, line) in lines.enumerate().filter(|(_, l)| !l.trim().is_empty()) {
+            let bad = || ScoresError::BadLine(i + 2);
+            let fields: Vec<&str> = line.splitn(6, '\t').collect();
+            let [score, length, duration, date, mode, name] = fields.as_slice() else {
+                return Err(bad());
+            };
+            let num = |s: &str| s.parse::<u64>().map_err(|_| bad());
+            scores.insert(ScoreEntry {
+                name: name.to_string(),
+                score: num(score)? as usize,
+                length: num(length)? as usize,
+                mode: mode.to_string(),
+                date: num(date)?,
+                duration: Duration::from_millis(num(duration)?),
+            });
+        }
+        Ok(scores)
+    }
+
+    /// Загружает таблицу; если файла ещё нет — пустая таблица
+    pub fn load(path: &Path) -> Result<Self, ScoresError> {
+        match fs::read_to_string(path) {
+            Ok(text) => Self::parse(&text),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(ScoresError::Io(e.to_string())),
+        }
+    }
+
+    pub fn store(&self, path: &Path) -> io::Result<()> {
+        write_atomic(path, self.to_text().as_bytes())
+    }
+
+    /// Все режимы, встречающиеся в таблице, в порядке первого появления
+    pub fn modes(&self) -> Vec<&str> {
+        let mut modes: Vec<&str> = Vec::new();
+        for e in &self.entries {
+            if !modes.contains(&e.mode.as_str()) {
+                modes.push(&e.mode);
+            }
+        }
+        modes
+    }
+
+    /// Записи выбранного режима (или все) вместе с их местом в таблице своего режима
+    fn filtered<'a>(&'a self, mode: Option<&'a str>) -> impl Iterator<Item = (usize, &'a ScoreEntry)> {
+        self.entries.iter().enumerate().filter(move |(_, e)| mode.is_none_or(|m| e.mode == m)).map(|(i, e)| {
+            let rank = self.entries[..i].iter().filter(|p| p.mode == e.mode).count();
+            (rank, e)
+        })
+    }
+}
+
+/// Экран таблицы рекордов: прокрутка, фильтр по режиму, выделенная строка
+pub struct ScoresView {
+    pub state: TableState,
+    /// Фильтр по режиму; `None` — все режимы
+    pub mode: Option<String>,
+}
+
+impl ScoresView {
+    const PAGE: usize = 10;
+
+    /// `highlight` — место записи в таблице режима `mode`, которую нужно выделить (например, только что сыгранная партия)
+    pub fn new(mode: Option<&str>, highlight: Option<usize>) -> Self {
+        Self {
+            state: TableState::default().with_selected(Some(highlight.unwrap_or(0))),
+            mode: mode.map(str::to_string),
+        }
+    }
+
+    pub fn up(&mut self, rows: usize) {
+        let selected = self.state.selected().unwrap_or(0);
+        self.state.select(Some(selected.saturating_sub(rows)));
+    }
+
+    pub fn down(&mut self, rows: usize, scores: &HighScores) {
+        let count = scores.filtered(self.mode.as_deref()).count();
+        let selected = self.state.selected().unwrap_or(0);
+        self.state.select(Some((selected + rows).min(count.saturating_sub(1))));
+    }
+
+    pub fn page_up(&mut self) {
+        self.up(Self::PAGE);
+    }
+
+    pub fn page_down(&mut self, scores: &HighScores) {
+        self.down(Self::PAGE, scores);
+    }
+
+    /// Следующий фильтр: все режимы → каждый режим по очереди → снова все
+    pub fn next_mode(&mut self, scores: &HighScores) {
+        let modes = scores.modes();
+        let next = match &self.mode {
+            None => modes.first(),
+            Some(current) => modes.iter().skip_while(|m| **m != current).nth(1),
+        };
+        self.mode = next.map(|m| m.to_string());
+        self.state.select(Some(0));
+    }
+
+    pub fn widget<'a>(&'a mut self, scores: &'a HighScores, theme: &'a Theme) -> ScoresTable<'a> {
+        ScoresTable { view: self, scores, theme }
+    }
+}
+
+pub struct ScoresTable<'a> {
+    view: &'a mut ScoresView,
+    scores: &'a HighScores,
+    theme: &'a Theme,
+}
+
+impl Widget for ScoresTable<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let theme = self.theme;
+        let rows: Vec<Row> = self
+            .scores
+            .filtered(self.view.mode.as_deref())
+            .map(|(rank, e)| {
+                Row::new(vec![
+                    (rank + 1).to_string(),
+                    e.name.clone(),
+                    e.score.to_string(),
+                    e.length.to_string(),
+                    e.mode.clone(),
+                    format_duration(e.duration),
+                    format_date(e.date),
+                ])
+            })
+            .collect();
+        let title = format!(" Рекорды: {} ", self.view.mode.as_deref().unwrap_or("все режимы"));
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .title_bottom(Line::from(" ↑↓ PgUp PgDn - прокрутка, Tab - режим, ESC - назад "))
+            .border_style(Style::default().fg(theme.border))
+            .style(Style::default().bg(theme.popup_bg).fg(theme.text));
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(3),
+                Constraint::Min(6),
+                Constraint::Length(6),
+                Constraint::Length(6),
+                Constraint::Length(10),
+                Constraint::Length(6),
+                Constraint::Length(10),
+            ],
+        )
+        .header(
+            Row::new(vec!["#", "Имя", "Счёт", "Длина", "Режим", "Время", "Дата"])
+                .style(Style::default().fg(theme.accent).add_modifier(Modifier::BOLD)),
+        )
+        .block(block)
+        .row_highlight_style(Style::default().fg(theme.popup_bg).bg(theme.accent));
+
+        Clear.render(area, buf);
+        StatefulWidget::render(table, area, buf, &mut self.view.state);
+    }
+}
+
+/// Дата в виде ГГГГ-ММ-ДД (UTC) из секунд с начала эпохи Unix
+pub fn format_date(secs: u64) -> String {
+    // Алгоритм days-to-civil Говарда Хиннанта
+    let days = (secs / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let doe = days.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(score: usize, mode: &str) -> ScoreEntry {
+        ScoreEntry {
+            name: "x".into(),
+            score,
+            length: score + 1,
+            mode: mode.into(),
+            date: 0,
+            duration: Duration::from_secs(score as u64),
+        }
+    }
+
+    #[test]
+    fn insert_keeps_order_and_cap() {
+        let mut scores = HighScores::default();
+        for s in 0..HighScores::CAP {
+            scores.insert(entry(s + 10, "a"));
+        }
+        assert_eq!(scores.rank("a", 5), None);
+        assert_eq!(scores.insert(entry(5, "a")), None);
+        assert_eq!(scores.insert(entry(100, "a")), Some(0));
+        assert_eq!(scores.entries.len(), HighScores::CAP);
+        assert!(scores.entries.windows(2).all(|w| w[0].score >= w[1].score));
+        // Равный счёт встаёт под прежним
+        assert_eq!(scores.rank("a", 100), Some(1));
+    }
+
+    #[test]
+    fn modes_have_separate_tables() {
+        let mut scores = HighScores::default();
+        for s in 0..HighScores::CAP {
+            scores.insert(entry(s + 10, "a"));
+        }
+        // Слабый результат другого режима попадает в свою таблицу и не вытесняет чужие
+        assert_eq!(scores.insert(entry(1, "b")), Some(0));
+        assert_eq!(scores.entries.len(), HighScores::CAP + 1);
+        assert_eq!(scores.insert(entry(15, "b")), Some(0));
+        let ranks: Vec<usize> = scores.filtered(Some("b")).map(|(rank, _)| rank).collect();
+        assert_eq!(ranks, [0, 1]);
+        assert_eq!(scores.filtered(Some("a")).count(), HighScores::CAP);
+    }
+
+    #[test]
+    fn tab_cycles_through_modes() {
+        let mut scores = HighScores::default();
+        scores.insert(entry(3, "a"));
+        scores.insert(entry(2, "b"));
+        let mut view = ScoresView::new(None, None);
+        view.next_mode(&scores);
+        assert_eq!(view.mode.as_deref(), Some("a"));
+        view.next_mode(&scores);
+        assert_eq!(view.mode.as_deref(), Some("b"));
+        view.next_mode(&scores);
+        assert_eq!(view.mode, None);
+    }
+
+    #[test]
+    fn formats_dates() {
+        assert_eq!(format_date(0), "1970-01-01");
+        assert_eq!(format_date(951_782_400), "2000-02-29");
+        assert_eq!(format_date(1_700_000_000), "2023-11-14");
+    }
+
+    #[test]
+    fn file_round_trip() {
+        let mut scores = HighScores::default();
+        scores.insert(entry(3, "классика"));
+        scores.insert(ScoreEntry { name: "Вася Пупкин".into(), ..entry(7, "классика") });
+        let loaded = HighScores::parse(&scores.to_text()).unwrap();
+        assert_eq!(loaded.entries.len(), 2);
+        let best = loaded.best("классика").unwrap();
+        assert_eq!((best.name.as_str(), best.score, best.duration), ("Вася Пупкин", 7, Duration::from_secs(7)));
+        assert!(loaded.best("другой").is_none());
+    }
+
+    #[test]
+    fn rejects_bad_files() {
+        assert_eq!(HighScores::parse("").err(), Some(ScoresError::NotScores));
+        assert_eq!(HighScores::parse("zmiy-scores 2").err(), Some(ScoresError::UnsupportedFormat(2)));
+        assert_eq!(HighScores::parse("zmiy-scores 1\n1\t2\n").err(), Some(ScoresError::BadLine(2)));
+    }
+}
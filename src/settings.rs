@@ -0,0 +1,31 @@
+use crate::theme::BorderStyle;
+
+/// Настройки игры
+#[derive(Debug)]
+pub struct Settings {
+    /// Звук через терминальный звонок (S - вкл/выкл)
+    pub sound: bool,
+    /// Фоновая сетка из точек (G - вкл/выкл)
+    pub grid: bool,
+    /// Шкала длины змейки в строке статуса
+    pub length_gauge: bool,
+    /// Без анимаций (пульсация еды и т.п.)
+    pub reduced_motion: bool,
+    /// Рамка поля (`--border plain|rounded|double|thick|none`)
+    pub border: BorderStyle,
+    /// На сколько клеток змейка растёт за еду (`--growth`); меняется со следующей партии
+    pub growth_per_food: usize,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            sound: false,
+            grid: false,
+            length_gauge: true,
+            reduced_motion: false,
+            border: BorderStyle::default(),
+            growth_per_food: 1,
+        }
+    }
+}
@@ -0,0 +1,238 @@
+//! Партии без терминала для `--simulate`: встроенная стратегия играет много партий подряд,
+//! а в конце печатается сводка. Годится для CI и для сравнения правил.
+
+use std::{
+    fmt::Write,
+    time::{Duration, Instant},
+};
+
+use crate::game::{DeathCause, DirectionSnake, Game, Point};
+
+/// Стратегия, которая ведёт змейку вместо игрока
+pub trait Policy {
+    /// Куда повернуть перед следующим шагом; `None` — ехать прямо
+    fn decide(&mut self, game: &Game) -> Option<DirectionSnake>;
+}
+
+/// Имена стратегий для `--policy`
+pub const POLICIES: &[&str] = &["greedy"];
+
+pub fn policy(name: &str) -> Option<Box<dyn Policy>> {
+    match name {
+        "greedy" => Some(Box::new(Greedy)),
+        _ => None,
+    }
+}
+
+/// Жадная стратегия: из безопасных клеток рядом с головой выбирает ближайшую к еде.
+/// Далеко вперёд не смотрит, поэтому рано или поздно запирает себя.
+pub struct Greedy;
+
+impl Policy for Greedy {
+    fn decide(&mut self, game: &Game) -> Option<DirectionSnake> {
+        let head = game.snake()[0];
+        // Хвост на следующем шаге уйдёт, если змейка не ест
+        let tail = game.snake().back().copied().filter(|_| game.snake().len() > 1);
+        let safe = |p: &Point| !game.is_snake(*p) || (Some(*p) == tail && *p != game.food);
+        [DirectionSnake::Up, DirectionSnake::Down, DirectionSnake::Left, DirectionSnake::Right]
+            .into_iter()
+            .filter(|&dir| game.snake().len() == 1 || dir != game.dir.opposite())
+            .filter_map(|dir| Some((dir, game.neighbour(head, dir)?)))
+            .filter(|(_, p)| safe(p))
+            .min_by_key(|(_, p)| p.x.abs_diff(game.food.x) + p.y.abs_diff(game.food.y))
+            .map(|(dir, _)| dir)
+    }
+}
+
+/// Итог одной партии
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Run {
+    pub score: usize,
+    pub length: usize,
+    pub ticks: u64,
+    /// `None` — партия остановлена: змейка слишком долго не находила еду
+    pub death: Option<DeathCause>,
+}
+
+/// Играет одну партию до конца. Партия без еды дольше, чем клеток на поле, вдвое,
+/// считается зациклившейся и останавливается.
+pub fn play(game: &mut Game, policy: &mut dyn Policy) -> Run {
+    let stall_limit = 2 * game.width as u64 * game.height as u64;
+    let (mut ticks, mut hungry) = (0u64, 0u64);
+    while game.game_over.is_none() && hungry < stall_limit {
+        if let Some(dir) = policy.decide(game) {
+            game.change_dir(dir);
+        }
+        let score = game.score;
+        game.step();
+        ticks += 1;
+        hungry = if game.score > score { 0 } else { hungry + 1 };
+    }
+    Run { score: game.score, length: game.snake().len(), ticks, death: game.game_over }
+}
+
+/// Сводка по многим партиям
+pub struct Stats {
+    pub policy: String,
+    pub width: u16,
+    pub height: u16,
+    /// Сид первой партии; у следующих он на единицу больше
+    pub seed: u64,
+    pub runs: Vec<Run>,
+    pub elapsed: Duration,
+}
+
+/// Причины конца в порядке сводки: имя для JSON и описание для текста
+const ENDINGS: [(Option<DeathCause>, &str, &str); 5] = [
+    (Some(DeathCause::Wall), "wall", "Врезались в стену"),
+    (Some(DeathCause::SelfCollision), "self_collision", "Укусили себя"),
+    (Some(DeathCause::BoardTooSmall), "board_too_small", "Поле стало слишком маленьким"),
+    (Some(DeathCause::BoardFull), "board_full", "Заняли всё поле"),
+    (None, "stalled", "Остановлены: долго без еды"),
+];
+
+/// Играет `games` партий на поле `width`×`height` с сидами `seed`, `seed + 1`, …
+pub fn simulate(games: u64, width: u16, height: u16, seed: u64, name: &str, policy: &mut dyn Policy) -> Stats {
+    let started = Instant::now();
+    let runs = (0..games)
+        .map(|i| {
+            let mut game = Game::with_seed(width, height, seed.wrapping_add(i));
+            game.spawn_food();
+            play(&mut game, policy)
+        })
+        .collect();
+    Stats { policy: name.to_string(), width, height, seed, runs, elapsed: started.elapsed() }
+}
+
+impl Stats {
+    pub fn mean_score(&self) -> f64 {
+        self.mean(|r| r.score)
+    }
+
+    pub fn median_score(&self) -> f64 {
+        let mut scores: Vec<usize> = self.runs.iter().map(|r| r.score).collect();
+        scores.sort_unstable();
+        match scores.len() {
+            0 => 0.0,
+            n if n % 2 == 1 => scores[n / 2] as f64,
+            n => (scores[n / 2 - 1] + scores[n / 2]) as f64 / 2.0,
+        }
+    }
+
+    pub fn max_score(&self) -> usize {
+        self.runs.iter().map(|r| r.score).max().unwrap_or(0)
+    }
+
+    pub fn mean_length(&self) -> f64 {
+        self.mean(|r| r.length)
+    }
+
+    pub fn ticks(&self) -> u64 {
+        self.runs.iter().map(|r| r.ticks).sum()
+    }
+
+    fn mean(&self, value: impl Fn(&Run) -> usize) -> f64 {
+        if self.runs.is_empty() {
+            return 0.0;
+        }
+        self.runs.iter().map(value).sum::<usize>() as f64 / self.runs.len() as f64
+    }
+
+    fn endings(&self) -> impl Iterator<Item = (&'static str, &'static str, usize)> + '_ {
+        ENDINGS
+            .iter()
+            .map(|&(cause, code, text)| (code, text, self.runs.iter().filter(|r| r.death == cause).count()))
+    }
+
+    /// Сводка для человека
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        let secs = self.elapsed.as_secs_f64();
+        let _ = writeln!(out, "Партий: {} ({}, поле {}x{}, сид {})", self.runs.len(), self.policy, self.width, self.height, self.seed);
+        let _ = writeln!(
+            out,
+            "Счёт: средний {:.1}, медиана {:.1}, максимум {}",
+            self.mean_score(),
+            self.median_score(),
+            self.max_score()
+        );
+        let _ = writeln!(out, "Длина: средняя {:.1}", self.mean_length());
+        let _ = writeln!(out, "Конец партии:");
+        for (_, text, count) in self.endings() {
+            let _ = writeln!(out, "  {text}: {count}");
+        }
+        let _ = writeln!(
+            out,
+            "Время: {secs:.2} с, {:.0} партий/с, {:.0} шагов/с",
+            self.runs.len() as f64 / secs.max(f64::EPSILON),
+            self.ticks() as f64 / secs.max(f64::EPSILON)
+        );
+        out
+    }
+
+    /// Сводка одной строкой JSON для `--json`
+    pub fn to_json(&self) -> String {
+        let deaths: Vec<String> = self.endings().map(|(code, _, count)| format!("\"{code}\":{count}")).collect();
+        format!(
+            "{{\"policy\":\"{}\",\"games\":{},\"width\":{},\"height\":{},\"seed\":{},\
+             \"score\":{{\"mean\":{:.3},\"median\":{:.1},\"max\":{}}},\"mean_length\":{:.3},\
+             \"deaths\":{{{}}},\"ticks\":{},\"seconds\":{:.3}}}",
+            self.policy,
+            self.runs.len(),
+            self.width,
+            self.height,
+            self.seed,
+            self.mean_score(),
+            self.median_score(),
+            self.max_score(),
+            self.mean_length(),
+            deaths.join(","),
+            self.ticks(),
+            self.elapsed.as_secs_f64()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn greedy_eats() {
+        let stats = simulate(20, 20, 12, 1, "greedy", &mut Greedy);
+        assert_eq!(stats.runs.len(), 20);
+        assert!(stats.mean_score() > 10.0, "{}", stats.mean_score());
+        assert!(stats.runs.iter().all(|r| r.length == r.score + 1));
+    }
+
+    #[test]
+    fn same_seed_same_stats() {
+        let a = simulate(5, 15, 10, 9, "greedy", &mut Greedy);
+        let b = simulate(5, 15, 10, 9, "greedy", &mut Greedy);
+        assert_eq!(a.runs, b.runs);
+    }
+
+    #[test]
+    fn summary_formats() {
+        let run = |score, death| Run { score, length: score + 1, ticks: 10, death };
+        let stats = Stats {
+            policy: "greedy".to_string(),
+            width: 10,
+            height: 5,
+            seed: 3,
+            runs: vec![run(4, Some(DeathCause::SelfCollision)), run(1, Some(DeathCause::Wall)), run(9, None), run(2, None)],
+            elapsed: Duration::from_secs(2),
+        };
+        assert_eq!(stats.median_score(), 3.0);
+        assert_eq!(
+            stats.to_json(),
+            "{\"policy\":\"greedy\",\"games\":4,\"width\":10,\"height\":5,\"seed\":3,\
+             \"score\":{\"mean\":4.000,\"median\":3.0,\"max\":9},\"mean_length\":5.000,\
+             \"deaths\":{\"wall\":1,\"self_collision\":1,\"board_too_small\":0,\"board_full\":0,\"stalled\":2},\
+             \"ticks\":40,\"seconds\":2.000}"
+        );
+        let text = stats.to_text();
+        assert!(text.contains("Счёт: средний 4.0, медиана 3.0, максимум 9"), "{text}");
+        assert!(text.contains("  Остановлены: долго без еды: 2"), "{text}");
+    }
+}
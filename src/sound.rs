@@ -0,0 +1,45 @@
+use std::{
+    io::{self, Write},
+    time::{Duration, Instant},
+};
+
+use crossterm::{execute, style::Print};
+
+/// Звук через терминальный звонок (BEL).
+///
+/// Звонки копятся за кадр и выводятся одним `execute!` уже после отрисовки,
+/// чтобы не вклиниваться в вывод ratatui.
+#[derive(Default)]
+pub struct Bell {
+    pending: u8,
+    last: Option<Instant>,
+}
+
+impl Bell {
+    /// Минимальный интервал между звонками, чтобы не было «шторма»
+    const MIN_INTERVAL: Duration = Duration::from_millis(150);
+
+    /// Одиночный звонок (еда)
+    pub fn ring(&mut self) {
+        self.pending = self.pending.max(1);
+    }
+
+    /// Двойной звонок (конец игры)
+    pub fn ring_twice(&mut self) {
+        self.pending = 2;
+    }
+
+    /// Выводит накопленные звонки. Вызывается раз за кадр после `terminal.draw`.
+    pub fn flush<W: Write>(&mut self, out: &mut W) -> io::Result<()> {
+        let count = std::mem::take(&mut self.pending);
+        if count == 0 {
+            return Ok(());
+        }
+        if self.last.is_some_and(|t| t.elapsed() < Self::MIN_INTERVAL) {
+            return Ok(());
+        }
+        self.last = Some(Instant::now());
+        let bells = "\x07".repeat(count as usize);
+        execute!(out, Print(bells))
+    }
+}
@@ -0,0 +1,124 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::Style,
+    text::Line,
+    widgets::{Paragraph, Widget},
+};
+
+use crate::{theme::Theme, widget::centered};
+
+const LARGE_LATIN: &[&str] = &[
+    "███████ ███    ███ ██ ██    ██",
+    "   ███  ████  ████ ██  ██  ██ ",
+    "  ███   ██ ████ ██ ██   ████  ",
+    " ███    ██  ██  ██ ██    ██   ",
+    "███████ ██      ██ ██    ██   ",
+];
+
+const LARGE_CYRILLIC: &[&str] = &[
+    "█████  ██   ██ ██████ ██ ▀ ██ ██  ██   ██  ",
+    "    ██ ███ ███ ██     ██  ███ ██ ██   ████ ",
+    " ████  ██ █ ██ █████  ██ █ ██ ████   ██  ██",
+    "    ██ ██   ██ ██     ███  ██ ██ ██  ██████",
+    "█████  ██   ██ ██████ ██   ██ ██  ██ ██  ██",
+];
+
+const SMALL: &[&str] = &[
+    " _____ __  __ ___ __   __",
+    "|__  /|  \\/  |_ _|\\ \\ / /",
+    "  / / | |\\/| || |  \\ V / ",
+    " / /_ | |  | || |   | |  ",
+    "/____||_|  |_|___|  |_|  ",
+];
+
+/// Заставка с логотипом. Вариант логотипа выбирается по ширине терминала,
+/// чтобы он не переносился на узких экранах.
+///
+/// Все строки одного логотипа одной ширины, поэтому их можно центрировать по отдельности.
+pub struct Splash<'a> {
+    theme: &'a Theme,
+    saved: bool,
+    ghost: Option<usize>,
+    mode: Option<&'a str>,
+    speed_level: Option<usize>,
+    editor: bool,
+}
+
+impl<'a> Splash<'a> {
+    pub fn new(theme: &'a Theme) -> Self {
+        Self { theme, saved: false, ghost: None, mode: None, speed_level: None, editor: false }
+    }
+
+    /// Есть сохранённая партия: предлагаем её продолжить
+    pub fn saved(mut self, saved: bool) -> Self {
+        self.saved = saved;
+        self
+    }
+
+    /// Счёт призрака `--ghost`, если он нашёлся
+    pub fn ghost(mut self, score: Option<usize>) -> Self {
+        self.ghost = score;
+        self
+    }
+
+    /// Режим, который можно сменить клавишей Tab; `None` — режим задан при запуске
+    pub fn mode(mut self, mode: Option<&'a str>) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Скорость режима «нокиа» от 1 до 9, которую выбирают цифрами
+    pub fn speed_level(mut self, level: Option<usize>) -> Self {
+        self.speed_level = level;
+        self
+    }
+
+    /// Можно открыть редактор поля клавишей E и свои уровни клавишей L
+    pub fn editor(mut self, editor: bool) -> Self {
+        self.editor = editor;
+        self
+    }
+}
+
+impl Widget for Splash<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let theme = self.theme;
+        let logo = |l: &'static str| Line::styled(l, Style::default().fg(theme.snake)).centered();
+        let fits = |variant: &[&str]| variant.iter().all(|l| l.chars().count() as u16 <= area.width);
+        let mut lines: Vec<Line> = Vec::new();
+        if fits(LARGE_CYRILLIC) {
+            lines.extend(LARGE_LATIN.iter().map(|l| logo(l)));
+            lines.push(Line::default());
+            lines.extend(LARGE_CYRILLIC.iter().map(|l| logo(l)));
+        } else if fits(SMALL) {
+            lines.extend(SMALL.iter().map(|l| logo(l)));
+            lines.push(logo("З М Е Й К А"));
+        } else {
+            lines.push(logo("ЗМЕЙКА"));
+        }
+        lines.push(Line::default());
+        lines.push(Line::styled(concat!("v", env!("CARGO_PKG_VERSION")), Style::default().fg(theme.muted)).centered());
+        if let Some(score) = self.ghost {
+            lines.push(Line::styled(format!("Призрак: лучшая партия, счёт {score}"), Style::default().fg(theme.muted)).centered());
+        }
+        if let Some(mode) = self.mode {
+            lines.push(Line::styled(format!("Tab - режим: {mode}"), Style::default().fg(theme.text)).centered());
+        }
+        if let Some(level) = self.speed_level {
+            lines.push(Line::styled(format!("1-9 - скорость: {level}"), Style::default().fg(theme.text)).centered());
+        }
+        if self.editor {
+            lines.push(Line::styled("E - редактор поля, L - свои уровни", Style::default().fg(theme.text)).centered());
+        }
+        if self.saved {
+            lines.push(Line::styled("Enter - продолжить сохранённую игру", Style::default().fg(theme.accent)).centered());
+            lines.push(Line::styled("Любая другая клавиша - новая игра", Style::default().fg(theme.text)).centered());
+        } else {
+            lines.push(Line::styled("Нажмите любую клавишу", Style::default().fg(theme.accent)).centered());
+        }
+
+        let height = lines.len() as u16;
+        Paragraph::new(lines).render(centered(area, area.width, height), buf);
+    }
+}
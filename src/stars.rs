@@ -0,0 +1,182 @@
+//! Звёзды за уровни: от одной до трёх за прохождение уровня (см. [`crate::level`]).
+//! Одна звезда — за то, что счёт дошёл до пара, две — если это заняло не больше
+//! двух времён пара, три — не больше одного.
+//!
+//! Лучшие звёзды каждого уровня хранятся в `<данные>/stars.txt`, у каждого профиля
+//! свои. Уровень узнаётся по хешу раскладки ([`Level::layout_hash`]):
+//!
+//! ```text
+//! zmiy-stars 1
+//! 8c2a4f0e91d3b765 2       хеш раскладки и лучшее число звёзд
+//! ```
+
+use std::{
+    collections::BTreeMap,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use crate::{
+    level::Level,
+    paths::{data_dir, write_atomic},
+};
+
+/// Версия формата файла звёзд
+pub const FORMAT_VERSION: u32 = 1;
+/// Первое слово файла
+pub const SIGNATURE: &str = "zmiy-stars";
+/// Больше звёзд за уровень не бывает
+pub const MAX: u8 = 3;
+
+/// Файл звёзд: `<данные>/stars.txt`
+pub fn path() -> Option<PathBuf> {
+    Some(data_dir()?.join("stars.txt"))
+}
+
+/// Сколько звёзд стоит партия на уровне `level` со счётом `score` за время `elapsed`;
+/// 0 — уровень не пройден
+pub fn rate(level: &Level, score: usize, elapsed: Duration) -> u8 {
+    if score < level.par() {
+        0
+    } else if elapsed <= level.par_time() {
+        3
+    } else if elapsed <= level.par_time() * 2 {
+        2
+    } else {
+        1
+    }
+}
+
+/// Звёзды в виде ★★☆
+pub fn format(stars: u8) -> String {
+    (0..MAX).map(|i| if i < stars { '★' } else { '☆' }).collect()
+}
+
+/// Почему файл звёзд не удалось прочитать
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum StarsError {
+    /// Файл не прочитался
+    Io(String),
+    /// Нет сигнатуры `zmiy-stars`
+    NotStars,
+    /// Формат другой версии
+    UnsupportedFormat(u32),
+    /// Непонятная строка, номер с единицы
+    BadLine(usize),
+}
+
+impl fmt::Display for StarsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StarsError::Io(e) => write!(f, "{e}"),
+            StarsError::NotStars => write!(f, "это не файл звёзд"),
+            StarsError::UnsupportedFormat(v) => {
+                write!(f, "формат звёзд версии {v} не поддерживается (нужна {FORMAT_VERSION})")
+            }
+            StarsError::BadLine(n) => write!(f, "ошибка в строке {n}"),
+        }
+    }
+}
+
+impl std::error::Error for StarsError {}
+
+/// Лучшие звёзды по уровням
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+pub struct Stars {
+    best: BTreeMap<u64, u8>,
+}
+
+impl Stars {
+    /// Лучшие звёзды уровня; `None` — уровень ещё не пройден
+    pub fn get(&self, level: &Level) -> Option<u8> {
+        self.best.get(&level.layout_hash()).copied()
+    }
+
+    /// Запоминает `stars` звёзд за уровень, если это лучше прежнего, и возвращает прежние
+    pub fn record(&mut self, level: &Level, stars: u8) -> Option<u8> {
+        let best = self.best.entry(level.layout_hash()).or_default();
+        let previous = (*best > 0).then_some(*best);
+        *best = (*best).max(stars.min(MAX));
+        previous
+    }
+
+    /// Звёзды в формате файла: сигнатура, затем уровень на строку
+    pub fn to_text(&self) -> String {
+        let mut text = format!("{SIGNATURE} {FORMAT_VERSION}\n");
+        for (hash, stars) in &self.best {
+            text.push_str(&format!("{hash:016x} {stars}\n"));
+        }
+        text
+    }
+
+    pub fn parse(text: &str) -> Result<Self, StarsError> {
+        let mut lines = text.lines();
+        match lines.next().map(|l| l.split_whitespace().collect::<Vec<_>>()) {
+            Some(words) if words.first() == Some(&SIGNATURE) => match words.get(1).map(|v| v.parse::<u32>()) {
+                Some(Ok(FORMAT_VERSION)) => {}
+                Some(Ok(other)) => return Err(StarsError::UnsupportedFormat(other)),
+                _ => return Err(StarsError::BadLine(1)),
+            },
+            _ => return Err(StarsError::NotStars),
+        }
+        let mut stars = Self::default();
+        for (i, line) in lines.enumerate().filter(|(_, l)| !l.trim().is_empty()) {
+            let bad = || StarsError::BadLine(i + 2);
+            let [hash, n] = line.split_whitespace().collect::<Vec<_>>()[..] else {
+                return Err(bad());
+            };
+            let hash = u64::from_str_radix(hash, 16).map_err(|_| bad())?;
+            let n: u8 = n.parse().ok().filter(|n| (1..=MAX).contains(n)).ok_or_else(bad)?;
+            stars.best.insert(hash, n);
+        }
+        Ok(stars)
+    }
+
+    /// Загружает звёзды; если файла ещё нет — пустые
+    pub fn load(path: &Path) -> Result<Self, StarsError> {
+        match fs::read_to_string(path) {
+            Ok(text) => Self::parse(&text),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(StarsError::Io(e.to_string())),
+        }
+    }
+
+    pub fn store(&self, path: &Path) -> io::Result<()> {
+        write_atomic(path, self.to_text().as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Mode;
+
+    #[test]
+    fn stars_follow_score_and_time() {
+        let level = Level::built_in(Mode::TwoRooms).unwrap();
+        let par_time = level.par_time();
+        assert_eq!(rate(&level, level.par() - 1, Duration::ZERO), 0);
+        assert_eq!(rate(&level, level.par(), par_time), 3);
+        assert_eq!(rate(&level, level.par() + 5, par_time * 2), 2);
+        assert_eq!(rate(&level, level.par(), par_time * 2 + Duration::from_secs(1)), 1);
+        assert_eq!((format(0), format(2), format(3)), ("☆☆☆".to_string(), "★★☆".to_string(), "★★★".to_string()));
+    }
+
+    #[test]
+    fn best_stars_are_kept() {
+        let level = Level::built_in(Mode::TwoRooms).unwrap();
+        let mut stars = Stars::default();
+        assert_eq!((stars.get(&level), stars.record(&level, 2)), (None, None));
+        assert_eq!(stars.record(&level, 1), Some(2));
+        assert_eq!(stars.record(&level, 3), Some(2));
+        assert_eq!(stars.get(&level), Some(3));
+
+        let text = stars.to_text();
+        assert_eq!(Stars::parse(&text), Ok(stars));
+        assert_eq!(Stars::parse("zmiy-stars 1\nxyz 2\n"), Err(StarsError::BadLine(2)));
+        assert_eq!(Stars::parse("zmiy-stars 1\n00000000000000ff 4\n"), Err(StarsError::BadLine(2)));
+        assert_eq!(Stars::parse("zmiy-stars 2\n"), Err(StarsError::UnsupportedFormat(2)));
+        assert_eq!(Stars::parse("zmiy-stats 2\n"), Err(StarsError::NotStars));
+    }
+}
@@ -0,0 +1,55 @@
+//! Полноэкранный режим терминала и его восстановление.
+
+use std::{io, panic};
+
+use crossterm::{
+    cursor::Show,
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+
+use crate::{log_error, report::Report};
+
+/// Возвращает терминал в обычный режим: эхо ввода, основной экран, видимый курсор.
+/// Ошибки пропускаются: восстановить терминал — последнее, что ещё можно сделать.
+pub fn restore() {
+    let _ = disable_raw_mode();
+    let _ = execute!(io::stdout(), LeaveAlternateScreen, Show);
+}
+
+/// Ставит обработчик паники, который сначала восстанавливает терминал и пишет панику
+/// в журнал, а потом печатает её обычным образом и пишет отчёт о сбое. Без него сообщение
+/// о панике осталось бы на альтернативном экране, а терминал — без эха.
+pub fn install_panic_hook() {
+    let default = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        restore();
+        log_error!("паника: {info}");
+        default(info);
+        match Report::from_panic(info.to_string()).save() {
+            Ok(dir) => eprintln!("Отчёт о сбое: {} — приложите его к сообщению об ошибке", dir.display()),
+            Err(e) => eprintln!("Отчёт о сбое не записан: {e}"),
+        }
+    }));
+}
+
+/// Полноэкранный режим на время жизни значения. Терминал восстанавливается при удалении,
+/// в том числе при выходе по `?` из середины игры.
+pub struct TerminalGuard(());
+
+impl TerminalGuard {
+    /// Включает сырой режим и альтернативный экран
+    pub fn enter() -> io::Result<Self> {
+        enable_raw_mode()?;
+        // Дальше терминал уже надо возвращать, даже если что-то не получится
+        let guard = Self(());
+        execute!(io::stdout(), EnterAlternateScreen)?;
+        Ok(guard)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore();
+    }
+}
@@ -0,0 +1,112 @@
+use ratatui::{
+    style::{Modifier, Style},
+    text::{Line, Span},
+};
+
+/// Однострочное поле ввода с курсором и ограничением длины в символах
+pub struct TextInput {
+    chars: Vec<char>,
+    /// Позиция курсора в символах, от 0 до длины включительно
+    cursor: usize,
+    max: usize,
+}
+
+impl TextInput {
+    /// Поле с начальным текстом (обрезается до `max`), курсор в конце
+    pub fn new(initial: &str, max: usize) -> Self {
+        let chars: Vec<char> = initial.chars().filter(|c| !c.is_control()).take(max).collect();
+        Self { cursor: chars.len(), chars, max }
+    }
+
+    pub fn value(&self) -> String {
+        self.chars.iter().collect()
+    }
+
+    pub fn insert(&mut self, c: char) {
+        if self.chars.len() < self.max && !c.is_control() {
+            self.chars.insert(self.cursor, c);
+            self.cursor += 1;
+        }
+    }
+
+    /// Стирает символ перед курсором
+    pub fn backspace(&mut self) {
+        if self.cursor > 0 {
+            self.cursor -= 1;
+            self.chars.remove(self.cursor);
+        }
+    }
+
+    /// Стирает символ под курсором
+    pub fn delete(&mut self) {
+        if self.cursor < self.chars.len() {
+            self.chars.remove(self.cursor);
+        }
+    }
+
+    pub fn left(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    pub fn right(&mut self) {
+        self.cursor = (self.cursor + 1).min(self.chars.len());
+    }
+
+    pub fn home(&mut self) {
+        self.cursor = 0;
+    }
+
+    pub fn end(&mut self) {
+        self.cursor = self.chars.len();
+    }
+
+    /// Строка для отрисовки: курсор — инвертированная клетка, поле дополнено до полной ширины
+    pub fn line(&self, style: Style) -> Line<'static> {
+        let before: String = self.chars[..self.cursor].iter().collect();
+        let under = self.chars.get(self.cursor).map_or(" ".to_string(), char::to_string);
+        let after: String = self.chars.get(self.cursor + 1..).unwrap_or_default().iter().collect();
+        // Курсор в конце занимает лишнюю клетку, поэтому отступ на одну меньше
+        let used = self.chars.len().max(self.cursor + 1);
+        let pad = " ".repeat((self.max + 1).saturating_sub(used));
+        Line::from(vec![
+            Span::styled(before, style),
+            Span::styled(under, style.add_modifier(Modifier::REVERSED)),
+            Span::styled(after, style),
+            Span::styled(pad, style.add_modifier(Modifier::UNDERLINED)),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edits_at_cursor() {
+        let mut input = TextInput::new("Вася", 12);
+        input.left();
+        input.left();
+        input.insert('-');
+        assert_eq!(input.value(), "Ва-ся");
+        input.backspace();
+        input.delete();
+        assert_eq!(input.value(), "Вая");
+        input.home();
+        input.backspace();
+        input.insert('!');
+        input.end();
+        input.insert('?');
+        assert_eq!(input.value(), "!Вая?");
+    }
+
+    #[test]
+    fn respects_max_length() {
+        let mut input = TextInput::new("очень длинное имя", 5);
+        assert_eq!(input.value(), "очень");
+        input.insert('x');
+        assert_eq!(input.value(), "очень");
+        input.backspace();
+        input.insert('x');
+        assert_eq!(input.value(), "оченx");
+    }
+}
@@ -0,0 +1,328 @@
+use ratatui::{style::Color, symbols::border, widgets::BorderType};
+
+/// Сколько цветов умеет терминал
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ColorSupport {
+    TrueColor,
+    Ansi256,
+    Ansi16,
+}
+
+impl ColorSupport {
+    /// Определяет поддержку цветов по `COLORTERM` и `TERM`
+    pub fn detect() -> Self {
+        let colorterm = std::env::var("COLORTERM").unwrap_or_default();
+        let term = std::env::var("TERM").unwrap_or_default();
+        if colorterm == "truecolor" || colorterm == "24bit" {
+            Self::TrueColor
+        } else if term.contains("256color") {
+            Self::Ansi256
+        } else {
+            Self::Ansi16
+        }
+    }
+
+    /// Значение для ручного выбора: `truecolor`, `256` или `16`
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "truecolor" | "24bit" => Some(Self::TrueColor),
+            "256" => Some(Self::Ansi256),
+            "16" => Some(Self::Ansi16),
+            _ => None,
+        }
+    }
+
+    /// Ближайший доступный цвет к `rgb`
+    pub fn color(self, rgb: (u8, u8, u8)) -> Color {
+        let (r, g, b) = rgb;
+        match self {
+            Self::TrueColor => Color::Rgb(r, g, b),
+            Self::Ansi256 => Color::Indexed(to_ansi256(rgb)),
+            Self::Ansi16 => to_ansi16(rgb),
+        }
+    }
+}
+
+/// Рамка вокруг поля
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum BorderStyle {
+    #[default]
+    Plain,
+    Rounded,
+    Double,
+    Thick,
+    /// Без рамки: поле занимает весь экран, края видны только по смерти об стену
+    None,
+}
+
+impl BorderStyle {
+    /// `plain`, `rounded`, `double`, `thick` или `none`
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "plain" => Some(Self::Plain),
+            "rounded" => Some(Self::Rounded),
+            "double" => Some(Self::Double),
+            "thick" => Some(Self::Thick),
+            "none" => Some(Self::None),
+            _ => None,
+        }
+    }
+
+    /// Тип рамки ratatui или `None` для поля без рамки
+    pub fn border_type(self) -> Option<BorderType> {
+        match self {
+            Self::Plain => Some(BorderType::Plain),
+            Self::Rounded => Some(BorderType::Rounded),
+            Self::Double => Some(BorderType::Double),
+            Self::Thick => Some(BorderType::Thick),
+            Self::None => None,
+        }
+    }
+}
+
+/// Рамка поля из одних ASCII-символов для `--ascii`
+pub const ASCII_BORDER: border::Set = border::Set {
+    top_left: "+",
+    top_right: "+",
+    bottom_left: "+",
+    bottom_right: "+",
+    vertical_left: "|",
+    vertical_right: "|",
+    horizontal_top: "-",
+    horizontal_bottom: "-",
+};
+
+/// Уровни компонент в кубе 6×6×6 палитры xterm-256
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// 16 базовых цветов в том виде, как их рисует xterm
+const ANSI16: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (205, 0, 0)),
+    (Color::Green, (0, 205, 0)),
+    (Color::Yellow, (205, 205, 0)),
+    (Color::Blue, (0, 0, 238)),
+    (Color::Magenta, (205, 0, 205)),
+    (Color::Cyan, (0, 205, 205)),
+    (Color::Gray, (229, 229, 229)),
+    (Color::DarkGray, (127, 127, 127)),
+    (Color::LightRed, (255, 0, 0)),
+    (Color::LightGreen, (0, 255, 0)),
+    (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (92, 92, 255)),
+    (Color::LightMagenta, (255, 0, 255)),
+    (Color::LightCyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let d = |x: u8, y: u8| (x as i32 - y as i32).pow(2) as u32;
+    d(a.0, b.0) + d(a.1, b.1) + d(a.2, b.2)
+}
+
+/// Ближайший цвет палитры xterm-256: из куба 6×6×6 или из серой шкалы
+pub fn to_ansi256(rgb: (u8, u8, u8)) -> u8 {
+    let level = |v: u8| {
+        (0..CUBE_LEVELS.len())
+            .min_by_key(|&i| (CUBE_LEVELS[i] as i32 - v as i32).abs())
+            .unwrap_or(0)
+    };
+    let (r, g, b) = (level(rgb.0), level(rgb.1), level(rgb.2));
+    let cube = (CUBE_LEVELS[r], CUBE_LEVELS[g], CUBE_LEVELS[b]);
+    let cube_index = 16 + 36 * r as u8 + 6 * g as u8 + b as u8;
+
+    // Серая шкала 232..=255: 8, 18, ..., 238
+    let avg = (rgb.0 as u32 + rgb.1 as u32 + rgb.2 as u32) / 3;
+    let gray_step = ((avg.saturating_sub(3)) / 10).min(23) as u8;
+    let gray_value = 8 + 10 * gray_step;
+    let gray = (gray_value, gray_value, gray_value);
+
+    if distance(rgb, gray) < distance(rgb, cube) {
+        232 + gray_step
+    } else {
+        cube_index
+    }
+}
+
+/// Ближайший из 16 базовых цветов
+pub fn to_ansi16(rgb: (u8, u8, u8)) -> Color {
+    ANSI16
+        .iter()
+        .min_by_key(|(_, c)| distance(rgb, *c))
+        .map_or(Color::Reset, |(color, _)| *color)
+}
+
+/// Сколько оттенков проходит угасающий сегмент
+pub const FADE_SHADES: usize = 5;
+
+/// Цвета интерфейса. Задаются в RGB и один раз приводятся к возможностям терминала.
+pub struct Theme {
+    pub snake: Color,
+    /// Змейка соперника в игре по сети
+    pub rival: Color,
+    pub food: Color,
+    /// Оттенки еды для пульсации: от яркого к тусклому
+    pub food_pulse: [Color; 4],
+    /// Синяя еда режима «две еды»
+    pub bonus_food: Color,
+    /// Бомба: почти как еда, чуть темнее
+    pub bomb: Color,
+    /// Большая еда, которую съедают в несколько касаний
+    pub boss: Color,
+    /// Звенья гусеницы
+    pub caterpillar: Color,
+    /// Фон под гусеницей, связывающий её звенья в одну линию
+    pub caterpillar_link: Color,
+    /// Портал и змейка, проходящая через него
+    pub portal: Color,
+    /// Щит на поле и в строке статуса
+    pub shield: Color,
+    /// Удвоитель очков на поле и его отсчёт в строке статуса
+    pub doubler: Color,
+    /// Заморозка на поле, её отсчёт в строке статуса и замёрзшие бомба и убегающая еда
+    pub frozen: Color,
+    /// Фон клетки, мимо которой голова прошла вплотную
+    pub near_miss: Color,
+    /// Мёртвая змейка, «Игра окончена»
+    pub danger: Color,
+    /// Угасание мёртвой змейки: от `danger` почти до фона
+    pub dead_fade: [Color; FADE_SHADES],
+    /// Заголовки, счёт, отсчёт
+    pub accent: Color,
+    pub text: Color,
+    /// Сетка, затемнение, второстепенный текст
+    pub muted: Color,
+    /// Рекорд
+    pub record: Color,
+    pub border: Color,
+    /// Стены внутри поля на уровнях с комнатами
+    pub wall: Color,
+    /// Фон клеток быстрой зоны
+    pub zone_fast: Color,
+    /// Фон клеток медленной зоны
+    pub zone_slow: Color,
+    /// Фон слизи за хвостом: от свежей почти до фона поля
+    pub slime: [Color; FADE_SHADES],
+    /// Фон всплывающих окон
+    pub popup_bg: Color,
+    /// Фон поля; `None` — фон терминала
+    pub board_bg: Option<Color>,
+    /// Шкала скорости: от медленной (зелёный) к быстрой (красный)
+    pub speed: [Color; 5],
+}
+
+impl Theme {
+    pub fn new(support: ColorSupport) -> Self {
+        let c = |rgb| support.color(rgb);
+        Self {
+            snake: c((80, 200, 120)),
+            rival: c((90, 160, 240)),
+            food: c((230, 70, 70)),
+            food_pulse: [c((255, 110, 110)), c((230, 70, 70)), c((190, 50, 50)), c((150, 35, 35))],
+            bonus_food: c((70, 130, 255)),
+            bomb: c((200, 45, 45)),
+            boss: c((230, 150, 40)),
+            caterpillar: c((170, 220, 60)),
+            caterpillar_link: c((45, 65, 25)),
+            portal: c((180, 100, 240)),
+            shield: c((90, 210, 230)),
+            doubler: c((255, 190, 40)),
+            frozen: c((150, 200, 255)),
+            near_miss: c((110, 80, 20)),
+            danger: c((230, 70, 70)),
+            dead_fade: gradient(support, (230, 70, 70), (45, 20, 24)),
+            accent: c((240, 200, 60)),
+            text: c((225, 225, 225)),
+            muted: c((110, 110, 110)),
+            record: c((200, 100, 220)),
+            border: c((200, 200, 200)),
+            wall: c((160, 120, 80)),
+            zone_fast: c((20, 45, 60)),
+            zone_slow: c((55, 40, 25)),
+            slime: gradient(support, (60, 80, 40), (22, 26, 20)),
+            popup_bg: c((20, 20, 28)),
+            board_bg: None,
+            speed: gradient(support, (80, 200, 120), (230, 70, 70)),
+        }
+    }
+
+    /// Тема режима «нокиа»: всё зелёное на тёмно-зелёном, как экран старого телефона.
+    /// Что на поле еда, а что змейка, видно по символам, а не по цвету.
+    pub fn nokia(support: ColorSupport) -> Self {
+        let c = |rgb| support.color(rgb);
+        let (bright, mid, dim) = (c((170, 220, 90)), c((120, 170, 60)), c((60, 90, 35)));
+        Self {
+            snake: bright,
+            rival: mid,
+            food: bright,
+            food_pulse: [bright, bright, mid, mid],
+            bonus_food: mid,
+            bomb: mid,
+            boss: bright,
+            caterpillar: mid,
+            caterpillar_link: c((30, 45, 20)),
+            portal: bright,
+            shield: mid,
+            doubler: bright,
+            frozen: mid,
+            near_miss: c((50, 75, 30)),
+            danger: bright,
+            dead_fade: gradient(support, (170, 220, 90), (30, 45, 20)),
+            accent: bright,
+            text: mid,
+            muted: dim,
+            record: bright,
+            border: mid,
+            wall: mid,
+            zone_fast: c((35, 55, 25)),
+            zone_slow: c((25, 40, 18)),
+            slime: gradient(support, (50, 75, 30), (22, 35, 15)),
+            popup_bg: c((15, 25, 10)),
+            board_bg: Some(c((20, 32, 14))),
+            speed: [dim, mid, mid, bright, bright],
+        }
+    }
+}
+
+/// `N` цветов, равномерно от `from` до `to`
+fn gradient<const N: usize>(support: ColorSupport, from: (u8, u8, u8), to: (u8, u8, u8)) -> [Color; N] {
+    let lerp = |a: u8, b: u8, t: f32| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+    std::array::from_fn(|i| {
+        let t = i as f32 / (N - 1).max(1) as f32;
+        support.color((lerp(from.0, to.0, t), lerp(from.1, to.1, t), lerp(from.2, to.2, t)))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ansi256_exact_cube_colors() {
+        assert_eq!(to_ansi256((0, 0, 0)), 16);
+        assert_eq!(to_ansi256((255, 255, 255)), 231);
+        assert_eq!(to_ansi256((255, 0, 0)), 196);
+        assert_eq!(to_ansi256((0, 255, 0)), 46);
+        assert_eq!(to_ansi256((95, 135, 175)), 16 + 36 + 12 + 3);
+    }
+
+    #[test]
+    fn ansi256_prefers_gray_ramp_for_grays() {
+        assert_eq!(to_ansi256((128, 128, 128)), 244);
+        assert_eq!(to_ansi256((20, 20, 28)), 233);
+    }
+
+    #[test]
+    fn ansi16_nearest() {
+        assert_eq!(to_ansi16((250, 10, 10)), Color::LightRed);
+        assert_eq!(to_ansi16((0, 190, 0)), Color::Green);
+        assert_eq!(to_ansi16((120, 120, 120)), Color::DarkGray);
+        assert_eq!(to_ansi16((5, 5, 5)), Color::Black);
+    }
+
+    #[test]
+    fn truecolor_is_passed_through() {
+        assert_eq!(ColorSupport::TrueColor.color((1, 2, 3)), Color::Rgb(1, 2, 3));
+        assert_eq!(ColorSupport::Ansi256.color((255, 0, 0)), Color::Indexed(196));
+    }
+}
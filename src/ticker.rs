@@ -0,0 +1,185 @@
+//! Расписание тиков игры по абсолютным срокам.
+//!
+//! Следующий тик назначается от срока предыдущего, а не от момента, когда цикл до него
+//! добрался, поэтому время на отрисовку не копится и скорость не зависит от размера
+//! терминала. После долгой задержки (например, пока меняется размер окна) догоняется
+//! не больше одного пропущенного тика.
+//!
+//! В пошаговом режиме для отладки часы не идут вовсе: тик делается по одному на
+//! каждый запрос [`Ticker::advance`].
+
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+/// По скольким последним тикам считается измеренная скорость
+const MEASURED_TICKS: usize = 16;
+
+pub struct Ticker {
+    next: Instant,
+    /// Моменты последних тиков для [`Ticker::measured_rate`]
+    recent: VecDeque<Instant>,
+    /// Пошаговый режим
+    manual: bool,
+    /// Сколько тиков запрошено в пошаговом режиме и ещё не сделано
+    requested: u32,
+}
+
+impl Ticker {
+    /// Первый тик через `interval` после `now`
+    pub fn new(now: Instant, interval: Duration) -> Self {
+        Self { next: now + interval, recent: VecDeque::with_capacity(MEASURED_TICKS), manual: false, requested: 0 }
+    }
+
+    /// Включает или выключает пошаговый режим. После выключения отсчёт начинается
+    /// заново от `now`, чтобы игра не догоняла простоявшее время.
+    pub fn set_manual(&mut self, manual: bool, now: Instant, interval: Duration) {
+        self.manual = manual;
+        self.requested = 0;
+        self.restart(now, interval);
+    }
+
+    pub fn is_manual(&self) -> bool {
+        self.manual
+    }
+
+    /// Запрашивает один тик в пошаговом режиме; без него ничего не делает
+    pub fn advance(&mut self) {
+        if self.manual {
+            self.requested += 1;
+        }
+    }
+
+    /// Начинает отсчёт заново, например после паузы или обратного отсчёта
+    pub fn restart(&mut self, now: Instant, interval: Duration) {
+        self.next = now + interval;
+        self.recent.clear();
+    }
+
+    /// Откладывает уже назначенный тик на `extra`, например для замедления
+    pub fn stretch(&mut self, extra: Duration) {
+        self.next += extra;
+    }
+
+    /// Сколько осталось до следующего тика. В пошаговом режиме тик либо уже запрошен,
+    /// либо его не будет, пока не запросят.
+    pub fn until_next(&self, now: Instant) -> Duration {
+        match (self.manual, self.requested) {
+            (true, 0) => Duration::MAX,
+            (true, _) => Duration::ZERO,
+            (false, _) => self.next.saturating_duration_since(now),
+        }
+    }
+
+    /// Насколько тик опаздывает; ноль, если срок ещё не наступил или часы стоят
+    pub fn late(&self, now: Instant) -> Duration {
+        if self.manual {
+            return Duration::ZERO;
+        }
+        now.saturating_duration_since(self.next)
+    }
+
+    /// Пора ли делать тик. Если пора, назначает следующий срок через `interval` от этого.
+    /// В пошаговом режиме пора, только если тик запрошен.
+    pub fn due(&mut self, now: Instant, interval: Duration) -> bool {
+        if self.manual {
+            let due = self.requested > 0;
+            self.requested = self.requested.saturating_sub(1);
+            return due;
+        }
+        if now < self.next {
+            return false;
+        }
+        self.next += interval;
+        // Отстали больше чем на тик: один догоняющий тик сразу, дальше по расписанию от него
+        if now >= self.next + interval {
+            self.next = now;
+        }
+        if self.recent.len() == MEASURED_TICKS {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(now);
+        true
+    }
+
+    /// Измеренные тики в секунду по последним тикам; `None`, пока их меньше двух
+    pub fn measured_rate(&self) -> Option<f64> {
+        let (first, last) = (self.recent.front()?, self.recent.back()?);
+        let span = last.duration_since(*first).as_secs_f64();
+        (self.recent.len() > 1 && span > 0.0).then(|| (self.recent.len() - 1) as f64 / span)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TICK: Duration = Duration::from_millis(120);
+
+    #[test]
+    fn render_cost_does_not_drift() {
+        let start = Instant::now();
+        let mut ticker = Ticker::new(start, TICK);
+        // Цикл просыпается на 7 мс позже срока: тики всё равно идут ровно через 120 мс
+        let mut ticks = Vec::new();
+        let mut now = start;
+        while ticks.len() < 10 {
+            now += ticker.until_next(now) + Duration::from_millis(7);
+            if ticker.due(now, TICK) {
+                ticks.push(now);
+            }
+        }
+        for (i, t) in ticks.iter().enumerate() {
+            assert_eq!(*t - start, TICK * (i as u32 + 1) + Duration::from_millis(7));
+        }
+        let rate = ticker.measured_rate().unwrap();
+        assert!((rate - 1.0 / TICK.as_secs_f64()).abs() < 1e-6, "{rate}");
+    }
+
+    #[test]
+    fn long_stall_catches_up_once() {
+        let start = Instant::now();
+        let mut ticker = Ticker::new(start, TICK);
+        let now = start + Duration::from_secs(2);
+        assert!(ticker.due(now, TICK));
+        // Один догоняющий тик сразу, потом обычный интервал
+        assert!(ticker.due(now, TICK));
+        assert!(!ticker.due(now, TICK));
+        assert_eq!(ticker.until_next(now), TICK);
+    }
+
+    #[test]
+    fn not_due_before_deadline() {
+        let start = Instant::now();
+        let mut ticker = Ticker::new(start, TICK);
+        assert!(!ticker.due(start + TICK - Duration::from_millis(1), TICK));
+        assert!(ticker.due(start + TICK, TICK));
+        assert_eq!(ticker.measured_rate(), None);
+        ticker.restart(start + TICK * 5, TICK);
+        assert_eq!(ticker.until_next(start + TICK * 5), TICK);
+        // Отложенный тик ждёт вдвое дольше
+        ticker.stretch(TICK);
+        assert_eq!(ticker.until_next(start + TICK * 5), TICK * 2);
+    }
+
+    #[test]
+    fn manual_ticks_only_on_request() {
+        let start = Instant::now();
+        let mut ticker = Ticker::new(start, TICK);
+        ticker.set_manual(true, start, TICK);
+        let later = start + Duration::from_secs(5);
+        assert!(!ticker.due(later, TICK));
+        assert_eq!((ticker.until_next(later), ticker.late(later)), (Duration::MAX, Duration::ZERO));
+        ticker.advance();
+        ticker.advance();
+        assert_eq!(ticker.until_next(later), Duration::ZERO);
+        assert!(ticker.due(later, TICK) && ticker.due(later, TICK));
+        assert!(!ticker.due(later, TICK));
+        // После выключения часы идут от момента выключения, без догоняющих тиков
+        ticker.set_manual(false, later, TICK);
+        ticker.advance();
+        assert!(!ticker.is_manual() && !ticker.due(later, TICK));
+        assert_eq!(ticker.until_next(later), TICK);
+    }
+}
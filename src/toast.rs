@@ -0,0 +1,77 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::Line,
+    widgets::{Clear, Widget},
+};
+
+use crate::theme::Theme;
+
+/// Короткие всплывающие сообщения в углу поля («Рекорд!», «Длина 20» и т.п.).
+///
+/// Каждое живёт `LIFETIME`, под конец тускнеет. Одновременно видно не больше
+/// `MAX_VISIBLE`, более старые вытесняются.
+#[derive(Default)]
+pub struct Toasts {
+    items: VecDeque<(String, Instant)>,
+}
+
+impl Toasts {
+    const LIFETIME: Duration = Duration::from_millis(2000);
+    const FADE_AFTER: Duration = Duration::from_millis(1500);
+    const MAX_VISIBLE: usize = 3;
+
+    pub fn push(&mut self, text: impl Into<String>) {
+        self.items.push_back((text.into(), Instant::now()));
+        while self.items.len() > Self::MAX_VISIBLE {
+            self.items.pop_front();
+        }
+    }
+
+    pub fn view<'a>(&'a self, theme: &'a Theme) -> ToastView<'a> {
+        ToastView { toasts: self, theme }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Убирает истёкшие сообщения. Вызывается раз за кадр.
+    pub fn expire(&mut self) {
+        self.items.retain(|(_, at)| at.elapsed() < Self::LIFETIME);
+    }
+}
+
+/// Рисуется последним слоем: сообщения в правом верхнем углу, новые снизу.
+pub struct ToastView<'a> {
+    toasts: &'a Toasts,
+    theme: &'a Theme,
+}
+
+impl Widget for ToastView<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let area = area.intersection(buf.area);
+        for (row, (text, at)) in self.toasts.items.iter().enumerate() {
+            let row = row as u16;
+            if row >= area.height {
+                break;
+            }
+            let style = if at.elapsed() >= Toasts::FADE_AFTER {
+                Style::default().fg(self.theme.muted).add_modifier(Modifier::DIM)
+            } else {
+                Style::default().fg(self.theme.popup_bg).bg(self.theme.accent)
+            };
+            let line = Line::styled(format!(" {text} "), style);
+            let width = (line.width() as u16).min(area.width);
+            let rect = Rect { x: area.right() - width, y: area.y + row, width, height: 1 };
+            Clear.render(rect, buf);
+            line.render(rect, buf);
+        }
+    }
+}
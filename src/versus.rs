@@ -0,0 +1,781 @@
+//! Игра вдвоём по сети: `--host адрес` и `--join адрес`.
+//!
+//! Хост ведёт партию целиком, а клиент только шлёт свои повороты и рисует присланное
+//! состояние. Сообщения — текст в кадрах с длиной: четыре байта длины (big-endian),
+//! затем UTF-8.
+//!
+//! ```text
+//! клиент → хост   hello 1 0.1.0 play       версия протокола и игры, play или watch для зрителя
+//! хост → клиент   welcome 1                или reject <причина>
+//! клиент → хост   dir 7 U                  поворот с порядковым номером
+//! клиент → хост   restart                  ещё партию после конца
+//! хост → клиент   state 120                состояние после каждого шага, по полю на строку:
+//!                 board 40 20
+//!                 food 3 4
+//!                 score 2 5
+//!                 over -                   - пока идёт, won 0/won 1 или draw
+//!                 handicap0 1 100 0        фора хоста: длина в начале, скорость в процентах, щит
+//!                 handicap1 4 80 1         фора клиента
+//!                 shields 0 1              у кого щит ещё цел
+//!                 snake0 5 5 4 5           змейка хоста, от головы к хвосту
+//!                 snake1 30 10             змейка клиента
+//! ```
+//!
+//! Повороты клиента нумеруются, и хост применяет только номера больше уже применённого,
+//! поэтому порядок ввода не нарушается даже после переподключения.
+//!
+//! Фору выбирает хост перед партией (см. [`VersusSetup`]): змейка может начать длиннее,
+//! ходить реже соперника или пережить одно столкновение щитом.
+
+use std::{
+    collections::VecDeque,
+    fmt,
+    io::{self, Read, Write},
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+    time::Duration,
+};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::Style,
+    widgets::{Block, Widget},
+};
+
+use crate::{
+    game::{DirectionSnake, Point},
+    theme::Theme,
+};
+
+/// Версия протокола: хост и клиент разных версий не играют
+pub const PROTOCOL_VERSION: u32 = 2;
+/// Самый длинный кадр, который примем: защита от мусора в сокете
+const MAX_FRAME: u32 = 1 << 20;
+/// Сколько ждать ответа на приветствие
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Индекс змейки хоста; у клиента — `CLIENT`
+pub const HOST: usize = 0;
+pub const CLIENT: usize = 1;
+
+/// Чем кончилась партия
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Outcome {
+    /// Победил игрок с этим индексом
+    Won(usize),
+    /// Обе змейки разбились на одном шаге
+    Draw,
+}
+
+/// Фора игрока
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Handicap {
+    /// Длина змейки в начале партии
+    pub length: usize,
+    /// Сколько шагов из ста делает змейка: на остальных она стоит
+    pub speed: u8,
+    /// Змейка переживёт одно столкновение: вместо смерти простоит шаг
+    pub shield: bool,
+}
+
+impl Default for Handicap {
+    fn default() -> Self {
+        Self { length: 1, speed: 100, shield: false }
+    }
+}
+
+impl Handicap {
+    /// Самая длинная змейка в начале, как у `--length`
+    pub const MAX_LENGTH: usize = 10;
+    /// Медленнее змейка не ходит
+    pub const MIN_SPEED: u8 = 50;
+    /// Шаг настройки скорости, в процентах
+    pub const SPEED_STEP: u8 = 10;
+
+    /// Фора словами для итогов партии
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if self.length != 1 {
+            parts.push(format!("длина {}", self.length));
+        }
+        if self.speed != 100 {
+            parts.push(format!("скорость {}%", self.speed));
+        }
+        if self.shield {
+            parts.push("щит".to_string());
+        }
+        if parts.is_empty() { "без форы".to_string() } else { parts.join(", ") }
+    }
+}
+
+/// Партия на двоих на общем поле с одной едой
+#[derive(Debug)]
+pub struct Versus {
+    pub width: u16,
+    pub height: u16,
+    pub snakes: [VecDeque<Point>; 2],
+    pub dirs: [DirectionSnake; 2],
+    pub food: Point,
+    pub scores: [usize; 2],
+    pub tick: u64,
+    pub outcome: Option<Outcome>,
+    pub handicaps: [Handicap; 2],
+    /// У кого щит ещё не сработал
+    pub shields: [bool; 2],
+    /// Сколько шагов сделала каждая змейка: у каждой свой счёт, см. [`Handicap::speed`]
+    moves: [u64; 2],
+    rng: StdRng,
+}
+
+impl Versus {
+    /// Змейки стартуют на левой и правой четверти поля лицом друг к другу
+    pub fn new(width: u16, height: u16, seed: u64) -> Self {
+        Self::with_handicaps(width, height, seed, [Handicap::default(); 2])
+    }
+
+    /// Партия с форой: хвосты змеек тянутся назад к краям поля, но не дальше них
+    pub fn with_handicaps(width: u16, height: u16, seed: u64, handicaps: [Handicap; 2]) -> Self {
+        let y = height / 2;
+        let room = usize::from(width / 4) + 1;
+        let body = |head: u16, back: i32, length: usize| -> VecDeque<Point> {
+            (0..length.clamp(1, room) as i32).map(|k| Point { x: (i32::from(head) + back * k) as u16, y }).collect()
+        };
+        let mut game = Self {
+            width,
+            height,
+            snakes: [body(width / 4, -1, handicaps[HOST].length), body(width - 1 - width / 4, 1, handicaps[CLIENT].length)],
+            dirs: [DirectionSnake::Right, DirectionSnake::Left],
+            food: Point { x: 0, y: 0 },
+            scores: [0, 0],
+            tick: 0,
+            outcome: None,
+            handicaps,
+            shields: handicaps.map(|h| h.shield),
+            moves: [0, 0],
+            rng: StdRng::seed_from_u64(seed),
+        };
+        game.spawn_food();
+        game
+    }
+
+    fn occupied(&self, p: &Point) -> bool {
+        self.snakes.iter().any(|s| s.contains(p))
+    }
+
+    fn spawn_food(&mut self) {
+        let taken: usize = self.snakes.iter().map(VecDeque::len).sum();
+        let free = (self.width as usize * self.height as usize).saturating_sub(taken);
+        if free == 0 {
+            return;
+        }
+        loop {
+            let p = Point { x: self.rng.random_range(0..self.width), y: self.rng.random_range(0..self.height) };
+            if !self.occupied(&p) {
+                self.food = p;
+                break;
+            }
+        }
+    }
+
+    /// Поворот игрока; разворот на 180 у змейки длиннее клетки отклоняется
+    pub fn change_dir(&mut self, player: usize, dir: DirectionSnake) {
+        if self.snakes[player].len() == 1 || dir != self.dirs[player].opposite() {
+            self.dirs[player] = dir;
+        }
+    }
+
+    /// Игроки шагают одновременно, но каждый по своему счёту шагов: змейка со скоростью
+    /// меньше 100 процентов пропускает тики, чтобы за `tick` тиков сделать
+    /// `tick * speed / 100` шагов. Разбивается тот, кто въехал в стену или в любую
+    /// змейку; столкнувшиеся лбами разбиваются оба. Змейка со щитом вместо этого
+    /// стоит на месте, а щит пропадает.
+    pub fn step(&mut self) {
+        if self.outcome.is_some() {
+            return;
+        }
+        self.tick += 1;
+        let due: [bool; 2] = std::array::from_fn(|i| self.moves[i] < self.tick * u64::from(self.handicaps[i].speed) / 100);
+        let mut moving = due;
+        let (heads, eats, dead) = loop {
+            let heads: [Option<Point>; 2] =
+                std::array::from_fn(|i| neighbour(self.snakes[i][0], self.dirs[i], self.width, self.height));
+            let eats: [bool; 2] = std::array::from_fn(|i| moving[i] && heads[i] == Some(self.food));
+            // Хвост уходит раньше, чем голова занимает клетку, если змейка не ест
+            let leaves: [bool; 2] = std::array::from_fn(|i| moving[i] && heads[i].is_some() && !eats[i]);
+            let blocked = |p: Point| {
+                self.snakes.iter().zip(leaves).any(|(snake, leaves)| snake.iter().take(snake.len() - usize::from(leaves)).any(|&q| q == p))
+            };
+            let dead: [bool; 2] = std::array::from_fn(|i| {
+                moving[i]
+                    && match heads[i] {
+                        None => true,
+                        Some(head) => blocked(head) || (moving[1 - i] && heads[1 - i] == Some(head)),
+                    }
+            });
+            let saved: Vec<usize> = (0..2).filter(|&i| dead[i] && self.shields[i]).collect();
+            if saved.is_empty() {
+                break (heads, eats, dead);
+            }
+            // Спасённая щитом змейка стоит, и всё считается заново: в неё теперь можно врезаться
+            for i in saved {
+                self.shields[i] = false;
+                moving[i] = false;
+            }
+        };
+        for (i, snake) in self.snakes.iter_mut().enumerate() {
+            if due[i] {
+                self.moves[i] += 1;
+            }
+            if let (true, Some(head)) = (moving[i], heads[i]) {
+                if !eats[i] {
+                    snake.pop_back();
+                }
+                snake.push_front(head);
+            }
+        }
+        self.outcome = match dead {
+            [true, true] => Some(Outcome::Draw),
+            [true, false] => Some(Outcome::Won(1)),
+            [false, true] => Some(Outcome::Won(0)),
+            [false, false] => None,
+        };
+        if self.outcome.is_none() && eats.contains(&true) {
+            for (score, eats) in self.scores.iter_mut().zip(eats) {
+                *score += usize::from(eats);
+            }
+            self.spawn_food();
+        }
+    }
+
+    /// Состояние для клиента
+    pub fn snapshot(&self) -> String {
+        let over = match self.outcome {
+            None => "-".to_string(),
+            Some(Outcome::Won(i)) => format!("won {i}"),
+            Some(Outcome::Draw) => "draw".to_string(),
+        };
+        let mut out = format!(
+            "state {}\nboard {} {}\nfood {} {}\nscore {} {}\nover {over}\n",
+            self.tick, self.width, self.height, self.food.x, self.food.y, self.scores[0], self.scores[1]
+        );
+        for (i, h) in self.handicaps.iter().enumerate() {
+            out.push_str(&format!("handicap{i} {} {} {}\n", h.length, h.speed, u8::from(h.shield)));
+        }
+        out.push_str(&format!("shields {} {}\n", u8::from(self.shields[0]), u8::from(self.shields[1])));
+        for (i, snake) in self.snakes.iter().enumerate() {
+            out.push_str(&format!("snake{i}"));
+            for p in snake {
+                out.push_str(&format!(" {} {}", p.x, p.y));
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Состояние из снимка хоста. Еду клиент не выбирает, поэтому генератор ему не нужен.
+    pub fn from_snapshot(text: &str) -> Result<Self, NetError> {
+        let bad = || NetError::BadMessage(text.lines().next().unwrap_or_default().to_string());
+        let mut game = Self::new(1, 1, 0);
+        let mut seen = 0;
+        for line in text.lines() {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let nums: Vec<u64> = words.iter().skip(1).filter_map(|w| w.parse().ok()).collect();
+            let point = |at: usize| -> Option<Point> {
+                Some(Point { x: u16::try_from(*nums.get(at)?).ok()?, y: u16::try_from(*nums.get(at + 1)?).ok()? })
+            };
+            match words.as_slice() {
+                ["state", _] => game.tick = *nums.first().ok_or_else(bad)?,
+                ["board", _, _] => {
+                    let size = point(0).ok_or_else(bad)?;
+                    (game.width, game.height) = (size.x, size.y);
+                }
+                ["food", _, _] => game.food = point(0).ok_or_else(bad)?,
+                ["score", _, _] if nums.len() == 2 => game.scores = [nums[0] as usize, nums[1] as usize],
+                ["over", "-"] => game.outcome = None,
+                ["over", "draw"] => game.outcome = Some(Outcome::Draw),
+                ["over", "won", i @ ("0" | "1")] => game.outcome = Some(Outcome::Won(i.parse().map_err(|_| bad())?)),
+                [name @ ("handicap0" | "handicap1"), _, _, _] if nums.len() == 3 && nums[2] <= 1 => {
+                    let speed = u8::try_from(nums[1]).map_err(|_| bad())?;
+                    game.handicaps[usize::from(*name == "handicap1")] =
+                        Handicap { length: nums[0] as usize, speed, shield: nums[2] == 1 };
+                }
+                ["shields", _, _] if nums.len() == 2 && nums.iter().all(|&n| n <= 1) => {
+                    game.shields = [nums[0] == 1, nums[1] == 1];
+                }
+                [name @ ("snake0" | "snake1"), rest @ ..] if !rest.is_empty() && rest.len().is_multiple_of(2) => {
+                    let snake: Option<VecDeque<Point>> = (0..rest.len()).step_by(2).map(point).collect();
+                    let snake = snake.filter(|s| s.len() == rest.len() / 2).ok_or_else(bad)?;
+                    game.snakes[usize::from(*name == "snake1")] = snake;
+                }
+                _ => return Err(bad()),
+            }
+            seen += 1;
+        }
+        if seen < 10 || !game.snakes.iter().flatten().all(|p| p.x < game.width && p.y < game.height) {
+            return Err(bad());
+        }
+        Ok(game)
+    }
+}
+
+/// Экран форы перед партией: хост выбирает по три настройки на игрока
+#[derive(Clone, Default, Debug)]
+pub struct VersusSetup {
+    pub handicaps: [Handicap; 2],
+    /// Выбранная строка: по три на игрока, сначала хост
+    pub row: usize,
+}
+
+impl VersusSetup {
+    pub const ROWS: usize = 6;
+
+    pub fn up(&mut self) {
+        self.row = self.row.saturating_sub(1);
+    }
+
+    pub fn down(&mut self) {
+        self.row = (self.row + 1).min(Self::ROWS - 1);
+    }
+
+    /// Меняет выбранную настройку на шаг вверх (`more`) или вниз
+    pub fn change(&mut self, more: bool) {
+        let h = &mut self.handicaps[self.row / 3];
+        match self.row % 3 {
+            0 if more => h.length = (h.length + 1).min(Handicap::MAX_LENGTH),
+            0 => h.length = h.length.saturating_sub(1).max(1),
+            1 if more => h.speed = (h.speed + Handicap::SPEED_STEP).min(100),
+            1 => h.speed = h.speed.saturating_sub(Handicap::SPEED_STEP).max(Handicap::MIN_SPEED),
+            _ => h.shield = !h.shield,
+        }
+    }
+
+    /// Подпись и значение строки `row`
+    pub fn row_text(&self, row: usize) -> (&'static str, String) {
+        let h = &self.handicaps[row / 3];
+        match row % 3 {
+            0 => ("длина", h.length.to_string()),
+            1 => ("скорость", format!("{}%", h.speed)),
+            _ => ("щит", if h.shield { "есть" } else { "нет" }.to_string()),
+        }
+    }
+}
+
+/// Соседняя клетка или `None` за краем поля
+fn neighbour(Point { x, y }: Point, dir: DirectionSnake, width: u16, height: u16) -> Option<Point> {
+    match dir {
+        DirectionSnake::Up => y.checked_sub(1).map(|y| Point { x, y }),
+        DirectionSnake::Down => (y + 1 < height).then(|| Point { x, y: y + 1 }),
+        DirectionSnake::Left => x.checked_sub(1).map(|x| Point { x, y }),
+        DirectionSnake::Right => (x + 1 < width).then(|| Point { x: x + 1, y }),
+    }
+}
+
+/// Сбой сетевой игры
+#[derive(Debug, PartialEq, Eq)]
+pub enum NetError {
+    Io(String),
+    /// Другая сторона отказалась играть, например из-за версии
+    Rejected(String),
+    /// Непонятное сообщение
+    BadMessage(String),
+}
+
+impl fmt::Display for NetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NetError::Io(e) => write!(f, "{e}"),
+            NetError::Rejected(reason) => write!(f, "соединение отклонено: {reason}"),
+            NetError::BadMessage(line) => write!(f, "непонятное сообщение {line:?}"),
+        }
+    }
+}
+
+impl std::error::Error for NetError {}
+
+impl From<io::Error> for NetError {
+    fn from(e: io::Error) -> Self {
+        NetError::Io(e.to_string())
+    }
+}
+
+/// Пишет кадр: длина и текст
+pub fn write_frame(out: &mut impl Write, text: &str) -> io::Result<()> {
+    out.write_all(&(text.len() as u32).to_be_bytes())?;
+    out.write_all(text.as_bytes())?;
+    out.flush()
+}
+
+/// Читает один кадр
+pub fn read_frame(input: &mut impl Read) -> io::Result<String> {
+    let mut len = [0; 4];
+    input.read_exact(&mut len)?;
+    let len = u32::from_be_bytes(len);
+    if len > MAX_FRAME {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "слишком длинное сообщение"));
+    }
+    let mut text = vec![0; len as usize];
+    input.read_exact(&mut text)?;
+    String::from_utf8(text).map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "сообщение не в UTF-8"))
+}
+
+/// Соединение с другим игроком: запись напрямую, чтение в отдельном потоке,
+/// чтобы главный цикл не ждал сеть
+pub struct Peer {
+    stream: TcpStream,
+    messages: Receiver<String>,
+}
+
+impl Peer {
+    fn new(stream: TcpStream) -> io::Result<Self> {
+        stream.set_read_timeout(None)?;
+        stream.set_nodelay(true)?;
+        let mut reader = stream.try_clone()?;
+        let (tx, messages) = mpsc::channel();
+        thread::spawn(move || {
+            while let Ok(text) = read_frame(&mut reader) {
+                if tx.send(text).is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(Self { stream, messages })
+    }
+
+    pub fn send(&mut self, text: &str) -> io::Result<()> {
+        write_frame(&mut self.stream, text)
+    }
+
+    /// Следующее пришедшее сообщение. `Err` — соединение закрыто.
+    pub fn poll(&self) -> io::Result<Option<String>> {
+        match self.messages.try_recv() {
+            Ok(text) => Ok(Some(text)),
+            Err(TryRecvError::Empty) => Ok(None),
+            Err(TryRecvError::Disconnected) => Err(io::ErrorKind::ConnectionAborted.into()),
+        }
+    }
+}
+
+impl Drop for Peer {
+    fn drop(&mut self) {
+        let _ = self.stream.shutdown(std::net::Shutdown::Both);
+    }
+}
+
+/// Открывает сокет хоста. Адрес без узла, например `:7777`, слушает на всех адресах.
+pub fn listen(addr: &str) -> io::Result<TcpListener> {
+    let addr = if addr.starts_with(':') { format!("0.0.0.0{addr}") } else { addr.to_string() };
+    let listener = TcpListener::bind(addr)?;
+    // Соперника ждём, не останавливая главный цикл
+    listener.set_nonblocking(true)?;
+    Ok(listener)
+}
+
+/// Кем подключается другая сторона
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Seat {
+    /// Соперник в игре вдвоём
+    Player,
+    /// Зритель трансляции
+    Spectator,
+}
+
+impl Seat {
+    fn code(self) -> &'static str {
+        match self {
+            Seat::Player => "play",
+            Seat::Spectator => "watch",
+        }
+    }
+
+    fn describe(self) -> &'static str {
+        match self {
+            Seat::Player => "здесь играют вдвоём (--join)",
+            Seat::Spectator => "здесь идёт трансляция (--spectate)",
+        }
+    }
+}
+
+/// Приветствие со стороны хоста: ждёт `hello` и отвечает `welcome` или `reject`
+pub fn handshake(stream: &mut TcpStream, seat: Seat) -> Result<(), NetError> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+    let greeting = read_frame(stream)?;
+    let (version, wanted) = match greeting.split_whitespace().collect::<Vec<_>>().as_slice() {
+        ["hello", version, _, wanted] => (version.parse::<u32>().ok(), *wanted),
+        _ => return Err(NetError::BadMessage(greeting)),
+    };
+    let reason = if version != Some(PROTOCOL_VERSION) {
+        format!("у хоста протокол версии {PROTOCOL_VERSION}")
+    } else if wanted != seat.code() {
+        seat.describe().to_string()
+    } else {
+        return Ok(write_frame(stream, &format!("welcome {PROTOCOL_VERSION}"))?);
+    };
+    let _ = write_frame(stream, &format!("reject {reason}"));
+    Err(NetError::Rejected(reason))
+}
+
+/// Принимает соперника без ожидания: `None`, если никто не подключался
+pub fn accept(listener: &TcpListener) -> Result<Option<Peer>, NetError> {
+    let mut stream = match listener.accept() {
+        Ok((stream, _)) => stream,
+        Err(e) if e.kind() == io::ErrorKind::WouldBlock => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    handshake(&mut stream, Seat::Player)?;
+    Ok(Some(Peer::new(stream)?))
+}
+
+/// Подключается к хосту и здоровается
+pub fn connect(addr: &str, seat: Seat) -> Result<Peer, NetError> {
+    let addr = addr
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| NetError::Io(format!("адрес {addr} не найден")))?;
+    let mut stream = TcpStream::connect_timeout(&addr, HANDSHAKE_TIMEOUT)?;
+    stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+    write_frame(&mut stream, &format!("hello {PROTOCOL_VERSION} {} {}", env!("CARGO_PKG_VERSION"), seat.code()))?;
+    let reply = read_frame(&mut stream)?;
+    match reply.split_once(' ') {
+        Some(("welcome", _)) => Ok(Peer::new(stream)?),
+        Some(("reject", reason)) => Err(NetError::Rejected(reason.to_string())),
+        _ => Err(NetError::BadMessage(reply)),
+    }
+}
+
+/// Поворот клиента с порядковым номером
+pub fn dir_message(seq: u64, dir: DirectionSnake) -> String {
+    format!("dir {seq} {}", dir.code())
+}
+
+/// Сообщение клиента хосту
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ClientMessage {
+    Dir(u64, DirectionSnake),
+    Restart,
+}
+
+impl ClientMessage {
+    pub fn parse(text: &str) -> Result<Self, NetError> {
+        match text.split_whitespace().collect::<Vec<_>>().as_slice() {
+            ["dir", seq, dir] => {
+                let seq = seq.parse().ok();
+                let dir = DirectionSnake::from_code(dir);
+                seq.zip(dir).map(|(seq, dir)| ClientMessage::Dir(seq, dir)).ok_or_else(|| NetError::BadMessage(text.to_string()))
+            }
+            ["restart"] => Ok(ClientMessage::Restart),
+            _ => Err(NetError::BadMessage(text.to_string())),
+        }
+    }
+}
+
+/// Поле на двоих: своя змейка цветом темы, соперник — цветом `rival`
+pub struct VersusWidget<'a> {
+    pub theme: &'a Theme,
+    pub game: &'a Versus,
+    /// Чья змейка своя
+    pub me: usize,
+    pub block: Option<Block<'a>>,
+}
+
+impl Widget for VersusWidget<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let area = area.intersection(buf.area);
+        let inner = match self.block {
+            Some(block) => {
+                let inner = block.inner(area);
+                block.render(area, buf);
+                inner
+            }
+            None => area,
+        };
+        let game = self.game;
+        let (width, height) = (inner.width.min(game.width), inner.height.min(game.height));
+        let mut put = |p: Point, symbol: &str, style: Style| {
+            if p.x < width && p.y < height {
+                buf[(inner.x + p.x, inner.y + p.y)].set_symbol(symbol).set_style(style);
+            }
+        };
+        put(game.food, "*", Style::default().fg(self.theme.food));
+        for (i, snake) in game.snakes.iter().enumerate() {
+            let color = if i == self.me { self.theme.snake } else { self.theme.rival };
+            for (j, p) in snake.iter().enumerate().rev() {
+                put(*p, if j == 0 { "O" } else { "o" }, Style::default().fg(color));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snake(points: &[(u16, u16)]) -> VecDeque<Point> {
+        points.iter().map(|&(x, y)| Point { x, y }).collect()
+    }
+
+    #[test]
+    fn head_on_is_a_draw() {
+        let mut game = Versus::new(11, 5, 1);
+        game.snakes = [snake(&[(4, 2)]), snake(&[(6, 2)])];
+        game.food = Point { x: 0, y: 0 };
+        game.step();
+        assert_eq!(game.outcome, Some(Outcome::Draw));
+    }
+
+    #[test]
+    fn crashing_into_the_other_snake_loses() {
+        let mut game = Versus::new(10, 6, 1);
+        game.snakes = [snake(&[(2, 2)]), snake(&[(4, 3), (3, 3), (2, 3), (1, 3)])];
+        game.dirs = [DirectionSnake::Down, DirectionSnake::Right];
+        game.food = Point { x: 9, y: 0 };
+        game.step();
+        assert_eq!(game.outcome, Some(Outcome::Won(CLIENT)));
+
+        // В уходящий хвост въезжать можно
+        let mut game = Versus::new(10, 6, 1);
+        game.snakes = [snake(&[(1, 2)]), snake(&[(3, 3), (2, 3), (1, 3)])];
+        game.dirs = [DirectionSnake::Down, DirectionSnake::Right];
+        game.food = Point { x: 9, y: 0 };
+        game.step();
+        assert_eq!(game.outcome, None);
+    }
+
+    #[test]
+    fn eating_scores_and_grows() {
+        let mut game = Versus::new(10, 6, 1);
+        game.snakes = [snake(&[(2, 2)]), snake(&[(7, 4)])];
+        game.food = Point { x: 3, y: 2 };
+        game.step();
+        assert_eq!(game.scores, [1, 0]);
+        assert_eq!(game.snakes[HOST].len(), 2);
+        assert!(!game.occupied(&game.food));
+        game.change_dir(HOST, DirectionSnake::Left);
+        assert_eq!(game.dirs[HOST], DirectionSnake::Right);
+    }
+
+    #[test]
+    fn slower_snake_skips_ticks() {
+        let slow = Handicap { speed: 50, ..Handicap::default() };
+        let mut game = Versus::with_handicaps(40, 9, 1, [Handicap::default(), slow]);
+        game.food = Point { x: 0, y: 0 };
+        let starts = [game.snakes[HOST][0], game.snakes[CLIENT][0]];
+        for _ in 0..6 {
+            game.step();
+        }
+        assert_eq!(game.snakes[HOST][0], Point { x: starts[0].x + 6, ..starts[0] });
+        assert_eq!(game.snakes[CLIENT][0], Point { x: starts[1].x - 3, ..starts[1] });
+        assert_eq!(game.outcome, None);
+    }
+
+    #[test]
+    fn longer_start_and_shield() {
+        let long = Handicap { length: 4, shield: true, ..Handicap::default() };
+        let mut game = Versus::with_handicaps(20, 5, 1, [long, Handicap::default()]);
+        assert_eq!(game.snakes[HOST], snake(&[(5, 2), (4, 2), (3, 2), (2, 2)]));
+        assert_eq!(game.snakes[CLIENT].len(), 1);
+
+        // Щит спасает от стены один раз: змейка стоит, а на следующем шаге разбивается
+        game.snakes = [snake(&[(19, 1), (18, 1)]), snake(&[(5, 3)])];
+        game.food = Point { x: 0, y: 0 };
+        game.step();
+        assert_eq!((game.outcome, game.shields), (None, [false, false]));
+        assert_eq!(game.snakes[HOST], snake(&[(19, 1), (18, 1)]));
+        game.step();
+        assert_eq!(game.outcome, Some(Outcome::Won(CLIENT)));
+        assert_eq!(Handicap { speed: 80, ..long }.describe(), "длина 4, скорость 80%, щит");
+        assert_eq!(Handicap::default().describe(), "без форы");
+    }
+
+    #[test]
+    fn setup_keeps_handicaps_in_range() {
+        let mut setup = VersusSetup::default();
+        setup.change(false);
+        assert_eq!(setup.handicaps[HOST].length, 1);
+        setup.down();
+        for _ in 0..10 {
+            setup.change(false);
+        }
+        assert_eq!(setup.row_text(1), ("скорость", "50%".to_string()));
+        for _ in 0..10 {
+            setup.down();
+        }
+        setup.change(true);
+        assert!(setup.handicaps[CLIENT].shield);
+    }
+
+    #[test]
+    fn snapshot_round_trip() {
+        let mut game = Versus::new(20, 8, 3);
+        for _ in 0..4 {
+            game.step();
+        }
+        let copy = Versus::from_snapshot(&game.snapshot()).unwrap();
+        assert_eq!((copy.width, copy.height, copy.tick), (20, 8, 4));
+        assert_eq!(copy.snakes, game.snakes);
+        assert_eq!(copy.food, game.food);
+        let handicaps = [Handicap { length: 3, speed: 70, shield: true }, Handicap::default()];
+        let copy = Versus::from_snapshot(&Versus::with_handicaps(20, 8, 3, handicaps).snapshot()).unwrap();
+        assert_eq!((copy.handicaps, copy.shields), (handicaps, [true, false]));
+        assert!(Versus::from_snapshot("state 1\nboard 2 2\n").is_err());
+        assert_eq!(
+            ClientMessage::parse(&dir_message(5, DirectionSnake::Up)).unwrap(),
+            ClientMessage::Dir(5, DirectionSnake::Up)
+        );
+        assert!(ClientMessage::parse("dir x U").is_err());
+    }
+
+    #[test]
+    fn frames_and_handshake_over_tcp() {
+        let listener = listen("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let client = thread::spawn(move || connect(&addr, Seat::Player));
+        let mut host = loop {
+            if let Some(peer) = accept(&listener).unwrap() {
+                break peer;
+            }
+        };
+        let mut client = client.join().unwrap().unwrap();
+        client.send(&dir_message(1, DirectionSnake::Up)).unwrap();
+        host.send("state 0").unwrap();
+        let wait = |peer: &Peer| loop {
+            if let Some(text) = peer.poll().unwrap() {
+                break text;
+            }
+        };
+        assert_eq!(wait(&host), "dir 1 U");
+        assert_eq!(wait(&client), "state 0");
+        drop(client);
+        while host.poll().is_ok() {}
+    }
+
+    #[test]
+    fn rejects_other_protocol_versions() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let client = thread::spawn(move || {
+            let mut stream = TcpStream::connect(addr).unwrap();
+            write_frame(&mut stream, "hello 99 9.9.9 play").unwrap();
+            read_frame(&mut stream).unwrap()
+        });
+        let result = loop {
+            match accept(&listener) {
+                Ok(None) => {}
+                other => break other,
+            }
+        };
+        assert!(matches!(result, Err(NetError::Rejected(_))));
+        assert!(client.join().unwrap().starts_with("reject "));
+
+        // Зритель в игру вдвоём не попадает
+        let client = thread::spawn(move || connect(&addr.to_string(), Seat::Spectator));
+        while matches!(accept(&listener), Ok(None)) {}
+        assert_eq!(
+            client.join().unwrap().err(),
+            Some(NetError::Rejected("здесь играют вдвоём (--join)".to_string()))
+        );
+    }
+}
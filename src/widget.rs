@@ -0,0 +1,537 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Sparkline, StatefulWidget, Widget, Wrap},
+};
+
+use std::{collections::VecDeque, time::Duration};
+
+use crate::{
+    game::{Blackout, DeathCause, Game, Point, Zone},
+    hud::format_duration,
+    theme::{Theme, FADE_SHADES},
+};
+
+/// Сколько кадров держится один оттенок пульсирующей еды
+const FRAMES_PER_PULSE_STEP: u64 = 6;
+
+/// Сколько кадров рамка горит и сколько гаснет, мигая перед затмением
+const FRAMES_PER_FLASH: u64 = 4;
+
+/// Видна ли бомба в кадре `frame`: последние шаги она мигает, и тем чаще, чем меньше
+/// ей осталось лежать
+fn bomb_visible(frame: u64, left: u64) -> bool {
+    let frames = match left {
+        0..=10 => 2,
+        11..=25 => 4,
+        26..=40 => 8,
+        _ => return true,
+    };
+    (frame / frames).is_multiple_of(2)
+}
+
+/// Длительность анимации смерти в кадрах
+pub const DEATH_FRAMES: u64 = 60;
+/// Длительность анимации переноса через портал в кадрах
+pub const WARP_FRAMES: u64 = 40;
+/// За сколько кадров угасает один сегмент
+const FADE_FRAMES: u64 = 10;
+
+/// Оттенок сегмента мёртвой змейки: индекс в `Theme::dead_fade` или `None`, если он уже угас.
+/// Сегменты начинают угасать по очереди, от хвоста (`from_tail == 0`) к голове.
+fn corpse_shade(frames: u64, from_tail: usize, len: usize) -> Option<usize> {
+    let start = from_tail as u64 * (DEATH_FRAMES - FADE_FRAMES) / len.max(1) as u64;
+    let shades = FADE_SHADES as u64;
+    let shade = frames.saturating_sub(start) * shades / FADE_FRAMES;
+    (shade < shades).then_some(shade as usize)
+}
+
+/// Виджет игрового поля: рамка (если задана), змейка и еда.
+///
+/// Рисует в любой переданный `Rect`. Если область меньше поля,
+/// всё, что не помещается, просто обрезается.
+pub struct GameWidget<'a> {
+    theme: &'a Theme,
+    block: Option<Block<'a>>,
+    dying: Option<u64>,
+    grid: bool,
+    frame: Option<u64>,
+    plain: bool,
+    ascii: bool,
+    ghost: Option<&'a VecDeque<Point>>,
+    blackout: Blackout,
+    overtime: bool,
+    wide: bool,
+    near_miss: Option<Point>,
+    warp: Option<(&'a [Point], u64)>,
+}
+
+impl<'a> GameWidget<'a> {
+    pub fn new(theme: &'a Theme) -> Self {
+        Self {
+            theme,
+            block: None,
+            dying: None,
+            grid: false,
+            frame: None,
+            plain: false,
+            ascii: false,
+            ghost: None,
+            blackout: Blackout::Clear,
+            overtime: false,
+            wide: false,
+            near_miss: None,
+            warp: None,
+        }
+    }
+
+    /// Рамка вокруг поля. Поле рисуется внутри неё.
+    pub fn block(mut self, block: Block<'a>) -> Self {
+        self.block = Some(block);
+        self
+    }
+
+    /// Фоновая сетка из точек в шахматном порядке
+    pub fn grid(mut self, grid: bool) -> Self {
+        self.grid = grid;
+        self
+    }
+
+    /// Номер кадра для анимаций (пульсация еды). Без него поле рисуется статично.
+    pub fn frame(mut self, frame: u64) -> Self {
+        self.frame = Some(frame);
+        self
+    }
+
+    /// Анимация смерти, `frames` кадров с момента гибели
+    pub fn dying(mut self, frames: u64) -> Self {
+        self.dying = Some(frames);
+        self
+    }
+
+    /// Только ASCII: сетка рисуется точками `.`
+    pub fn ascii(mut self, ascii: bool) -> Self {
+        self.ascii = ascii;
+        self
+    }
+
+    /// Терминал без цветов: анимации передаются символами, а не оттенками
+    pub fn plain(mut self, plain: bool) -> Self {
+        self.plain = plain;
+        self
+    }
+
+    /// Змейка-призрак: рисуется бледно, под едой и змейкой игрока
+    pub fn ghost(mut self, cells: &'a VecDeque<Point>) -> Self {
+        self.ghost = Some(cells);
+        self
+    }
+
+    /// Фаза затмения: перед ним рамка мигает (с [`Self::frame`]) или горит, а в темноте
+    /// видны только голова и рамка
+    pub fn blackout(mut self, blackout: Blackout) -> Self {
+        self.blackout = blackout;
+        self
+    }
+
+    /// Овертайм: рамка мигает цветом рекорда (с [`Self::frame`]) или горит им
+    pub fn overtime(mut self, overtime: bool) -> Self {
+        self.overtime = overtime;
+        self
+    }
+
+    /// Крупные клетки: каждая рисуется в две колонки (см. [`crate::game::Mode::cell_width`])
+    pub fn wide(mut self, wide: bool) -> Self {
+        self.wide = wide;
+        self
+    }
+
+    /// Клетка, мимо которой голова только что прошла вплотную: подсвечивается фоном,
+    /// а без цветов — инверсией
+    pub fn near_miss(mut self, cell: Option<Point>) -> Self {
+        self.near_miss = cell;
+        self
+    }
+
+    /// Перенос через портал, `frames` кадров назад: прежнее тело `from` (от портала
+    /// к хвосту) уходит в портал, а новое проступает от головы к хвосту
+    pub fn warp(mut self, from: &'a [Point], frames: u64) -> Self {
+        self.warp = Some((from, frames));
+        self
+    }
+}
+
+impl StatefulWidget for GameWidget<'_> {
+    type State = Game;
+
+    fn render(self, area: Rect, buf: &mut Buffer, game: &mut Game) {
+        let area = area.intersection(buf.area);
+        let theme = self.theme;
+        let flash = (self.blackout == Blackout::Warning || self.overtime)
+            && self.frame.is_none_or(|frame| (frame / FRAMES_PER_FLASH).is_multiple_of(2));
+        // Предупреждение о затмении важнее овертайма
+        let color = if self.blackout == Blackout::Warning { theme.danger } else { theme.record };
+        let block = match self.block {
+            Some(block) if flash && self.plain => Some(block.border_style(Style::default().add_modifier(Modifier::REVERSED))),
+            Some(block) if flash => Some(block.border_style(Style::default().fg(color))),
+            block => block,
+        };
+        let inner = match block {
+            Some(block) => {
+                let inner = block.inner(area);
+                block.render(area, buf);
+                inner
+            }
+            None => area,
+        };
+        if let Some(bg) = theme.board_bg
+            && !self.plain
+        {
+            buf.set_style(inner, Style::default().bg(bg));
+        }
+        // Видимая часть поля; крупная клетка — это символ, повторённый в соседней колонке
+        let cell_width = if self.wide { 2 } else { 1 };
+        let width = (inner.width / cell_width).min(game.width);
+        let height = inner.height.min(game.height);
+        let column = |x: u16| inner.x + x * cell_width;
+
+        let mut put = |x: u16, y: u16, symbol: &str, style: Style| {
+            if x < width && y < height {
+                for dx in 0..cell_width {
+                    buf[(column(x) + dx, inner.y + y)].set_symbol(symbol).set_style(style);
+                }
+            }
+        };
+
+        if self.blackout == Blackout::Dark {
+            if let Some(head) = game.snake().front() {
+                put(head.x, head.y, "O", Style::default().fg(theme.snake));
+            }
+            return;
+        }
+        // Зоны скорости — подкрашенным фоном, поверх него рисуется всё остальное
+        if !self.plain {
+            for (zone, tint) in [(Zone::Fast, theme.zone_fast), (Zone::Slow, theme.zone_slow)] {
+                for p in game.zones(zone) {
+                    put(p.x, p.y, " ", Style::default().bg(tint));
+                }
+            }
+            // Слизь тускнеет, пока сохнет
+            for &(p, left) in game.slime() {
+                let age = (Game::SLIME_TICKS - left.min(Game::SLIME_TICKS)) as usize;
+                put(p.x, p.y, " ", Style::default().bg(theme.slime[age * FADE_SHADES / Game::SLIME_TICKS as usize]));
+            }
+        }
+        if self.grid {
+            let dot = Style::default().fg(theme.muted).add_modifier(Modifier::DIM);
+            for y in 0..height {
+                for x in (y % 2..width).step_by(2) {
+                    put(x, y, if self.ascii { "." } else { "·" }, dot);
+                }
+            }
+        }
+        // Без цветов фона нет, и зону выдаёт символ поверх сетки
+        if self.plain {
+            for (zone, symbol) in [(Zone::Fast, if self.ascii { ">" } else { "»" }), (Zone::Slow, "~")] {
+                for p in game.zones(zone) {
+                    put(p.x, p.y, symbol, Style::default());
+                }
+            }
+            for &(p, _) in game.slime() {
+                put(p.x, p.y, ",", Style::default());
+            }
+        }
+        // Стены уровня — сплошными клетками, чтобы не путать их с рамкой поля
+        let wall = Style::default().fg(theme.wall);
+        for p in game.walls() {
+            put(p.x, p.y, if self.ascii { "%" } else { "█" }, wall);
+        }
+        // Без цветов призрак отличается от змейки символом
+        let ghost = Style::default().fg(theme.muted).add_modifier(Modifier::DIM);
+        for p in self.ghost.into_iter().flatten().rev() {
+            put(p.x, p.y, if self.plain { ":" } else { "o" }, ghost);
+        }
+        // Сброшенные хвосты — цветом мёртвой змейки; без цветов их выдаёт символ
+        let shed = Style::default().fg(theme.dead_fade[2]);
+        for p in game.obstacles() {
+            put(p.x, p.y, if self.plain { "#" } else { "o" }, shed);
+        }
+        // Пульсация еды: туда и обратно по оттенкам, по смене на каждые несколько кадров.
+        // Замёрзшая убегающая еда не пульсирует и подёрнута льдом.
+        let frozen = game.world_frozen();
+        let food_color = match self.frame {
+            _ if frozen && game.fleeing => theme.frozen,
+            Some(frame) => {
+                let pulse = &theme.food_pulse;
+                let period = 2 * pulse.len() as u64 - 2;
+                let phase = (frame / FRAMES_PER_PULSE_STEP % period) as usize;
+                pulse[if phase < pulse.len() { phase } else { period as usize - phase }]
+            }
+            None => theme.food,
+        };
+        put(game.food.x, game.food.y, "*", Style::default().fg(food_color));
+        // Синяя еда не пульсирует; без цветов её выдаёт символ
+        if let Some(bonus) = game.bonus {
+            put(bonus.x, bonus.y, if self.plain { "+" } else { "*" }, Style::default().fg(theme.bonus_food));
+        }
+        // Звенья гусеницы связаны фоном; без цветов — концами и перемычками между ними
+        let pellet = if self.ascii { "o" } else { "●" };
+        let links = match game.caterpillar[..] {
+            [first, second, ..] if first.x == second.x => ["^", "|", "v"],
+            _ => ["<", "=", ">"],
+        };
+        let style = Style::default().fg(theme.caterpillar).bg(theme.caterpillar_link);
+        let last = game.caterpillar.len().saturating_sub(1);
+        for (i, p) in game.caterpillar.iter().enumerate() {
+            let symbol = match i {
+                _ if !self.plain => pellet,
+                0 => links[0],
+                i if i == last => links[2],
+                _ => links[1],
+            };
+            put(p.x, p.y, symbol, style);
+        }
+        if let Some(portal) = game.portal {
+            put(portal.x, portal.y, if self.ascii { "&" } else { "◎" }, Style::default().fg(theme.portal));
+        }
+        if let Some(shield) = game.shield_pickup {
+            put(shield.x, shield.y, if self.ascii { "S" } else { "◈" }, Style::default().fg(theme.shield));
+        }
+        if let Some(doubler) = game.doubler {
+            put(doubler.x, doubler.y, "2", Style::default().fg(theme.doubler).add_modifier(Modifier::BOLD));
+        }
+        if let Some(freezer) = game.freezer {
+            put(freezer.x, freezer.y, if self.ascii { "F" } else { "❄" }, Style::default().fg(theme.frozen));
+        }
+        if let Some(bomb) = game.bomb
+            && self.frame.is_none_or(|frame| bomb_visible(frame, bomb.left))
+        {
+            put(bomb.at.x, bomb.at.y, "@", Style::default().fg(if frozen { theme.frozen } else { theme.bomb }));
+        }
+        // Босс трескается после каждого касания: символ тем реже, чем меньше ему осталось
+        if let Some(boss) = game.boss {
+            let cracks = if self.ascii { ["v", "x", "X"] } else { ["▒", "▓", "█"] };
+            let symbol = cracks[usize::from(boss.hits.clamp(1, 3)) - 1];
+            for p in boss.cells() {
+                put(p.x, p.y, symbol, Style::default().fg(theme.boss));
+            }
+        }
+        // Прежнее тело уходит в портал ближним к нему концом вперёд
+        let warp = self.warp.filter(|&(_, frames)| frames < WARP_FRAMES);
+        if let Some((from, frames)) = warp {
+            let gone = (frames * from.len() as u64 / WARP_FRAMES) as usize;
+            let style = Style::default().fg(theme.portal).add_modifier(Modifier::DIM);
+            for p in from.iter().skip(gone) {
+                put(p.x, p.y, if self.plain { ":" } else { "o" }, style);
+            }
+        }
+        // От хвоста к голове, чтобы голова всегда была сверху
+        let len = game.snake().len();
+        for (i, p) in game.snake().iter().enumerate().rev() {
+            let symbol = if i == 0 { "O" } else { "o" };
+            let Some(frames) = self.dying else {
+                // Новое тело после портала проступает от головы к хвосту
+                if warp.is_none_or(|(_, frames)| (i as u64) < (frames + 1) * len as u64 / WARP_FRAMES) {
+                    put(p.x, p.y, symbol, Style::default().fg(theme.snake));
+                } else if self.plain {
+                    put(p.x, p.y, ".", Style::default());
+                } else {
+                    put(p.x, p.y, symbol, Style::default().fg(theme.portal));
+                }
+                continue;
+            };
+            match corpse_shade(frames, len - 1 - i, len) {
+                Some(0) => put(p.x, p.y, symbol, Style::default().fg(theme.danger)),
+                Some(_) if self.plain => put(p.x, p.y, ".", Style::default()),
+                Some(shade) => put(p.x, p.y, symbol, Style::default().fg(theme.dead_fade[shade])),
+                None => {}
+            }
+        }
+        if let Some(p) = self.near_miss
+            && p.x < width
+            && p.y < height
+        {
+            for dx in 0..cell_width {
+                let cell = &mut buf[(column(p.x) + dx, inner.y + p.y)];
+                if self.plain {
+                    cell.modifier.insert(Modifier::REVERSED);
+                } else {
+                    cell.set_bg(theme.near_miss);
+                }
+            }
+        }
+    }
+}
+
+/// Затемняет всё, что уже нарисовано в области. Рисуется перед всплывающими окнами.
+pub struct Dim<'a>(pub &'a Theme);
+
+impl Widget for Dim<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let style = Style::default().fg(self.0.muted).add_modifier(Modifier::DIM);
+        buf.set_style(area.intersection(buf.area), style);
+    }
+}
+
+/// Прямоугольник `width`×`height` по центру `area`, обрезанный по её границам.
+pub fn centered(area: Rect, width: u16, height: u16) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
+}
+
+/// Всплывающее окно: рамка с фоном по центру переданной области, под ним всё стирается.
+///
+/// Размер подбирается по тексту. Если места не хватает, окно сжимается до
+/// размеров области, строки переносятся, а текст можно прокручивать.
+pub struct Popup<'a> {
+    theme: &'a Theme,
+    border: Option<Color>,
+    lines: Vec<Line<'a>>,
+    sparkline: Option<&'a [u64]>,
+    scroll: u16,
+}
+
+impl<'a> Popup<'a> {
+    /// Высота графика под текстом
+    const CHART_HEIGHT: u16 = 3;
+
+    pub fn new(theme: &'a Theme, lines: Vec<Line<'a>>) -> Self {
+        Self { theme, border: None, lines, sparkline: None, scroll: 0 }
+    }
+
+    /// График под текстом (например, счёт по ходу партии)
+    pub fn sparkline(mut self, data: &'a [u64]) -> Self {
+        self.sparkline = Some(data).filter(|d| !d.is_empty());
+        self
+    }
+
+    /// Цвет рамки вместо цвета из темы
+    pub fn border(mut self, color: Color) -> Self {
+        self.border = Some(color);
+        self
+    }
+
+    /// Прокрутка текста на `scroll` строк вниз
+    pub fn scroll(mut self, scroll: u16) -> Self {
+        self.scroll = scroll;
+        self
+    }
+
+    /// Насколько можно прокрутить текст, чтобы он весь поместился в `area`
+    pub fn max_scroll(&self, area: Rect) -> u16 {
+        let (popup, rows, chart_height) = self.layout(area);
+        rows.saturating_sub(popup.height.saturating_sub(chart_height + 2))
+    }
+
+    /// Место окна в `area`, число строк текста с переносами и высота графика
+    fn layout(&self, area: Rect) -> (Rect, u16, u16) {
+        let text_width = self.lines.iter().map(Line::width).max().unwrap_or(0) as u16;
+        let chart_width = self.sparkline.map_or(0, |d| d.len()) as u16;
+        let chart_height = if self.sparkline.is_some() { Self::CHART_HEIGHT } else { 0 };
+        let width = (text_width.max(chart_width) + 2).min(area.width);
+        // Сколько строк займёт текст с переносами при такой ширине
+        let inner_width = width.saturating_sub(2).max(1) as usize;
+        let rows: usize = self.lines.iter().map(|l| l.width().div_ceil(inner_width).max(1)).sum();
+        let rows = rows as u16;
+        (centered(area, width, rows + chart_height + 2), rows, chart_height)
+    }
+}
+
+impl Widget for Popup<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let area = area.intersection(buf.area);
+        let scroll = self.scroll.min(self.max_scroll(area));
+        let (area, _, chart_height) = self.layout(area);
+
+        Clear.render(area, buf);
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(self.border.unwrap_or(self.theme.border)))
+            .style(Style::default().bg(self.theme.popup_bg));
+        let inner = block.inner(area);
+        block.render(area, buf);
+        let [text, chart] = Layout::vertical([Constraint::Min(0), Constraint::Length(chart_height)]).areas(inner);
+        Paragraph::new(self.lines)
+            .wrap(Wrap { trim: true })
+            .scroll((scroll, 0))
+            .render(text, buf);
+        if let Some(data) = self.sparkline {
+            Sparkline::default()
+                .data(data)
+                .style(Style::default().fg(self.theme.accent))
+                .render(chart, buf);
+        }
+    }
+}
+
+/// Экран конца игры: итоги партии и график счёта.
+/// `record` — партия побила лучший результат режима.
+pub fn game_over_popup<'a>(theme: &'a Theme, game: &'a Game, elapsed: Duration, record: bool) -> Popup<'a> {
+    let (title, color) = match game.game_over {
+        Some(DeathCause::BoardFull) => ("Победа!", theme.record),
+        _ => ("Игра окончена!", theme.danger),
+    };
+    let mut lines = vec![Line::from(Span::styled(title, Style::default().fg(color)))];
+    if record {
+        lines.push(Line::from(Span::styled(
+            "Новый рекорд!",
+            Style::default().fg(theme.record).add_modifier(Modifier::BOLD),
+        )));
+    }
+    lines.extend([
+        Line::from(Span::styled(game.game_over.map_or("", DeathCause::describe), Style::default().fg(color))),
+    ]);
+    if game.overtime {
+        lines.push(Line::from(Span::styled("Дошли до овертайма", Style::default().fg(theme.record))));
+    }
+    lines.extend([
+        Line::from(Span::styled(format!("Счёт: {}", game.score), Style::default().fg(theme.accent))),
+        Line::from(Span::styled(format!("Длина: {}", game.snake().len()), Style::default().fg(theme.accent))),
+        Line::from(Span::styled(format!("Время: {}", format_duration(elapsed)), Style::default().fg(theme.accent))),
+        Line::from(Span::styled("Пробел - рестарт", Style::default().fg(theme.text))),
+        Line::from(Span::styled("H - рекорды", Style::default().fg(theme.text))),
+        Line::from(Span::styled("ESC - выход", Style::default().fg(theme.text))),
+    ]);
+    Popup::new(theme, lines).sparkline(&game.history.points)
+}
+
+/// Длительность праздничной анимации нового рекорда в кадрах
+pub const CELEBRATION_FRAMES: u64 = 180;
+
+/// Праздник нового рекорда: звёздочки падают вдоль левого и правого краёв области
+pub struct Celebration<'a> {
+    pub theme: &'a Theme,
+    pub frames: u64,
+}
+
+impl Celebration<'_> {
+    /// Цвет на текущем кадре: перебирает яркие цвета темы
+    pub fn color(&self) -> Color {
+        let colors = [self.theme.accent, self.theme.record, self.theme.snake, self.theme.food];
+        colors[(self.frames / 8) as usize % colors.len()]
+    }
+}
+
+impl Widget for Celebration<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let area = area.intersection(buf.area);
+        if area.width < 2 || area.height == 0 {
+            return;
+        }
+        let style = Style::default().fg(self.color());
+        // По две колонки у каждого края, у каждой своя скорость и сдвиг
+        let columns = [(0, 3, 0), (1, 4, 5), (area.width - 2, 4, 2), (area.width - 1, 3, 7)];
+        for (dx, speed, offset) in columns {
+            let y = (self.frames / speed + offset) % area.height as u64;
+            buf[(area.x + dx, area.y + y as u16)].set_symbol("*").set_style(style);
+        }
+    }
+}
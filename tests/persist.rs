@@ -0,0 +1,734 @@
+//! Файлы сохранений и повторов всех прежних форматов из `tests/fixtures` читаются
+//! и дают то же, что файл текущего формата.
+
+use std::time::Duration;
+
+use zmiy::{
+    game::{Bomb, Boss, DeathCause, DirectionSnake, FoodKind, Game, GameEvent, Mutation, Point, Zone},
+    persist::{self, FormatError, Save},
+    replay::{Diverged, Input, Playback, Replay, ReplayError},
+    save::{SaveError, SavedGame},
+};
+
+const SAVE_V1: &str = include_str!("fixtures/save-v1.zsv");
+const SAVE_V2: &str = include_str!("fixtures/save-v2.zsv");
+const SAVE_V3: &str = include_str!("fixtures/save-v3.zsv");
+const SAVE_V4: &str = include_str!("fixtures/save-v4.zsv");
+const SAVE_V5: &str = include_str!("fixtures/save-v5.zsv");
+const SAVE_V6: &str = include_str!("fixtures/save-v6.zsv");
+const SAVE_V7: &str = include_str!("fixtures/save-v7.zsv");
+const SAVE_V8: &str = include_str!("fixtures/save-v8.zsv");
+const SAVE_V9: &str = include_str!("fixtures/save-v9.zsv");
+const SAVE_V10: &str = include_str!("fixtures/save-v10.zsv");
+const SAVE_V11: &str = include_str!("fixtures/save-v11.zsv");
+const SAVE_V12: &str = include_str!("fixtures/save-v12.zsv");
+const SAVE_V13: &str = include_str!("fixtures/save-v13.zsv");
+const SAVE_V14: &str = include_str!("fixtures/save-v14.zsv");
+const SAVE_V15: &str = include_str!("fixtures/save-v15.zsv");
+const SAVE_V16: &str = include_str!("fixtures/save-v16.zsv");
+const SAVE_V17: &str = include_str!("fixtures/save-v17.zsv");
+const SAVE_V18: &str = include_str!("fixtures/save-v18.zsv");
+const SAVE_V19: &str = include_str!("fixtures/save-v19.zsv");
+const SAVE_V20: &str = include_str!("fixtures/save-v20.zsv");
+const REPLAY_V1: &str = include_str!("fixtures/replay-v1.zrp");
+const REPLAY_V2: &str = include_str!("fixtures/replay-v2.zrp");
+const REPLAY_V3: &str = include_str!("fixtures/replay-v3.zrp");
+const REPLAY_V4: &str = include_str!("fixtures/replay-v4.zrp");
+const REPLAY_V5: &str = include_str!("fixtures/replay-v5.zrp");
+const REPLAY_V6: &str = include_str!("fixtures/replay-v6.zrp");
+const REPLAY_V7: &str = include_str!("fixtures/replay-v7.zrp");
+const REPLAY_V8: &str = include_str!("fixtures/replay-v8.zrp");
+const REPLAY_V9: &str = include_str!("fixtures/replay-v9.zrp");
+const REPLAY_V10: &str = include_str!("fixtures/replay-v10.zrp");
+const REPLAY_V11: &str = include_str!("fixtures/replay-v11.zrp");
+const REPLAY_V12: &str = include_str!("fixtures/replay-v12.zrp");
+const REPLAY_V13: &str = include_str!("fixtures/replay-v13.zrp");
+const REPLAY_V14: &str = include_str!("fixtures/replay-v14.zrp");
+const REPLAY_V15: &str = include_str!("fixtures/replay-v15.zrp");
+const REPLAY_V16: &str = include_str!("fixtures/replay-v16.zrp");
+const REPLAY_V17: &str = include_str!("fixtures/replay-v17.zrp");
+const REPLAY_V18: &str = include_str!("fixtures/replay-v18.zrp");
+const REPLAY_V19: &str = include_str!("fixtures/replay-v19.zrp");
+const REPLAY_V20: &str = include_str!("fixtures/replay-v20.zrp");
+const REPLAY_V21: &str = include_str!("fixtures/replay-v21.zrp");
+const REPLAY_V22: &str = include_str!("fixtures/replay-v22.zrp");
+const REPLAY_V23: &str = include_str!("fixtures/replay-v23.zrp");
+
+#[test]
+fn every_save_format_loads() {
+    let v1 = SavedGame::parse(SAVE_V1).unwrap();
+    let v2 = SavedGame::parse(SAVE_V2).unwrap();
+    // В формате 1 скорости не было, остальное совпадает
+    assert_eq!(v1.speed, None);
+    assert_eq!(v2.speed, Some(Duration::from_millis(80)));
+    for saved in [&v1, &v2] {
+        assert_eq!(saved.mode, "классика");
+        assert_eq!((saved.ticks, saved.elapsed), (8, Duration::from_millis(62_000)));
+        assert_eq!((saved.game.width, saved.game.height, saved.game.seed), (20, 12, 5));
+        assert_eq!(saved.game.dir, DirectionSnake::Down);
+        assert_eq!(saved.game.food, Point { x: 6, y: 4 });
+    }
+    assert_eq!(v1.game.snake(), v2.game.snake());
+    assert!(v2.game.obstacles().is_empty());
+
+    // С формата 3 сохраняются сброшенные хвосты
+    let v3 = SavedGame::parse(SAVE_V3).unwrap();
+    assert_eq!(v3.game.obstacles(), [Point { x: 11, y: 5 }]);
+    assert_eq!((v3.game.shed_cooldown, v3.game.score, v3.game.snake().len()), (95, 1, 3));
+    assert!(v3.game.is_obstacle(Point { x: 11, y: 5 }));
+    let outside = SAVE_V3.replacen("obstacles 11 5", "obstacles 12 5", 1);
+    assert_eq!(SavedGame::parse(&outside).err(), Some(SaveError::Inconsistent));
+
+    // С формата 4 сохраняется, из скольких клеток выбирается еда
+    assert_eq!(v3.game.food_candidates, 1);
+    let v4 = SavedGame::parse(SAVE_V4).unwrap();
+    assert_eq!((v4.game.food_candidates, v4.game.score), (3, 3));
+
+    // С формата 5 сохраняются стены уровня
+    assert!(v4.game.walls().is_empty());
+    let v5 = SavedGame::parse(SAVE_V5).unwrap();
+    assert_eq!((v5.mode.as_str(), v5.game.walls().len(), v5.game.score), ("проход", 7, 10));
+    assert!(v5.game.is_wall(Point { x: 8, y: 0 }) && !v5.game.is_wall(Point { x: 8, y: 1 }));
+    let on_wall = SAVE_V5.replacen("food 7 4", "food 8 4", 1);
+    assert_eq!(SavedGame::parse(&on_wall).err(), Some(SaveError::Inconsistent));
+
+    // С формата 6 сохраняются зоны скорости
+    assert!(v5.game.zones(Zone::Fast).is_empty());
+    let v6 = SavedGame::parse(SAVE_V6).unwrap();
+    assert_eq!((v6.game.zones(Zone::Fast).len(), v6.game.zones(Zone::Slow).len()), (28, 25));
+    assert_eq!(v6.game.zone(Point { x: 8, y: 2 }), Some(Zone::Slow));
+
+    // С формата 7 сохраняются бомбы
+    assert_eq!((v6.game.bombs, v6.game.bomb), (false, None));
+    let v7 = SavedGame::parse(SAVE_V7).unwrap();
+    assert_eq!((v7.game.bombs, v7.game.score), (true, 12));
+    assert_eq!(v7.game.bomb, Some(Bomb { at: Point { x: 2, y: 7 }, left: 49 }));
+    let under_snake = SAVE_V7.replacen("bomb 2 7 49", "bomb 8 6 49", 1);
+    assert_eq!(SavedGame::parse(&under_snake).err(), Some(SaveError::Inconsistent));
+
+    // С формата 8 сохраняются очки за риск и шаг скорости от длины
+    assert_eq!((v7.game.risk_scoring, v7.game.length_speedup), (false, None));
+    let mut v8 = SavedGame::parse(SAVE_V8).unwrap();
+    v8.game.base_tick = v8.speed.unwrap();
+    assert_eq!((v8.game.risk_scoring, v8.game.length_speedup), (true, Some(Game::LENGTH_SPEEDUP)));
+    // Змейка из 10 клеток: 70 − 9 × 1,5 = 56,5 мс, еда стоит ⌈120 / 56,5⌉
+    assert_eq!((v8.game.score, v8.game.food_value()), (20, 3));
+
+    // С формата 9 сохраняются бонус у края и накопленные к нему шаги
+    assert_eq!(v8.game.edge_progress(), None);
+    let v9 = SavedGame::parse(SAVE_V9).unwrap();
+    assert_eq!((v9.game.edge_progress(), v9.game.score), (Some((12, Game::EDGE_TICKS)), 2));
+    let overfull = SAVE_V9.replacen("edge_ticks 12", "edge_ticks 40", 1);
+    assert_eq!(SavedGame::parse(&overfull).err(), Some(SaveError::Inconsistent));
+
+    // С формата 10 сохраняются боссы: еда прячется под боссом, пока его не съедят
+    assert!(!v9.game.bosses && v9.game.boss.is_none());
+    let v10 = SavedGame::parse(SAVE_V10).unwrap();
+    assert!(v10.game.bosses);
+    assert_eq!((v10.game.boss, v10.game.food), (Some(Boss { at: Point { x: 9, y: 5 }, hits: 2 }), Point { x: 9, y: 5 }));
+    for broken in ["boss 9 5 0", "boss 9 5 4", "boss 15 5 2"] {
+        let text = SAVE_V10.replacen("boss 9 5 2", broken, 1);
+        assert_eq!(SavedGame::parse(&text).err(), Some(SaveError::Inconsistent), "{broken}");
+    }
+    assert_eq!(SavedGame::parse(&SAVE_V10.replacen("boss 9 5 2", "boss 9 5", 1)).err(), Some(SaveError::BadLine(16)));
+
+    // С формата 11 сохраняются гусеницы с оставшимися звеньями
+    assert!(!v10.game.caterpillars && v10.game.caterpillar.is_empty());
+    let v11 = SavedGame::parse(SAVE_V11).unwrap();
+    assert!(v11.game.caterpillars);
+    assert_eq!(v11.game.caterpillar, [Point { x: 14, y: 3 }, Point { x: 14, y: 4 }]);
+    for broken in ["caterpillar 14 3 15 4", "caterpillar 14 2 14 4", "caterpillar 14 4 14 5", "caterpillar 2 7 2 8"] {
+        let text = SAVE_V11.replacen("caterpillar 14 3 14 4", broken, 1);
+        assert_eq!(SavedGame::parse(&text).err(), Some(SaveError::Inconsistent), "{broken}");
+    }
+    let reversed = SavedGame::parse(&SAVE_V11.replacen("caterpillar 14 3 14 4", "caterpillar 14 4 14 3", 1)).unwrap();
+    assert_eq!(reversed.game.caterpillar.len(), 2);
+
+    // С формата 12 сохраняется рост за еду; раньше змейка росла на клетку
+    assert_eq!(v11.game.growth_per_food, 1);
+    let v12 = SavedGame::parse(SAVE_V12).unwrap();
+    assert_eq!((v12.game.growth_per_food, v12.game.growth), (3, 2));
+    let greedy = SAVE_V12.replacen("grow 3", "grow 6", 1);
+    assert_eq!(SavedGame::parse(&greedy).err(), Some(SaveError::Inconsistent));
+
+    // С формата 13 сохраняется овертайм
+    assert!(!v12.game.overtime);
+    let v13 = SavedGame::parse(SAVE_V13).unwrap();
+    assert!(v13.game.overtime && v13.game.sudden_death);
+    assert_eq!(v13.game.tick_rate(), Game::MIN_TICK);
+
+    // С формата 14 сохраняются мутации бесконечного режима
+    assert!(v13.game.mutations.is_empty() && !v13.game.mutating);
+    let v14 = SavedGame::parse(SAVE_V14).unwrap();
+    assert_eq!(v14.game.mutations, [Mutation::Caterpillars, Mutation::Growth, Mutation::Growth, Mutation::Mirrored]);
+    assert_eq!((v14.game.mutating, v14.game.mutation_due, v14.game.mirrored), (true, 7, 30));
+    let unknown = SAVE_V14.replacen("mutations caterpillars", "mutations lava", 1);
+    assert_eq!(SavedGame::parse(&unknown).err(), Some(SaveError::BadLine(16)));
+    let overdue = SAVE_V14.replacen("mutate 7", "mutate 21", 1);
+    assert_eq!(SavedGame::parse(&overdue).err(), Some(SaveError::Inconsistent));
+    // Проходимые стены и убегающая еда включаются по списку мутаций
+    assert!(!v14.game.wrapping && !v14.game.fleeing);
+    let moving = SAVE_V14.replacen("mutations caterpillars", "mutations wrap flee caterpillars", 1);
+    let moving = SavedGame::parse(&moving).unwrap();
+    assert!(moving.game.wrapping && moving.game.fleeing);
+
+    // С формата 15 сохраняется таяние счёта и шаги без еды
+    assert!(!v14.game.score_decay && v14.game.since_food == 0);
+    let v15 = SavedGame::parse(SAVE_V15).unwrap();
+    assert_eq!((v15.game.score_decay, v15.game.since_food), (true, 42));
+
+    // С формата 16 сохраняется след слизи
+    assert!(!v15.game.slime_trail && v15.game.slime().is_empty());
+    let v16 = SavedGame::parse(SAVE_V16).unwrap();
+    assert!(v16.game.slime_trail);
+    assert_eq!(v16.game.slime(), &[(Point { x: 11, y: 8 }, 17), (Point { x: 12, y: 8 }, 18)]);
+    let dried = SAVE_V16.replacen("12 8 18", "12 8 0", 1);
+    assert_eq!(SavedGame::parse(&dried).err(), Some(SaveError::BadLine(22)));
+    let outside = SAVE_V16.replacen("12 8 18", "16 8 18", 1);
+    assert_eq!(SavedGame::parse(&outside).err(), Some(SaveError::Inconsistent));
+    let mut out = Vec::new();
+    let mut game = v16.game;
+    SavedGame::write(&mut out, &mut game, &v16.mode, v16.elapsed, v16.ticks).unwrap();
+    assert!(String::from_utf8(out).unwrap().contains("\nslime\nslimy 11 8 17 12 8 18\n"));
+
+    // С формата 17 сохраняются порталы
+    let v17 = SavedGame::parse(SAVE_V17).unwrap();
+    assert_eq!((v17.game.teleports, v17.game.portal), (true, Some(Point { x: 8, y: 2 })));
+    let on_snake = SAVE_V17.replacen("portal 8 2", "portal 14 5", 1);
+    assert_eq!(SavedGame::parse(&on_snake).err(), Some(SaveError::Inconsistent));
+
+    // С формата 18 сохраняются щиты
+    let v18 = SavedGame::parse(SAVE_V18).unwrap();
+    assert_eq!((v18.game.shields, v18.game.shield_pickup, v18.game.shield), (true, Some(Point { x: 5, y: 2 }), 1));
+    let two = SAVE_V18.replacen("shield 1", "shield 2", 1);
+    assert_eq!(SavedGame::parse(&two).err(), Some(SaveError::Inconsistent));
+    let on_portal = SAVE_V18.replacen("shield_pickup 5 2", "shield_pickup 2 8", 1);
+    assert_eq!(SavedGame::parse(&on_portal).err(), Some(SaveError::Inconsistent));
+
+    // С формата 19 сохраняются удвоители
+    let v19 = SavedGame::parse(SAVE_V19).unwrap();
+    assert_eq!((v19.game.doubling, v19.game.doubler, v19.game.doubled), (true, Some(Point { x: 4, y: 4 }), 57));
+    let on_food = SAVE_V19.replacen("doubler 4 4", "doubler 2 8", 1);
+    assert_eq!(SavedGame::parse(&on_food).err(), Some(SaveError::Inconsistent));
+
+    // С формата 20 сохраняются заморозки
+    assert!(!v19.game.freezing && v19.game.freezer.is_none() && !v19.game.world_frozen());
+    let v20 = SavedGame::parse(SAVE_V20).unwrap();
+    assert_eq!((v20.game.freezing, v20.game.freezer, v20.game.frozen), (true, Some(Point { x: 3, y: 7 }), 31));
+    let on_food = SAVE_V20.replacen("freezer 3 7", "freezer 2 8", 1);
+    assert_eq!(SavedGame::parse(&on_food).err(), Some(SaveError::Inconsistent));
+}
+
+#[test]
+fn old_formats_read_with_defaults() {
+    let (format, fields) = persist::read_header(SAVE_V1, "zmiy-save", 20).unwrap();
+    assert_eq!(format, 1);
+    let v1 = Save::from_fields(format, fields).unwrap();
+    assert_eq!(persist::read_save(SAVE_V1), Ok(v1.clone()));
+    // Строк, которых в формате 1 ещё не было, в файле нет, и поля под них по умолчанию
+    assert_eq!((v1.speed, v1.candidates, v1.grow, v1.bomb, v1.mutations.len(), v1.frozen), (None, 1, 1, None, 0, 0));
+    // Текущий формат, записанный из старого файла, читается как тот же файл
+    for text in [SAVE_V2, SAVE_V3, SAVE_V4, SAVE_V5, SAVE_V6, SAVE_V7, SAVE_V8, SAVE_V9, SAVE_V10, SAVE_V11, SAVE_V12, SAVE_V13, SAVE_V14, SAVE_V15, SAVE_V16, SAVE_V17, SAVE_V18, SAVE_V19, SAVE_V20] {
+        let current = persist::read_save(text).unwrap();
+        assert_eq!(persist::read_save(&current.to_text()), Ok(current));
+    }
+}
+
+#[test]
+fn every_replay_format_plays_back() {
+    for (text, speed) in
+        [(REPLAY_V1, Game::BASE_TICK), (REPLAY_V2, Duration::from_millis(80)), (REPLAY_V3, Duration::from_millis(80))]
+    {
+        let mut playback = Playback::new(Replay::parse(text).unwrap());
+        assert_eq!(playback.game.base_tick, speed);
+        while !playback.finished() {
+            playback.step();
+        }
+        assert_eq!((playback.tick(), playback.diverged()), (18, None));
+        assert!(playback.warnings().is_empty(), "{:?}", playback.warnings());
+    }
+    // Контрольные суммы есть только с формата 3; прежние форматы поднимаются без них
+    assert_eq!(Replay::parse(REPLAY_V2).unwrap().checks, []);
+    let v3 = Replay::parse(REPLAY_V3).unwrap();
+    assert_eq!(v3.checks, [(10, 0xbdc7_e8f3_5461_18b0), (18, 0x5d07_0eff_3107_c219)]);
+    assert_eq!(v3, Replay { checks: v3.checks.clone(), ..Replay::parse(REPLAY_V2).unwrap() });
+}
+
+#[test]
+fn shed_replays_back_to_the_same_board() {
+    let replay = Replay::parse(REPLAY_V4).unwrap();
+    assert!(replay.inputs.contains(&(14, Input::Shed)));
+    let mut playback = Playback::new(replay);
+    while !playback.finished() {
+        playback.step();
+    }
+    assert_eq!((playback.tick(), playback.diverged(), playback.game.score), (19, None, 1));
+    assert_eq!(playback.game.obstacles(), [Point { x: 11, y: 5 }]);
+    // Без сброса повтор расходится с записью на первой же сумме после него
+    let unshed = REPLAY_V4.replacen("x 14\n", "", 1);
+    let mut playback = Playback::new(Replay::parse(&unshed).unwrap());
+    while !playback.finished() {
+        playback.step();
+    }
+    assert_eq!(playback.diverged(), Some(Diverged(18)));
+    // Сброс хвоста — строка формата 4, в повторе формата 3 её быть не может
+    let old = REPLAY_V4.replacen("zmiy-replay 4", "zmiy-replay 3", 1);
+    assert_eq!(Replay::parse(&old), Err(ReplayError::BadLine(11)));
+}
+
+#[test]
+fn changed_replay_stops_at_first_bad_checksum() {
+    // Поворот на тик позже: змейка приходит на тик 10 в другое место
+    let changed = REPLAY_V3.replacen("t 6 L", "t 7 L", 1);
+    let mut playback = Playback::new(Replay::parse(&changed).unwrap());
+    while !playback.finished() {
+        playback.step();
+    }
+    assert_eq!(playback.tick(), 10);
+    assert_eq!(playback.diverged(), Some(Diverged(10)));
+    // Строка суммы без суммы или не по порядку тиков — ошибка в строке
+    assert_eq!(Replay::parse(&REPLAY_V3.replacen("c 18 5d070eff3107c219", "c 18", 1)), Err(ReplayError::BadLine(11)));
+    assert_eq!(Replay::parse(&REPLAY_V3.replacen("c 18", "c 9", 1)), Err(ReplayError::BadLine(11)));
+}
+
+#[test]
+fn far_food_replays_with_its_candidates() {
+    let replay = Replay::parse(REPLAY_V5).unwrap();
+    assert_eq!(replay.candidates, 3);
+    assert_eq!(Replay::parse(REPLAY_V4).unwrap().candidates, 1);
+    let mut playback = Playback::new(replay);
+    assert_eq!(playback.game.food_candidates, 3);
+    while !playback.finished() {
+        playback.step();
+    }
+    assert_eq!((playback.tick(), playback.diverged(), playback.game.score), (28, None, 3));
+    // С одной клеткой на выбор еда встаёт в другие места, и повтор расходится
+    let uniform = REPLAY_V5.replacen("candidates 3\n", "", 1);
+    let mut playback = Playback::new(Replay::parse(&uniform).unwrap());
+    while !playback.finished() {
+        playback.step();
+    }
+    assert!(playback.diverged().is_some());
+}
+
+#[test]
+fn walled_rooms_replay_with_their_walls() {
+    let replay = Replay::parse(REPLAY_V6).unwrap();
+    assert_eq!((replay.mode.as_str(), replay.walls.len()), ("проход", 7));
+    assert!(Replay::parse(REPLAY_V5).unwrap().walls.is_empty());
+    let mut playback = Playback::new(replay);
+    assert!(playback.game.is_wall(Point { x: 8, y: 7 }));
+    while !playback.finished() {
+        playback.step();
+    }
+    assert_eq!((playback.tick(), playback.diverged(), playback.game.score), (60, None, 10));
+    // Без стен еда встаёт и за стеной, где змейке до неё не дойти, и повтор расходится
+    let open = REPLAY_V6.replacen("walls 8 0 8 2 8 3 8 4 8 5 8 6 8 7\n", "", 1);
+    let mut playback = Playback::new(Replay::parse(&open).unwrap());
+    while !playback.finished() {
+        playback.step();
+    }
+    assert!(playback.diverged().is_some());
+}
+
+#[test]
+fn speed_zones_replay_at_their_pace() {
+    // Сумма интервалов всех шагов: так идёт просмотр
+    let pace = |text: &str| {
+        let mut playback = Playback::new(Replay::parse(text).unwrap());
+        let mut total = Duration::ZERO;
+        while !playback.finished() {
+            total += playback.game.tick_rate();
+            playback.step();
+        }
+        assert_eq!((playback.tick(), playback.diverged(), playback.game.score), (20, None, 4));
+        total
+    };
+    assert_eq!(pace(REPLAY_V7), Duration::from_millis(1816));
+    // Зоны меняют только темп, а не ход партии: без них повтор идёт ровно по 80 мс
+    let fast = REPLAY_V7.lines().find(|l| l.starts_with("fast ")).unwrap();
+    let slow = REPLAY_V7.lines().find(|l| l.starts_with("slow ")).unwrap();
+    let plain = REPLAY_V7.replacen(&format!("{fast}\n"), "", 1).replacen(&format!("{slow}\n"), "", 1);
+    assert_eq!(pace(&plain), Duration::from_millis(1600));
+    assert!(Replay::parse(REPLAY_V6).unwrap().fast.is_empty());
+}
+
+#[test]
+fn bombs_replay_with_their_timer() {
+    let mut playback = Playback::new(Replay::parse(REPLAY_V8).unwrap());
+    assert!(playback.game.bombs);
+    while !playback.finished() {
+        playback.step();
+    }
+    assert_eq!((playback.tick(), playback.diverged(), playback.game.score), (75, None, 12));
+    assert_eq!(playback.game.bomb, Some(Bomb { at: Point { x: 2, y: 7 }, left: 49 }));
+    // Без бомб еда после счёта 10 встаёт иначе, и повтор расходится с записью
+    let mut playback = Playback::new(Replay::parse(&REPLAY_V8.replacen("bombs\n", "", 1)).unwrap());
+    while !playback.finished() {
+        playback.step();
+    }
+    assert!(playback.diverged().is_some());
+    assert!(!Replay::parse(REPLAY_V7).unwrap().bombs);
+}
+
+#[test]
+fn risk_scores_replay_with_the_speed_rules() {
+    let mut playback = Playback::new(Replay::parse(REPLAY_V9).unwrap());
+    assert!(playback.game.risk_scoring);
+    while !playback.finished() {
+        playback.step();
+    }
+    assert_eq!((playback.tick(), playback.diverged(), playback.game.score), (90, None, 20));
+    // Без шага скорости от длины змейка медленнее, еда дешевле, и суммы не сходятся
+    let mut playback = Playback::new(Replay::parse(&REPLAY_V9.replacen("speedup 1500\n", "", 1)).unwrap());
+    while !playback.finished() {
+        playback.step();
+    }
+    assert!(playback.diverged().is_some());
+    let old = Replay::parse(REPLAY_V8).unwrap();
+    assert_eq!((old.risk, old.speedup), (false, None));
+}
+
+#[test]
+fn edge_bonus_replays_from_the_flag() {
+    let mut playback = Playback::new(Replay::parse(REPLAY_V10).unwrap());
+    assert!(playback.game.edge_bonus);
+    while !playback.finished() {
+        playback.step();
+    }
+    // Круги вдоль стен без единой еды: оба очка — за игру у края
+    assert_eq!((playback.tick(), playback.diverged(), playback.game.score), (95, None, 2));
+    assert_eq!(playback.game.edge_ticks, 12);
+    let mut playback = Playback::new(Replay::parse(&REPLAY_V10.replacen("edge\n", "", 1)).unwrap());
+    while !playback.finished() {
+        playback.step();
+    }
+    assert_eq!((playback.diverged(), playback.game.score), (Some(Diverged(10)), 0));
+    assert!(!Replay::parse(REPLAY_V9).unwrap().edge);
+}
+
+#[test]
+fn bosses_replay_from_the_flag() {
+    let mut playback = Playback::new(Replay::parse(REPLAY_V11).unwrap());
+    assert!(playback.game.bosses);
+    let mut hits = Vec::new();
+    while !playback.finished() {
+        for event in playback.step() {
+            if let GameEvent::BossHit { left, .. } = event {
+                hits.push((playback.tick(), left));
+            }
+        }
+    }
+    // Еда по 5 очков на шаге 24 мс: босс выходит на 50 и приносит ещё 50
+    assert_eq!(hits, [(81, 2), (84, 1), (87, 0)]);
+    assert_eq!((playback.tick(), playback.diverged(), playback.game.score), (92, None, 100));
+    assert_eq!((playback.game.boss, playback.game.growth), (None, 0));
+    let mut playback = Playback::new(Replay::parse(&REPLAY_V11.replacen("bosses\n", "", 1)).unwrap());
+    while !playback.finished() {
+        playback.step();
+    }
+    assert!(playback.diverged().is_some());
+    assert!(!Replay::parse(REPLAY_V10).unwrap().bosses);
+}
+
+#[test]
+fn caterpillars_replay_from_the_flag() {
+    let mut playback = Playback::new(Replay::parse(REPLAY_V12).unwrap());
+    assert!(playback.game.caterpillars);
+    let mut pellets = Vec::new();
+    while !playback.finished() {
+        for event in playback.step() {
+            if let GameEvent::Pellet { left, .. } = event {
+                pellets.push((playback.tick(), left));
+            }
+        }
+    }
+    // Четыре звена подряд с одного конца: 4 очка за звенья и 3 за целую гусеницу
+    assert_eq!(pellets, [(27, 3), (28, 2), (29, 1), (30, 0)]);
+    assert_eq!((playback.tick(), playback.diverged(), playback.game.score), (33, None, 11));
+    let mut playback = Playback::new(Replay::parse(&REPLAY_V12.replacen("caterpillars\n", "", 1)).unwrap());
+    while !playback.finished() {
+        playback.step();
+    }
+    assert!(playback.diverged().is_some());
+    assert!(!Replay::parse(REPLAY_V11).unwrap().caterpillars);
+}
+
+#[test]
+fn random_start_replays_from_the_start_line() {
+    let replay = Replay::parse(REPLAY_V13).unwrap();
+    assert_eq!(replay.start, Some((Point { x: 11, y: 5 }, DirectionSnake::Up)));
+    let mut playback = Playback::new(replay);
+    // Первая еда стоит от начала так же, как от середины поля
+    assert_eq!((playback.game.dir, playback.game.food), (DirectionSnake::Up, Point { x: 7, y: 3 }));
+    while !playback.finished() {
+        playback.step();
+    }
+    assert_eq!((playback.tick(), playback.diverged(), playback.game.score), (52, None, 4));
+    // Без строки `start` змейка начинает посередине, и повтор расходится
+    let mut playback = Playback::new(Replay::parse(&REPLAY_V13.replacen("start 11 5 U\n", "", 1)).unwrap());
+    while !playback.finished() {
+        playback.step();
+    }
+    assert!(playback.diverged().is_some());
+    assert_eq!(Replay::parse(&REPLAY_V13.replacen("start 11 5 U", "start 11 5 X", 1)), Err(ReplayError::BadLine(7)));
+    assert_eq!(Replay::parse(REPLAY_V12).unwrap().start, None);
+}
+
+#[test]
+fn starting_length_replays_from_the_length_line() {
+    let replay = Replay::parse(REPLAY_V14).unwrap();
+    assert_eq!((replay.length, replay.start), (4, None));
+    let mut playback = Playback::new(replay);
+    assert_eq!(playback.game.snake().len(), 4);
+    // С телом за головой развернуться назад нельзя; отказ направление не меняет
+    assert!(!playback.game.change_dir(DirectionSnake::Left));
+    while !playback.finished() {
+        playback.step();
+    }
+    assert_eq!((playback.tick(), playback.diverged(), playback.game.score), (26, None, 3));
+    let mut playback = Playback::new(Replay::parse(&REPLAY_V14.replacen("length 4\n", "", 1)).unwrap());
+    while !playback.finished() {
+        playback.step();
+    }
+    assert!(playback.diverged().is_some());
+    assert_eq!(Replay::parse(&REPLAY_V14.replacen("length 4", "length four", 1)), Err(ReplayError::BadLine(7)));
+    assert_eq!(Replay::parse(REPLAY_V13).unwrap().length, 1);
+}
+
+#[test]
+fn growth_per_food_replays_from_the_grow_line() {
+    let mut playback = Playback::new(Replay::parse(REPLAY_V15).unwrap());
+    assert_eq!(playback.game.growth_per_food, 2);
+    while !playback.finished() {
+        playback.step();
+    }
+    // Три еды по две клетки роста
+    assert_eq!((playback.tick(), playback.diverged(), playback.game.snake().len()), (30, None, 7));
+    let mut playback = Playback::new(Replay::parse(&REPLAY_V15.replacen("grow 2\n", "", 1)).unwrap());
+    while !playback.finished() {
+        playback.step();
+    }
+    assert!(playback.diverged().is_some());
+    assert_eq!(Replay::parse(REPLAY_V14).unwrap().grow, 1);
+}
+
+#[test]
+fn overtime_replays_only_from_format_16() {
+    let mut playback = Playback::new(Replay::parse(REPLAY_V16).unwrap());
+    assert!(playback.game.sudden_death);
+    let mut overtime = None;
+    while !playback.finished() {
+        if playback.step().contains(&GameEvent::Overtime) {
+            overtime = Some((playback.tick(), playback.game.score));
+        }
+    }
+    // Змейка обходит всё поле 10×8 и выигрывает; последние пять еды — вдвое дороже
+    assert_eq!(overtime, Some((419, 13)));
+    assert_eq!((playback.tick(), playback.diverged(), playback.game.score), (440, None, 23));
+    assert_eq!(playback.game.game_over, Some(DeathCause::BoardFull));
+    // Тот же файл как повтор формата 15 идёт без овертайма: еда в конце стоит как обычно
+    let old = REPLAY_V16.replacen("zmiy-replay 16", "zmiy-replay 15", 1);
+    let mut playback = Playback::new(Replay::parse(&old).unwrap());
+    assert!(!playback.game.sudden_death);
+    while !playback.finished() {
+        playback.step();
+    }
+    assert_eq!((playback.game.overtime, playback.game.score), (false, 18));
+}
+
+#[test]
+fn score_decay_replays_from_the_flag() {
+    let mut playback = Playback::new(Replay::parse(REPLAY_V17).unwrap());
+    assert!(playback.game.score_decay);
+    let mut decays = Vec::new();
+    while !playback.finished() {
+        let before = playback.game.score;
+        playback.step();
+        if playback.game.score < before {
+            decays.push(playback.tick());
+        }
+    }
+    // Змейка без роста ходит по ряду туда и обратно и иногда находит еду на пути
+    assert_eq!(decays, [112, 220, 325]);
+    assert_eq!((playback.tick(), playback.diverged(), playback.game.score), (425, None, 3));
+    let mut playback = Playback::new(Replay::parse(&REPLAY_V17.replacen("decay\n", "", 1)).unwrap());
+    while !playback.finished() {
+        playback.step();
+    }
+    assert!(playback.diverged().is_some());
+    assert!(!Replay::parse(REPLAY_V16).unwrap().decay);
+}
+
+#[test]
+fn slime_trail_replays_from_the_flag() {
+    let mut playback = Playback::new(Replay::parse(REPLAY_V18).unwrap());
+    assert!(playback.game.slime_trail);
+    let mut bogged = 0;
+    while !playback.finished() {
+        playback.step();
+        if playback.game.tick_rate() > playback.game.base_tick {
+            bogged += 1;
+        }
+    }
+    // Змейка идёт к еде напрямик и то и дело наступает на свой след
+    assert_eq!(bogged, 44);
+    assert_eq!((playback.tick(), playback.diverged(), playback.game.score), (232, None, 28));
+    let mut playback = Playback::new(Replay::parse(&REPLAY_V18.replacen("slime\n", "", 1)).unwrap());
+    while !playback.finished() {
+        playback.step();
+    }
+    assert!(playback.diverged().is_some());
+    assert!(!Replay::parse(REPLAY_V17).unwrap().slime);
+}
+
+#[test]
+fn portals_replay_from_the_flag() {
+    let mut playback = Playback::new(Replay::parse(REPLAY_V19).unwrap());
+    assert!(playback.game.teleports);
+    let mut warps = Vec::new();
+    while !playback.finished() {
+        for event in playback.step() {
+            if let GameEvent::Teleported { to, .. } = event {
+                warps.push((playback.tick(), to));
+            }
+        }
+    }
+    // Змейка идёт к порталу, если он есть, иначе к еде
+    let to = |x, y| Point { x, y };
+    assert_eq!(warps, [(8, to(11, 3)), (50, to(11, 1)), (67, to(10, 5)), (83, to(1, 2))]);
+    assert_eq!((playback.tick(), playback.diverged(), playback.game.score), (229, None, 20));
+    // Без порталов змейка разбивается задолго до конца записи
+    let mut playback = Playback::new(Replay::parse(&REPLAY_V19.replacen("teleports\n", "", 1)).unwrap());
+    while !playback.finished() {
+        playback.step();
+    }
+    assert_eq!((playback.tick(), playback.game.game_over), (23, Some(DeathCause::Wall)));
+    assert!(!Replay::parse(REPLAY_V18).unwrap().teleports);
+}
+
+#[test]
+fn shields_replay_from_the_flag() {
+    let mut playback = Playback::new(Replay::parse(REPLAY_V20).unwrap());
+    assert!(playback.game.shields);
+    let mut shielded = Vec::new();
+    while !playback.finished() {
+        for event in playback.step() {
+            if let GameEvent::Shielded(cause) = event {
+                shielded.push((playback.tick(), cause));
+            }
+        }
+    }
+    // Подобрав щит, змейка нарочно въезжает в стену и остаётся цела
+    assert_eq!(shielded, [(34, DeathCause::Wall)]);
+    assert_eq!((playback.tick(), playback.diverged(), playback.game.score), (94, None, 12));
+    // Без щитов та же запись обрывается о стену
+    let mut playback = Playback::new(Replay::parse(&REPLAY_V20.replacen("shields\n", "", 1)).unwrap());
+    while !playback.finished() {
+        playback.step();
+    }
+    assert_eq!((playback.tick(), playback.game.game_over), (34, Some(DeathCause::Wall)));
+    assert!(!Replay::parse(REPLAY_V19).unwrap().shields);
+}
+
+#[test]
+fn doublers_replay_from_the_flag() {
+    let mut playback = Playback::new(Replay::parse(REPLAY_V21).unwrap());
+    assert!(playback.game.doubling);
+    let mut picked = Vec::new();
+    while !playback.finished() {
+        for event in playback.step() {
+            if let GameEvent::Doubled(_) = event {
+                picked.push(playback.tick());
+            }
+        }
+    }
+    // Змейка идёт к удвоителю, если он есть, иначе к еде; еды 11, а очков почти вдвое больше
+    assert_eq!(picked, [23, 71, 100]);
+    assert_eq!((playback.tick(), playback.diverged(), playback.game.score), (131, None, 20));
+    assert_eq!(playback.game.eaten(FoodKind::Normal), 11);
+    // Без удвоителей партия расходится с записью
+    let mut playback = Playback::new(Replay::parse(&REPLAY_V21.replacen("doubling\n", "", 1)).unwrap());
+    while !playback.finished() {
+        playback.step();
+    }
+    assert!(playback.diverged().is_some());
+    assert!(!Replay::parse(REPLAY_V20).unwrap().doubling);
+}
+
+#[test]
+fn moving_mutations_replay_only_from_format_22() {
+    let mut playback = Playback::new(Replay::parse(REPLAY_V22).unwrap());
+    assert!(playback.game.moving_mutations);
+    let (mut mutated, mut wrapped) = (Vec::new(), Vec::new());
+    while !playback.finished() {
+        let head = playback.game.snake()[0];
+        for event in playback.step() {
+            if let GameEvent::Mutated(mutation) = event {
+                mutated.push((playback.tick(), mutation));
+            }
+        }
+        let to = playback.game.snake()[0];
+        if head.x.abs_diff(to.x) + head.y.abs_diff(to.y) > 1 {
+            wrapped.push(playback.tick());
+        }
+    }
+    // После двадцатой еды стены начинают пропускать, и змейка дважды уходит за край
+    assert_eq!(mutated, [(166, Mutation::Wrap)]);
+    assert_eq!(wrapped, [171, 189]);
+    assert_eq!((playback.tick(), playback.diverged(), playback.game.score), (199, None, 24));
+    // В повторе формата 21 такой мутации ещё нет: выпадает зеркальное управление, и змейка,
+    // поворачивая не туда, бьётся о край
+    let old = REPLAY_V22.replacen("zmiy-replay 22", "zmiy-replay 21", 1);
+    let mut playback = Playback::new(Replay::parse(&old).unwrap());
+    while !playback.finished() {
+        playback.step();
+    }
+    assert_eq!(playback.game.mutations, [Mutation::Mirrored]);
+    assert_eq!((playback.tick(), playback.game.game_over), (177, Some(DeathCause::Wall)));
+    assert!(!Replay::parse(REPLAY_V21).unwrap().moving_mutations);
+}
+
+#[test]
+fn freezers_replay_from_the_flag() {
+    let mut playback = Playback::new(Replay::parse(REPLAY_V23).unwrap());
+    assert!(playback.game.freezing);
+    let (mut picked, mut fuse) = (Vec::new(), Vec::new());
+    while !playback.finished() {
+        for event in playback.step() {
+            if let GameEvent::Froze(_) = event {
+                picked.push(playback.tick());
+            }
+        }
+        if playback.game.world_frozen() {
+            fuse.push(playback.game.bomb.map(|bomb| bomb.left));
+        }
+    }
+    // Змейка идёт к заморозке, если она есть, иначе к еде; бомба, легшая в заморозку,
+    // ждёт оттепели с полным запалом
+    assert_eq!(picked, [49]);
+    assert_eq!(fuse.len(), Game::FREEZE_TICKS as usize);
+    assert_eq!(fuse.iter().flatten().count(), 12);
+    assert!(fuse.iter().flatten().all(|&left| left == Game::BOMB_TICKS));
+    assert_eq!((playback.tick(), playback.diverged(), playback.game.score), (141, None, 16));
+    // Без заморозок партия расходится с записью
+    let mut playback = Playback::new(Replay::parse(&REPLAY_V23.replacen("freezing\n", "", 1)).unwrap());
+    while !playback.finished() {
+        playback.step();
+    }
+    assert_eq!(playback.diverged(), Some(Diverged(100)));
+    assert!(!Replay::parse(REPLAY_V22).unwrap().freezing);
+}
+
+#[test]
+fn newer_formats_are_refused() {
+    let newer_save = SAVE_V20.replacen("zmiy-save 20", "zmiy-save 21", 1) + "colour green\n";
+    assert_eq!(SavedGame::parse(&newer_save).err(), Some(SaveError::Newer(21)));
+    assert!(SaveError::Newer(21).to_string().contains("создано более новой версией zmiy"));
+    let newer_replay = REPLAY_V2.replacen("zmiy-replay 2", "zmiy-replay 24", 1);
+    assert_eq!(Replay::parse(&newer_replay), Err(ReplayError::Newer(24)));
+    assert_eq!(persist::read_replay(&newer_replay), Err(FormatError::Newer(24)));
+    // Поле формата 2 в файле формата 1 — ошибка, а не тихо принятое значение
+    let mixed = SAVE_V1.replacen("dir D", "speed 80\ndir D", 1);
+    assert_eq!(SavedGame::parse(&mixed).err(), Some(SaveError::BadLine(7)));
+}
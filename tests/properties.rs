@@ -0,0 +1,179 @@
+//! Свойства `Game::step`, которые должны держаться для любого поля и любых ходов.
+//!
+//! Случаи генерируются из сида: поле случайного размера и ходы — то жадные к еде, чтобы
+//! змейка успевала вырасти, то случайные. Упавший случай ужимается до короткого
+//! списка ходов и печатается целиком, чтобы его можно было вставить в тест.
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use zmiy::{
+    game::{DirectionSnake, Game, GameEvent},
+    sim::{Greedy, Policy},
+};
+
+const DIRECTIONS: [DirectionSnake; 4] =
+    [DirectionSnake::Up, DirectionSnake::Down, DirectionSnake::Left, DirectionSnake::Right];
+
+/// Случай: поле, сид еды и ход перед каждым шагом (`None` — без поворота)
+#[derive(Clone, Debug)]
+struct Case {
+    width: u16,
+    height: u16,
+    seed: u64,
+    inputs: Vec<Option<DirectionSnake>>,
+}
+
+impl Case {
+    fn random(rng: &mut StdRng) -> Self {
+        let (width, height) = (rng.random_range(3..=30), rng.random_range(3..=20));
+        let seed = rng.random();
+        let mut game = Game::with_seed(width, height, seed);
+        let mut inputs = Vec::new();
+        while game.game_over.is_none() && inputs.len() < 600 {
+            let input = match rng.random_range(0..10) {
+                0..6 => Greedy.decide(&game),
+                6..8 => Some(DIRECTIONS[rng.random_range(0..4)]),
+                _ => None,
+            };
+            if let Some(dir) = input {
+                game.change_dir(dir);
+            }
+            game.step();
+            inputs.push(input);
+        }
+        Self { width, height, seed, inputs }
+    }
+
+    /// Проигрывает ходы и проверяет свойства после каждого шага.
+    /// Ошибка — номер шага, на котором свойство нарушилось, и какое.
+    fn check(&self) -> Result<(), (usize, String)> {
+        let mut game = Game::with_seed(self.width, self.height, self.seed);
+        let initial = game.snake().len();
+        let mut eaten = 0;
+        for (i, input) in self.inputs.iter().enumerate() {
+            if let Some(dir) = input {
+                game.change_dir(*dir);
+            }
+            let events = game.step();
+            eaten += events.iter().filter(|e| matches!(e, GameEvent::Ate { .. })).count();
+            if game.game_over.is_some() {
+                return Ok(());
+            }
+            invariants(&game, initial + eaten).map_err(|e| (i, e))?;
+        }
+        Ok(())
+    }
+
+    /// Ужимает упавший случай: отрезает ходы после ошибки, затем выкидывает по одному
+    /// ходу и убирает повороты, пока случай продолжает падать. `failing_step` — шаг,
+    /// на котором случай падает, или `None`, если не падает.
+    fn shrink(mut self, failing_step: impl Fn(&Case) -> Option<usize>) -> Self {
+        let fails = |c: &Case| failing_step(c).is_some();
+        if let Some(step) = failing_step(&self) {
+            self.inputs.truncate(step + 1);
+        }
+        let mut i = 0;
+        while i < self.inputs.len() {
+            let mut fewer = self.clone();
+            fewer.inputs.remove(i);
+            if fails(&fewer) {
+                self = fewer;
+                continue;
+            }
+            if self.inputs[i].is_some() {
+                let mut straight = self.clone();
+                straight.inputs[i] = None;
+                if fails(&straight) {
+                    self = straight;
+                }
+            }
+            i += 1;
+        }
+        self
+    }
+}
+
+fn invariants(game: &Game, expected_len: usize) -> Result<(), String> {
+    if game.snake().len() != expected_len {
+        return Err(format!("длина {} вместо {expected_len}", game.snake().len()));
+    }
+    let head = game.snake()[0];
+    if game.snake().iter().skip(1).any(|p| *p == head) {
+        return Err(format!("голова {head:?} на теле"));
+    }
+    if let Some(p) = game.snake().iter().find(|p| p.x >= game.width || p.y >= game.height) {
+        return Err(format!("клетка {p:?} за полем {}x{}", game.width, game.height));
+    }
+    for (a, b) in game.snake().iter().zip(game.snake().iter().skip(1)) {
+        if a.x.abs_diff(b.x) + a.y.abs_diff(b.y) != 1 {
+            return Err(format!("соседние клетки {a:?} и {b:?} не рядом"));
+        }
+    }
+    if game.snake().contains(&game.food) {
+        return Err(format!("еда {:?} на змейке", game.food));
+    }
+    Ok(())
+}
+
+#[test]
+fn step_keeps_invariants() {
+    let mut rng = StdRng::seed_from_u64(0x5eed);
+    let mut longest = 0;
+    for _ in 0..300 {
+        let case = Case::random(&mut rng);
+        if let Err((step, error)) = case.check() {
+            let small = case.shrink(|c| c.check().err().map(|(step, _)| step));
+            let (step_small, error_small) = small.check().unwrap_err();
+            panic!("шаг {step}: {error}\nужатый случай, шаг {step_small}: {error_small}\n{small:?}");
+        }
+        longest = longest.max(case.inputs.len());
+    }
+    // Генератор должен давать и долгие партии, иначе длинные змейки не проверяются
+    assert!(longest > 200, "самая долгая партия {longest} шагов");
+}
+
+#[test]
+fn food_never_spawns_on_snake() {
+    let mut rng = StdRng::seed_from_u64(7);
+    for _ in 0..200 {
+        let case = Case::random(&mut rng);
+        let mut game = Game::with_seed(case.width, case.height, case.seed);
+        for input in &case.inputs {
+            if let Some(dir) = input {
+                game.change_dir(*dir);
+            }
+            game.step();
+            if game.game_over.is_some() {
+                break;
+            }
+            // Повторный выбор еды тоже не должен попадать на змейку
+            for _ in 0..3 {
+                game.spawn_food();
+                assert!(!game.snake().contains(&game.food), "{case:?}");
+            }
+        }
+    }
+}
+
+#[test]
+fn shrinking_keeps_the_failure() {
+    // Нарочно ложное свойство: «змейка не съедает три еды»
+    let third_food = |c: &Case| {
+        let mut game = Game::with_seed(c.width, c.height, c.seed);
+        c.inputs.iter().position(|input| {
+            if let Some(dir) = input {
+                game.change_dir(*dir);
+            }
+            game.step();
+            game.score >= 3
+        })
+    };
+    let mut rng = StdRng::seed_from_u64(3);
+    let case = (0..50).map(|_| Case::random(&mut rng)).find(|c| third_food(c).is_some()).unwrap();
+    let small = case.clone().shrink(third_food);
+    assert_eq!(third_food(&small), Some(small.inputs.len() - 1));
+    assert!(small.inputs.len() <= third_food(&case).unwrap() + 1);
+    // Ужатый случай кончается ровно на третьей еде
+    let mut fewer = small.clone();
+    fewer.inputs.pop();
+    assert_eq!(third_food(&fewer), None);
+}
@@ -0,0 +1,691 @@
+//! Снимки отрисовки известных состояний игры в `TestBackend`
+
+use std::time::Duration;
+
+use ratatui::{
+    backend::TestBackend,
+    style::Modifier,
+    widgets::{Block, Borders},
+    Frame, Terminal,
+};
+use zmiy::{
+    game::{Blackout, Bomb, Boss, DeathCause, DirectionSnake, Game, Point, Zone},
+    input::Keymap,
+    lifetime::Lifetime,
+    pause::{PauseAction, PauseMenu, PauseTab, PauseView},
+    settings::Settings,
+    theme::{ColorSupport, Theme},
+    widget::{game_over_popup, Dim, GameWidget, WARP_FRAMES},
+};
+
+/// Строитель игр в заданном состоянии
+struct Fixture {
+    game: Game,
+}
+
+impl Fixture {
+    fn new(width: u16, height: u16) -> Self {
+        Self { game: Game::with_seed(width, height, 0) }
+    }
+
+    /// Тело змейки от головы к хвосту
+    fn snake(mut self, points: &[(u16, u16)]) -> Self {
+        self.game.set_snake(points.iter().map(|&(x, y)| Point { x, y }));
+        self
+    }
+
+    fn food(mut self, x: u16, y: u16) -> Self {
+        self.game.food = Point { x, y };
+        self
+    }
+
+    fn dir(mut self, dir: DirectionSnake) -> Self {
+        self.game.dir = dir;
+        self
+    }
+
+    fn score(mut self, score: usize) -> Self {
+        self.game.score = score;
+        self
+    }
+
+    fn dead(mut self, cause: DeathCause) -> Self {
+        self.game.game_over = Some(cause);
+        self
+    }
+
+    fn build(self) -> Game {
+        self.game
+    }
+}
+
+/// Рисует кадр и возвращает буфер построчно, без стилей
+fn render(width: u16, height: u16, draw: impl FnOnce(&mut Frame)) -> Vec<String> {
+    let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+    terminal.draw(draw).unwrap();
+    let buffer = terminal.backend().buffer();
+    (0..height)
+        .map(|y| (0..width).map(|x| buffer[(x, y)].symbol()).collect())
+        .collect()
+}
+
+fn board(f: &mut Frame, theme: &Theme, game: &mut Game) {
+    let block = Block::default().borders(Borders::ALL);
+    f.render_stateful_widget(GameWidget::new(theme).block(block), f.area(), game);
+}
+
+fn theme() -> Theme {
+    Theme::new(ColorSupport::Ansi16)
+}
+
+/// Змейка из пяти сегментов посреди партии
+fn mid_run() -> Game {
+    Fixture::new(12, 6)
+        .snake(&[(6, 2), (5, 2), (4, 2), (4, 3), (4, 4)])
+        .food(9, 4)
+        .dir(DirectionSnake::Right)
+        .score(4)
+        .build()
+}
+
+fn game_over(f: &mut Frame, theme: &Theme, game: &mut Game, elapsed: Duration) {
+    board(f, theme, game);
+    f.render_widget(Dim(theme), f.area());
+    f.render_widget(game_over_popup(theme, game, elapsed, false), f.area());
+}
+
+#[test]
+fn fresh_game() {
+    let theme = theme();
+    let mut game = Fixture::new(12, 6).build();
+    assert_eq!(render(14, 8, |f| board(f, &theme, &mut game)), [
+        "┌────────────┐",
+        "│            │",
+        "│            │",
+        "│    *       │",
+        "│      O     │",
+        "│            │",
+        "│            │",
+        "└────────────┘",
+    ]);
+}
+
+#[test]
+fn nokia_cells_are_two_columns_wide() {
+    let theme = Theme::nokia(ColorSupport::Ansi16);
+    let mut game = mid_run();
+    let block = Block::default().borders(Borders::ALL);
+    assert_eq!(render(26, 8, |f| f.render_stateful_widget(GameWidget::new(&theme).block(block).wide(true), f.area(), &mut game)), [
+        "┌────────────────────────┐",
+        "│                        │",
+        "│                        │",
+        "│        ooooOO          │",
+        "│        oo              │",
+        "│        oo        **    │",
+        "│                        │",
+        "└────────────────────────┘",
+    ]);
+}
+
+#[test]
+fn mid_run_snake() {
+    let theme = theme();
+    let mut game = mid_run();
+    assert_eq!(render(14, 8, |f| board(f, &theme, &mut game)), [
+        "┌────────────┐",
+        "│            │",
+        "│            │",
+        "│    ooO     │",
+        "│    o       │",
+        "│    o    *  │",
+        "│            │",
+        "└────────────┘",
+    ]);
+}
+
+#[test]
+fn ghost_under_snake_and_food() {
+    let theme = theme();
+    let mut game = mid_run();
+    // Призрак проходит через еду и хвост игрока: они рисуются поверх
+    let ghost: std::collections::VecDeque<Point> = [(9, 4), (8, 4), (7, 4), (6, 4), (5, 4), (4, 4), (3, 4)]
+        .into_iter()
+        .map(|(x, y)| Point { x, y })
+        .collect();
+    let lines = render(14, 8, |f| {
+        let block = Block::default().borders(Borders::ALL);
+        f.render_stateful_widget(GameWidget::new(&theme).block(block).ghost(&ghost), f.area(), &mut game);
+    });
+    assert_eq!(lines, [
+        "┌────────────┐",
+        "│            │",
+        "│            │",
+        "│    ooO     │",
+        "│    o       │",
+        "│   oooooo*  │",
+        "│            │",
+        "└────────────┘",
+    ]);
+}
+
+#[test]
+fn two_foods_differ_in_color_or_symbol() {
+    let theme = theme();
+    let mut game = mid_run();
+    game.bonus = Some(Point { x: 1, y: 1 });
+    let mut terminal = Terminal::new(TestBackend::new(14, 8)).unwrap();
+    terminal.draw(|f| board(f, &theme, &mut game)).unwrap();
+    let buffer = terminal.backend().buffer();
+    assert_eq!((buffer[(2, 2)].symbol(), buffer[(2, 2)].fg), ("*", theme.bonus_food));
+    assert_eq!((buffer[(10, 5)].symbol(), buffer[(10, 5)].fg), ("*", theme.food));
+    assert_ne!(theme.bonus_food, theme.food);
+
+    // Без цветов синяя еда видна по символу
+    let lines = render(14, 8, |f| {
+        let block = Block::default().borders(Borders::ALL);
+        f.render_stateful_widget(GameWidget::new(&theme).block(block).plain(true), f.area(), &mut game);
+    });
+    assert_eq!(lines[2], "│ +          │");
+    assert_eq!(lines[5], "│    o    *  │");
+}
+
+#[test]
+fn shed_tail_stays_on_the_board() {
+    let theme = theme();
+    let mut game = mid_run();
+    assert_eq!(game.shed(), Some(2));
+    let mut terminal = Terminal::new(TestBackend::new(14, 8)).unwrap();
+    terminal.draw(|f| board(f, &theme, &mut game)).unwrap();
+    let cell = &terminal.backend().buffer()[(5, 5)];
+    assert_eq!((cell.symbol(), cell.fg), ("o", theme.dead_fade[2]));
+
+    let lines = render(14, 8, |f| {
+        let block = Block::default().borders(Borders::ALL);
+        f.render_stateful_widget(GameWidget::new(&theme).block(block).plain(true), f.area(), &mut game);
+    });
+    assert_eq!(lines[3..6], ["│    ooO     │", "│    #       │", "│    #    *  │"]);
+}
+
+#[test]
+fn interior_walls_differ_from_the_border() {
+    let theme = theme();
+    let mut game = mid_run();
+    game.set_walls((0..6).filter(|&y| y != 3).map(|y| Point { x: 8, y }));
+    let lines = render(14, 8, |f| board(f, &theme, &mut game));
+    assert_eq!(lines[1..7], [
+        "│        █   │",
+        "│        █   │",
+        "│    ooO █   │",
+        "│    o       │",
+        "│    o   █*  │",
+        "│        █   │",
+    ]);
+    let mut terminal = Terminal::new(TestBackend::new(14, 8)).unwrap();
+    terminal.draw(|f| board(f, &theme, &mut game)).unwrap();
+    assert_eq!(terminal.backend().buffer()[(9, 1)].fg, theme.wall);
+    assert_ne!(theme.wall, theme.border);
+
+    let lines = render(14, 8, |f| {
+        let block = Block::default().borders(Borders::ALL);
+        f.render_stateful_widget(GameWidget::new(&theme).block(block).ascii(true), f.area(), &mut game);
+    });
+    assert_eq!(lines[3], "│    ooO %   │");
+}
+
+#[test]
+fn bomb_blinks_faster_before_it_expires() {
+    let theme = theme();
+    let mut game = mid_run();
+    let at = Point { x: 1, y: 1 };
+    // Сколько раз бомба появляется или пропадает за первые 16 кадров
+    let mut blinks = |left| {
+        game.bomb = Some(Bomb { at, left });
+        let shown: Vec<bool> = (0..16u64)
+            .map(|frame| {
+                let mut terminal = Terminal::new(TestBackend::new(14, 8)).unwrap();
+                terminal
+                    .draw(|f| {
+                        let block = Block::default().borders(Borders::ALL);
+                        f.render_stateful_widget(GameWidget::new(&theme).block(block).frame(frame), f.area(), &mut game);
+                    })
+                    .unwrap();
+                let cell = &terminal.backend().buffer()[(2, 2)];
+                cell.symbol() == "@" && cell.fg == theme.bomb
+            })
+            .collect();
+        (shown[0], shown.windows(2).filter(|w| w[0] != w[1]).count())
+    };
+    assert_eq!(blinks(Game::BOMB_TICKS), (true, 0));
+    assert_eq!(blinks(30), (true, 1));
+    assert_eq!(blinks(15), (true, 3));
+    assert_eq!(blinks(5), (true, 7));
+}
+
+#[test]
+fn boss_cracks_after_each_hit() {
+    let theme = theme();
+    let mut game = mid_run();
+    let at = Point { x: 8, y: 3 };
+    game.food = at;
+    let mut draw = |hits, ascii| {
+        game.boss = Some(Boss { at, hits });
+        render(14, 8, |f| {
+            let block = Block::default().borders(Borders::ALL);
+            f.render_stateful_widget(GameWidget::new(&theme).block(block).ascii(ascii), f.area(), &mut game);
+        })
+    };
+    // Еда прячется под боссом, пока его не съедят
+    assert_eq!(draw(3, false)[4..6], ["│    o   ██  │", "│    o   ██  │"]);
+    assert_eq!(draw(2, false)[4], "│    o   ▓▓  │");
+    assert_eq!(draw(1, false)[5], "│    o   ▒▒  │");
+    assert_eq!(draw(3, true)[4], "│    o   XX  │");
+    assert_eq!(draw(1, true)[4], "│    o   vv  │");
+}
+
+#[test]
+fn caterpillar_pellets_are_linked() {
+    let theme = theme();
+    let mut game = mid_run();
+    game.caterpillar = (7..11).map(|x| Point { x, y: 1 }).collect();
+    let draw = |plain, game: &mut Game| {
+        let mut terminal = Terminal::new(TestBackend::new(14, 8)).unwrap();
+        terminal
+            .draw(|f| {
+                let block = Block::default().borders(Borders::ALL);
+                f.render_stateful_widget(GameWidget::new(&theme).block(block).plain(plain), f.area(), game);
+            })
+            .unwrap();
+        terminal.backend().buffer().clone()
+    };
+    // Звенья одного цвета на общем фоне, который выдаёт в них одну гусеницу
+    let buffer = draw(false, &mut game);
+    for x in 8..12 {
+        assert_eq!((buffer[(x, 2)].symbol(), buffer[(x, 2)].bg), ("●", theme.caterpillar_link));
+    }
+    assert_ne!(buffer[(12, 2)].bg, theme.caterpillar_link);
+    let buffer = draw(true, &mut game);
+    assert_eq!((8..12).map(|x| buffer[(x, 2)].symbol()).collect::<String>(), "<==>");
+    game.caterpillar = (1..4).map(|y| Point { x: 1, y }).collect();
+    let buffer = draw(true, &mut game);
+    assert_eq!([buffer[(2, 2)].symbol(), buffer[(2, 3)].symbol(), buffer[(2, 4)].symbol()], ["^", "|", "v"]);
+}
+
+#[test]
+fn speed_zones_tint_the_background() {
+    let theme = theme();
+    let mut game = mid_run();
+    game.set_zones(Zone::Fast, [Point { x: 1, y: 1 }, Point { x: 6, y: 2 }]);
+    game.set_zones(Zone::Slow, [Point { x: 9, y: 1 }]);
+    let mut terminal = Terminal::new(TestBackend::new(14, 8)).unwrap();
+    terminal.draw(|f| board(f, &theme, &mut game)).unwrap();
+    let buffer = terminal.backend().buffer();
+    assert_eq!((buffer[(2, 2)].symbol(), buffer[(2, 2)].bg), (" ", theme.zone_fast));
+    assert_eq!((buffer[(10, 2)].symbol(), buffer[(10, 2)].bg), (" ", theme.zone_slow));
+    // Голова в зоне: символ змейки поверх подкрашенного фона
+    assert_eq!((buffer[(7, 3)].symbol(), buffer[(7, 3)].fg, buffer[(7, 3)].bg), ("O", theme.snake, theme.zone_fast));
+
+    // Без цветов зоны выдают символы, и сетка их не закрывает
+    let lines = render(14, 8, |f| {
+        let block = Block::default().borders(Borders::ALL);
+        f.render_stateful_widget(GameWidget::new(&theme).block(block).plain(true).grid(true), f.area(), &mut game);
+    });
+    assert_eq!(lines[2], "│ » · · · ~ ·│");
+}
+
+#[test]
+fn near_miss_highlights_the_cell_it_passed() {
+    let theme = theme();
+    let mut game = mid_run();
+    let cell = Point { x: 4, y: 3 };
+    let draw = |plain, game: &mut Game| {
+        let mut terminal = Terminal::new(TestBackend::new(14, 8)).unwrap();
+        terminal
+            .draw(|f| {
+                let block = Block::default().borders(Borders::ALL);
+                f.render_stateful_widget(GameWidget::new(&theme).block(block).plain(plain).near_miss(Some(cell)), f.area(), game);
+            })
+            .unwrap();
+        terminal.backend().buffer().clone()
+    };
+    // Сегмент остаётся собой, меняется только фон
+    let buffer = draw(false, &mut game);
+    assert_eq!((buffer[(5, 4)].symbol(), buffer[(5, 4)].fg, buffer[(5, 4)].bg), ("o", theme.snake, theme.near_miss));
+    assert_ne!(buffer[(5, 3)].bg, theme.near_miss);
+    let buffer = draw(true, &mut game);
+    assert!(buffer[(5, 4)].modifier.contains(Modifier::REVERSED));
+}
+
+#[test]
+fn portal_warp_moves_the_body_through_the_portal() {
+    let theme = theme();
+    let mut game = mid_run();
+    game.portal = Some(Point { x: 10, y: 1 });
+    let from: Vec<Point> = (1..6).map(|x| Point { x, y: 0 }).collect();
+    let draw = |frames, game: &mut Game| {
+        let mut terminal = Terminal::new(TestBackend::new(14, 8)).unwrap();
+        terminal
+            .draw(|f| {
+                let block = Block::default().borders(Borders::ALL);
+                f.render_stateful_widget(GameWidget::new(&theme).block(block).warp(&from, frames), f.area(), game);
+            })
+            .unwrap();
+        terminal.backend().buffer().clone()
+    };
+    let buffer = draw(0, &mut game);
+    assert_eq!((buffer[(11, 2)].symbol(), buffer[(11, 2)].fg), ("◎", theme.portal));
+    // Сразу после переноса видны оба тела, и оба цвета портала
+    assert_eq!((buffer[(2, 1)].symbol(), buffer[(2, 1)].fg), ("o", theme.portal));
+    assert_eq!((buffer[(7, 3)].symbol(), buffer[(7, 3)].fg), ("O", theme.portal));
+    // На середине прежнее тело ушло в портал наполовину, а новое проступило с головы
+    let buffer = draw(WARP_FRAMES / 2, &mut game);
+    assert_eq!((buffer[(2, 1)].symbol(), buffer[(3, 1)].symbol(), buffer[(4, 1)].symbol()), (" ", " ", "o"));
+    assert_eq!((buffer[(7, 3)].fg, buffer[(6, 3)].fg, buffer[(5, 3)].fg), (theme.snake, theme.snake, theme.portal));
+    // После анимации остаётся только новое тело
+    let buffer = draw(WARP_FRAMES, &mut game);
+    assert_eq!((buffer[(6, 1)].symbol(), buffer[(5, 5)].fg), (" ", theme.snake));
+}
+
+#[test]
+fn shield_pickup_is_drawn_in_its_colour() {
+    let theme = theme();
+    let mut game = mid_run();
+    game.shield_pickup = Some(Point { x: 10, y: 1 });
+    let draw = |ascii, game: &mut Game| {
+        let mut terminal = Terminal::new(TestBackend::new(14, 8)).unwrap();
+        terminal
+            .draw(|f| {
+                let block = Block::default().borders(Borders::ALL);
+                f.render_stateful_widget(GameWidget::new(&theme).block(block).ascii(ascii), f.area(), game);
+            })
+            .unwrap();
+        terminal.backend().buffer().clone()
+    };
+    let buffer = draw(false, &mut game);
+    assert_eq!((buffer[(11, 2)].symbol(), buffer[(11, 2)].fg), ("◈", theme.shield));
+    assert_eq!(draw(true, &mut game)[(11, 2)].symbol(), "S");
+}
+
+#[test]
+fn frozen_world_is_tinted_icy_blue() {
+    let theme = theme();
+    let mut game = mid_run();
+    game.freezer = Some(Point { x: 10, y: 1 });
+    game.bomb = Some(Bomb { at: Point { x: 1, y: 0 }, left: 30 });
+    game.fleeing = true;
+    game.frozen = 10;
+    let draw = |game: &mut Game| {
+        let mut terminal = Terminal::new(TestBackend::new(14, 8)).unwrap();
+        terminal
+            .draw(|f| {
+                let block = Block::default().borders(Borders::ALL);
+                f.render_stateful_widget(GameWidget::new(&theme).block(block).frame(0), f.area(), game);
+            })
+            .unwrap();
+        terminal.backend().buffer().clone()
+    };
+    let buffer = draw(&mut game);
+    assert_eq!((buffer[(11, 2)].symbol(), buffer[(11, 2)].fg), ("❄", theme.frozen));
+    // Убегающая еда и бомба замёрзли, змейка — нет
+    assert_eq!((buffer[(10, 5)].fg, buffer[(2, 1)].fg), (theme.frozen, theme.frozen));
+    assert_eq!(buffer[(7, 3)].fg, theme.snake);
+    // Оттаявший мир снова в своих цветах
+    game.frozen = 0;
+    let buffer = draw(&mut game);
+    assert_eq!((buffer[(10, 5)].fg, buffer[(2, 1)].fg), (theme.food_pulse[0], theme.bomb));
+}
+
+#[test]
+fn blackout_hides_all_but_head_and_border() {
+    let theme = theme();
+    let mut game = mid_run();
+    let draw = |blackout, frame, game: &mut Game| {
+        let mut terminal = Terminal::new(TestBackend::new(14, 8)).unwrap();
+        terminal
+            .draw(|f| {
+                let block = Block::default().borders(Borders::ALL);
+                f.render_stateful_widget(GameWidget::new(&theme).block(block).frame(frame).blackout(blackout), f.area(), game);
+            })
+            .unwrap();
+        terminal.backend().buffer().clone()
+    };
+    let lines = |buffer: &ratatui::buffer::Buffer| -> Vec<String> {
+        (0..8).map(|y| (0..14).map(|x| buffer[(x, y)].symbol()).collect()).collect()
+    };
+
+    let dark = draw(Blackout::Dark, 0, &mut game);
+    assert_eq!(lines(&dark), [
+        "┌────────────┐",
+        "│            │",
+        "│            │",
+        "│      O     │",
+        "│            │",
+        "│            │",
+        "│            │",
+        "└────────────┘",
+    ]);
+    // Предупреждение: рамка мигает цветом опасности, поле видно целиком
+    let warning = draw(Blackout::Warning, 0, &mut game);
+    assert_eq!(lines(&warning), lines(&draw(Blackout::Clear, 0, &mut game)));
+    assert_eq!(warning[(0, 0)].fg, theme.danger);
+    assert_ne!(draw(Blackout::Warning, 4, &mut game)[(0, 0)].fg, theme.danger);
+}
+
+#[test]
+fn board_clipped_to_small_area() {
+    let theme = theme();
+    let mut game = mid_run();
+    assert_eq!(render(6, 5, |f| board(f, &theme, &mut game)), [
+        "┌────┐",
+        "│    │",
+        "│    │",
+        "│    │",
+        "└────┘",
+    ]);
+}
+
+#[test]
+fn paused() {
+    let theme = theme();
+    let mut game = mid_run();
+    let menu = PauseMenu::default();
+    let settings = Settings::default();
+    let keymap = Keymap::default();
+    let lines = render(48, 20, |f| {
+        board(f, &theme, &mut game);
+        f.render_widget(Dim(&theme), f.area());
+        let view = PauseView {
+            menu: &menu,
+            theme: &theme,
+            game: &game,
+            settings: &settings,
+            keymap: &keymap,
+            elapsed: Duration::from_secs(75),
+            session_best: 7,
+            lifetime: &Lifetime::default(),
+        };
+        f.render_widget(view, f.area());
+    });
+    assert_eq!(lines, [
+        "┌──────────────────────────────────────────────┐",
+        "│ ┌──────────────────────────────────────────┐ │",
+        "│ │ Пауза │ Статистика │ Клавиши │ Настройки │ │",
+        "│ │                                          │ │",
+        "│ │Игра на паузе                             │ │",
+        "│ │                                          │ │",
+        "│ │Счёт: 4   Длина: 5                        │ │",
+        "│ │Время: 01:15                              │ │",
+        "│ │                                          │ │",
+        "│ │ Продолжить                               │ │",
+        "│ │ Начать заново                            │ │",
+        "│ │ Настройки                                │ │",
+        "│ │ Сохранить и выйти                        │ │",
+        "│ │ Выйти без сохранения                     │ │",
+        "│ │                                          │ │",
+        "│ │↑↓ - выбор, Enter - выполнить             │ │",
+        "│ │                                          │ │",
+        "│ │                                          │ │",
+        "│ └ ←→/Tab - вкладки, ESC - продолжить ──────┘ │",
+        "└──────────────────────────────────────────────┘",
+    ]);
+}
+
+#[test]
+fn pause_menu_wraps_and_confirms_dropping_a_session_best() {
+    let mut menu = PauseMenu::default();
+    menu.up();
+    assert_eq!(menu.selected(), PauseAction::Quit);
+    menu.down();
+    menu.down();
+    assert_eq!(menu.selected(), PauseAction::Restart);
+    // Со счётом не лучше сессионного бросить партию можно сразу
+    assert_eq!(menu.choose(4, 7), Some(PauseAction::Restart));
+    // С лучшим — только со второго Enter, а уход со строки снимает вопрос
+    assert_eq!(menu.choose(9, 7), None);
+    menu.down();
+    menu.up();
+    assert_eq!(menu.choose(9, 7), None);
+
+    let theme = theme();
+    let mut game = mid_run();
+    let (settings, keymap) = (Settings::default(), Keymap::default());
+    let lines = render(48, 20, |f| {
+        board(f, &theme, &mut game);
+        let view = PauseView {
+            menu: &menu,
+            theme: &theme,
+            game: &game,
+            settings: &settings,
+            keymap: &keymap,
+            elapsed: Duration::from_secs(75),
+            session_best: 2,
+            lifetime: &Lifetime::default(),
+        };
+        f.render_widget(view, f.area());
+    });
+    assert_eq!(lines[10..17], [
+        "│ │ Начать заново                            │ │",
+        "│ │ Настройки                                │ │",
+        "│ │ Сохранить и выйти                        │ │",
+        "│ │ Выйти без сохранения                     │ │",
+        "│ │                                          │ │",
+        "│ │Это лучший счёт сессии. Enter - ещё раз,  │ │",
+        "│ │если точно                                │ │",
+    ]);
+    assert_eq!(menu.choose(9, 7), Some(PauseAction::Restart));
+    // Продолжить и настройки не спрашивают
+    menu.up();
+    assert_eq!(menu.choose(9, 7), Some(PauseAction::Resume));
+}
+
+#[test]
+fn pause_settings_cycle_growth_per_food() {
+    let theme = theme();
+    let mut game = mid_run();
+    let mut menu = PauseMenu::default();
+    menu.tab = PauseTab::Settings;
+    let mut settings = Settings::default();
+    for _ in 0..4 {
+        menu.down();
+    }
+    // Рост идёт по кругу: 1, 2, …, 5, 0
+    for _ in 0..Game::MAX_GROWTH_PER_FOOD {
+        menu.toggle(&mut settings);
+    }
+    assert_eq!((settings.growth_per_food, settings.sound), (0, false));
+    let keymap = Keymap::default();
+    let lines = render(48, 20, |f| {
+        board(f, &theme, &mut game);
+        let view = PauseView {
+            menu: &menu,
+            theme: &theme,
+            game: &game,
+            settings: &settings,
+            keymap: &keymap,
+            elapsed: Duration::from_secs(75),
+            session_best: 7,
+            lifetime: &Lifetime::default(),
+        };
+        f.render_widget(view, f.area());
+    });
+    assert_eq!(lines[4..10], [
+        "│ │[ ] Звук                                  │ │",
+        "│ │[ ] Сетка                                 │ │",
+        "│ │[x] Шкала длины                           │ │",
+        "│ │[ ] Без анимаций                          │ │",
+        "│ │[0] Рост за еду (со следующей партии)     │ │",
+        "│ │                                          │ │",
+    ]);
+}
+
+#[test]
+fn game_over_screen() {
+    let theme = theme();
+    let mut game = Fixture::new(28, 14)
+        .snake(&[(27, 3), (26, 3)])
+        .score(1)
+        .dead(DeathCause::Wall)
+        .build();
+    assert_eq!(render(30, 16, |f| game_over(f, &theme, &mut game, Duration::from_secs(42))), [
+        "┌────────────────────────────┐",
+        "│                            │",
+        "│                            │",
+        "│    ┌─────────────────┐     │",
+        "│    │Игра окончена!   │   oO│",
+        "│    │Врезались в стену│     │",
+        "│    │Счёт: 1          │     │",
+        "│    │Длина: 2         │     │",
+        "│    │Время: 00:42     │     │",
+        "│    │Пробел - рестарт │     │",
+        "│    │H - рекорды      │     │",
+        "│    │ESC - выход      │     │",
+        "│    └─────────────────┘     │",
+        "│                            │",
+        "│                            │",
+        "└────────────────────────────┘",
+    ]);
+}
+
+#[test]
+fn game_over_after_overtime() {
+    let theme = theme();
+    let mut game = Fixture::new(28, 14)
+        .snake(&[(27, 3), (26, 3)])
+        .score(1)
+        .dead(DeathCause::Wall)
+        .build();
+    game.overtime = true;
+    assert_eq!(render(30, 16, |f| game_over(f, &theme, &mut game, Duration::from_secs(42))), [
+        "┌────────────────────────────┐",
+        "│                            │",
+        "│    ┌──────────────────┐    │",
+        "│    │Игра окончена!    │    │",
+        "│    │Врезались в стену │  oO│",
+        "│    │Дошли до овертайма│    │",
+        "│    │Счёт: 1           │    │",
+        "│    │Длина: 2          │    │",
+        "│    │Время: 00:42      │    │",
+        "│    │Пробел - рестарт  │    │",
+        "│    │H - рекорды       │    │",
+        "│    │ESC - выход       │    │",
+        "│    └──────────────────┘    │",
+        "│                            │",
+        "│                            │",
+        "└────────────────────────────┘",
+    ]);
+}
+
+#[test]
+fn game_over_in_tiny_terminal() {
+    let theme = theme();
+    let mut game = Fixture::new(13, 6).dead(DeathCause::Wall).build();
+    assert_eq!(render(15, 8, |f| game_over(f, &theme, &mut game, Duration::ZERO)), [
+        "┌─────────────┐",
+        "│Игра         │",
+        "│окончена!    │",
+        "│Врезались в  │",
+        "│стену        │",
+        "│Счёт: 0      │",
+        "│Длина: 1     │",
+        "└─────────────┘",
+    ]);
+}
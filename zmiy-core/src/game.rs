@@ -0,0 +1,3413 @@
+use std::{collections::VecDeque, time::Duration};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DirectionSnake {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl DirectionSnake {
+    /// Буква направления для файлов: U, D, L или R
+    pub fn code(self) -> char {
+        match self {
+            DirectionSnake::Up => 'U',
+            DirectionSnake::Down => 'D',
+            DirectionSnake::Left => 'L',
+            DirectionSnake::Right => 'R',
+        }
+    }
+
+    pub fn opposite(self) -> Self {
+        match self {
+            DirectionSnake::Up => DirectionSnake::Down,
+            DirectionSnake::Down => DirectionSnake::Up,
+            DirectionSnake::Left => DirectionSnake::Right,
+            DirectionSnake::Right => DirectionSnake::Left,
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code {
+            "U" => Some(DirectionSnake::Up),
+            "D" => Some(DirectionSnake::Down),
+            "L" => Some(DirectionSnake::Left),
+            "R" => Some(DirectionSnake::Right),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Point {
+    pub x: u16,
+    pub y: u16,
+}
+
+/// Счёт по тикам за партию. Прореживается, чтобы хранить не больше `CAP` точек
+/// даже в очень долгих партиях.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ScoreHistory {
+    pub points: Vec<u64>,
+    /// Каждый какой тик записывается; нужен сохранениям, чтобы продолжить запись
+    pub stride: u32,
+    /// Тиков с последней записанной точки
+    pub since_last: u32,
+}
+
+impl Default for ScoreHistory {
+    fn default() -> Self {
+        Self { points: Vec::new(), stride: 1, since_last: 0 }
+    }
+}
+
+impl ScoreHistory {
+    pub const CAP: usize = 120;
+
+    pub fn record(&mut self, score: usize) {
+        self.since_last += 1;
+        if self.since_last < self.stride {
+            return;
+        }
+        self.since_last = 0;
+        self.points.push(score as u64);
+        if self.points.len() >= Self::CAP {
+            // Оставляем каждую вторую точку и дальше пишем в два раза реже
+            self.points = self.points.iter().copied().skip(1).step_by(2).collect();
+            self.stride *= 2;
+        }
+    }
+}
+
+/// Отчего закончилась игра
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DeathCause {
+    Wall,
+    SelfCollision,
+    /// Поле после изменения размера стало меньше змейки
+    BoardTooSmall,
+    /// Змейка заняла всё поле: еде негде появиться. Это победа.
+    BoardFull,
+    /// Врезались в сброшенный хвост (см. [`Game::shed`])
+    Obstacle,
+    /// Съели бомбу (см. [`Game::bombs`])
+    Bomb,
+}
+
+impl DeathCause {
+    pub const ALL: [DeathCause; 6] = [
+        DeathCause::Wall,
+        DeathCause::SelfCollision,
+        DeathCause::BoardTooSmall,
+        DeathCause::BoardFull,
+        DeathCause::Obstacle,
+        DeathCause::Bomb,
+    ];
+
+    /// Имя причины в файлах и JSON
+    pub fn code(self) -> &'static str {
+        match self {
+            DeathCause::Wall => "wall",
+            DeathCause::SelfCollision => "self_collision",
+            DeathCause::BoardTooSmall => "board_too_small",
+            DeathCause::BoardFull => "board_full",
+            DeathCause::Obstacle => "obstacle",
+            DeathCause::Bomb => "bomb",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|c| c.code() == code)
+    }
+
+    pub fn describe(self) -> &'static str {
+        match self {
+            DeathCause::Wall => "Врезались в стену",
+            DeathCause::SelfCollision => "Укусили себя",
+            DeathCause::BoardTooSmall => "Поле стало слишком маленьким",
+            DeathCause::BoardFull => "Змейка заняла всё поле",
+            DeathCause::Obstacle => "Врезались в сброшенный хвост",
+            DeathCause::Bomb => "Съели бомбу",
+        }
+    }
+}
+
+/// Режим игры
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Mode {
+    #[default]
+    Classic,
+    /// Две еды сразу: обычная растит змейку, синяя даёт два очка без роста
+    TwoFood,
+    /// Две комнаты, соединённые проходом: стены ставит встроенный уровень игры
+    TwoRooms,
+    /// Как на старом телефоне: маленькое поле [`Mode::NOKIA_BOARD`], скорость от 1 до 9
+    /// ([`Mode::NOKIA_TICKS`]) и еда, которая стоит столько очков, какая скорость
+    Nokia,
+    /// Бесконечная партия, правила которой мутируют каждые [`Game::MUTATION_EVERY`] еды
+    /// (см. [`Mutation`])
+    Endless,
+}
+
+impl Mode {
+    /// Имена режимов для `--mode`
+    pub const ARGS: &[&str] = &["classic", "two-food", "two-rooms", "nokia", "endless"];
+    pub const ALL: [Mode; 5] = [Mode::Classic, Mode::TwoFood, Mode::TwoRooms, Mode::Nokia, Mode::Endless];
+    /// Поле режима «нокиа»
+    pub const NOKIA_BOARD: (u16, u16) = (20, 11);
+    /// Интервалы между шагами на скоростях режима «нокиа», с первой по девятую
+    pub const NOKIA_TICKS: [Duration; 9] = [
+        Duration::from_millis(300),
+        Duration::from_millis(260),
+        Duration::from_millis(220),
+        Duration::from_millis(180),
+        Duration::from_millis(150),
+        Duration::from_millis(120),
+        Duration::from_millis(100),
+        Duration::from_millis(80),
+        Duration::from_millis(60),
+    ];
+
+    pub fn from_arg(arg: &str) -> Option<Self> {
+        match arg {
+            "classic" => Some(Mode::Classic),
+            "two-food" => Some(Mode::TwoFood),
+            "two-rooms" => Some(Mode::TwoRooms),
+            "nokia" => Some(Mode::Nokia),
+            "endless" => Some(Mode::Endless),
+            _ => None,
+        }
+    }
+
+    /// Название режима в таблице рекордов и файлах
+    pub fn name(self) -> &'static str {
+        match self {
+            Mode::Classic => "классика",
+            Mode::TwoFood => "две еды",
+            Mode::TwoRooms => "две комнаты",
+            Mode::Nokia => "нокиа",
+            Mode::Endless => "бесконечный",
+        }
+    }
+
+    /// Режим по названию из [`Mode::name`], например из сохранения
+    pub fn from_name(name: &str) -> Option<Self> {
+        Mode::ALL.into_iter().find(|m| m.name() == name)
+    }
+
+    /// Следующий режим по кругу: так режим выбирают на заставке
+    pub fn next(self) -> Self {
+        let i = Self::ALL.iter().position(|&m| m == self).unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+
+    /// Поле, на котором всегда идёт режим; `None` — поле любого размера
+    pub fn board(self) -> Option<(u16, u16)> {
+        match self {
+            Mode::Nokia => Some(Self::NOKIA_BOARD),
+            Mode::Classic | Mode::TwoFood | Mode::TwoRooms | Mode::Endless => None,
+        }
+    }
+
+    /// Сколько колонок терминала занимает клетка поля: в режиме «нокиа» две, клетки крупные
+    pub fn cell_width(self) -> u16 {
+        if self == Mode::Nokia { 2 } else { 1 }
+    }
+
+    /// Скорость режима «нокиа» от 1 до 9 для интервала `tick`: самая быстрая из тех,
+    /// чей интервал не короче `tick`
+    pub fn nokia_level(tick: Duration) -> usize {
+        Self::NOKIA_TICKS.iter().filter(|&&t| t >= tick).count().max(1)
+    }
+
+    /// Готовит партию к правилам режима. Скорость партии к этому времени уже задана.
+    pub fn setup(self, game: &mut Game) {
+        match self {
+            Mode::Classic | Mode::TwoRooms => {}
+            Mode::TwoFood => {
+                game.spawn_bonus();
+            }
+            Mode::Nokia => game.score_multiplier = Self::nokia_level(game.base_tick),
+            // У продолженной партии счёт еды до мутации уже свой
+            Mode::Endless => {
+                game.mutating = true;
+                if game.mutation_due == 0 {
+                    game.mutation_due = Game::MUTATION_EVERY;
+                }
+            }
+        }
+    }
+}
+
+/// Мутация правил бесконечного режима. Правило, которое включается насовсем,
+/// выпадает один раз, а ускорение, рост и зеркальное управление — сколько угодно.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Mutation {
+    Bombs,
+    Blackouts,
+    Caterpillars,
+    Bosses,
+    /// Еда убегает от головы (см. [`Game::fleeing`]); в повторах без
+    /// [`Game::moving_mutations`] — только появляется подальше от неё, из
+    /// [`Game::FLEE_CANDIDATES`] клеток на выбор
+    FleeingFood,
+    /// Шаг короче на [`Game::FASTER_PERCENT`] процентов
+    Faster,
+    /// Змейка растёт за еду на клетку больше
+    Growth,
+    /// Повороты наоборот на [`Game::MIRROR_TICKS`] шагов
+    Mirrored,
+    /// Внешние стены пропускают змейку (см. [`Game::wrapping`])
+    Wrap,
+}
+
+impl Mutation {
+    /// Новые мутации дописываются в конец: по порядку из этого списка выбирается мутация
+    /// в записанных партиях
+    pub const ALL: [Mutation; 9] = [
+        Mutation::Bombs,
+        Mutation::Blackouts,
+        Mutation::Caterpillars,
+        Mutation::Bosses,
+        Mutation::FleeingFood,
+        Mutation::Faster,
+        Mutation::Growth,
+        Mutation::Mirrored,
+        Mutation::Wrap,
+    ];
+
+    /// Имя мутации в файлах
+    pub fn code(self) -> &'static str {
+        match self {
+            Mutation::Bombs => "bombs",
+            Mutation::Blackouts => "blackouts",
+            Mutation::Caterpillars => "caterpillars",
+            Mutation::Bosses => "bosses",
+            Mutation::FleeingFood => "flee",
+            Mutation::Faster => "faster",
+            Mutation::Growth => "growth",
+            Mutation::Mirrored => "mirror",
+            Mutation::Wrap => "wrap",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|m| m.code() == code)
+    }
+
+    pub fn describe(self) -> &'static str {
+        match self {
+            Mutation::Bombs => "Появились бомбы",
+            Mutation::Blackouts => "Поле гаснет",
+            Mutation::Caterpillars => "Появились гусеницы",
+            Mutation::Bosses => "Появились боссы",
+            Mutation::FleeingFood => "Еда убегает от головы",
+            Mutation::Faster => "Змейка быстрее",
+            Mutation::Growth => "Змейка растёт быстрее",
+            Mutation::Mirrored => "Управление наоборот",
+            Mutation::Wrap => "Стены пропускают",
+        }
+    }
+
+    fn repeats(self) -> bool {
+        matches!(self, Mutation::Faster | Mutation::Growth | Mutation::Mirrored)
+    }
+}
+
+/// Вид еды
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FoodKind {
+    Normal,
+    /// Синяя еда режима «две еды»
+    Bonus,
+}
+
+impl FoodKind {
+    pub const ALL: [FoodKind; 2] = [FoodKind::Normal, FoodKind::Bonus];
+
+    /// Сколько очков даёт
+    pub fn points(self) -> usize {
+        match self {
+            FoodKind::Normal => 1,
+            FoodKind::Bonus => 2,
+        }
+    }
+
+    /// Растёт ли от неё змейка
+    pub fn grows(self) -> bool {
+        self == FoodKind::Normal
+    }
+}
+
+/// Бомба на поле: похожа на еду, но съевшая её змейка погибает
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Bomb {
+    pub at: Point,
+    /// Сколько шагов бомба ещё пролежит
+    pub left: u64,
+}
+
+/// Босс: еда 2×2, которую съедают за [`Game::BOSS_HITS`] отдельных касаний головой
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Boss {
+    /// Левая верхняя клетка
+    pub at: Point,
+    /// Сколько касаний ещё выдержит
+    pub hits: u8,
+}
+
+impl Boss {
+    /// Четыре клетки босса: верхний ряд, затем нижний
+    pub fn cells(&self) -> [Point; 4] {
+        let Point { x, y } = self.at;
+        [Point { x, y }, Point { x: x + 1, y }, Point { x, y: y + 1 }, Point { x: x + 1, y: y + 1 }]
+    }
+
+    pub fn covers(&self, p: Point) -> bool {
+        (self.at.x..=self.at.x + 1).contains(&p.x) && (self.at.y..=self.at.y + 1).contains(&p.y)
+    }
+}
+
+/// Зона скорости: пока голова в такой клетке, следующий шаг короче или длиннее
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Zone {
+    Fast,
+    Slow,
+}
+
+impl Zone {
+    pub const ALL: [Zone; 2] = [Zone::Fast, Zone::Slow];
+
+    /// Сколько процентов обычного интервала длится шаг из такой клетки
+    pub fn percent(self) -> u32 {
+        match self {
+            Zone::Fast => 70,
+            Zone::Slow => 130,
+        }
+    }
+}
+
+/// Фаза затмения (см. [`Game::blackout`])
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Blackout {
+    /// Поле видно целиком
+    Clear,
+    /// Скоро погаснет: рамка мигает
+    Warning,
+    /// Погасло: видны только голова и рамка
+    Dark,
+}
+
+/// Что произошло за один шаг игры
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GameEvent {
+    /// Голова переместилась в клетку
+    Moved(Point),
+    Ate { at: Point, kind: FoodKind },
+    /// Змейка выросла до такой длины
+    Grew(usize),
+    Died(DeathCause),
+    /// Начался новый уровень
+    LevelUp(usize),
+    /// Змейка заняла всё поле, партия выиграна
+    Won,
+    /// Голова коснулась босса в клетке `at`; `left` касаний осталось, 0 — босс съеден
+    BossHit { at: Point, left: u8 },
+    /// Голова съела звено гусеницы с конца; `left` звеньев осталось, 0 — гусеница
+    /// съедена целиком и принесла [`Game::CATERPILLAR_BONUS`]
+    Pellet { at: Point, left: usize },
+    /// Голова съела звено из середины гусеницы, и остальные звенья рассыпались
+    Collapsed(Point),
+    /// Голова на большой скорости прошла вплотную к своему телу или препятствию
+    /// в этой клетке (см. [`Game::NEAR_MISS_TICK`])
+    NearMiss(Point),
+    /// Змейка заняла больше [`Game::OVERTIME_PERCENT`] процентов поля, начался овертайм
+    Overtime,
+    /// Правила мутировали (см. [`Game::mutate`])
+    Mutated(Mutation),
+    /// Голова вошла в портал в клетке `from`, и змейка перенеслась головой в `to`
+    /// (см. [`Game::teleport`])
+    Teleported { from: Point, to: Point },
+    /// Голова подобрала щит в этой клетке
+    ShieldPicked(Point),
+    /// Щит поглотил столкновение, которое иначе было бы смертью по этой причине;
+    /// змейка этот шаг простояла на месте
+    Shielded(DeathCause),
+    /// Голова подобрала удвоитель очков в этой клетке (см. [`Game::doubled`])
+    Doubled(Point),
+    /// Голова подобрала заморозку в этой клетке (см. [`Game::frozen`])
+    Froze(Point),
+}
+
+/// Клетки совпадают или соседствуют, в том числе по диагонали
+fn touches(a: Point, b: Point) -> bool {
+    a.x.abs_diff(b.x) <= 1 && a.y.abs_diff(b.y) <= 1
+}
+
+/// Первая еда партии: от начала змейки в `start` на шестую часть поля вверх и влево,
+/// а там, где места не хватает, — вниз или вправо. При начале посреди поля это треть
+/// его ширины и высоты. На поле в три клетки шестая часть — ноль, и еда отходит от
+/// змейки хотя бы на клетку.
+fn first_food(width: u16, height: u16, start: Point) -> Point {
+    let mut offset = (width / 2 - width / 3, height / 2 - height / 3);
+    if offset == (0, 0) {
+        if width > 1 {
+            offset.0 = 1;
+        } else {
+            offset.1 = 1;
+        }
+    }
+    let shift = |at: u16, by: u16, size: u16| if at >= by { at - by } else { (at + by).min(size.saturating_sub(1)) };
+    Point { x: shift(start.x, offset.0, width), y: shift(start.y, offset.1, height) }
+}
+
+/// Сколько клеток змейки лежит в каждой клетке поля, чтобы проверка клетки не перебирала
+/// всю змейку. Счётчик, а не флаг: после сжатия поля клетки змейки могут совпадать.
+struct Occupancy {
+    width: u16,
+    height: u16,
+    cells: Vec<u16>,
+    /// Сколько клеток занято хотя бы раз
+    filled: usize,
+}
+
+impl Occupancy {
+    fn new<'a>(width: u16, height: u16, cells: impl IntoIterator<Item = &'a Point>) -> Self {
+        let mut grid = Self { width, height, cells: vec![0; width as usize * height as usize], filled: 0 };
+        for &p in cells {
+            grid.add(p);
+        }
+        grid
+    }
+
+    fn index(&self, p: Point) -> Option<usize> {
+        (p.x < self.width && p.y < self.height).then(|| p.y as usize * self.width as usize + p.x as usize)
+    }
+
+    fn contains(&self, p: Point) -> bool {
+        self.index(p).is_some_and(|i| self.cells[i] > 0)
+    }
+
+    fn add(&mut self, p: Point) {
+        if let Some(i) = self.index(p) {
+            if self.cells[i] == 0 {
+                self.filled += 1;
+            }
+            self.cells[i] += 1;
+        }
+    }
+
+    fn remove(&mut self, p: Point) {
+        if let Some(i) = self.index(p)
+            && self.cells[i] > 0
+        {
+            self.cells[i] -= 1;
+            if self.cells[i] == 0 {
+                self.filled -= 1;
+            }
+        }
+    }
+}
+
+pub struct Game {
+    /// Клетки змейки от головы к хвосту. Меняются только через методы `Game`,
+    /// чтобы не разойтись с сеткой занятых клеток.
+    snake: VecDeque<Point>,
+    occupied: Occupancy,
+    pub dir: DirectionSnake,
+    pub food: Point,
+    /// Синяя еда ([`FoodKind::Bonus`]); `None` — её нет на поле
+    pub bonus: Option<Point>,
+    /// Бомба; `None` — её нет на поле
+    pub bomb: Option<Bomb>,
+    /// Босс; `None` — его нет на поле. Пока он есть, обычная еда лежит под ним
+    /// в [`Boss::at`] и новая не появляется.
+    pub boss: Option<Boss>,
+    /// Звенья гусеницы от одного конца к другому; пусто — гусеницы нет на поле
+    pub caterpillar: Vec<Point>,
+    pub width: u16,
+    pub height: u16,
+    /// Причина конца игры; `None`, пока игра идёт
+    pub game_over: Option<DeathCause>,
+    pub score: usize,
+    pub history: ScoreHistory,
+    /// Сколько еды нужно на уровень; `None` — в режиме нет уровней
+    pub food_per_level: Option<usize>,
+    /// Сид, с которого начался генератор еды: одинаковый сид и одинаковые
+    /// ходы дают одинаковую партию
+    pub seed: u64,
+    /// Интервал между тиками без ускорений; по умолчанию `BASE_TICK`
+    pub base_tick: Duration,
+    /// Правило «скорость от длины»: на столько короче интервал за каждую клетку змейки
+    /// сверх первой; `None` — правило выключено
+    pub length_speedup: Option<Duration>,
+    /// Затмения: раз в [`Self::BLACKOUT_PERIOD`] шагов поле ненадолго гаснет (см. [`Game::blackout`])
+    pub blackouts: bool,
+    /// Бомбы: со счёта [`Self::BOMB_SCORE`] вместе с едой появляется бомба и лежит
+    /// [`Self::BOMB_TICKS`] шагов (см. [`Game::spawn_bomb`])
+    pub bombs: bool,
+    /// Очки за риск: еда стоит тем больше, чем короче шаг (см. [`Game::food_value`])
+    pub risk_scoring: bool,
+    /// Бонус за игру у края: очко за каждые [`Self::EDGE_TICKS`] шагов головой в клетке
+    /// у внешней стены (см. [`Game::edge_progress`]). Когда стены пропускают
+    /// ([`Self::wrapping`]), края нет, и бонус не идёт.
+    pub edge_bonus: bool,
+    /// Шагов у края, ещё не обменянных на очко; смерть их обнуляет
+    pub edge_ticks: u64,
+    /// Боссы: каждые [`Self::BOSS_EVERY`] очков вместо обычной еды появляется босс
+    /// (см. [`Game::spawn_boss`])
+    pub bosses: bool,
+    /// Гусеницы: после обычной еды с вероятностью 1 из [`Self::CATERPILLAR_CHANCE`]
+    /// появляется гусеница (см. [`Game::spawn_caterpillar`])
+    pub caterpillars: bool,
+    /// На сколько клеток змейка ещё вырастет в следующие шаги, по клетке за шаг
+    pub growth: usize,
+    /// На сколько клеток змейка вырастает за еду, от 0 до [`Self::MAX_GROWTH_PER_FOOD`].
+    /// При 0 змейка не растёт вовсе, поле не заполняется и выигрыша нет.
+    pub growth_per_food: usize,
+    /// Овертайм: когда змейка занимает больше [`Self::OVERTIME_PERCENT`] процентов клеток
+    /// без стен, партия переходит в [`Self::overtime`]. Выключен только в повторах,
+    /// записанных до его появления.
+    pub sudden_death: bool,
+    /// Партия дошла до овертайма: шаг самый короткий, еда стоит вдвое больше (см.
+    /// [`Game::tick_rate`] и [`Game::food_value`]). Обратно партия из него не выходит.
+    pub overtime: bool,
+    /// Во сколько раз больше очков даёт еда (см. [`Game::food_value`]); в режиме «нокиа» —
+    /// скорость партии
+    pub score_multiplier: usize,
+    /// Правила мутируют каждые [`Self::MUTATION_EVERY`] еды (см. [`Game::mutate`])
+    pub mutating: bool,
+    /// Выпавшие мутации по порядку
+    pub mutations: Vec<Mutation>,
+    /// Сколько ещё обычной еды до следующей мутации
+    pub mutation_due: usize,
+    /// Сколько ещё шагов повороты идут наоборот ([`Mutation::Mirrored`])
+    pub mirrored: u64,
+    /// Мутации, которые двигают поле: стены пропускают ([`Mutation::Wrap`]), еда убегает на
+    /// ходу ([`Mutation::FleeingFood`]). Выключены только в повторах, записанных до их
+    /// появления: там стены не пропускают, а еда лишь появляется подальше от головы.
+    pub moving_mutations: bool,
+    /// Голова, ушедшая за край поля, выходит с другой стороны ([`Mutation::Wrap`]).
+    /// Стены уровня внутри поля по-прежнему смертельны.
+    pub wrapping: bool,
+    /// Еда убегает ([`Mutation::FleeingFood`]): раз в [`Self::FLEE_EVERY`] шагов, пока голова
+    /// не дальше [`Self::FLEE_DISTANCE`], еда отходит от неё на клетку (см. [`Game::flee`])
+    pub fleeing: bool,
+    /// Счёт тает на очко за каждые [`Self::DECAY_TICKS`] шагов без еды, но не ниже нуля
+    pub score_decay: bool,
+    /// Шагов с последней съеденной еды, звена гусеницы или босса. Считается всегда, чтобы
+    /// правила, которым важно, давно ли змейка ела, брали его отсюда.
+    pub since_food: u64,
+    /// След слизи: клетка, которую освободил хвост, [`Self::SLIME_TICKS`] шагов остаётся
+    /// скользкой, и шаг после того, как на неё встала голова, вдвое длиннее (см. [`Game::slime`])
+    pub slime_trail: bool,
+    /// Порталы: после обычной еды с вероятностью 1 из [`Self::PORTAL_CHANCE`] появляется
+    /// портал, и змейка, вошедшая в него головой, переносится целиком (см. [`Game::teleport`])
+    pub teleports: bool,
+    /// Портал; `None` — его нет на поле
+    pub portal: Option<Point>,
+    /// Щиты: после обычной еды с вероятностью 1 из [`Self::SHIELD_CHANCE`] появляется щит,
+    /// и подобравшая его змейка переживает одно столкновение (см. [`Game::shield`])
+    pub shields: bool,
+    /// Щит на поле; `None` — его нет
+    pub shield_pickup: Option<Point>,
+    /// Сколько щитов у змейки, не больше [`Self::MAX_SHIELDS`]. Столкновение, которое
+    /// убило бы её, тратит щит, а змейка этот шаг стоит на месте.
+    pub shield: u8,
+    /// Удвоители: после обычной еды с вероятностью 1 из [`Self::DOUBLER_CHANCE`] появляется
+    /// удвоитель очков (см. [`Game::doubled`])
+    pub doubling: bool,
+    /// Удвоитель на поле; `None` — его нет
+    pub doubler: Option<Point>,
+    /// Сколько ещё шагов все прибавки к счёту удвоены. Каждый подобранный удвоитель
+    /// добавляет [`Self::DOUBLE_TICKS`] шагов, а множитель остаётся двойным.
+    pub doubled: u64,
+    /// Заморозки: после обычной еды с вероятностью 1 из [`Self::FREEZER_CHANCE`] появляется
+    /// заморозка, если на поле есть что морозить: бомбы или убегающая еда (см. [`Game::frozen`])
+    pub freezing: bool,
+    /// Заморозка на поле; `None` — её нет
+    pub freezer: Option<Point>,
+    /// Сколько ещё шагов мир заморожен: бомба не тикает, убегающая еда стоит. Змейка, её
+    /// эффекты, голод и таяние счёта идут как обычно. Каждая подобранная заморозка
+    /// добавляет [`Self::FREEZE_TICKS`] шагов.
+    pub frozen: u64,
+    /// Через сколько шагов снова можно заметить проход вплотную ([`GameEvent::NearMiss`])
+    pub near_miss_cooldown: u64,
+    /// Шагов с начала партии; по ним идёт расписание затмений
+    pub steps: u64,
+    /// Из скольких случайных свободных клеток [`Game::spawn_food`] выбирает самую далёкую
+    /// от головы; 1 — еда встаёт в любую свободную клетку с одной вероятностью
+    pub food_candidates: u32,
+    rng: StdRng,
+    /// Сколько чисел выдал генератор еды с начала партии; входит в [`Game::checksum`]
+    draws: u64,
+    /// Сколько съедено еды каждого вида, по порядку [`FoodKind::ALL`]
+    eaten: [usize; 2],
+    /// Сброшенные хвосты: клетки-препятствия в порядке сброса (см. [`Game::shed`])
+    obstacles: Vec<Point>,
+    blocked: Occupancy,
+    /// Через сколько шагов снова можно сбросить хвост; 0 — уже можно
+    pub shed_cooldown: u64,
+    /// Стены внутри поля, делящие его на комнаты; задаются уровнем и не меняются
+    walls: Vec<Point>,
+    walled: Occupancy,
+    /// Клетки зон скорости, по порядку [`Zone::ALL`]
+    zones: [Vec<Point>; 2],
+    zoned: [Occupancy; 2],
+    /// Клетки слизи с оставшимися им шагами, от самой старой к самой свежей
+    slime: Vec<(Point, u64)>,
+}
+
+impl Game {
+    pub const BASE_TICK: Duration = Duration::from_millis(120);
+    /// Шаг правила «скорость от длины» по умолчанию: 1,5 мс за клетку
+    pub const LENGTH_SPEEDUP: Duration = Duration::from_micros(1500);
+    /// Короче этого интервал от длины змейки не становится
+    pub const MIN_TICK: Duration = Duration::from_millis(40);
+    /// Сколько случайных клеток пробовать под еду, прежде чем перебрать свободные
+    pub const FOOD_TRIES: u32 = 64;
+    /// Сколько шагов ждать между сбросами хвоста
+    pub const SHED_COOLDOWN: u64 = 100;
+    /// Сколько очков стоит сброс хвоста
+    pub const SHED_PENALTY: usize = 3;
+    /// Раз в сколько шагов поле гаснет
+    pub const BLACKOUT_PERIOD: u64 = 300;
+    /// Сколько шагов мигает предупреждение перед затмением
+    pub const BLACKOUT_WARNING: u64 = 3;
+    /// Сколько шагов длится затмение
+    pub const BLACKOUT_LENGTH: u64 = 5;
+    /// С какого счёта появляются бомбы
+    pub const BOMB_SCORE: usize = 10;
+    /// Сколько шагов лежит бомба
+    pub const BOMB_TICKS: u64 = 60;
+    /// За сколько шагов у края начисляется очко
+    pub const EDGE_TICKS: u64 = 40;
+    /// Каждые сколько очков появляется босс
+    pub const BOSS_EVERY: usize = 50;
+    /// Сколько касаний выдерживает босс
+    pub const BOSS_HITS: u8 = 3;
+    /// Сколько очков стоит босс
+    pub const BOSS_POINTS: usize = 10;
+    /// На сколько клеток вырастает змейка, съевшая босса, если за еду она растёт на клетку
+    pub const BOSS_GROWTH: usize = 3;
+    /// Звеньев в гусенице, если на поле есть для неё прямой отрезок
+    pub const CATERPILLAR_LEN: usize = 4;
+    /// Короче гусеница не бывает: на тесном поле она просто не появляется
+    pub const CATERPILLAR_MIN_LEN: usize = 2;
+    /// После каждой обычной еды гусеница появляется с вероятностью 1 из стольких
+    pub const CATERPILLAR_CHANCE: u32 = 5;
+    /// Очков сверх звеньев за гусеницу, съеденную целиком
+    pub const CATERPILLAR_BONUS: usize = 3;
+    /// Проход вплотную к телу замечается, только если шаг не длиннее этого
+    pub const NEAR_MISS_TICK: Duration = Duration::from_millis(80);
+    /// Сколько шагов после прохода вплотную следующий не замечается: змейка, идущая
+    /// вдоль своего тела, иначе замечала бы его на каждом шаге
+    pub const NEAR_MISS_COOLDOWN: u64 = 30;
+    /// Сколько пятен зон скорости разбрасывает [`Game::scatter_zones`]
+    pub const ZONE_PATCHES: usize = 6;
+    /// Отличает сид пятен зон от сида еды
+    const ZONE_SALT: u64 = 0x5a0e_5a0e_5a0e_5a0e;
+    /// Больше стольких клеток за еду змейка не вырастает (см. [`Game::growth_per_food`])
+    pub const MAX_GROWTH_PER_FOOD: usize = 5;
+    /// Самая длинная змейка, с которой можно начать (см. [`Game::set_start_length`])
+    pub const MAX_START_LENGTH: usize = 10;
+    /// Сколько клеток до внешней стены оставляет случайное начало (см. [`Game::random_start`])
+    pub const START_MARGIN: u16 = 5;
+    /// Больше скольких процентов поля должна занять змейка, чтобы начался овертайм
+    pub const OVERTIME_PERCENT: usize = 80;
+    /// Отличает сид случайного начала от сида еды
+    const START_SALT: u64 = 0x57a7_57a7_57a7_57a7;
+    /// Раз во сколько обычной еды мутируют правила бесконечного режима
+    pub const MUTATION_EVERY: usize = 20;
+    /// Из скольких клеток выбирается еда, когда она убегает, в повторах без
+    /// [`Game::moving_mutations`]
+    pub const FLEE_CANDIDATES: u32 = 8;
+    /// Раз во сколько шагов убегающая еда отходит от головы: змейка быстрее и догоняет её
+    pub const FLEE_EVERY: u64 = 2;
+    /// Убегающая еда отходит, только если голова не дальше стольких клеток
+    pub const FLEE_DISTANCE: u16 = 4;
+    /// На сколько процентов короче становится шаг за ускорение
+    pub const FASTER_PERCENT: u32 = 10;
+    /// Сколько шагов повороты идут наоборот
+    pub const MIRROR_TICKS: u64 = 50;
+    /// Раз во сколько шагов без еды счёт тает на очко
+    pub const DECAY_TICKS: u64 = 100;
+    /// Сколько шагов сохнет слизь за хвостом
+    pub const SLIME_TICKS: u64 = 20;
+    /// После каждой обычной еды портал появляется с вероятностью 1 из стольких
+    pub const PORTAL_CHANCE: u32 = 4;
+    /// Ближе стольких клеток к порталу голова после переноса не встаёт
+    pub const TELEPORT_DISTANCE: u16 = 5;
+    /// После каждой обычной еды щит появляется с вероятностью 1 из стольких
+    pub const SHIELD_CHANCE: u32 = 6;
+    /// Больше стольких щитов у змейки не бывает; пока щит есть, новый не появляется
+    pub const MAX_SHIELDS: u8 = 1;
+    /// После каждой обычной еды удвоитель появляется с вероятностью 1 из стольких
+    pub const DOUBLER_CHANCE: u32 = 5;
+    /// На сколько шагов удваивает очки один удвоитель
+    pub const DOUBLE_TICKS: u64 = 100;
+    /// После каждой обычной еды заморозка появляется с вероятностью 1 из стольких
+    pub const FREEZER_CHANCE: u32 = 5;
+    /// На сколько шагов замораживает мир одна заморозка
+    pub const FREEZE_TICKS: u64 = 50;
+    /// Отличает сид мутаций от сида еды
+    const MUTATION_SALT: u64 = 0x3a7e_3a7e_3a7e_3a7e;
+
+    /// Новая партия со случайным сидом из системного времени. В wasm32 часов нет,
+    /// там сид передаётся в [`Game::with_seed`].
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn new(width: u16, height: u16) -> Self {
+        use std::time::{SystemTime, UNIX_EPOCH};
+
+        // Используем системное время как источник энтропии для сида
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("Ошибка получения времени")
+            .as_nanos() as u64;
+        Self::with_seed(width, height, seed)
+    }
+
+    /// Новая партия с заданным сидом генератора еды
+    pub fn with_seed(width: u16, height: u16, seed: u64) -> Self {
+        let mut snake = VecDeque::new();
+        let start = Point { x: width / 2, y: height / 2 };
+        snake.push_back(start);
+        let food = first_food(width, height, start);
+        Self {
+            occupied: Occupancy::new(width, height, &snake),
+            snake,
+            dir: DirectionSnake::Right,
+            food,
+            bonus: None,
+            bomb: None,
+            boss: None,
+            caterpillar: Vec::new(),
+            width,
+            height,
+            game_over: None,
+            score: 0,
+            history: ScoreHistory::default(),
+            food_per_level: None,
+            seed,
+            base_tick: Self::BASE_TICK,
+            length_speedup: None,
+            food_candidates: 1,
+            blackouts: false,
+            bombs: false,
+            risk_scoring: false,
+            edge_bonus: false,
+            edge_ticks: 0,
+            near_miss_cooldown: 0,
+            bosses: false,
+            caterpillars: false,
+            growth: 0,
+            growth_per_food: 1,
+            sudden_death: true,
+            overtime: false,
+            score_multiplier: 1,
+            mutating: false,
+            mutations: Vec::new(),
+            mutation_due: 0,
+            mirrored: 0,
+            moving_mutations: true,
+            wrapping: false,
+            fleeing: false,
+            score_decay: false,
+            since_food: 0,
+            slime_trail: false,
+            teleports: false,
+            portal: None,
+            shields: false,
+            shield_pickup: None,
+            shield: 0,
+            doubling: false,
+            doubler: None,
+            doubled: 0,
+            freezing: false,
+            freezer: None,
+            frozen: 0,
+            steps: 0,
+            rng: StdRng::seed_from_u64(seed),
+            draws: 0,
+            eaten: [0; 2],
+            obstacles: Vec::new(),
+            blocked: Occupancy::new(width, height, &[]),
+            shed_cooldown: 0,
+            walls: Vec::new(),
+            walled: Occupancy::new(width, height, &[]),
+            zones: [Vec::new(), Vec::new()],
+            zoned: [Occupancy::new(width, height, &[]), Occupancy::new(width, height, &[])],
+            slime: Vec::new(),
+        }
+    }
+
+    /// Перезапускает генератор еды со свежим сидом, взятым из него же, и возвращает этот сид.
+    /// После этого состояние генератора описывается одним числом, и его можно сохранить.
+    pub fn reseed(&mut self) -> u64 {
+        let seed = self.rng.random();
+        self.draws += 1;
+        self.rng = StdRng::seed_from_u64(seed);
+        seed
+    }
+
+    /// Клетки змейки от головы к хвосту
+    pub fn snake(&self) -> &VecDeque<Point> {
+        &self.snake
+    }
+
+    /// Ставит змейку целиком, например при загрузке сохранения
+    pub fn set_snake(&mut self, snake: impl IntoIterator<Item = Point>) {
+        self.snake = snake.into_iter().collect();
+        self.occupied = Occupancy::new(self.width, self.height, &self.snake);
+    }
+
+    /// Занята ли клетка змейкой. Не зависит от длины змейки.
+    pub fn is_snake(&self, p: Point) -> bool {
+        self.occupied.contains(p)
+    }
+
+    /// Сброшенные хвосты в порядке сброса
+    pub fn obstacles(&self) -> &[Point] {
+        &self.obstacles
+    }
+
+    /// Ставит препятствия целиком, например при загрузке сохранения
+    pub fn set_obstacles(&mut self, obstacles: impl IntoIterator<Item = Point>) {
+        self.obstacles = obstacles.into_iter().collect();
+        self.blocked = Occupancy::new(self.width, self.height, &self.obstacles);
+    }
+
+    /// Стоит ли в клетке препятствие
+    pub fn is_obstacle(&self, p: Point) -> bool {
+        self.blocked.contains(p)
+    }
+
+    /// Стены внутри поля
+    pub fn walls(&self) -> &[Point] {
+        &self.walls
+    }
+
+    /// Ставит стены целиком: из уровня, сохранения или повтора
+    pub fn set_walls(&mut self, walls: impl IntoIterator<Item = Point>) {
+        self.walls = walls.into_iter().collect();
+        self.walled = Occupancy::new(self.width, self.height, &self.walls);
+    }
+
+    /// Стоит ли в клетке стена
+    pub fn is_wall(&self, p: Point) -> bool {
+        self.walled.contains(p)
+    }
+
+    /// Клетки зоны `zone`
+    pub fn zones(&self, zone: Zone) -> &[Point] {
+        &self.zones[zone as usize]
+    }
+
+    /// Ставит клетки зоны `zone` целиком: из уровня, сохранения или повтора
+    pub fn set_zones(&mut self, zone: Zone, cells: impl IntoIterator<Item = Point>) {
+        self.zones[zone as usize] = cells.into_iter().collect();
+        self.zoned[zone as usize] = Occupancy::new(self.width, self.height, &self.zones[zone as usize]);
+    }
+
+    /// Зона скорости в клетке; если клетка попала в обе, быстрая важнее
+    pub fn zone(&self, p: Point) -> Option<Zone> {
+        Zone::ALL.into_iter().find(|&zone| self.zoned[zone as usize].contains(p))
+    }
+
+    /// Разбрасывает по полю [`Self::ZONE_PATCHES`] пятен зон скорости, быстрые и медленные
+    /// через одно, мимо стен. Пятна берутся из своего генератора с сидом партии, так что
+    /// еда от них не меняется, а одинаковый сид даёт одинаковые пятна.
+    pub fn scatter_zones(&mut self) {
+        if self.width == 0 || self.height == 0 {
+            return;
+        }
+        let mut rng = StdRng::seed_from_u64(self.seed ^ Self::ZONE_SALT);
+        let mut zones: [Vec<Point>; 2] = Default::default();
+        for zone in Zone::ALL.into_iter().cycle().take(Self::ZONE_PATCHES) {
+            let (w, h) = (rng.random_range(3..=6).min(self.width), rng.random_range(2..=4).min(self.height));
+            let (left, top) = (rng.random_range(0..=self.width - w), rng.random_range(0..=self.height - h));
+            let patch = (top..top + h).flat_map(|y| (left..left + w).map(move |x| Point { x, y }));
+            let free: Vec<Point> =
+                patch.filter(|&p| !self.is_wall(p) && !zones.iter().flatten().any(|&q| q == p)).collect();
+            zones[zone as usize].extend(free);
+        }
+        for zone in Zone::ALL {
+            self.set_zones(zone, std::mem::take(&mut zones[zone as usize]));
+        }
+    }
+
+    /// Ставит змейку головой в `at` и в сторону `dir`, а первую еду — так же относительно
+    /// головы, как в начале партии посреди поля. Тело в `len` клеток тянется по прямой
+    /// за головой и обрезается у края поля, стены или синей еды. Если еда попала на тело,
+    /// стену или синюю еду, она ставится как обычно, через [`Self::spawn_food`].
+    pub fn place_start(&mut self, at: Point, dir: DirectionSnake, len: usize) {
+        let mut body = vec![at];
+        while body.len() < len
+            && let Some(p) = self.neighbour(body[body.len() - 1], dir.opposite())
+            && !self.is_wall(p)
+            && self.bonus != Some(p)
+        {
+            body.push(p);
+        }
+        self.set_snake(body);
+        self.dir = dir;
+        self.food = first_food(self.width, self.height, at);
+        if self.is_snake(self.food) || self.is_wall(self.food) || self.bonus == Some(self.food) {
+            self.spawn_food();
+        }
+    }
+
+    /// Вытягивает змейку новой партии до `len` клеток (не больше [`Self::MAX_START_LENGTH`])
+    /// за головой, там же, где она стоит (см. [`Self::place_start`])
+    pub fn set_start_length(&mut self, len: usize) {
+        if let Some(&head) = self.snake.front() {
+            self.place_start(head, self.dir, len.min(Self::MAX_START_LENGTH));
+        }
+    }
+
+    /// Начало в случайной свободной клетке не ближе [`Self::START_MARGIN`] к внешней стене,
+    /// головой в случайную сторону, где нет стены вплотную. Как и пятна зон, начало берётся
+    /// из своего генератора с сидом партии: одинаковый сид даёт одинаковое начало. На поле,
+    /// где таких клеток нет, змейка остаётся посередине. Длина змейки не меняется.
+    pub fn random_start(&mut self) {
+        let margin = Self::START_MARGIN;
+        let (width, height, bonus) = (self.width, self.height, self.bonus);
+        let cells: Vec<Point> = (margin..height.saturating_sub(margin))
+            .flat_map(|y| (margin..width.saturating_sub(margin)).map(move |x| Point { x, y }))
+            .filter(|&p| !self.is_wall(p) && bonus != Some(p))
+            .collect();
+        if cells.is_empty() {
+            return;
+        }
+        let mut rng = StdRng::seed_from_u64(self.seed ^ Self::START_SALT);
+        let at = cells[rng.random_range(0..cells.len())];
+        let all = [DirectionSnake::Up, DirectionSnake::Down, DirectionSnake::Left, DirectionSnake::Right];
+        let open: Vec<DirectionSnake> =
+            all.into_iter().filter(|&dir| self.neighbour(at, dir).is_some_and(|p| !self.is_wall(p))).collect();
+        let dirs = if open.is_empty() { &all[..] } else { &open[..] };
+        let dir = dirs[rng.random_range(0..dirs.len())];
+        self.place_start(at, dir, self.snake.len());
+    }
+
+    /// Сбрасывает заднюю половину змейки: её клетки остаются на поле препятствиями,
+    /// змейка становится вдвое короче, а счёт уменьшается на [`Self::SHED_PENALTY`].
+    /// Возвращает, сколько клеток сброшено; `None`, если сбрасывать нечего, партия
+    /// кончилась или после прошлого сброса прошло меньше [`Self::SHED_COOLDOWN`] шагов.
+    pub fn shed(&mut self) -> Option<usize> {
+        if self.game_over.is_some() || self.shed_cooldown > 0 || self.snake.len() < 2 {
+            return None;
+        }
+        let dropped = self.snake.split_off(self.snake.len().div_ceil(2));
+        for &p in &dropped {
+            self.occupied.remove(p);
+            self.blocked.add(p);
+        }
+        self.obstacles.extend(&dropped);
+        self.score = self.score.saturating_sub(Self::SHED_PENALTY);
+        self.shed_cooldown = Self::SHED_COOLDOWN;
+        Some(dropped.len())
+    }
+
+    /// Один тик: змейка сдвигается на клетку. Возвращает всё, что при этом произошло.
+    pub fn step(&mut self) -> Vec<GameEvent> {
+        if self.game_over.is_some() { return Vec::new(); }
+        let Some(new_head) = self.next_head().filter(|&p| !self.is_wall(p)) else {
+            return self.crash(DeathCause::Wall);
+        };
+        if self.is_snake(new_head) {
+            return self.crash(DeathCause::SelfCollision);
+        }
+        if self.is_obstacle(new_head) {
+            return self.crash(DeathCause::Obstacle);
+        }
+        if self.bomb.is_some_and(|bomb| bomb.at == new_head) {
+            return self.crash(DeathCause::Bomb);
+        }
+        // Еда стоит столько, сколько давал шаг, которым до неё дошли
+        let value = self.food_value();
+        let fast = self.tick_rate() <= Self::NEAR_MISS_TICK;
+        self.shed_cooldown = self.shed_cooldown.saturating_sub(1);
+        self.near_miss_cooldown = self.near_miss_cooldown.saturating_sub(1);
+        self.mirrored = self.mirrored.saturating_sub(1);
+        self.slime.retain_mut(|(_, left)| {
+            *left -= 1;
+            *left > 0
+        });
+        self.steps += 1;
+        let mut events = vec![GameEvent::Moved(new_head)];
+        let from = self.snake[0];
+        if fast
+            && self.near_miss_cooldown == 0
+            && let Some(close) = self.near_miss(new_head)
+        {
+            self.near_miss_cooldown = Self::NEAR_MISS_COOLDOWN;
+            events.push(GameEvent::NearMiss(close));
+        }
+        self.snake.push_front(new_head);
+        self.occupied.add(new_head);
+        if !self.world_frozen()
+            && let Some(bomb) = &mut self.bomb
+        {
+            bomb.left -= 1;
+            if bomb.left == 0 {
+                self.bomb = None;
+            }
+        }
+        // Клетки босса — его, а не еды под ним
+        let kind = if self.boss.is_some_and(|boss| boss.covers(new_head)) {
+            None
+        } else if new_head == self.food {
+            Some(FoodKind::Normal)
+        } else if self.bonus == Some(new_head) {
+            Some(FoodKind::Bonus)
+        } else {
+            None
+        };
+        let pellet = self.caterpillar.iter().position(|&p| p == new_head);
+        // Первая клетка роста — сразу, на этом шаге, остальные — в следующие шаги
+        let grows = (kind.is_some_and(FoodKind::grows) || pellet.is_some()) && self.growth_per_food > 0;
+        if grows {
+            self.growth += self.growth_per_food - 1;
+        }
+        if !grows && self.growth > 0 {
+            self.growth -= 1;
+            events.push(GameEvent::Grew(self.snake.len()));
+        } else if !grows && let Some(tail) = self.snake.pop_back() {
+            self.occupied.remove(tail);
+            if self.slime_trail {
+                self.slime.retain(|&(p, _)| p != tail);
+                self.slime.push((tail, Self::SLIME_TICKS));
+            }
+        }
+        if let Some(kind) = kind {
+            let (level, before) = (self.level(), self.score);
+            self.gain(kind.points() * value);
+            self.eaten[kind as usize] += 1;
+            events.push(GameEvent::Ate { at: new_head, kind });
+            if grows {
+                events.push(GameEvent::Grew(self.snake.len()));
+            }
+            if self.level() > level {
+                events.push(GameEvent::LevelUp(self.level()));
+            }
+            let boss_due = self.bosses && self.boss.is_none() && before / Self::BOSS_EVERY < self.score / Self::BOSS_EVERY;
+            if kind == FoodKind::Normal && self.mutating {
+                self.mutation_due = self.mutation_due.saturating_sub(1);
+                if self.mutation_due == 0 {
+                    self.mutation_due = Self::MUTATION_EVERY;
+                    if let Some(mutation) = self.mutate() {
+                        events.push(GameEvent::Mutated(mutation));
+                    }
+                }
+            }
+            match kind {
+                FoodKind::Normal if boss_due && self.spawn_boss().is_some() => {}
+                FoodKind::Normal => {
+                    if self.spawn_food().is_none() && self.growth_per_food > 0 {
+                        self.game_over = Some(DeathCause::BoardFull);
+                        events.push(GameEvent::Won);
+                    } else {
+                        if self.bombs && self.bomb.is_none() && self.score >= Self::BOMB_SCORE {
+                            self.spawn_bomb();
+                        }
+                        if self.caterpillars && self.caterpillar.is_empty() {
+                            self.draws += 1;
+                            if self.rng.random_ratio(1, Self::CATERPILLAR_CHANCE) {
+                                self.spawn_caterpillar();
+                            }
+                        }
+                        if self.teleports && self.portal.is_none() {
+                            self.draws += 1;
+                            if self.rng.random_ratio(1, Self::PORTAL_CHANCE) {
+                                self.spawn_portal();
+                            }
+                        }
+                        if self.shields && self.shield_pickup.is_none() && self.shield < Self::MAX_SHIELDS {
+                            self.draws += 1;
+                            if self.rng.random_ratio(1, Self::SHIELD_CHANCE) {
+                                self.spawn_shield();
+                            }
+                        }
+                        if self.doubling && self.doubler.is_none() {
+                            self.draws += 1;
+                            if self.rng.random_ratio(1, Self::DOUBLER_CHANCE) {
+                                self.spawn_doubler();
+                            }
+                        }
+                        if self.freezing && self.freezer.is_none() && (self.bombs || self.fleeing) {
+                            self.draws += 1;
+                            if self.rng.random_ratio(1, Self::FREEZER_CHANCE) {
+                                self.spawn_freezer();
+                            }
+                        }
+                    }
+                }
+                FoodKind::Bonus => {
+                    self.spawn_bonus();
+                    if boss_due {
+                        self.spawn_boss();
+                    }
+                }
+            }
+        }
+        // Звено с конца съедается само, звено из середины рассыпает остальные
+        if let Some(i) = pellet {
+            let level = self.level();
+            self.gain(value);
+            if grows {
+                events.push(GameEvent::Grew(self.snake.len()));
+            }
+            if i == 0 || i + 1 == self.caterpillar.len() {
+                self.caterpillar.remove(i);
+                let left = self.caterpillar.len();
+                if left == 0 {
+                    self.gain(Self::CATERPILLAR_BONUS * value);
+                }
+                events.push(GameEvent::Pellet { at: new_head, left });
+            } else {
+                self.caterpillar.clear();
+                events.push(GameEvent::Collapsed(new_head));
+            }
+            if self.level() > level {
+                events.push(GameEvent::LevelUp(self.level()));
+            }
+        }
+        // Касание — это шаг в босса снаружи: ход по его клеткам касанием не считается
+        let mut slain = false;
+        if let Some(boss) = &mut self.boss
+            && boss.covers(new_head)
+            && !boss.covers(from)
+        {
+            boss.hits -= 1;
+            let left = boss.hits;
+            events.push(GameEvent::BossHit { at: new_head, left });
+            if left == 0 {
+                self.boss = None;
+                slain = true;
+                let level = self.level();
+                self.gain(Self::BOSS_POINTS * value);
+                self.growth += Self::BOSS_GROWTH * self.growth_per_food;
+                if self.level() > level {
+                    events.push(GameEvent::LevelUp(self.level()));
+                }
+                if self.spawn_food().is_none() && self.growth_per_food > 0 {
+                    self.game_over = Some(DeathCause::BoardFull);
+                    events.push(GameEvent::Won);
+                }
+            }
+        }
+        let doubler = self.doubler == Some(new_head);
+        if doubler {
+            self.doubler = None;
+            events.push(GameEvent::Doubled(new_head));
+        }
+        let freezer = self.freezer == Some(new_head);
+        if freezer {
+            self.freezer = None;
+            events.push(GameEvent::Froze(new_head));
+        }
+        if self.shield_pickup == Some(new_head) {
+            self.shield_pickup = None;
+            self.shield = (self.shield + 1).min(Self::MAX_SHIELDS);
+            events.push(GameEvent::ShieldPicked(new_head));
+        }
+        if self.portal == Some(new_head) && self.game_over.is_none() {
+            self.portal = None;
+            if let Some(to) = self.teleport() {
+                events.push(GameEvent::Teleported { from: new_head, to });
+            }
+        }
+        if kind.is_some() || pellet.is_some() || slain {
+            self.since_food = 0;
+        } else {
+            self.since_food += 1;
+            if self.score_decay && self.since_food.is_multiple_of(Self::DECAY_TICKS) {
+                self.score = self.score.saturating_sub(1);
+            }
+        }
+        if self.edge_bonus && !self.wrapping && self.at_edge(new_head) {
+            self.edge_ticks += 1;
+            if self.edge_ticks == Self::EDGE_TICKS {
+                self.edge_ticks = 0;
+                self.gain(1);
+            }
+        }
+        // Убегающая еда отходит через шаг; только что появившаяся и спрятанная под боссом стоят
+        if self.fleeing
+            && !self.world_frozen()
+            && kind.is_none()
+            && self.boss.is_none()
+            && self.game_over.is_none()
+            && self.steps.is_multiple_of(Self::FLEE_EVERY)
+        {
+            self.flee();
+        }
+        if self.sudden_death && !self.overtime && self.game_over.is_none() && self.crowded() {
+            self.overtime = true;
+            events.push(GameEvent::Overtime);
+        }
+        // Все очки этого шага уже начислены: удвоитель, подобранный сейчас, действует со следующего
+        self.doubled = self.doubled.saturating_sub(1);
+        if doubler {
+            self.doubled += Self::DOUBLE_TICKS;
+        }
+        // Заморозка тоже действует со следующего шага: этот шаг мир уже прожил
+        self.frozen = self.frozen.saturating_sub(1);
+        if freezer {
+            self.frozen += Self::FREEZE_TICKS;
+        }
+        self.history.record(self.score);
+        events
+    }
+
+    /// Еда отходит на клетку от головы, если та не дальше [`Self::FLEE_DISTANCE`]: в свободную
+    /// соседнюю клетку, откуда до головы дальше всего, из равных — в первую по порядку вверх,
+    /// вниз, влево, вправо. Свободна клетка так же, как для новой еды. Если отойти некуда,
+    /// еда стоит, и загнанную в угол её можно съесть.
+    pub fn flee(&mut self) {
+        let Some(&head) = self.snake.front() else { return };
+        let distance = |p: Point| p.x.abs_diff(head.x) + p.y.abs_diff(head.y);
+        if distance(self.food) > Self::FLEE_DISTANCE {
+            return;
+        }
+        let free = |p: Point| {
+            !self.is_snake(p)
+                && !self.is_obstacle(p)
+                && !self.is_wall(p)
+                && !self.item_at(p)
+                && !self.bomb.is_some_and(|bomb| touches(bomb.at, p))
+        };
+        let mut best = self.food;
+        for dir in [DirectionSnake::Up, DirectionSnake::Down, DirectionSnake::Left, DirectionSnake::Right] {
+            if let Some(p) = self.neighbour(self.food, dir)
+                && free(p)
+                && distance(p) > distance(best)
+            {
+                best = p;
+            }
+        }
+        self.food = best;
+    }
+
+    /// Змейка занимает больше [`Self::OVERTIME_PERCENT`] процентов клеток без стен
+    fn crowded(&self) -> bool {
+        let cells = self.width as usize * self.height as usize - self.walls.len();
+        self.occupied.filled * 100 > cells * Self::OVERTIME_PERCENT
+    }
+
+    /// Клетка тела или препятствия рядом с клеткой `head`, куда шагает голова, кроме шеи,
+    /// от которой голова отходит. Хвост, который на этом шаге уйдёт, тоже в счёт.
+    fn near_miss(&self, head: Point) -> Option<Point> {
+        let neck = self.snake[0];
+        [DirectionSnake::Up, DirectionSnake::Down, DirectionSnake::Left, DirectionSnake::Right]
+            .into_iter()
+            .filter_map(|dir| self.neighbour(head, dir))
+            .find(|&p| p != neck && (self.is_snake(p) || self.is_obstacle(p)))
+    }
+
+    /// Клетка у внешней стены: в первом или последнем ряду или столбце
+    pub fn at_edge(&self, Point { x, y }: Point) -> bool {
+        x == 0 || y == 0 || x + 1 == self.width || y + 1 == self.height
+    }
+
+    /// Счёт сейчас тает: змейка не ела [`Self::DECAY_TICKS`] шагов и больше, а терять
+    /// ещё есть что
+    pub fn decaying(&self) -> bool {
+        self.score_decay && self.since_food >= Self::DECAY_TICKS && self.score > 0
+    }
+
+    /// Мир заморожен ([`Self::frozen`]): бомба и убегающая еда стоят
+    pub fn world_frozen(&self) -> bool {
+        self.frozen > 0
+    }
+
+    /// Клетки слизи с оставшимися им шагами, от самой старой к самой свежей
+    pub fn slime(&self) -> &[(Point, u64)] {
+        &self.slime
+    }
+
+    /// Ставит слизь целиком, из сохранения
+    pub fn set_slime(&mut self, cells: impl IntoIterator<Item = (Point, u64)>) {
+        self.slime = cells.into_iter().filter(|&(_, left)| left > 0).collect();
+    }
+
+    /// В клетке лежит непросохшая слизь
+    pub fn is_slimy(&self, p: Point) -> bool {
+        self.slime.iter().any(|&(q, _)| q == p)
+    }
+
+    /// Сколько шагов у края накоплено к следующему очку и сколько нужно;
+    /// `None`, если бонус за игру у края выключен или стены пропускают
+    pub fn edge_progress(&self) -> Option<(u64, u64)> {
+        (self.edge_bonus && !self.wrapping).then_some((self.edge_ticks, Self::EDGE_TICKS))
+    }
+
+    /// Клетка, куда шагнёт голова; `None`, если там стена. Если стены пропускают
+    /// ([`Self::wrapping`]), за краем поля — клетка с другой стороны.
+    fn next_head(&self) -> Option<Point> {
+        let head @ Point { x, y } = self.snake[0];
+        self.neighbour(head, self.dir).or_else(|| {
+            self.wrapping.then(|| match self.dir {
+                DirectionSnake::Up => Point { x, y: self.height - 1 },
+                DirectionSnake::Down => Point { x, y: 0 },
+                DirectionSnake::Left => Point { x: self.width - 1, y },
+                DirectionSnake::Right => Point { x: 0, y },
+            })
+        })
+    }
+
+    /// Соседняя клетка в направлении `dir`; `None`, если она за краем поля
+    pub fn neighbour(&self, Point { x, y }: Point, dir: DirectionSnake) -> Option<Point> {
+        match dir {
+            DirectionSnake::Up => y.checked_sub(1).map(|y| Point { x, y }),
+            DirectionSnake::Down => (y + 1 < self.height).then(|| Point { x, y: y + 1 }),
+            DirectionSnake::Left => x.checked_sub(1).map(|x| Point { x, y }),
+            DirectionSnake::Right => (x + 1 < self.width).then(|| Point { x: x + 1, y }),
+        }
+    }
+
+    /// Столкновение по причине `cause`. Со щитом змейка не двигается, а щит тратится;
+    /// больше за этот шаг ничего не происходит. Без щита — смерть.
+    fn crash(&mut self, cause: DeathCause) -> Vec<GameEvent> {
+        if self.shield > 0 {
+            self.shield -= 1;
+            return vec![GameEvent::Shielded(cause)];
+        }
+        self.die(cause)
+    }
+
+    /// Прибавляет к счёту `points` очков, вдвое больше, пока действует удвоитель. Все
+    /// прибавки идут через неё, чтобы правила очков складывались с удвоением одинаково.
+    fn gain(&mut self, points: usize) {
+        self.score += if self.doubled > 0 { points * 2 } else { points };
+    }
+
+    fn die(&mut self, cause: DeathCause) -> Vec<GameEvent> {
+        self.game_over = Some(cause);
+        self.edge_ticks = 0;
+        vec![GameEvent::Died(cause)]
+    }
+
+    /// Подбираемые предметы: портал, щит, удвоитель и заморозка. Новый предмет
+    /// добавляется сюда и в [`Self::pickups_mut`], и его клетку сразу обходят все, кто
+    /// ставит что-то на поле, а при смене размера поля он пропадает, как остальные.
+    fn pickups(&self) -> [Option<Point>; 4] {
+        [self.portal, self.shield_pickup, self.doubler, self.freezer]
+    }
+
+    fn pickups_mut(&mut self) -> [&mut Option<Point>; 4] {
+        [&mut self.portal, &mut self.shield_pickup, &mut self.doubler, &mut self.freezer]
+    }
+
+    /// Клетки всего, что лежит на поле, кроме обычной еды и стен: синей еды, бомбы,
+    /// босса, гусеницы и подбираемых предметов
+    fn items(&self) -> impl Iterator<Item = Point> + '_ {
+        let boss = self.boss.map(|boss| boss.cells());
+        [self.bonus, self.bomb.map(|bomb| bomb.at)]
+            .into_iter()
+            .chain(self.pickups())
+            .flatten()
+            .chain(boss.into_iter().flatten())
+            .chain(self.caterpillar.iter().copied())
+    }
+
+    /// В клетке лежит что-то кроме обычной еды (см. [`Self::items`])
+    pub fn item_at(&self, p: Point) -> bool {
+        self.items().any(|item| item == p)
+    }
+
+    /// Ставит еду в случайную свободную клетку и возвращает её; `None`, если свободных нет.
+    ///
+    /// Сначала пробуются случайные клетки поля: пока поле почти пустое, это быстро и даёт
+    /// ту же еду, что и в записанных раньше повторах. Если за [`Self::FOOD_TRIES`] попыток
+    /// свободная клетка не нашлась, она выбирается прямо среди свободных. Обе ветки выбирают
+    /// любую свободную клетку с одной вероятностью. Клетки предметов (см. [`Self::item_at`]),
+    /// а также клетки вокруг бомбы тоже не свободны.
+    ///
+    /// На поле со стенами свободной считается только клетка, до которой голова может
+    /// доползти, не задев стен, препятствий и себя. Если таких нет, например змейка
+    /// сама перегородила проход, еда встаёт в любую свободную клетку.
+    ///
+    /// Если [`Self::food_candidates`] больше 1, выбирается столько клеток, и еда встаёт
+    /// в самую далёкую от головы по манхэттенскому расстоянию (из равных — в первую).
+    pub fn spawn_food(&mut self) -> Option<Point> {
+        let (items, bomb) = (self.items().collect::<Vec<_>>(), self.bomb);
+        let items = &items;
+        let taken = move |p: Point| items.contains(&p) || bomb.is_some_and(|bomb| touches(bomb.at, p));
+        let mut p = self.free_cell(taken)?;
+        if let Some(&head) = self.snake.front() {
+            let distance = |p: Point| p.x.abs_diff(head.x) + p.y.abs_diff(head.y);
+            for _ in 1..self.food_candidates {
+                let Some(candidate) = self.free_cell(taken) else { break };
+                if distance(candidate) > distance(p) {
+                    p = candidate;
+                }
+            }
+        }
+        self.food = p;
+        Some(p)
+    }
+
+    /// Ставит синюю еду так же, как [`Self::spawn_food`], но мимо обычной.
+    /// Если места нет, синей еды на поле не будет.
+    pub fn spawn_bonus(&mut self) -> Option<Point> {
+        let (items, food) = (self.items().collect::<Vec<_>>(), self.food);
+        let items = &items;
+        self.bonus = self.free_cell(move |p| p == food || items.contains(&p));
+        self.bonus
+    }
+
+    /// Ставит босса в случайный квадрат 2×2, где нет змейки, препятствий, стен и предметов
+    /// (см. [`Self::item_at`]), и прячет под него обычную еду. Если места нет, босса не будет.
+    pub fn spawn_boss(&mut self) -> Option<Boss> {
+        let (width, height) = (self.width, self.height);
+        let blocked: Vec<bool> = (0..height)
+            .flat_map(|y| (0..width).map(move |x| Point { x, y }))
+            .map(|p| self.is_snake(p) || self.is_obstacle(p) || self.is_wall(p) || self.item_at(p))
+            .collect();
+        let blocked = &blocked;
+        let taken = move |at: Point| {
+            at.x + 1 >= width
+                || at.y + 1 >= height
+                || Boss { at, hits: 0 }.cells().iter().any(|p| blocked[p.y as usize * width as usize + p.x as usize])
+        };
+        let at = self.free_cell(taken)?;
+        self.food = at;
+        self.boss = Some(Boss { at, hits: Self::BOSS_HITS });
+        self.boss
+    }
+
+    /// Кладёт бомбу на [`Self::BOMB_TICKS`] шагов в свободную клетку не рядом с обычной
+    /// едой, даже по диагонали, и не на другой предмет. Если места нет, бомбы не будет.
+    pub fn spawn_bomb(&mut self) -> Option<Bomb> {
+        let (items, food) = (self.items().collect::<Vec<_>>(), self.food);
+        let items = &items;
+        let at = self.free_cell(move |p| touches(food, p) || items.contains(&p));
+        self.bomb = at.map(|at| Bomb { at, left: Self::BOMB_TICKS });
+        self.bomb
+    }
+
+    /// Ставит гусеницу из [`Self::CATERPILLAR_LEN`] звеньев на прямой отрезок по горизонтали
+    /// или вертикали, где нет змейки, препятствий, стен, еды и предметов, а бомба не рядом.
+    /// Если такого отрезка нет, гусеница укорачивается до [`Self::CATERPILLAR_MIN_LEN`];
+    /// если нет и его, гусеницы не будет.
+    pub fn spawn_caterpillar(&mut self) -> Option<&[Point]> {
+        let (width, height) = (self.width, self.height);
+        let blocked: Vec<bool> = (0..height)
+            .flat_map(|y| (0..width).map(move |x| Point { x, y }))
+            .map(|p| {
+                self.is_snake(p)
+                    || self.is_obstacle(p)
+                    || self.is_wall(p)
+                    || self.food == p
+                    || self.item_at(p)
+                    || self.bomb.is_some_and(|bomb| touches(bomb.at, p))
+            })
+            .collect();
+        let blocked = &blocked;
+        let vertical = self.rng.random_bool(0.5);
+        self.draws += 1;
+        for len in (Self::CATERPILLAR_MIN_LEN..=Self::CATERPILLAR_LEN).rev() {
+            let len = len as u16;
+            for vertical in [vertical, !vertical] {
+                let run = move |at: Point| {
+                    (0..len).map(move |i| if vertical { Point { x: at.x, y: at.y + i } } else { Point { x: at.x + i, y: at.y } })
+                };
+                let taken = move |at: Point| {
+                    (if vertical { at.y + len > height } else { at.x + len > width })
+                        || run(at).any(|p| blocked[p.y as usize * width as usize + p.x as usize])
+                };
+                if let Some(at) = self.free_cell(taken) {
+                    self.caterpillar = run(at).collect();
+                    return Some(&self.caterpillar);
+                }
+            }
+        }
+        None
+    }
+
+    /// Ставит портал в клетку для подбираемого предмета (см. [`Self::free_pickup_cell`]).
+    /// Если места нет, портала не будет.
+    pub fn spawn_portal(&mut self) -> Option<Point> {
+        self.portal = self.free_pickup_cell();
+        self.portal
+    }
+
+    /// Ставит щит так же, как портал. Если места нет, щита не будет.
+    pub fn spawn_shield(&mut self) -> Option<Point> {
+        self.shield_pickup = self.free_pickup_cell();
+        self.shield_pickup
+    }
+
+    /// Ставит удвоитель так же, как портал. Если места нет, удвоителя не будет.
+    pub fn spawn_doubler(&mut self) -> Option<Point> {
+        self.doubler = self.free_pickup_cell();
+        self.doubler
+    }
+
+    /// Ставит заморозку так же, как портал. Если места нет, заморозки не будет.
+    pub fn spawn_freezer(&mut self) -> Option<Point> {
+        self.freezer = self.free_pickup_cell();
+        self.freezer
+    }
+
+    /// Случайная клетка для подбираемого предмета: не рядом с обычной едой, даже
+    /// по диагонали, и не под другим предметом (см. [`Self::item_at`])
+    fn free_pickup_cell(&mut self) -> Option<Point> {
+        let (items, food) = (self.items().collect::<Vec<_>>(), self.food);
+        let items = &items;
+        self.free_cell(move |p| touches(food, p) || items.contains(&p))
+    }
+
+    /// Переносит змейку целиком: голова встаёт в случайную свободную клетку не ближе
+    /// [`Self::TELEPORT_DISTANCE`] к прежней, а тело той же длины вытягивается за ней
+    /// по прямой. Клетка перед головой тоже должна быть свободна, иначе змейка разбилась бы
+    /// на первом же шаге. Как и гусеница, прямая ищется сначала в случайном направлении,
+    /// потом в остальных. Возвращает новую голову; `None`, если такой прямой на поле нет,
+    /// и тогда змейка остаётся где была.
+    pub fn teleport(&mut self) -> Option<Point> {
+        let from = *self.snake.front()?;
+        let len = i32::try_from(self.snake.len()).ok()?;
+        let (width, height) = (self.width, self.height);
+        let blocked: Vec<bool> = (0..height)
+            .flat_map(|y| (0..width).map(move |x| Point { x, y }))
+            .map(|p| {
+                self.is_snake(p)
+                    || self.is_obstacle(p)
+                    || self.is_wall(p)
+                    || self.food == p
+                    || self.item_at(p)
+                    || self.bomb.is_some_and(|bomb| touches(bomb.at, p))
+            })
+            .collect();
+        let blocked = &blocked;
+        let all = [DirectionSnake::Up, DirectionSnake::Down, DirectionSnake::Left, DirectionSnake::Right];
+        let first = self.rng.random_range(0..all.len());
+        self.draws += 1;
+        for i in 0..all.len() {
+            let dir = all[(first + i) % all.len()];
+            let (dx, dy) = match dir {
+                DirectionSnake::Up => (0, -1),
+                DirectionSnake::Down => (0, 1),
+                DirectionSnake::Left => (-1, 0),
+                DirectionSnake::Right => (1, 0),
+            };
+            // Клетка `k` прямой: 0 — голова, -1 — клетка перед ней, дальше тело к хвосту
+            let cell = move |at: Point, k: i32| {
+                let (x, y) = (i32::from(at.x) - dx * k, i32::from(at.y) - dy * k);
+                let (x, y) = (u16::try_from(x).ok()?, u16::try_from(y).ok()?);
+                (x < width && y < height).then_some(Point { x, y })
+            };
+            let taken = move |at: Point| {
+                at.x.abs_diff(from.x) + at.y.abs_diff(from.y) < Self::TELEPORT_DISTANCE
+                    || (-1..len).any(|k| cell(at, k).is_none_or(|p| blocked[p.y as usize * width as usize + p.x as usize]))
+            };
+            if let Some(at) = self.free_cell(taken) {
+                self.set_snake((0..len).filter_map(|k| cell(at, k)));
+                self.dir = dir;
+                return Some(at);
+            }
+        }
+        None
+    }
+
+    /// Случайная клетка без змейки, препятствий и тех, что отметил `taken`
+    fn free_cell(&mut self, taken: impl Fn(Point) -> bool + Copy) -> Option<Point> {
+        if self.width == 0 || self.height == 0 {
+            return None;
+        }
+        let reachable = self.reachable();
+        let reached = |p: Point| reachable.as_ref().is_none_or(|r| r.contains(p));
+        for _ in 0..Self::FOOD_TRIES {
+            let x = self.rng.random_range(0..self.width);
+            let y = self.rng.random_range(0..self.height);
+            self.draws += 2;
+            let p = Point { x, y };
+            if !self.is_snake(p) && !self.is_obstacle(p) && !self.is_wall(p) && !taken(p) && reached(p) {
+                return Some(p);
+            }
+        }
+        let (width, height) = (self.width, self.height);
+        let (occupied, blocked, walled) = (&self.occupied, &self.blocked, &self.walled);
+        let free = |reach: bool| {
+            (0..height).flat_map(move |y| (0..width).map(move |x| Point { x, y })).filter(move |&p| {
+                !occupied.contains(p) && !blocked.contains(p) && !walled.contains(p) && !taken(p) && (!reach || reached(p))
+            })
+        };
+        let reach = free(true).next().is_some();
+        let free = || free(reach);
+        let count = free().count();
+        if count == 0 {
+            return None;
+        }
+        let pick = self.rng.random_range(0..count);
+        self.draws += 1;
+        free().nth(pick)
+    }
+
+    /// Клетки, до которых голова может доползти, не задев стен, препятствий и себя.
+    /// `None`, если стен нет: без них вся свободная часть поля считается доступной.
+    fn reachable(&self) -> Option<Occupancy> {
+        if self.walls.is_empty() {
+            return None;
+        }
+        let head = *self.snake.front()?;
+        let mut seen = Occupancy::new(self.width, self.height, &[]);
+        let mut queue = VecDeque::from([head]);
+        while let Some(p) = queue.pop_front() {
+            for dir in [DirectionSnake::Up, DirectionSnake::Down, DirectionSnake::Left, DirectionSnake::Right] {
+                if let Some(next) = self.neighbour(p, dir)
+                    && !seen.contains(next)
+                    && !self.is_wall(next)
+                    && !self.is_obstacle(next)
+                    && !self.is_snake(next)
+                {
+                    seen.add(next);
+                    queue.push_back(next);
+                }
+            }
+        }
+        Some(seen)
+    }
+
+    /// Сколько съедено еды вида `kind`
+    pub fn eaten(&self, kind: FoodKind) -> usize {
+        self.eaten[kind as usize]
+    }
+
+    /// Контрольная сумма состояния, продолжающая сумму `prev`: по ней повтор проверяет,
+    /// что партия идёт так же, как при записи. Поля хешируются FNV-1a всегда в одном
+    /// порядке: размер поля, направление, длина и клетки змейки от головы к хвосту, еда,
+    /// синяя еда, если она есть, счёт, число выданных генератором еды чисел, число клеток
+    /// на выбор еды, если оно не 1, и, если хвост сбрасывался, препятствия с оставшимся
+    /// ожиданием сброса. Дальше идут стены, если они есть, и бомба с оставшимися ей
+    /// шагами, если она лежит, отметка очков за риск, если они включены, накопленные
+    /// шаги у края, если включён бонус за них, босс с оставшимися ему касаниями, если
+    /// он есть, несъеденный рост, если он есть, звенья гусеницы, если она на поле,
+    /// рост за еду, если он не 1, отметка овертайма, если он начался, множитель очков,
+    /// если он не 1, число мутаций с оставшимися шагами зеркального управления, если
+    /// правила уже мутировали, шаги без еды, если счёт тает, портал, если он есть, щит
+    /// на поле, если он есть, число щитов у змейки, если они есть, удвоитель на поле,
+    /// если он есть, оставшиеся шаги удвоения, если оно действует, заморозка на поле, если
+    /// она есть, оставшиеся шаги заморозки, если она действует, и клетки слизи
+    /// с оставшимися им шагами, если она есть.
+    pub fn checksum(&self, prev: u64) -> u64 {
+        const PRIME: u64 = 0x0100_0000_01b3;
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        let mut feed = |bytes: &[u8]| {
+            for &b in bytes {
+                hash = (hash ^ u64::from(b)).wrapping_mul(PRIME);
+            }
+        };
+        feed(&prev.to_le_bytes());
+        feed(&self.width.to_le_bytes());
+        feed(&self.height.to_le_bytes());
+        feed(&[self.dir.code() as u8]);
+        feed(&(self.snake.len() as u64).to_le_bytes());
+        for p in &self.snake {
+            feed(&p.x.to_le_bytes());
+            feed(&p.y.to_le_bytes());
+        }
+        feed(&self.food.x.to_le_bytes());
+        feed(&self.food.y.to_le_bytes());
+        if let Some(bonus) = self.bonus {
+            feed(&bonus.x.to_le_bytes());
+            feed(&bonus.y.to_le_bytes());
+        }
+        feed(&(self.score as u64).to_le_bytes());
+        feed(&self.draws.to_le_bytes());
+        if self.food_candidates != 1 {
+            feed(&self.food_candidates.to_le_bytes());
+        }
+        if !self.obstacles.is_empty() {
+            feed(&(self.obstacles.len() as u64).to_le_bytes());
+            for p in &self.obstacles {
+                feed(&p.x.to_le_bytes());
+                feed(&p.y.to_le_bytes());
+            }
+            feed(&self.shed_cooldown.to_le_bytes());
+        }
+        if !self.walls.is_empty() {
+            feed(&(self.walls.len() as u64).to_le_bytes());
+            for p in &self.walls {
+                feed(&p.x.to_le_bytes());
+                feed(&p.y.to_le_bytes());
+            }
+        }
+        if let Some(bomb) = self.bomb {
+            feed(&bomb.at.x.to_le_bytes());
+            feed(&bomb.at.y.to_le_bytes());
+            feed(&bomb.left.to_le_bytes());
+        }
+        if self.risk_scoring {
+            feed(b"risk");
+        }
+        if self.edge_bonus {
+            feed(&self.edge_ticks.to_le_bytes());
+        }
+        if let Some(boss) = self.boss {
+            feed(&boss.at.x.to_le_bytes());
+            feed(&boss.at.y.to_le_bytes());
+            feed(&[boss.hits]);
+        }
+        if self.growth > 0 {
+            feed(&(self.growth as u64).to_le_bytes());
+        }
+        if !self.caterpillar.is_empty() {
+            feed(&(self.caterpillar.len() as u64).to_le_bytes());
+            for p in &self.caterpillar {
+                feed(&p.x.to_le_bytes());
+                feed(&p.y.to_le_bytes());
+            }
+        }
+        if self.growth_per_food != 1 {
+            feed(&(self.growth_per_food as u64).to_le_bytes());
+        }
+        if self.overtime {
+            feed(b"overtime");
+        }
+        if self.score_multiplier != 1 {
+            feed(&(self.score_multiplier as u64).to_le_bytes());
+        }
+        if !self.mutations.is_empty() {
+            feed(&(self.mutations.len() as u64).to_le_bytes());
+            feed(&self.mirrored.to_le_bytes());
+        }
+        if self.score_decay {
+            feed(&self.since_food.to_le_bytes());
+        }
+        if let Some(portal) = self.portal {
+            feed(&portal.x.to_le_bytes());
+            feed(&portal.y.to_le_bytes());
+        }
+        if let Some(shield) = self.shield_pickup {
+            feed(&shield.x.to_le_bytes());
+            feed(&shield.y.to_le_bytes());
+        }
+        if self.shield > 0 {
+            feed(&[self.shield]);
+        }
+        if let Some(doubler) = self.doubler {
+            feed(&doubler.x.to_le_bytes());
+            feed(&doubler.y.to_le_bytes());
+        }
+        if self.doubled > 0 {
+            feed(&self.doubled.to_le_bytes());
+        }
+        if let Some(freezer) = self.freezer {
+            feed(&freezer.x.to_le_bytes());
+            feed(&freezer.y.to_le_bytes());
+        }
+        if self.frozen > 0 {
+            feed(&self.frozen.to_le_bytes());
+        }
+        if !self.slime.is_empty() {
+            feed(&(self.slime.len() as u64).to_le_bytes());
+            for &(p, left) in &self.slime {
+                feed(&p.x.to_le_bytes());
+                feed(&p.y.to_le_bytes());
+                feed(&left.to_le_bytes());
+            }
+        }
+        hash
+    }
+
+    /// Поле в виде текста теми же символами, что и на экране, по строке на ряд
+    pub fn to_text(&self) -> String {
+        let mut out = String::with_capacity((self.width as usize + 1) * self.height as usize);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let p = Point { x, y };
+                let c = if self.snake.front() == Some(&p) {
+                    'O'
+                } else if self.is_snake(p) {
+                    'o'
+                } else if self.boss.is_some_and(|boss| boss.covers(p)) {
+                    'B'
+                } else if self.food == p {
+                    '*'
+                } else if self.bonus == Some(p) {
+                    '+'
+                } else if self.caterpillar.contains(&p) {
+                    '='
+                } else if self.bomb.is_some_and(|bomb| bomb.at == p) {
+                    '@'
+                } else if self.portal == Some(p) {
+                    '&'
+                } else if self.shield_pickup == Some(p) {
+                    'S'
+                } else if self.doubler == Some(p) {
+                    '2'
+                } else if self.freezer == Some(p) {
+                    'F'
+                } else if self.is_obstacle(p) {
+                    '#'
+                } else if self.is_wall(p) {
+                    '█'
+                } else {
+                    ' '
+                };
+                out.push(c);
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Текущий интервал между тиками. Единственное место, где считается скорость:
+    /// им пользуются и главный цикл, и строка статуса.
+    ///
+    /// С правилом «скорость от длины» интервал — `base_tick` минус шаг правила за каждую
+    /// клетку сверх первой, но не меньше [`Self::MIN_TICK`] (или `base_tick`, если он
+    /// и так меньше). Считается от текущей длины, поэтому укоротившаяся змейка
+    /// сразу замедляется.
+    ///
+    /// Если голова стоит в зоне скорости, получившийся интервал умножается на процент
+    /// зоны ([`Zone::percent`]) уже после границы: быстрая зона ускоряет и змейку,
+    /// разогнавшуюся до [`Self::MIN_TICK`]. Голова на слизи ([`Game::slime`]) удваивает
+    /// то, что получилось.
+    ///
+    /// В овертайме интервал — всегда [`Self::MIN_TICK`] (или `base_tick`, если он меньше),
+    /// без длины, зон и слизи.
+    pub fn tick_rate(&self) -> Duration {
+        if self.overtime {
+            return Self::MIN_TICK.min(self.base_tick);
+        }
+        let rate = match self.length_speedup {
+            Some(step) => {
+                let cells = u32::try_from(self.snake.len().saturating_sub(1)).unwrap_or(u32::MAX);
+                self.base_tick.saturating_sub(step.saturating_mul(cells)).max(Self::MIN_TICK.min(self.base_tick))
+            }
+            None => self.base_tick,
+        };
+        let rate = match self.snake.front().and_then(|&head| self.zone(head)) {
+            Some(zone) => rate * zone.percent() / 100,
+            None => rate,
+        };
+        if self.snake.front().is_some_and(|&head| self.is_slimy(head)) { rate * 2 } else { rate }
+    }
+
+    /// Во сколько раз еда дороже обычного. С очками за риск это `BASE_TICK / tick_rate()`
+    /// с округлением вверх: на скорости вдвое выше базовой еда стоит вдвое больше,
+    /// а на медленной — всё равно хотя бы столько, сколько обычно. Без правила — 1.
+    /// В овертайме всё это удваивается, а потом умножается на [`Self::score_multiplier`].
+    pub fn food_value(&self) -> usize {
+        let value = if self.risk_scoring {
+            let tick = self.tick_rate().as_nanos().max(1);
+            Self::BASE_TICK.as_nanos().div_ceil(tick) as usize
+        } else {
+            1
+        };
+        let value = if self.overtime { value * 2 } else { value };
+        value * self.score_multiplier
+    }
+
+    /// Фаза затмения после [`Self::steps`] шагов. Каждые [`Self::BLACKOUT_PERIOD`] шагов
+    /// последние [`Self::BLACKOUT_LENGTH`] поле тёмное, а [`Self::BLACKOUT_WARNING`] шагов
+    /// перед ними об этом предупреждает рамка. Первое затмение — в конце первого периода,
+    /// после конца партии поле видно целиком.
+    pub fn blackout(&self) -> Blackout {
+        if !self.blackouts || self.game_over.is_some() {
+            return Blackout::Clear;
+        }
+        let left = Self::BLACKOUT_PERIOD - self.steps % Self::BLACKOUT_PERIOD;
+        if left <= Self::BLACKOUT_LENGTH {
+            Blackout::Dark
+        } else if left <= Self::BLACKOUT_LENGTH + Self::BLACKOUT_WARNING {
+            Blackout::Warning
+        } else {
+            Blackout::Clear
+        }
+    }
+
+    /// Текущий уровень, начиная с 1
+    pub fn level(&self) -> usize {
+        self.food_per_level.map_or(1, |n| self.score / n + 1)
+    }
+
+    /// Прогресс к следующему уровню: сколько еды съедено из скольких нужно.
+    /// `None`, если в режиме нет уровней.
+    pub fn level_progress(&self) -> Option<(usize, usize)> {
+        self.food_per_level.map(|n| (self.score % n, n))
+    }
+
+    /// Скорость в клетках в секунду
+    pub fn cells_per_sec(&self) -> f64 {
+        1.0 / self.tick_rate().as_secs_f64()
+    }
+
+    /// Меняет размер поля, сохраняя прогресс.
+    ///
+    /// Змейка сдвигается целиком, чтобы поместиться в новые границы, а то, что
+    /// всё равно не влезает, прижимается к краю. Игра заканчивается, только если
+    /// поле стало меньше самой змейки.
+    pub fn resize(&mut self, width: u16, height: u16) {
+        self.width = width;
+        self.height = height;
+        if (width as usize) * (height as usize) < self.snake.len() {
+            self.game_over = Some(DeathCause::BoardTooSmall);
+            self.occupied = Occupancy::new(width, height, &self.snake);
+            return;
+        }
+
+        // Сдвиг влево/вверх, но не дальше нулевой координаты
+        let min_x = self.snake.iter().map(|p| p.x).min().unwrap_or(0);
+        let max_x = self.snake.iter().map(|p| p.x).max().unwrap_or(0);
+        let min_y = self.snake.iter().map(|p| p.y).min().unwrap_or(0);
+        let max_y = self.snake.iter().map(|p| p.y).max().unwrap_or(0);
+        let dx = (max_x + 1).saturating_sub(width).min(min_x);
+        let dy = (max_y + 1).saturating_sub(height).min(min_y);
+        for p in self.snake.iter_mut() {
+            p.x = (p.x - dx).min(width - 1);
+            p.y = (p.y - dy).min(height - 1);
+        }
+        self.occupied = Occupancy::new(width, height, &self.snake);
+        // Препятствия не двигаются: за краем поля или под змейкой они пропадают
+        let obstacles: Vec<Point> =
+            self.obstacles.iter().copied().filter(|&p| p.x < width && p.y < height && !self.is_snake(p)).collect();
+        self.set_obstacles(obstacles);
+        let walls: Vec<Point> = self.walls.iter().copied().filter(|&p| p.x < width && p.y < height).collect();
+        self.set_walls(walls);
+        for zone in Zone::ALL {
+            let cells: Vec<Point> = self.zones(zone).iter().copied().filter(|&p| p.x < width && p.y < height).collect();
+            self.set_zones(zone, cells);
+        }
+        self.slime.retain(|&(p, _)| p.x < width && p.y < height);
+
+        // Бомба, гусеница и подбираемые предметы не переставляются: за краем поля или под
+        // змейкой они просто пропадают, гусеница — целиком
+        let gone = |p: Point| p.x >= width || p.y >= height || self.is_snake(p) || self.is_obstacle(p) || self.is_wall(p);
+        let bomb = self.bomb.filter(|bomb| !gone(bomb.at));
+        let caterpillar = self.caterpillar.iter().any(|&p| gone(p));
+        let pickups = self.pickups().map(|pickup| pickup.filter(|&p| !gone(p)));
+        self.bomb = bomb;
+        if caterpillar {
+            self.caterpillar.clear();
+        }
+        for (slot, pickup) in self.pickups_mut().into_iter().zip(pickups) {
+            *slot = pickup;
+        }
+
+        // Босс тоже не переставляется, а еда под ним остаётся на месте и выйдет из-под него
+        if let Some(boss) = self.boss
+            && (boss.at.x + 1 >= width
+                || boss.at.y + 1 >= height
+                || boss.cells().iter().any(|&p| self.is_obstacle(p) || self.is_wall(p)))
+        {
+            self.boss = None;
+        }
+
+
+        let taken = |p: Point| self.is_snake(p) || self.is_obstacle(p) || self.is_wall(p);
+        if self.boss.is_none() && (self.food.x >= width || self.food.y >= height || taken(self.food)) {
+            self.spawn_food();
+        }
+        if let Some(bonus) = self.bonus
+            && (bonus.x >= width
+                || bonus.y >= height
+                || self.is_snake(bonus)
+                || self.is_obstacle(bonus)
+                || self.is_wall(bonus)
+                || bonus == self.food
+                || self.boss.is_some_and(|boss| boss.covers(bonus)))
+        {
+            self.spawn_bonus();
+        }
+    }
+
+    /// Следующая мутация правил: выбирается по сиду партии и числу прошлых мутаций,
+    /// поэтому партии с одним сидом мутируют одинаково. `None`, если мутировать нечему.
+    pub fn mutate(&mut self) -> Option<Mutation> {
+        let available: Vec<Mutation> = Mutation::ALL
+            .into_iter()
+            .filter(|&m| m.repeats() || !self.mutations.contains(&m))
+            .filter(|&m| m != Mutation::Faster || self.base_tick > Self::MIN_TICK)
+            .filter(|&m| m != Mutation::Growth || (1..Self::MAX_GROWTH_PER_FOOD).contains(&self.growth_per_food))
+            .filter(|&m| m != Mutation::Wrap || self.moving_mutations)
+            .collect();
+        if available.is_empty() {
+            return None;
+        }
+        let mut rng = StdRng::seed_from_u64(self.seed ^ Self::MUTATION_SALT ^ self.mutations.len() as u64);
+        let mutation = available[rng.random_range(0..available.len())];
+        match mutation {
+            Mutation::Bombs => self.bombs = true,
+            Mutation::Blackouts => self.blackouts = true,
+            Mutation::Caterpillars => self.caterpillars = true,
+            Mutation::Bosses => self.bosses = true,
+            Mutation::FleeingFood if self.moving_mutations => self.fleeing = true,
+            Mutation::FleeingFood => self.food_candidates = self.food_candidates.max(Self::FLEE_CANDIDATES),
+            Mutation::Faster => {
+                self.base_tick = (self.base_tick * (100 - Self::FASTER_PERCENT) / 100).max(Self::MIN_TICK);
+            }
+            Mutation::Growth => self.growth_per_food += 1,
+            Mutation::Mirrored => self.mirrored += Self::MIRROR_TICKS,
+            // Накопленные у края шаги сгорают: бонуса за край больше нет
+            Mutation::Wrap => {
+                self.wrapping = true;
+                self.edge_ticks = 0;
+            }
+        }
+        self.mutations.push(mutation);
+        Some(mutation)
+    }
+
+    /// Поворот; `false`, если он отклонён как разворот на 180. Пока управление
+    /// зеркальное ([`Game::mirrored`]), змейка поворачивает в противоположную сторону.
+    pub fn change_dir(&mut self, dir: DirectionSnake) -> bool {
+        let dir = if self.mirrored > 0 { dir.opposite() } else { dir };
+        // Если длина змейки 1 — разрешаем любое направление
+        if self.snake.len() == 1 {
+            self.dir = dir;
+            return true;
+        }
+        // Не даём развернуться на 180
+        match (self.dir, dir) {
+            (DirectionSnake::Up, DirectionSnake::Down) => false,
+            (DirectionSnake::Down, DirectionSnake::Up) => false,
+            (DirectionSnake::Left, DirectionSnake::Right) => false,
+            (DirectionSnake::Right, DirectionSnake::Left) => false,
+            _ => {
+                self.dir = dir;
+                true
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snake_of(points: &[(u16, u16)]) -> VecDeque<Point> {
+        points.iter().map(|&(x, y)| Point { x, y }).collect()
+    }
+
+    #[test]
+    fn wall_death_in_every_direction() {
+        let cases = [
+            (DirectionSnake::Up, (2, 0)),
+            (DirectionSnake::Down, (2, 4)),
+            (DirectionSnake::Left, (0, 2)),
+            (DirectionSnake::Right, (4, 2)),
+        ];
+        for (dir, (x, y)) in cases {
+            let mut game = Game::new(5, 5);
+            game.set_snake(snake_of(&[(x, y)]));
+            game.food = Point { x: 1, y: 1 };
+            game.dir = dir;
+            game.step();
+            assert_eq!(game.game_over, Some(DeathCause::Wall));
+        }
+    }
+
+    #[test]
+    fn self_collision_death() {
+        let mut game = Game::new(10, 10);
+        // Голова на (2,2), тело огибает её так, что поворот вниз упирается в себя
+        game.set_snake(snake_of(&[(2, 2), (3, 2), (3, 3), (2, 3), (1, 3)]));
+        game.food = Point { x: 8, y: 8 };
+        game.dir = DirectionSnake::Down;
+        game.step();
+        assert_eq!(game.game_over, Some(DeathCause::SelfCollision));
+    }
+
+    #[test]
+    fn board_too_small_death_on_resize() {
+        let mut game = Game::new(10, 10);
+        game.set_snake(snake_of(&[(1, 1), (2, 1), (3, 1), (4, 1), (5, 1)]));
+        game.resize(2, 2);
+        assert_eq!(game.game_over, Some(DeathCause::BoardTooSmall));
+    }
+
+    #[test]
+    fn moves_one_cell_per_step() {
+        let mut game = Game::new(10, 10);
+        game.food = Point { x: 0, y: 0 };
+        game.set_snake(snake_of(&[(5, 5)]));
+        game.dir = DirectionSnake::Right;
+        game.step();
+        assert_eq!(game.snake, snake_of(&[(6, 5)]));
+        game.change_dir(DirectionSnake::Down);
+        game.step();
+        assert_eq!(game.snake, snake_of(&[(6, 6)]));
+        assert_eq!(game.game_over, None);
+    }
+
+    #[test]
+    fn grows_and_scores_on_food() {
+        let mut game = Game::new(10, 10);
+        game.set_snake(snake_of(&[(5, 5), (4, 5)]));
+        game.food = Point { x: 6, y: 5 };
+        game.dir = DirectionSnake::Right;
+        game.step();
+        assert_eq!(game.snake, snake_of(&[(6, 5), (5, 5), (4, 5)]));
+        assert_eq!(game.score, 1);
+        assert!(!game.snake.contains(&game.food));
+    }
+
+    #[test]
+    fn growth_per_food_spreads_over_the_next_steps() {
+        let mut game = Game::with_seed(20, 10, 1);
+        game.growth_per_food = 3;
+        game.set_snake(snake_of(&[(5, 5), (4, 5)]));
+        game.food = Point { x: 6, y: 5 };
+        game.dir = DirectionSnake::Right;
+        assert!(game.step().contains(&GameEvent::Grew(3)));
+        assert_eq!((game.snake().len(), game.growth), (3, 2));
+        game.food = Point { x: 0, y: 0 };
+        let lengths: Vec<usize> = (0..3)
+            .map(|_| {
+                game.step();
+                game.snake().len()
+            })
+            .collect();
+        assert_eq!(lengths, [4, 5, 5]);
+
+        // Без роста еда даёт только очки
+        let mut game = Game::with_seed(20, 10, 1);
+        game.growth_per_food = 0;
+        game.set_snake(snake_of(&[(5, 5), (4, 5)]));
+        game.food = Point { x: 6, y: 5 };
+        game.dir = DirectionSnake::Right;
+        let events = game.step();
+        assert!(!events.iter().any(|e| matches!(e, GameEvent::Grew(_))));
+        assert_eq!((game.snake().len(), game.score, game.growth), (2, 1, 0));
+        // и поле не заполняется: там, где змейка с ростом выиграла бы, игра идёт дальше
+        let mut game = Game::with_seed(2, 1, 1);
+        game.growth_per_food = 0;
+        game.set_snake(snake_of(&[(0, 0)]));
+        game.food = Point { x: 1, y: 0 };
+        game.dir = DirectionSnake::Right;
+        assert!(!game.step().contains(&GameEvent::Won));
+        assert_eq!((game.game_over, game.food), (None, Point { x: 0, y: 0 }));
+    }
+
+    #[test]
+    fn overtime_starts_once_the_board_is_crowded() {
+        // Поле 5×2, змейка в 8 клеток занимает ровно 80% — это ещё не овертайм
+        let crowded = || {
+            let mut game = Game::with_seed(5, 2, 1);
+            game.set_snake(snake_of(&[(2, 1), (3, 1), (4, 1), (4, 0), (3, 0), (2, 0), (1, 0), (0, 0)]));
+            game.food = Point { x: 1, y: 1 };
+            game.dir = DirectionSnake::Left;
+            game
+        };
+        let mut game = crowded();
+        assert_eq!((game.overtime, game.tick_rate(), game.food_value()), (false, Game::BASE_TICK, 1));
+        let events = game.step();
+        assert!(events.contains(&GameEvent::Overtime));
+        assert_eq!((game.overtime, game.tick_rate(), game.food_value()), (true, Game::MIN_TICK, 2));
+        // Еда в овертайме стоит вдвое больше, и второй раз он не начинается
+        assert_eq!(game.food, Point { x: 0, y: 1 });
+        let events = game.step();
+        assert!(events.contains(&GameEvent::Won) && !events.contains(&GameEvent::Overtime));
+        assert_eq!((game.score, game.overtime), (3, true));
+
+        // Без правила (повторы до овертайма) партия идёт как раньше
+        let mut game = crowded();
+        game.sudden_death = false;
+        game.step();
+        assert_eq!((game.overtime, game.food_value()), (false, 1));
+    }
+
+    #[test]
+    fn cannot_reverse_into_itself() {
+        let mut game = Game::new(10, 10);
+        game.set_snake(snake_of(&[(5, 5), (4, 5)]));
+        game.dir = DirectionSnake::Right;
+        game.change_dir(DirectionSnake::Left);
+        assert_eq!(game.dir, DirectionSnake::Right);
+        game.change_dir(DirectionSnake::Up);
+        assert_eq!(game.dir, DirectionSnake::Up);
+    }
+
+    #[test]
+    fn single_segment_may_turn_around() {
+        let mut game = Game::new(10, 10);
+        game.dir = DirectionSnake::Right;
+        game.change_dir(DirectionSnake::Left);
+        assert_eq!(game.dir, DirectionSnake::Left);
+    }
+
+    /// Жадно ведёт змейку к еде и возвращает позиции всей съеденной еды
+    fn play_greedy(game: &mut Game, steps: usize) -> Vec<Point> {
+        let mut foods = vec![game.food];
+        for _ in 0..steps {
+            let head = game.snake[0];
+            let dir = if game.food.y > head.y {
+                DirectionSnake::Down
+            } else if game.food.y < head.y {
+                DirectionSnake::Up
+            } else if game.food.x > head.x {
+                DirectionSnake::Right
+            } else {
+                DirectionSnake::Left
+            };
+            // Разворот запрещён — уходим в сторону, а к еде вернёмся потом
+            let dir = match (game.dir, dir) {
+                (DirectionSnake::Up, DirectionSnake::Down)
+                | (DirectionSnake::Down, DirectionSnake::Up) => {
+                    if head.x > 0 { DirectionSnake::Left } else { DirectionSnake::Right }
+                }
+                (DirectionSnake::Left, DirectionSnake::Right)
+                | (DirectionSnake::Right, DirectionSnake::Left) => {
+                    if head.y > 0 { DirectionSnake::Up } else { DirectionSnake::Down }
+                }
+                _ => dir,
+            };
+            game.change_dir(dir);
+            game.step();
+            if foods.last() != Some(&game.food) {
+                foods.push(game.food);
+            }
+        }
+        foods
+    }
+
+    #[test]
+    fn same_seed_same_game() {
+        let mut a = Game::with_seed(20, 12, 42);
+        let mut b = Game::with_seed(20, 12, 42);
+        a.spawn_food();
+        b.spawn_food();
+        let foods = play_greedy(&mut a, 200);
+        assert_eq!(foods, play_greedy(&mut b, 200));
+        assert!(foods.len() > 3);
+        assert_eq!(a.score, b.score);
+        assert_eq!(a.snake, b.snake);
+    }
+
+    #[test]
+    fn different_seeds_diverge() {
+        let foods = |seed| {
+            let mut game = Game::with_seed(20, 12, seed);
+            (0..5).map(|_| { game.spawn_food(); game.food }).collect::<Vec<_>>()
+        };
+        assert_eq!(foods(1), foods(1));
+        assert_ne!(foods(1), foods(2));
+    }
+
+    #[test]
+    fn step_events() {
+        let mut game = Game::with_seed(10, 10, 7);
+        game.set_snake(snake_of(&[(5, 5), (4, 5)]));
+        game.food = Point { x: 0, y: 0 };
+        game.dir = DirectionSnake::Right;
+        assert_eq!(game.step(), [GameEvent::Moved(Point { x: 6, y: 5 })]);
+
+        game.food = Point { x: 7, y: 5 };
+        assert_eq!(game.step(), [
+            GameEvent::Moved(Point { x: 7, y: 5 }),
+            GameEvent::Ate { at: Point { x: 7, y: 5 }, kind: FoodKind::Normal },
+            GameEvent::Grew(3),
+        ]);
+    }
+
+    #[test]
+    fn death_events() {
+        let mut game = Game::new(5, 5);
+        game.set_snake(snake_of(&[(4, 2)]));
+        game.food = Point { x: 0, y: 0 };
+        game.dir = DirectionSnake::Right;
+        assert_eq!(game.step(), [GameEvent::Died(DeathCause::Wall)]);
+        // После смерти шаги ничего не делают
+        assert_eq!(game.step(), []);
+
+        let mut game = Game::new(10, 10);
+        game.set_snake(snake_of(&[(2, 2), (3, 2), (3, 3), (2, 3), (1, 3)]));
+        game.food = Point { x: 8, y: 8 };
+        game.dir = DirectionSnake::Down;
+        assert_eq!(game.step(), [GameEvent::Died(DeathCause::SelfCollision)]);
+    }
+
+    #[test]
+    fn level_up_event() {
+        let mut game = Game::new(10, 10);
+        game.food_per_level = Some(2);
+        game.score = 1;
+        game.set_snake(snake_of(&[(5, 5)]));
+        game.food = Point { x: 6, y: 5 };
+        game.dir = DirectionSnake::Right;
+        assert_eq!(game.step().last(), Some(&GameEvent::LevelUp(2)));
+    }
+
+    #[test]
+    fn occupancy_follows_the_snake() {
+        let same = |game: &Game| {
+            (0..game.width)
+                .flat_map(|x| (0..game.height).map(move |y| Point { x, y }))
+                .all(|p| game.is_snake(p) == game.snake().contains(&p))
+        };
+        let mut game = Game::with_seed(8, 6, 3);
+        game.set_snake(snake_of(&[(4, 3), (3, 3), (2, 3)]));
+        game.food = Point { x: 5, y: 3 };
+        for dir in [DirectionSnake::Right, DirectionSnake::Up, DirectionSnake::Left, DirectionSnake::Left] {
+            game.change_dir(dir);
+            game.step();
+            assert!(same(&game));
+        }
+        // После сжатия поля клетки змейки прижимаются к краю и могут совпасть
+        game.resize(3, 3);
+        assert!(same(&game));
+        assert!(!game.is_snake(Point { x: 3, y: 0 }));
+    }
+
+    #[test]
+    fn food_on_a_filling_board() {
+        // Змейка змейкой по полю 3×3: первые `len` клеток заняты
+        let path = [(0, 0), (1, 0), (2, 0), (2, 1), (1, 1), (0, 1), (0, 2), (1, 2), (2, 2)];
+        for len in 1..path.len() {
+            let mut game = Game::with_seed(3, 3, len as u64);
+            game.set_snake(snake_of(&path[..len]));
+            let mut seen = Vec::new();
+            for _ in 0..200 {
+                let food = game.spawn_food().unwrap();
+                assert!(!game.is_snake(food), "{len}: {food:?}");
+                assert_eq!(game.food, food);
+                if !seen.contains(&food) {
+                    seen.push(food);
+                }
+            }
+            // Каждая свободная клетка рано или поздно выпадает
+            assert_eq!(seen.len(), path.len() - len, "{len}");
+        }
+
+        let mut game = Game::with_seed(3, 3, 1);
+        game.set_snake(snake_of(&path));
+        let food = game.food;
+        assert_eq!(game.spawn_food(), None);
+        assert_eq!(game.food, food);
+    }
+
+    #[test]
+    fn filling_the_board_wins() {
+        let mut game = Game::with_seed(2, 1, 1);
+        game.set_snake(snake_of(&[(0, 0)]));
+        game.food = Point { x: 1, y: 0 };
+        game.dir = DirectionSnake::Right;
+        let events = game.step();
+        assert_eq!(events.last(), Some(&GameEvent::Won));
+        assert_eq!(game.game_over, Some(DeathCause::BoardFull));
+        assert_eq!(game.score, 1);
+    }
+
+    #[test]
+    fn checksum_follows_state() {
+        let game = Game::with_seed(10, 6, 1);
+        let mut same = Game::with_seed(10, 6, 1);
+        assert_eq!(game.checksum(0), same.checksum(0));
+        // Сумма продолжает предыдущую
+        assert_ne!(game.checksum(0), game.checksum(1));
+        same.score = 1;
+        assert_ne!(game.checksum(0), same.checksum(0));
+        // Змейка из тех же клеток в другом порядке — другое состояние
+        let mut a = Game::with_seed(10, 6, 1);
+        a.set_snake(snake_of(&[(2, 2), (3, 2)]));
+        let mut b = Game::with_seed(10, 6, 1);
+        b.set_snake(snake_of(&[(3, 2), (2, 2)]));
+        assert_ne!(a.checksum(0), b.checksum(0));
+        // Еда на том же месте, но генератор уже выдал числа
+        let mut drawn = Game::with_seed(10, 6, 1);
+        drawn.reseed();
+        assert_ne!(Game::with_seed(10, 6, 1).checksum(0), drawn.checksum(0));
+    }
+
+    #[test]
+    fn speed_follows_length() {
+        let mut game = Game::with_seed(40, 10, 1);
+        game.set_snake(snake_of(&[(10, 5), (9, 5), (8, 5), (7, 5), (6, 5)]));
+        assert_eq!(game.tick_rate(), Game::BASE_TICK);
+        game.length_speedup = Some(Game::LENGTH_SPEEDUP);
+        // Четыре клетки сверх первой: 120 − 4 × 1,5 мс
+        assert_eq!(game.tick_rate(), Duration::from_millis(114));
+        assert!((game.cells_per_sec() - 1000.0 / 114.0).abs() < 1e-9);
+        // Змейку укоротили — снова медленнее
+        game.set_snake(snake_of(&[(10, 5), (9, 5)]));
+        assert_eq!(game.tick_rate(), Duration::from_micros(118_500));
+        // Очень длинная змейка упирается в нижнюю границу
+        game.set_snake((0..80).map(|i| Point { x: i % 40, y: i / 40 }));
+        assert_eq!(game.tick_rate(), Game::MIN_TICK);
+        // Интервал и так короче границы — правило его не удлиняет
+        game.base_tick = Duration::from_millis(20);
+        assert_eq!(game.tick_rate(), Duration::from_millis(20));
+    }
+
+    #[test]
+    fn zones_scale_the_next_tick() {
+        let mut game = Game::with_seed(40, 10, 1);
+        game.set_snake(snake_of(&[(10, 5), (9, 5), (8, 5), (7, 5), (6, 5)]));
+        game.set_zones(Zone::Fast, [Point { x: 10, y: 5 }, Point { x: 12, y: 5 }]);
+        game.set_zones(Zone::Slow, [Point { x: 11, y: 5 }, Point { x: 12, y: 5 }]);
+        // Голова в быстрой клетке: 70 % от 120 мс
+        assert_eq!(game.tick_rate(), Duration::from_millis(84));
+        game.dir = DirectionSnake::Right;
+        game.step();
+        assert_eq!(game.tick_rate(), Duration::from_millis(156));
+        // Клетка в обеих зонах считается быстрой
+        game.step();
+        assert_eq!((game.zone(Point { x: 12, y: 5 }), game.tick_rate()), (Some(Zone::Fast), Duration::from_millis(84)));
+        game.step();
+        assert_eq!(game.tick_rate(), Game::BASE_TICK);
+
+        // С правилом «скорость от длины» зона умножает уже укороченный интервал
+        game.length_speedup = Some(Game::LENGTH_SPEEDUP);
+        game.set_zones(Zone::Slow, [Point { x: 13, y: 5 }]);
+        assert_eq!(game.tick_rate(), Duration::from_micros(114_000 * 13 / 10));
+        // и интервал на нижней границе
+        game.set_snake((0..80).rev().map(|i| Point { x: i % 40, y: i / 40 }));
+        game.set_zones(Zone::Fast, [Point { x: 39, y: 1 }]);
+        assert_eq!(game.tick_rate(), Game::MIN_TICK * 7 / 10);
+        assert!((game.cells_per_sec() - 1.0 / (Game::MIN_TICK * 7 / 10).as_secs_f64()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn risk_scoring_pays_for_speed() {
+        let mut game = Game::with_seed(20, 10, 1);
+        game.base_tick = Duration::from_millis(50);
+        assert_eq!(game.food_value(), 1);
+        game.risk_scoring = true;
+        // 120 / 50 = 2,4 — округляется вверх
+        assert_eq!(game.food_value(), 3);
+        game.base_tick = Duration::from_millis(200);
+        assert_eq!(game.food_value(), 1);
+        game.base_tick = Game::BASE_TICK;
+        assert_eq!(game.food_value(), 1);
+        let sum = game.checksum(0);
+        game.risk_scoring = false;
+        assert_ne!(sum, game.checksum(0));
+
+        // Цена — по шагу, которым дошли до еды: голова в быстрой зоне, еда в обычной клетке
+        game.risk_scoring = true;
+        game.set_snake(snake_of(&[(5, 5)]));
+        game.set_zones(Zone::Fast, [Point { x: 5, y: 5 }]);
+        game.food = Point { x: 6, y: 5 };
+        game.dir = DirectionSnake::Right;
+        assert_eq!(game.food_value(), 2);
+        game.step();
+        assert_eq!((game.score, game.food_value()), (2, 1));
+    }
+
+    #[test]
+    fn near_misses_are_noticed_at_speed_with_a_cooldown() {
+        // Змейка-крючок: голова идёт вверх вдоль своего тела справа
+        let hook = snake_of(&[(6, 6), (6, 7), (7, 7), (8, 7), (8, 6), (7, 5), (7, 4)]);
+        let near_miss = |events: Vec<GameEvent>| events.into_iter().find(|e| matches!(e, GameEvent::NearMiss(_)));
+        let mut game = Game::with_seed(20, 10, 1);
+        game.food = Point { x: 0, y: 0 };
+        game.dir = DirectionSnake::Up;
+        // На обычной скорости проход не замечается
+        game.set_snake(hook.clone());
+        assert_eq!(near_miss(game.step()), None);
+        game.base_tick = Game::NEAR_MISS_TICK;
+        game.set_snake(hook);
+        assert_eq!(near_miss(game.step()), Some(GameEvent::NearMiss(Point { x: 7, y: 5 })));
+        assert_eq!(game.near_miss_cooldown, Game::NEAR_MISS_COOLDOWN);
+        // Дальше вдоль того же тела — перерыв
+        assert_eq!(near_miss(game.step()), None);
+        game.near_miss_cooldown = 1;
+        game.set_snake(snake_of(&[(2, 5), (2, 6)]));
+        game.set_obstacles(vec![Point { x: 3, y: 4 }]);
+        assert_eq!(near_miss(game.step()), Some(GameEvent::NearMiss(Point { x: 3, y: 4 })));
+    }
+
+    #[test]
+    fn edge_bonus_pays_every_forty_ticks() {
+        let mut game = Game::with_seed(60, 10, 1);
+        game.set_snake(snake_of(&[(1, 0)]));
+        game.food = Point { x: 0, y: 5 };
+        assert_eq!(game.edge_progress(), None);
+        game.step();
+        assert_eq!((game.edge_ticks, game.score), (0, 0));
+
+        game.edge_bonus = true;
+        let sum = game.checksum(0);
+        for _ in 0..Game::EDGE_TICKS - 1 {
+            game.step();
+        }
+        assert_ne!(sum, game.checksum(0));
+        assert_eq!((game.edge_progress(), game.score), (Some((39, 40)), 0));
+        game.step();
+        assert_eq!((game.edge_progress(), game.score), (Some((0, 40)), 1));
+        // Вдали от края шаги не копятся, а смерть обнуляет накопленное
+        game.step();
+        game.dir = DirectionSnake::Down;
+        game.step();
+        assert_eq!(game.edge_ticks, 1);
+        game.step();
+        assert_eq!(game.edge_ticks, 1);
+        game.dir = DirectionSnake::Up;
+        game.set_snake(snake_of(&[(50, 0)]));
+        game.step();
+        assert_eq!((game.game_over, game.edge_ticks), (Some(DeathCause::Wall), 0));
+    }
+
+    #[test]
+    fn scattered_zones_avoid_walls_and_follow_the_seed() {
+        let mut game = Game::with_seed(30, 12, 8);
+        game.set_walls((0..12).map(|y| Point { x: 15, y }));
+        game.scatter_zones();
+        let (fast, slow) = (game.zones(Zone::Fast).to_vec(), game.zones(Zone::Slow).to_vec());
+        assert!(!fast.is_empty() && !slow.is_empty());
+        assert!(fast.iter().chain(&slow).all(|&p| !game.is_wall(p) && p.x < 30 && p.y < 12));
+        assert!(fast.iter().all(|p| !slow.contains(p)));
+        // Еда от пятен не меняется, а тот же сид даёт те же пятна
+        let mut plain = Game::with_seed(30, 12, 8);
+        plain.set_walls((0..12).map(|y| Point { x: 15, y }));
+        assert_eq!(game.spawn_food(), plain.spawn_food());
+        plain.scatter_zones();
+        assert_eq!((plain.zones(Zone::Fast), plain.zones(Zone::Slow)), (&fast[..], &slow[..]));
+    }
+
+    #[test]
+    fn random_start_keeps_off_the_walls_and_follows_the_seed() {
+        let mut starts = Vec::new();
+        for seed in 0..40 {
+            let mut game = Game::with_seed(30, 14, seed);
+            game.set_walls((0..14).map(|y| Point { x: 15, y }));
+            game.random_start();
+            let head = game.snake()[0];
+            assert!((5..25).contains(&head.x) && (5..9).contains(&head.y) && !game.is_wall(head), "{seed}: {head:?}");
+            assert!(game.neighbour(head, game.dir).is_some_and(|p| !game.is_wall(p)), "{seed}");
+            assert!(game.food != head && !game.is_wall(game.food) && game.food.x < 30 && game.food.y < 14, "{seed}");
+            let mut again = Game::with_seed(30, 14, seed);
+            again.set_walls((0..14).map(|y| Point { x: 15, y }));
+            again.random_start();
+            assert_eq!((again.snake()[0], again.dir, again.food), (head, game.dir, game.food));
+            starts.push((head, game.dir));
+        }
+        starts.sort_by_key(|&(p, dir)| (p.x, p.y, dir.code()));
+        starts.dedup();
+        assert!(starts.len() > 20);
+        // Поле, где нет клеток в пяти от стены, оставляет змейку посередине
+        let mut small = Game::with_seed(10, 20, 1);
+        small.random_start();
+        assert_eq!((small.snake()[0], small.dir, small.food), (Point { x: 5, y: 10 }, DirectionSnake::Right, Point { x: 3, y: 6 }));
+    }
+
+    #[test]
+    fn first_food_follows_the_start() {
+        assert_eq!(Game::with_seed(40, 20, 1).food, Point { x: 13, y: 6 });
+        // На поле 3×3 треть и половина совпадают: еда уходит с головы
+        let tiny = Game::with_seed(3, 3, 1);
+        assert_eq!((tiny.snake()[0], tiny.food), (Point { x: 1, y: 1 }, Point { x: 0, y: 1 }));
+        let mut game = Game::with_seed(40, 20, 1);
+        game.place_start(Point { x: 5, y: 14 }, DirectionSnake::Up, 1);
+        assert_eq!((game.snake().len(), game.dir, game.food), (1, DirectionSnake::Up, Point { x: 12, y: 10 }));
+        // Еда не встаёт в стену
+        game.set_walls([Point { x: 32, y: 6 }]);
+        game.place_start(Point { x: 39, y: 10 }, DirectionSnake::Left, 1);
+        assert!(game.food != Point { x: 32, y: 6 } && !game.is_snake(game.food));
+    }
+
+    #[test]
+    fn starting_length_is_trimmed_at_the_edge() {
+        let mut game = Game::with_seed(20, 10, 1);
+        game.set_start_length(4);
+        assert_eq!(game.snake(), &snake_of(&[(10, 5), (9, 5), (8, 5), (7, 5)]));
+        assert_eq!(game.food, Point { x: 6, y: 3 });
+        // Не больше десяти клеток, даже если место есть
+        game.set_start_length(15);
+        assert_eq!(game.snake().len(), Game::MAX_START_LENGTH);
+        // У края тело обрезается, а еда уходит с него
+        game.place_start(Point { x: 2, y: 1 }, DirectionSnake::Right, 5);
+        assert_eq!(game.snake(), &snake_of(&[(2, 1), (1, 1), (0, 1)]));
+        assert!(!game.is_snake(game.food));
+        game.place_start(Point { x: 3, y: 8 }, DirectionSnake::Up, 5);
+        assert_eq!(game.snake(), &snake_of(&[(3, 8), (3, 9)]));
+        // Развернуться на месте нельзя, пока за головой есть тело
+        assert!(!game.change_dir(DirectionSnake::Down) && game.change_dir(DirectionSnake::Left));
+        game.place_start(Point { x: 0, y: 4 }, DirectionSnake::Right, 5);
+        assert_eq!(game.snake().len(), 1);
+        assert!(game.change_dir(DirectionSnake::Left));
+
+        // Поле 3×3: еда у начала легла бы на тело
+        let mut tiny = Game::with_seed(3, 3, 1);
+        tiny.set_start_length(3);
+        assert_eq!(tiny.snake(), &snake_of(&[(1, 1), (0, 1)]));
+        assert!(!tiny.is_snake(tiny.food));
+        // Тело упирается в стену уровня
+        let mut walled = Game::with_seed(20, 10, 1);
+        walled.set_walls([Point { x: 8, y: 5 }]);
+        walled.set_start_length(6);
+        assert_eq!(walled.snake(), &snake_of(&[(10, 5), (9, 5)]));
+    }
+
+    #[test]
+    fn two_foods() {
+        let mut game = Game::with_seed(10, 10, 3);
+        game.set_snake(snake_of(&[(5, 5), (4, 5)]));
+        Mode::TwoFood.setup(&mut game);
+        let bonus = game.bonus.unwrap();
+        assert!(bonus != game.food && !game.is_snake(bonus));
+
+        // Синяя: два очка, змейка не растёт, синяя встаёт в новую клетку
+        game.food = Point { x: 0, y: 0 };
+        game.bonus = Some(Point { x: 6, y: 5 });
+        game.dir = DirectionSnake::Right;
+        assert_eq!(game.step(), [
+            GameEvent::Moved(Point { x: 6, y: 5 }),
+            GameEvent::Ate { at: Point { x: 6, y: 5 }, kind: FoodKind::Bonus },
+        ]);
+        assert_eq!((game.score, game.snake.len()), (2, 2));
+        let bonus = game.bonus.unwrap();
+        assert!(bonus != game.food && !game.is_snake(bonus));
+
+        // Обычная: очко и рост, синяя остаётся на месте
+        game.food = Point { x: 7, y: 5 };
+        game.step();
+        assert_eq!((game.score, game.snake.len(), game.bonus), (3, 3, Some(bonus)));
+        assert_ne!(game.food, bonus);
+        assert_eq!((game.eaten(FoodKind::Normal), game.eaten(FoodKind::Bonus)), (1, 1));
+    }
+
+    #[test]
+    fn foods_never_share_a_cell() {
+        // На поле 2×2 с змейкой в одну клетку свободны ровно три клетки
+        let mut game = Game::with_seed(2, 2, 5);
+        game.set_snake(snake_of(&[(0, 0)]));
+        Mode::TwoFood.setup(&mut game);
+        for _ in 0..100 {
+            game.spawn_food();
+            game.spawn_bonus();
+            assert_ne!(Some(game.food), game.bonus);
+            assert!(!game.is_snake(game.food));
+        }
+        // Свободна одна клетка, и она под обычной едой: синей места нет
+        game.set_snake(snake_of(&[(0, 0), (1, 0), (1, 1)]));
+        game.food = Point { x: 0, y: 1 };
+        assert_eq!(game.spawn_bonus(), None);
+        assert_eq!(game.bonus, None);
+    }
+
+    #[test]
+    fn pickups_never_land_on_other_items() {
+        // Поле 6×1: змейка слева, остальное занято предметами, кроме одной клетки
+        let mut game = Game::with_seed(6, 1, 3);
+        game.set_snake(snake_of(&[(0, 0)]));
+        game.food = Point { x: 0, y: 0 };
+        game.portal = Some(Point { x: 2, y: 0 });
+        game.shield_pickup = Some(Point { x: 3, y: 0 });
+        game.doubler = Some(Point { x: 4, y: 0 });
+        assert!((2..5).all(|x| game.item_at(Point { x, y: 0 })) && !game.item_at(Point { x: 5, y: 0 }));
+        assert_eq!(game.spawn_freezer(), Some(Point { x: 5, y: 0 }));
+        // Места больше нет: клетка 1 0 рядом с едой
+        assert_eq!(game.spawn_bomb(), None);
+
+        // При смене размера все предметы за краем поля пропадают разом
+        game.resize(3, 1);
+        assert_eq!((game.portal, game.shield_pickup, game.freezer), (Some(Point { x: 2, y: 0 }), None, None));
+    }
+
+    #[test]
+    fn mode_names_round_trip() {
+        for arg in Mode::ARGS {
+            let mode = Mode::from_arg(arg).unwrap();
+            assert_eq!(Mode::from_name(mode.name()), Some(mode));
+        }
+        assert_eq!(Mode::from_name("нет такого"), None);
+        assert_eq!((Mode::Classic.next(), Mode::Endless.next()), (Mode::TwoFood, Mode::Classic));
+    }
+
+    #[test]
+    fn endless_rules_mutate_every_twenty_foods() {
+        let mut game = Game::with_seed(20, 10, 1);
+        Mode::Endless.setup(&mut game);
+        assert_eq!((game.mutating, game.mutation_due), (true, Game::MUTATION_EVERY));
+        game.mutation_due = 1;
+        game.food = Point { x: 11, y: 5 };
+        let events = game.step();
+        let [mutation] = game.mutations[..] else { panic!("одна мутация: {events:?}") };
+        assert!(events.contains(&GameEvent::Mutated(mutation)));
+        assert_eq!(game.mutation_due, Game::MUTATION_EVERY);
+
+        // Мутации копятся, правила насовсем не повторяются, а порядок задаёт сид
+        let (mut first, mut second) = (Game::with_seed(20, 10, 3), Game::with_seed(20, 10, 3));
+        for _ in 0..30 {
+            assert_eq!(first.mutate(), second.mutate());
+        }
+        assert_eq!(first.mutations.len(), 30);
+        for m in Mutation::ALL.into_iter().filter(|m| !m.repeats()) {
+            assert!(first.mutations.iter().filter(|&&x| x == m).count() <= 1, "{m:?} дважды");
+        }
+        assert!(first.bombs || first.blackouts || first.caterpillars || first.bosses || first.fleeing || first.wrapping);
+        assert_eq!(Mutation::from_code(Mutation::FleeingFood.code()), Some(Mutation::FleeingFood));
+
+        // В повторах до движущихся мутаций стены не пропускают, а еда только появляется подальше
+        let mut old = Game::with_seed(20, 10, 3);
+        old.moving_mutations = false;
+        for _ in 0..30 {
+            old.mutate();
+        }
+        assert!(!old.mutations.contains(&Mutation::Wrap));
+        assert_eq!((old.fleeing, old.wrapping, old.food_candidates), (false, false, Game::FLEE_CANDIDATES));
+    }
+
+    #[test]
+    fn wrap_lets_the_snake_through_the_outer_walls() {
+        let mut game = Game::with_seed(10, 6, 1);
+        game.set_snake(snake_of(&[(9, 2), (8, 2)]));
+        game.dir = DirectionSnake::Right;
+        game.food = Point { x: 5, y: 5 };
+        game.wrapping = true;
+        game.step();
+        assert_eq!((game.game_over, game.snake()[0]), (None, Point { x: 0, y: 2 }));
+        game.dir = DirectionSnake::Up;
+        game.step();
+        game.step();
+        game.step();
+        assert_eq!((game.game_over, game.snake()[0]), (None, Point { x: 0, y: 5 }));
+        // Стены уровня внутри поля по-прежнему смертельны
+        game.set_walls([Point { x: 0, y: 4 }]);
+        game.step();
+        assert_eq!(game.game_over, Some(DeathCause::Wall));
+
+        // Край, через который можно уйти, — не край: очков за него нет
+        let mut game = Game::with_seed(10, 6, 1);
+        game.edge_bonus = true;
+        game.edge_ticks = 5;
+        assert!((0..50).any(|_| game.mutate() == Some(Mutation::Wrap)));
+        assert_eq!((game.wrapping, game.edge_ticks, game.edge_progress()), (true, 0, None));
+        game.set_snake(snake_of(&[(0, 0)]));
+        game.dir = DirectionSnake::Right;
+        game.food = Point { x: 5, y: 3 };
+        for _ in 0..Game::EDGE_TICKS {
+            game.step();
+        }
+        assert!(game.snake().iter().all(|&p| p.y == 0) && game.game_over.is_none());
+        assert_eq!((game.score, game.edge_ticks, game.edge_progress()), (0, 0, None));
+
+        // Без мутации край поля — стена
+        let mut game = Game::with_seed(10, 6, 1);
+        game.set_snake(snake_of(&[(9, 2), (8, 2)]));
+        game.dir = DirectionSnake::Right;
+        game.step();
+        assert_eq!(game.game_over, Some(DeathCause::Wall));
+    }
+
+    #[test]
+    fn fleeing_food_steps_away_from_the_head() {
+        let mut game = Game::with_seed(20, 10, 1);
+        game.set_snake(snake_of(&[(2, 5), (1, 5)]));
+        game.dir = DirectionSnake::Right;
+        game.food = Point { x: 6, y: 5 };
+        game.fleeing = true;
+        // Шаг 1 нечётный: еда стоит; на втором отходит: вверх и вправо до головы одинаково
+        // далеко, из равных — вверх
+        game.step();
+        assert_eq!(game.food, Point { x: 6, y: 5 });
+        game.step();
+        assert_eq!(game.food, Point { x: 6, y: 4 });
+        // Голова дальше Game::FLEE_DISTANCE — еда не замечает её
+        game.food = Point { x: 15, y: 5 };
+        game.step();
+        game.step();
+        assert_eq!(game.food, Point { x: 15, y: 5 });
+
+        // Загнанная в угол еда стоит, и её можно съесть
+        let mut game = Game::with_seed(20, 10, 1);
+        game.set_snake(snake_of(&[(16, 0), (15, 0)]));
+        game.dir = DirectionSnake::Right;
+        game.food = Point { x: 19, y: 0 };
+        game.set_walls([Point { x: 19, y: 1 }]);
+        game.fleeing = true;
+        for _ in 0..3 {
+            game.step();
+        }
+        assert_eq!((game.score, game.eaten(FoodKind::Normal)), (1, 1));
+    }
+
+    #[test]
+    fn idle_score_decays_until_the_snake_eats() {
+        // Длинное поле: змейка идёт по прямой, а еда лежит позади
+        let mut game = Game::with_seed(600, 3, 1);
+        game.score_decay = true;
+        game.score = 2;
+        game.set_snake(snake_of(&[(1, 1)]));
+        game.dir = DirectionSnake::Right;
+        game.food = Point { x: 0, y: 0 };
+        let walk = |game: &mut Game, steps: u64| {
+            for _ in 0..steps {
+                game.step();
+            }
+        };
+        walk(&mut game, Game::DECAY_TICKS - 1);
+        assert_eq!((game.score, game.decaying()), (2, false));
+        // Пауза — это шаги, которых нет: пока игра стоит, счёт не тает
+        walk(&mut game, 1);
+        assert_eq!((game.score, game.decaying()), (1, true));
+        walk(&mut game, 2 * Game::DECAY_TICKS);
+        assert_eq!((game.score, game.decaying()), (0, false));
+        assert!(game.game_over.is_none());
+
+        // Еда сбрасывает счётчик шагов
+        game.score = 5;
+        game.food = game.neighbour(game.snake()[0], game.dir).unwrap();
+        game.step();
+        game.food = Point { x: 0, y: 0 };
+        assert_eq!((game.score, game.since_food), (6, 0));
+        walk(&mut game, Game::DECAY_TICKS - 1);
+        assert_eq!(game.score, 6);
+
+        // Без правила шаги считаются, но счёт не тает
+        game.score_decay = false;
+        walk(&mut game, Game::DECAY_TICKS);
+        assert_eq!((game.score, game.since_food), (6, 2 * Game::DECAY_TICKS - 1));
+    }
+
+    #[test]
+    fn slime_bogs_the_snake_down_until_it_dries() {
+        let mut game = Game::with_seed(40, 10, 1);
+        game.slime_trail = true;
+        game.set_snake(snake_of(&[(10, 5), (9, 5), (8, 5)]));
+        game.food = Point { x: 0, y: 0 };
+        game.dir = DirectionSnake::Right;
+        game.step();
+        assert_eq!(game.slime(), &[(Point { x: 8, y: 5 }, Game::SLIME_TICKS)]);
+        // Разворот петлёй: голова встаёт туда, где только что был хвост
+        for dir in [DirectionSnake::Up, DirectionSnake::Left, DirectionSnake::Down] {
+            game.dir = dir;
+            game.step();
+        }
+        assert_eq!(game.snake()[0], Point { x: 10, y: 5 });
+        assert_eq!((game.game_over, game.tick_rate()), (None, Game::BASE_TICK * 2));
+        game.step();
+        assert_eq!(game.tick_rate(), Game::BASE_TICK);
+
+        // Слизь сохнет через SLIME_TICKS шагов
+        game.dir = DirectionSnake::Right;
+        for _ in 0..15 {
+            game.step();
+        }
+        assert!(game.is_slimy(Point { x: 8, y: 5 }));
+        let sum = game.checksum(0);
+        game.step();
+        assert!(!game.is_slimy(Point { x: 8, y: 5 }));
+        assert_ne!(sum, game.checksum(0));
+
+        // Без правила слизи нет
+        let mut plain = Game::with_seed(40, 10, 1);
+        plain.step();
+        assert!(plain.slime().is_empty());
+    }
+
+    #[test]
+    fn portal_moves_the_whole_snake_in_a_line() {
+        let start = |seed| {
+            let mut game = Game::with_seed(30, 10, seed);
+            game.teleports = true;
+            game.set_snake(snake_of(&[(10, 5), (9, 5), (8, 5)]));
+            game.dir = DirectionSnake::Right;
+            game.food = Point { x: 0, y: 0 };
+            game.portal = Some(Point { x: 11, y: 5 });
+            game
+        };
+        let mut game = start(1);
+        let events = game.step();
+        let Some(&GameEvent::Teleported { from, to }) = events.last() else { panic!("{events:?}") };
+        assert_eq!((from, game.portal, game.snake()[0]), (Point { x: 11, y: 5 }, None, to));
+        assert!(to.x.abs_diff(from.x) + to.y.abs_diff(from.y) >= Game::TELEPORT_DISTANCE);
+        // Тело той же длины вытянуто по прямой за головой, а клетка впереди свободна
+        let back = game.dir.opposite();
+        let body: Vec<Point> = std::iter::successors(Some(to), |&p| game.neighbour(p, back)).take(3).collect();
+        assert_eq!(*game.snake(), body);
+        assert!(game.step().iter().all(|e| !matches!(e, GameEvent::Died(_))));
+
+        // Одинаковый сид — одинаковое место
+        let mut same = start(1);
+        same.step();
+        assert_eq!(same.snake()[0], to);
+
+        // Места нет: портал пропадает, а змейка идёт дальше
+        let mut cramped = Game::with_seed(8, 1, 1);
+        cramped.teleports = true;
+        cramped.set_snake(snake_of(&[(2, 0), (1, 0), (0, 0)]));
+        cramped.dir = DirectionSnake::Right;
+        cramped.food = Point { x: 7, y: 0 };
+        cramped.portal = Some(Point { x: 3, y: 0 });
+        assert_eq!(cramped.step(), [GameEvent::Moved(Point { x: 3, y: 0 })]);
+        assert_eq!((cramped.portal, cramped.snake().len()), (None, 3));
+    }
+
+    #[test]
+    fn portal_keeps_off_the_food() {
+        let mut game = Game::with_seed(6, 6, 3);
+        for _ in 0..20 {
+            let portal = game.spawn_portal().unwrap();
+            assert!(!touches(portal, game.food) && !game.is_snake(portal));
+        }
+        let sum = game.checksum(0);
+        game.portal = None;
+        assert_ne!(sum, game.checksum(0));
+    }
+
+    #[test]
+    fn shield_absorbs_one_collision() {
+        let mut game = Game::with_seed(10, 5, 1);
+        game.shields = true;
+        game.set_snake(snake_of(&[(7, 2), (6, 2), (5, 2)]));
+        game.dir = DirectionSnake::Right;
+        game.food = Point { x: 0, y: 0 };
+        game.shield_pickup = Some(Point { x: 8, y: 2 });
+        let sum = game.checksum(0);
+        let events = game.step();
+        assert_eq!(events.last(), Some(&GameEvent::ShieldPicked(Point { x: 8, y: 2 })));
+        assert_eq!((game.shield, game.shield_pickup), (1, None));
+        assert_ne!(sum, game.checksum(0));
+
+        // Стена: щит тратится, змейка стоит на месте, а второго удара она уже не переживёт
+        game.step();
+        let before: Vec<Point> = game.snake().iter().copied().collect();
+        assert_eq!(game.step(), [GameEvent::Shielded(DeathCause::Wall)]);
+        assert_eq!((game.shield, game.game_over, game.snake().iter().copied().collect::<Vec<_>>()), (0, None, before));
+        assert_eq!(game.step(), [GameEvent::Died(DeathCause::Wall)]);
+
+        // Своё тело: так же, и со стоянки можно отвернуть
+        let mut game = Game::with_seed(10, 5, 1);
+        game.set_snake(snake_of(&[(3, 2), (3, 1), (4, 1), (4, 2), (4, 3)]));
+        game.dir = DirectionSnake::Right;
+        game.food = Point { x: 0, y: 0 };
+        game.shield = 1;
+        assert_eq!(game.step(), [GameEvent::Shielded(DeathCause::SelfCollision)]);
+        assert!(game.change_dir(DirectionSnake::Down));
+        assert_eq!(game.step(), [GameEvent::Moved(Point { x: 3, y: 3 })]);
+    }
+
+    #[test]
+    fn doubler_doubles_every_gain_for_a_while() {
+        let mut game = Game::with_seed(20, 5, 1);
+        game.doubling = true;
+        game.edge_bonus = true;
+        game.set_snake(snake_of(&[(3, 2), (2, 2), (1, 2)]));
+        game.dir = DirectionSnake::Right;
+        game.food = Point { x: 5, y: 2 };
+        game.doubler = Some(Point { x: 4, y: 2 });
+        let sum = game.checksum(0);
+        assert_eq!(game.step().last(), Some(&GameEvent::Doubled(Point { x: 4, y: 2 })));
+        assert_eq!((game.doubled, game.doubler), (Game::DOUBLE_TICKS, None));
+        assert_ne!(sum, game.checksum(0));
+        game.step();
+        assert_eq!((game.score, game.doubled), (2, Game::DOUBLE_TICKS - 1));
+
+        // Второй удвоитель продлевает удвоение, а не удваивает очки ещё раз
+        game.doubler = Some(Point { x: 6, y: 2 });
+        game.step();
+        assert_eq!(game.doubled, 2 * Game::DOUBLE_TICKS - 2);
+        game.food = Point { x: 7, y: 2 };
+        game.step();
+        assert_eq!(game.score, 4);
+
+        // Гусеница и очки у края идут через то же удвоение
+        game.caterpillar = vec![Point { x: 8, y: 2 }];
+        game.step();
+        assert_eq!(game.score, 4 + 2 * (1 + Game::CATERPILLAR_BONUS));
+        game.edge_ticks = Game::EDGE_TICKS - 1;
+        game.set_snake(snake_of(&[(9, 1), (8, 1), (7, 1)]));
+        game.dir = DirectionSnake::Up;
+        let score = game.score;
+        game.step();
+        assert_eq!(game.score, score + 2);
+
+        // Удвоитель и очко у края на одном шаге: очко ещё обычное, как и еда
+        game.doubled = 0;
+        game.edge_ticks = Game::EDGE_TICKS - 1;
+        game.set_snake(snake_of(&[(11, 1), (10, 1), (9, 1)]));
+        game.dir = DirectionSnake::Up;
+        game.doubler = Some(Point { x: 11, y: 0 });
+        let score = game.score;
+        assert!(game.step().contains(&GameEvent::Doubled(Point { x: 11, y: 0 })));
+        assert_eq!((game.score, game.doubled), (score + 1, Game::DOUBLE_TICKS));
+
+        // На последнем шаге удвоения удвоены и еда, и очко у края
+        game.doubled = 1;
+        game.edge_ticks = Game::EDGE_TICKS - 1;
+        game.set_snake(snake_of(&[(13, 1), (13, 2), (13, 3)]));
+        game.dir = DirectionSnake::Up;
+        game.food = Point { x: 13, y: 0 };
+        let score = game.score;
+        game.step();
+        assert_eq!((game.score, game.doubled), (score + 4, 0));
+
+        // Когда удвоение кончилось, еда снова стоит очко
+        game.doubled = 1;
+        game.set_snake(snake_of(&[(3, 3), (2, 3), (1, 3)]));
+        game.dir = DirectionSnake::Right;
+        game.food = Point { x: 4, y: 3 };
+        let score = game.score;
+        game.step();
+        assert_eq!((game.score, game.doubled), (score + 2, 0));
+        game.food = Point { x: 5, y: 3 };
+        game.step();
+        assert_eq!(game.score, score + 3);
+    }
+
+    #[test]
+    fn freezer_stops_the_world_but_not_the_snake() {
+        let mut game = Game::with_seed(20, 10, 1);
+        game.freezing = true;
+        game.fleeing = true;
+        game.score_decay = true;
+        game.set_snake(snake_of(&[(3, 5), (2, 5), (1, 5)]));
+        game.dir = DirectionSnake::Right;
+        game.food = Point { x: 7, y: 5 };
+        game.bomb = Some(Bomb { at: Point { x: 15, y: 8 }, left: 10 });
+        game.freezer = Some(Point { x: 4, y: 5 });
+        let sum = game.checksum(0);
+        // Заморозка действует со следующего шага: на этом бомба ещё тикает
+        assert!(game.step().contains(&GameEvent::Froze(Point { x: 4, y: 5 })));
+        assert_eq!((game.frozen, game.freezer, game.bomb.map(|bomb| bomb.left)), (Game::FREEZE_TICKS, None, Some(9)));
+        assert_ne!(sum, game.checksum(0));
+        // Бомба и убегающая еда стоят, а змейка идёт, и шаги без еды считаются
+        game.step();
+        game.step();
+        assert!(game.world_frozen());
+        assert_eq!((game.food, game.bomb.map(|bomb| bomb.left)), (Point { x: 7, y: 5 }, Some(9)));
+        assert_eq!((game.snake()[0], game.since_food), (Point { x: 6, y: 5 }, 3));
+        game.step();
+        assert_eq!((game.score, game.frozen), (1, Game::FREEZE_TICKS - 3));
+
+        // Когда заморозка кончилась, бомба снова тикает
+        game.frozen = 1;
+        game.step();
+        assert!(!game.world_frozen());
+        game.step();
+        assert_eq!(game.bomb.map(|bomb| bomb.left), Some(8));
+    }
+
+    #[test]
+    fn mirrored_controls_turn_the_other_way() {
+        let mut game = Game::with_seed(20, 10, 1);
+        game.mirrored = 2;
+        assert!(game.change_dir(DirectionSnake::Up));
+        assert_eq!(game.dir, DirectionSnake::Down);
+        game.step();
+        game.step();
+        assert_eq!(game.mirrored, 0);
+        assert!(game.change_dir(DirectionSnake::Left));
+        assert_eq!(game.dir, DirectionSnake::Left);
+    }
+
+    #[test]
+    fn nokia_food_is_worth_the_speed_level() {
+        assert_eq!(Mode::nokia_level(Mode::NOKIA_TICKS[0]), 1);
+        assert_eq!(Mode::nokia_level(Mode::NOKIA_TICKS[8]), 9);
+        // Скорость между уровнями — более медленный из двух
+        assert_eq!(Mode::nokia_level(Duration::from_millis(90)), 7);
+        assert_eq!((Mode::nokia_level(Duration::from_secs(1)), Mode::nokia_level(Duration::from_millis(10))), (1, 9));
+
+        let mut game = Game::with_seed(20, 11, 1);
+        game.base_tick = Mode::NOKIA_TICKS[3];
+        Mode::Nokia.setup(&mut game);
+        game.food = Point { x: 11, y: 5 };
+        game.step();
+        assert_eq!((game.score_multiplier, game.score), (4, 4));
+        assert_eq!(Mode::Nokia.board(), Some((20, 11)));
+    }
+
+    #[test]
+    fn food_spawns_where_the_head_can_reach() {
+        // Стена по x = 4 с проходом в (4, 2); змейка стоит в проходе и закрывает его
+        let mut game = Game::with_seed(9, 5, 7);
+        game.set_walls((0..5).filter(|&y| y != 2).map(|y| Point { x: 4, y }));
+        game.set_snake(snake_of(&[(3, 2), (4, 2), (5, 2)]));
+        for _ in 0..100 {
+            let food = game.spawn_food().unwrap();
+            assert!(food.x < 4 && !game.is_snake(food), "{food:?}");
+        }
+        // Комната за проходом недоступна целиком: еда встаёт в любую свободную клетку
+        game.set_walls((0..5).map(|y| Point { x: 4, y }).chain([Point { x: 3, y: 1 }, Point { x: 3, y: 3 }, Point { x: 2, y: 2 }]));
+        let food = game.spawn_food().unwrap();
+        assert!(!game.is_wall(food) && !game.is_snake(food));
+
+        // Голова в стену — смерть от стены
+        game.set_walls([Point { x: 2, y: 2 }]);
+        game.dir = DirectionSnake::Left;
+        assert_eq!(game.step(), [GameEvent::Died(DeathCause::Wall)]);
+    }
+
+    #[test]
+    fn shedding_leaves_the_tail_behind() {
+        let mut game = Game::with_seed(10, 10, 1);
+        game.set_snake(snake_of(&[(5, 5), (4, 5), (3, 5), (3, 6), (3, 7)]));
+        game.dir = DirectionSnake::Right;
+        game.food = Point { x: 0, y: 0 };
+        game.score = 10;
+        assert_eq!(game.shed(), Some(2));
+        assert_eq!(game.snake, snake_of(&[(5, 5), (4, 5), (3, 5)]));
+        assert_eq!(game.obstacles(), [Point { x: 3, y: 6 }, Point { x: 3, y: 7 }]);
+        assert!(game.is_obstacle(Point { x: 3, y: 7 }) && !game.is_snake(Point { x: 3, y: 7 }));
+        assert_eq!((game.score, game.shed_cooldown), (7, Game::SHED_COOLDOWN));
+        // Второй сброс — только через SHED_COOLDOWN шагов
+        assert_eq!(game.shed(), None);
+        game.shed_cooldown = 1;
+        game.step();
+        assert_eq!(game.shed(), Some(1));
+        assert_eq!(game.score, 4);
+
+        // Еда не встаёт на препятствия, а голова в них разбивается
+        for _ in 0..100 {
+            let food = game.spawn_food().unwrap();
+            assert!(!game.is_obstacle(food));
+        }
+        game.set_snake(snake_of(&[(3, 5)]));
+        game.dir = DirectionSnake::Down;
+        assert_eq!(game.step(), [GameEvent::Died(DeathCause::Obstacle)]);
+    }
+
+    #[test]
+    fn more_candidates_put_food_farther() {
+        // Средняя дистанция от головы до еды за 2 000 появлений на поле 30×20
+        let mean_distance = |candidates| {
+            let mut game = Game::with_seed(30, 20, 11);
+            game.set_snake(snake_of(&[(7, 5), (6, 5), (5, 5)]));
+            game.food_candidates = candidates;
+            let total: u32 = (0..2000)
+                .map(|_| {
+                    let food = game.spawn_food().unwrap();
+                    assert!(!game.is_snake(food));
+                    food.x.abs_diff(7) as u32 + food.y.abs_diff(5) as u32
+                })
+                .sum();
+            total as f64 / 2000.0
+        };
+        let means: Vec<f64> = [1, 2, 4, 8].into_iter().map(mean_distance).collect();
+        // Клетка поля в среднем в 15,4 шага от головы, а самая далёкая — в 36
+        assert!((means[0] - 15.4).abs() < 1.0, "{means:?}");
+        assert!(means.windows(2).all(|w| w[1] > w[0] + 1.0), "{means:?}");
+        assert!(means[3] < 36.0, "{means:?}");
+
+        // Одна клетка на выбор — та же еда, что и без правила
+        let foods = |candidates| {
+            let mut game = Game::with_seed(30, 20, 11);
+            game.food_candidates = candidates;
+            (0..20).map(|_| game.spawn_food().unwrap()).collect::<Vec<_>>()
+        };
+        assert_eq!(foods(1), foods(0));
+        assert_ne!(foods(1), foods(3));
+    }
+
+    #[test]
+    fn bombs_come_after_score_and_expire() {
+        let mut game = Game::with_seed(12, 8, 4);
+        game.bombs = true;
+        game.set_snake(snake_of(&[(2, 2)]));
+        game.dir = DirectionSnake::Right;
+        // До счёта BOMB_SCORE бомб нет
+        game.score = Game::BOMB_SCORE - 2;
+        game.food = Point { x: 3, y: 2 };
+        game.step();
+        assert_eq!(game.bomb, None);
+        game.food = Point { x: 4, y: 2 };
+        game.step();
+        let bomb = game.bomb.unwrap();
+        assert_eq!(bomb.left, Game::BOMB_TICKS);
+        assert!(!touches(bomb.at, game.food) && !game.is_snake(bomb.at));
+        assert_eq!(game.to_text().matches('@').count(), 1);
+
+        // Не съеденная бомба лежит BOMB_TICKS шагов и пропадает
+        game.set_snake(snake_of(&[(0, 0)]));
+        game.bomb = Some(Bomb { at: Point { x: 11, y: 7 }, left: 2 });
+        game.food = Point { x: 5, y: 7 };
+        game.step();
+        assert_eq!(game.bomb.map(|b| b.left), Some(1));
+        game.step();
+        assert_eq!((game.bomb, game.game_over), (None, None));
+    }
+
+    #[test]
+    fn boss_comes_every_fifty_points_and_hides_the_food() {
+        let mut game = Game::with_seed(12, 8, 4);
+        game.bosses = true;
+        game.set_snake(snake_of(&[(2, 2)]));
+        game.dir = DirectionSnake::Right;
+        game.score = Game::BOSS_EVERY - 1;
+        game.food = Point { x: 3, y: 2 };
+        game.step();
+        let boss = game.boss.unwrap();
+        assert_eq!((boss.hits, game.food), (Game::BOSS_HITS, boss.at));
+        assert!(boss.cells().iter().all(|&p| !game.is_snake(p)));
+        // Еда под боссом не рисуется и не съедается
+        assert_eq!((game.to_text().matches('B').count(), game.to_text().matches('*').count()), (4, 0));
+        let sum = game.checksum(0);
+        game.boss = None;
+        assert_ne!(sum, game.checksum(0));
+
+        // Пока счёт не перешёл следующую отметку, босса нет
+        game.set_snake(snake_of(&[(2, 2)]));
+        game.food = Point { x: 3, y: 2 };
+        game.step();
+        assert_eq!((game.score, game.boss), (Game::BOSS_EVERY + 1, None));
+    }
+
+    #[test]
+    fn boss_takes_three_separate_hits() {
+        let mut game = Game::with_seed(12, 8, 4);
+        game.set_snake(snake_of(&[(2, 3)]));
+        game.boss = Some(Boss { at: Point { x: 3, y: 3 }, hits: Game::BOSS_HITS });
+        game.food = Point { x: 3, y: 3 };
+        game.dir = DirectionSnake::Right;
+        let hit = |events: Vec<GameEvent>| events.into_iter().find(|e| matches!(e, GameEvent::BossHit { .. }));
+        assert_eq!(hit(game.step()), Some(GameEvent::BossHit { at: Point { x: 3, y: 3 }, left: 2 }));
+        // Ход по клеткам босса — не новое касание, и еда под ним не съедается
+        assert_eq!(hit(game.step()), None);
+        assert_eq!((game.score, game.snake().len()), (0, 1));
+        // Вышли и вошли снова: второе касание
+        game.step();
+        game.dir = DirectionSnake::Down;
+        game.step();
+        game.dir = DirectionSnake::Left;
+        assert_eq!(hit(game.step()), Some(GameEvent::BossHit { at: Point { x: 4, y: 4 }, left: 1 }));
+        game.set_snake(snake_of(&[(2, 4), (1, 4)]));
+        game.dir = DirectionSnake::Right;
+        let idle = game.since_food;
+        let events = game.step();
+        assert_eq!(hit(events), Some(GameEvent::BossHit { at: Point { x: 3, y: 4 }, left: 0 }));
+        assert_eq!((game.boss, game.score, game.growth), (None, Game::BOSS_POINTS, Game::BOSS_GROWTH));
+        // Поверженный босс — это еда: счёт без еды начинается заново
+        assert_eq!((idle, game.since_food), (5, 0));
+        assert!(game.food != Point { x: 3, y: 3 } && !game.is_snake(game.food));
+        // Рост — по клетке за шаг
+        for len in 3..=5 {
+            assert!(game.step().contains(&GameEvent::Grew(len)));
+        }
+        game.step();
+        assert_eq!((game.snake().len(), game.growth), (5, 0));
+    }
+
+    #[test]
+    fn caterpillar_eaten_from_an_end_pays_a_bonus() {
+        let mut game = Game::with_seed(12, 8, 4);
+        game.set_snake(snake_of(&[(4, 3)]));
+        game.caterpillar = (5..9).map(|x| Point { x, y: 3 }).collect();
+        game.dir = DirectionSnake::Right;
+        assert_eq!(game.to_text().lines().nth(3), Some("    O====   "));
+        for left in (0..Game::CATERPILLAR_LEN).rev() {
+            let events = game.step();
+            assert!(events.contains(&GameEvent::Pellet { at: game.snake()[0], left }), "{events:?}");
+        }
+        // По очку за звено, бонус за целую гусеницу, и змейка выросла на каждое звено
+        assert_eq!((game.score, game.snake().len()), (Game::CATERPILLAR_LEN + Game::CATERPILLAR_BONUS, 5));
+        assert!(game.caterpillar.is_empty());
+
+        // С другого конца — так же
+        game.set_snake(snake_of(&[(9, 5)]));
+        game.caterpillar = (5..9).map(|x| Point { x, y: 5 }).collect();
+        game.dir = DirectionSnake::Left;
+        game.score = 0;
+        assert_eq!(game.step(), [GameEvent::Moved(Point { x: 8, y: 5 }), GameEvent::Grew(2), GameEvent::Pellet {
+            at: Point { x: 8, y: 5 },
+            left: 3
+        }]);
+        assert_eq!(game.caterpillar.last(), Some(&Point { x: 7, y: 5 }));
+    }
+
+    #[test]
+    fn caterpillar_collapses_when_bitten_in_the_middle() {
+        let mut game = Game::with_seed(12, 8, 4);
+        game.set_snake(snake_of(&[(5, 3), (4, 3)]));
+        game.caterpillar = (1..5).map(|y| Point { x: 6, y }).collect();
+        game.dir = DirectionSnake::Right;
+        let sum = game.checksum(0);
+        let events = game.step();
+        assert!(events.contains(&GameEvent::Collapsed(Point { x: 6, y: 3 })));
+        assert_eq!((game.score, game.snake().len()), (1, 3));
+        assert!(game.caterpillar.is_empty());
+        assert_ne!(sum, game.checksum(0));
+    }
+
+    #[test]
+    fn caterpillar_needs_a_straight_free_run() {
+        let mut game = Game::with_seed(12, 8, 4);
+        let run = game.spawn_caterpillar().unwrap().to_vec();
+        assert_eq!(run.len(), Game::CATERPILLAR_LEN);
+        let (first, last) = (run[0], run[run.len() - 1]);
+        assert!(first.x == last.x || first.y == last.y);
+        assert!(run.windows(2).all(|w| w[0].x.abs_diff(w[1].x) + w[0].y.abs_diff(w[1].y) == 1));
+        assert!(run.iter().all(|&p| !game.is_snake(p) && p != game.food));
+        // На тесном поле — короче, а без двух свободных клеток подряд её нет
+        let free = [(0, 0), (1, 0), (2, 0), (11, 7)];
+        let crowd = |free: &[(u16, u16)], game: &Game| {
+            let (head, food) = (game.snake()[0], game.food);
+            (0..8)
+                .flat_map(|y| (0..12).map(move |x| Point { x, y }))
+                .filter(|&p| p != head && p != food && !free.contains(&(p.x, p.y)))
+                .collect::<Vec<_>>()
+        };
+        game.caterpillar.clear();
+        game.set_obstacles(crowd(&free, &game));
+        assert_eq!(game.spawn_caterpillar().map(<[Point]>::len), Some(3));
+        game.caterpillar.clear();
+        game.set_obstacles(crowd(&[(0, 0), (2, 0), (11, 7)], &game));
+        assert_eq!(game.spawn_caterpillar(), None);
+    }
+
+    #[test]
+    fn eating_a_bomb_ends_the_run() {
+        let mut game = Game::with_seed(10, 5, 1);
+        game.set_snake(snake_of(&[(2, 2), (1, 2)]));
+        game.dir = DirectionSnake::Right;
+        game.bomb = Some(Bomb { at: Point { x: 3, y: 2 }, left: 5 });
+        let sum = game.checksum(0);
+        assert_eq!(game.step(), [GameEvent::Died(DeathCause::Bomb)]);
+        assert_eq!((game.game_over, game.snake[0]), (Some(DeathCause::Bomb), Point { x: 2, y: 2 }));
+        // Бомба входит в контрольную сумму
+        game.bomb = None;
+        assert_ne!(game.checksum(0), sum);
+    }
+
+    #[test]
+    fn food_keeps_away_from_the_bomb() {
+        // Поле 3×3: вокруг бомбы в углу занято четыре клетки, змейка в другом углу
+        let mut game = Game::with_seed(3, 3, 2);
+        game.set_snake(snake_of(&[(2, 2)]));
+        game.bomb = Some(Bomb { at: Point { x: 0, y: 0 }, left: 10 });
+        for _ in 0..50 {
+            let food = game.spawn_food().unwrap();
+            assert!(!touches(food, Point { x: 0, y: 0 }) && food != Point { x: 2, y: 2 });
+        }
+        // Вокруг еды в середине поля места бомбе нет
+        game.food = Point { x: 1, y: 1 };
+        assert_eq!(game.spawn_bomb(), None);
+    }
+
+    #[test]
+    fn blackout_schedule() {
+        let mut game = Game::with_seed(40, 10, 1);
+        game.dir = DirectionSnake::Down;
+        let phase = |game: &mut Game, steps| {
+            game.steps = steps;
+            game.blackout()
+        };
+        assert_eq!(phase(&mut game, 295), Blackout::Clear);
+        game.blackouts = true;
+        // 300 шагов: 292 ясно, 3 шага предупреждения, 5 шагов темно, дальше снова ясно
+        let phases: Vec<Blackout> = (0..600).map(|steps| phase(&mut game, steps)).collect();
+        let count = |p| phases[..300].iter().filter(|&&q| q == p).count();
+        assert_eq!((count(Blackout::Clear), count(Blackout::Warning), count(Blackout::Dark)), (292, 3, 5));
+        assert_eq!(phases[291..301], [
+            Blackout::Clear,
+            Blackout::Warning,
+            Blackout::Warning,
+            Blackout::Warning,
+            Blackout::Dark,
+            Blackout::Dark,
+            Blackout::Dark,
+            Blackout::Dark,
+            Blackout::Dark,
+            Blackout::Clear,
+        ]);
+        assert_eq!(phases[..300], phases[300..]);
+
+        // Шаги считает сама игра; после конца партии поле снова видно
+        game.steps = 0;
+        game.step();
+        assert_eq!(game.steps, 1);
+        game.steps = 296;
+        assert_eq!(game.blackout(), Blackout::Dark);
+        game.game_over = Some(DeathCause::Wall);
+        assert_eq!(game.blackout(), Blackout::Clear);
+    }
+}
@@ -0,0 +1,5 @@
+//! Правила змейки без терминала, часов и системного генератора случайных чисел.
+//! Собирается и под `wasm32-unknown-unknown`: `cargo build --target wasm32-unknown-unknown -p zmiy-core`.
+
+pub mod game;
+pub mod web;
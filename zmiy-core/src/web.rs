@@ -0,0 +1,92 @@
+//! Игра для веб-демо: на границе только числа и байты, чтобы её было просто
+//! отдать в JavaScript любой обвязкой. Правила те же, что в терминале.
+
+use crate::game::{DirectionSnake, Game};
+
+/// Клетки в [`WebGame::cells`]
+pub const EMPTY: u8 = 0;
+pub const SNAKE: u8 = 1;
+pub const HEAD: u8 = 2;
+pub const FOOD: u8 = 3;
+
+pub struct WebGame {
+    game: Game,
+}
+
+impl WebGame {
+    /// Новая партия; сид задаёт вызывающий, например из `Math.random()`
+    pub fn new(width: u16, height: u16, seed: u64) -> Self {
+        Self { game: Game::with_seed(width, height, seed) }
+    }
+
+    /// Поворот: 0 вверх, 1 вниз, 2 влево, 3 вправо. `false` — поворот не принят.
+    pub fn set_direction(&mut self, dir: u8) -> bool {
+        let dir = match dir {
+            0 => DirectionSnake::Up,
+            1 => DirectionSnake::Down,
+            2 => DirectionSnake::Left,
+            3 => DirectionSnake::Right,
+            _ => return false,
+        };
+        self.game.change_dir(dir)
+    }
+
+    /// Один шаг. `false` — партия кончилась.
+    pub fn tick(&mut self) -> bool {
+        self.game.step();
+        self.game.game_over.is_none()
+    }
+
+    /// Поле по строкам сверху вниз, по клетке на байт: [`EMPTY`], [`SNAKE`], [`HEAD`] или [`FOOD`]
+    pub fn cells(&self) -> Vec<u8> {
+        let width = self.game.width as usize;
+        let mut cells = vec![EMPTY; width * self.game.height as usize];
+        let mut put = |p: crate::game::Point, cell| {
+            if let Some(c) = cells.get_mut(p.y as usize * width + p.x as usize) {
+                *c = cell;
+            }
+        };
+        put(self.game.food, FOOD);
+        for (i, &p) in self.game.snake().iter().enumerate() {
+            put(p, if i == 0 { HEAD } else { SNAKE });
+        }
+        cells
+    }
+
+    pub fn width(&self) -> u16 {
+        self.game.width
+    }
+
+    pub fn height(&self) -> u16 {
+        self.game.height
+    }
+
+    pub fn score(&self) -> usize {
+        self.game.score
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plays_like_the_terminal_game() {
+        let mut web = WebGame::new(6, 3, 1);
+        let mut game = Game::with_seed(6, 3, 1);
+        assert_eq!(web.cells(), [
+            EMPTY, EMPTY, EMPTY, EMPTY, EMPTY, EMPTY, //
+            EMPTY, EMPTY, FOOD, HEAD, EMPTY, EMPTY, //
+            EMPTY, EMPTY, EMPTY, EMPTY, EMPTY, EMPTY,
+        ]);
+        assert!(!web.set_direction(9));
+        assert!(web.set_direction(0));
+        game.change_dir(DirectionSnake::Up);
+        while web.tick() {
+            game.step();
+        }
+        game.step();
+        assert_eq!((web.score(), web.game.snake()), (game.score, game.snake()));
+        assert!(game.game_over.is_some());
+    }
+}